@@ -0,0 +1,3 @@
+-- Amounts for metered ("upto") x402 payments: the signed maximum and what was actually charged
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS authorized_amount VARCHAR(78);
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS settled_amount VARCHAR(78);
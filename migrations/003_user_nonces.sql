@@ -0,0 +1,15 @@
+-- Sequential relayer nonces: the nonce each user must sign next
+CREATE TABLE IF NOT EXISTS user_nonces (
+    user_address VARCHAR(42) PRIMARY KEY,
+    next_nonce BIGINT NOT NULL DEFAULT 0 CHECK (next_nonce >= 0),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Unordered relayer nonces: Permit2-style bitmaps of 256 nonces per word
+CREATE TABLE IF NOT EXISTS user_nonce_bitmaps (
+    user_address VARCHAR(42) NOT NULL,
+    word_pos BIGINT NOT NULL,
+    bitmap BYTEA NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_address, word_pos)
+);
@@ -0,0 +1,6 @@
+-- Pre-flight simulation of relayed calls, run from a relayer wallet before queuing
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS simulation_from VARCHAR(42);
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS simulation_success BOOLEAN;
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS simulation_gas_estimate BIGINT;
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS simulation_revert_reason TEXT;
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS simulated_at TIMESTAMP WITH TIME ZONE;
@@ -0,0 +1,14 @@
+-- Native value and gas cost charged against rolling budgets. One row per transaction and
+-- budget subject (`user:<address>` or `api_key:<name>`); gas cost starts as an estimate and
+-- is replaced by the actual cost once the transaction is mined.
+CREATE TABLE IF NOT EXISTS spending_ledger (
+    transaction_id UUID NOT NULL,
+    subject VARCHAR(128) NOT NULL,
+    value NUMERIC(78, 0) NOT NULL,
+    gas_cost NUMERIC(78, 0) NOT NULL,
+    reconciled BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (transaction_id, subject)
+);
+
+CREATE INDEX IF NOT EXISTS idx_spending_ledger_subject_created_at ON spending_ledger(subject, created_at);
@@ -0,0 +1,7 @@
+-- Client-signed validity windows, and the terminal state of requests whose window closed unsent
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS valid_after TIMESTAMP WITH TIME ZONE;
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS valid_before TIMESTAMP WITH TIME ZONE;
+
+ALTER TABLE transactions DROP CONSTRAINT IF EXISTS transactions_status_check;
+ALTER TABLE transactions ADD CONSTRAINT transactions_status_check
+    CHECK (status IN ('pending', 'processing', 'submitted', 'confirmed', 'failed', 'cancelled', 'expired'));
@@ -0,0 +1,16 @@
+-- Every signed broadcast of a transaction. A stuck transaction is replaced by re-signing
+-- the same wallet nonce with higher fees, so one transaction can have several hashes; at
+-- most one of them is mined.
+CREATE TABLE IF NOT EXISTS transaction_broadcasts (
+    tx_hash VARCHAR(66) PRIMARY KEY,
+    transaction_id UUID NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+    kind VARCHAR(16) NOT NULL CHECK (kind IN ('original', 'speed_up')),
+    wallet_address VARCHAR(42) NOT NULL,
+    nonce BIGINT NOT NULL,
+    max_fee_per_gas NUMERIC(78, 0) NOT NULL,
+    max_priority_fee_per_gas NUMERIC(78, 0) NOT NULL,
+    mined BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_transaction_broadcasts_transaction_id ON transaction_broadcasts(transaction_id);
@@ -0,0 +1,4 @@
+-- Zero-value self-transfers that take the nonce of a broadcast transaction the user cancelled
+ALTER TABLE transaction_broadcasts DROP CONSTRAINT IF EXISTS transaction_broadcasts_kind_check;
+ALTER TABLE transaction_broadcasts ADD CONSTRAINT transaction_broadcasts_kind_check
+    CHECK (kind IN ('original', 'speed_up', 'cancel'));
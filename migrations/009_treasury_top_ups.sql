@@ -0,0 +1,6 @@
+-- Pool wallets whose key is configured outside the database are registered without one, so
+-- their wallet_logs entries (such as treasury top-ups) have a wallet to reference
+ALTER TABLE wallets ALTER COLUMN encrypted_private_key DROP NOT NULL;
+
+-- Treasury spending is summed per event type over the last day
+CREATE INDEX IF NOT EXISTS idx_wallet_logs_event_type_created_at ON wallet_logs(event_type, created_at);
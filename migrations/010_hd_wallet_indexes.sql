@@ -0,0 +1,12 @@
+-- Pool wallets derived from the HD mnemonic, by index under a BIP-44 path. Restarts re-derive
+-- the indexes that are not retired, so the pool comes back with the same addresses; retired
+-- indexes are reused before new ones are derived.
+CREATE TABLE IF NOT EXISTS hd_wallet_indexes (
+    derivation_path VARCHAR(128) NOT NULL,
+    derivation_index INTEGER NOT NULL CHECK (derivation_index >= 0),
+    address VARCHAR(42) NOT NULL,
+    retired BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (derivation_path, derivation_index)
+);
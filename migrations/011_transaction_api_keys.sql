@@ -0,0 +1,2 @@
+-- API key a transaction was submitted with, which alone (besides admin keys) may cancel it
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS api_key_name VARCHAR(255);
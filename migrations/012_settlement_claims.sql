@@ -0,0 +1,11 @@
+-- x402 authorizations with a settlement under way or done, by the contract tracking the nonce,
+-- the payer and the nonce. A later /settle of the same authorization is refused while the
+-- claiming transaction is live, since verification only sees the nonce spent once it is mined.
+CREATE TABLE IF NOT EXISTS settlement_claims (
+    nonce_contract VARCHAR(42) NOT NULL,
+    payer VARCHAR(42) NOT NULL,
+    nonce VARCHAR(78) NOT NULL,
+    transaction_id UUID NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (nonce_contract, payer, nonce)
+);
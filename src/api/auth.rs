@@ -1,6 +1,7 @@
 use axum::{
-    extract::{Request, State},
-    http::{HeaderMap, StatusCode},
+    body::Body,
+    extract::State,
+    http::{HeaderMap, Request, StatusCode},
     middleware::Next,
     response::Response,
 };
@@ -10,12 +11,12 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use tokio::time::{Duration, Instant};
 
+use crate::config::{ApiKeyConfig, SpendingLimits};
 use crate::types::RelayerError;
 
 #[derive(Debug, Clone)]
 pub struct AuthManager {
     api_keys: Arc<RwLock<HashMap<String, ApiKeyInfo>>>,
-    jwt_secret: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,9 +28,40 @@ pub struct ApiKeyInfo {
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub last_used: Option<chrono::DateTime<chrono::Utc>>,
     pub is_active: bool,
+    /// Overrides `budgets.api_key` for this key
+    #[serde(default)]
+    pub spending_limits: Option<SpendingLimits>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl From<&ApiKeyConfig> for ApiKeyInfo {
+    fn from(config: &ApiKeyConfig) -> Self {
+        Self {
+            key: config.key.clone(),
+            name: config.name.clone(),
+            permissions: config.permissions.clone(),
+            rate_limit: RateLimit::default(),
+            created_at: chrono::Utc::now(),
+            last_used: None,
+            is_active: true,
+            spending_limits: config.spending_limits.clone(),
+        }
+    }
+}
+
+/// Permission an API key needs to reach the `/admin` routes
+pub const ADMIN_PERMISSION: &str = "admin";
+
+impl ApiKeyInfo {
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.permissions.iter().any(|p| p == permission)
+    }
+
+    pub fn is_admin(&self) -> bool {
+        self.has_permission(ADMIN_PERMISSION)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RateLimit {
     pub requests_per_minute: u32,
     pub requests_per_hour: u32,
@@ -39,14 +71,71 @@ pub struct RateLimit {
 #[derive(Debug, Clone)]
 pub struct RateLimiter {
     requests: Arc<RwLock<HashMap<String, Vec<Instant>>>>,
+    restrictions: Arc<RwLock<HashMap<String, Restriction>>>,
     limits: RateLimit,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RestrictionKind {
+    /// Requests are held to `Restriction::limits` instead of the default limits
+    Throttle,
+    /// Every request is refused
+    Ban,
+}
+
+/// Temporary throttle or ban of one identifier
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Restriction {
+    pub kind: RestrictionKind,
+    pub reason: String,
+    #[serde(default)]
+    pub limits: Option<RateLimit>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Restriction {
+    pub fn throttle(limits: RateLimit, duration: Duration, reason: String) -> Self {
+        Self {
+            kind: RestrictionKind::Throttle,
+            reason,
+            limits: Some(limits),
+            expires_at: Self::expiry(duration),
+        }
+    }
+
+    pub fn ban(duration: Duration, reason: String) -> Self {
+        Self {
+            kind: RestrictionKind::Ban,
+            reason,
+            limits: None,
+            expires_at: Self::expiry(duration),
+        }
+    }
+
+    pub fn is_ban(&self) -> bool {
+        self.kind == RestrictionKind::Ban
+    }
+
+    pub fn is_active(&self) -> bool {
+        chrono::Utc::now() < self.expires_at
+    }
+
+    fn expiry(duration: Duration) -> chrono::DateTime<chrono::Utc> {
+        chrono::Utc::now() + chrono::Duration::from_std(duration).unwrap_or(chrono::Duration::MAX)
+    }
+}
+
+impl Default for AuthManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl AuthManager {
-    pub fn new(jwt_secret: String) -> Self {
+    pub fn new() -> Self {
         Self {
             api_keys: Arc::new(RwLock::new(HashMap::new())),
-            jwt_secret,
         }
     }
 
@@ -72,6 +161,11 @@ impl AuthManager {
         }
     }
 
+    pub async fn get_api_key_by_name(&self, name: &str) -> Option<ApiKeyInfo> {
+        let keys = self.api_keys.read().await;
+        keys.values().find(|k| k.name == name).cloned()
+    }
+
     pub async fn revoke_api_key(&self, key: &str) -> Result<bool, RelayerError> {
         let mut keys = self.api_keys.write().await;
         
@@ -102,11 +196,52 @@ impl RateLimiter {
     pub fn new(limits: RateLimit) -> Self {
         Self {
             requests: Arc::new(RwLock::new(HashMap::new())),
+            restrictions: Arc::new(RwLock::new(HashMap::new())),
             limits,
         }
     }
 
+    /// Throttle or ban `identifier` until the restriction expires, replacing any current one
+    pub async fn restrict(&self, identifier: &str, restriction: Restriction) {
+        self.restrictions.write().await.insert(identifier.to_string(), restriction);
+    }
+
+    /// Lift the restriction on `identifier`, returning whether one was active
+    pub async fn lift_restriction(&self, identifier: &str) -> bool {
+        self.restrictions.write().await
+            .remove(identifier)
+            .is_some_and(|restriction| restriction.is_active())
+    }
+
+    /// The restriction in force on `identifier`, if any
+    pub async fn get_restriction(&self, identifier: &str) -> Option<Restriction> {
+        let mut restrictions = self.restrictions.write().await;
+        match restrictions.get(identifier) {
+            Some(restriction) if restriction.is_active() => Some(restriction.clone()),
+            Some(_) => {
+                restrictions.remove(identifier);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Every restriction in force, by identifier
+    pub async fn get_restrictions(&self) -> Vec<(String, Restriction)> {
+        let mut restrictions = self.restrictions.write().await;
+        restrictions.retain(|_, restriction| restriction.is_active());
+        restrictions.iter()
+            .map(|(identifier, restriction)| (identifier.clone(), restriction.clone()))
+            .collect()
+    }
+
     pub async fn check_rate_limit(&self, identifier: &str) -> Result<bool, RelayerError> {
+        let limits = match self.get_restriction(identifier).await {
+            Some(restriction) if restriction.is_ban() => return Ok(false),
+            Some(restriction) => restriction.limits.unwrap_or_else(|| self.limits.clone()),
+            None => self.limits.clone(),
+        };
+
         let mut requests = self.requests.write().await;
         let now = Instant::now();
         
@@ -118,21 +253,21 @@ impl RateLimiter {
         request_history.retain(|&time| time > cutoff);
         
         // Check daily limit
-        if request_history.len() >= self.limits.requests_per_day as usize {
+        if request_history.len() >= limits.requests_per_day as usize {
             return Ok(false);
         }
         
         // Check hourly limit
         let hour_cutoff = now - Duration::from_secs(3600); // 1 hour
         let hourly_requests = request_history.iter().filter(|&&time| time > hour_cutoff).count();
-        if hourly_requests >= self.limits.requests_per_hour as usize {
+        if hourly_requests >= limits.requests_per_hour as usize {
             return Ok(false);
         }
         
         // Check minute limit
         let minute_cutoff = now - Duration::from_secs(60); // 1 minute
         let minute_requests = request_history.iter().filter(|&&time| time > minute_cutoff).count();
-        if minute_requests >= self.limits.requests_per_minute as usize {
+        if minute_requests >= limits.requests_per_minute as usize {
             return Ok(false);
         }
         
@@ -182,8 +317,8 @@ pub async fn auth_middleware(
     State(auth_manager): State<Arc<AuthManager>>,
     State(rate_limiter): State<Arc<RateLimiter>>,
     headers: HeaderMap,
-    request: Request,
-    next: Next,
+    request: Request<Body>,
+    next: Next<Body>,
 ) -> Result<Response, StatusCode> {
     // Extract API key from headers
     let api_key = headers
@@ -223,10 +358,54 @@ pub async fn auth_middleware(
     Ok(next.run(request).await)
 }
 
-pub async fn cors_middleware(
+/// Attach the `ApiKeyInfo` of a valid key to the request when one is sent, leaving
+/// anonymous requests through; an unknown or revoked key is rejected
+pub async fn api_key_middleware(
+    State(auth_manager): State<Arc<AuthManager>>,
     headers: HeaderMap,
-    request: Request,
-    next: Next,
+    mut request: Request<Body>,
+    next: Next<Body>,
+) -> Result<Response, StatusCode> {
+    let api_key = headers
+        .get("x-api-key")
+        .and_then(|h| h.to_str().ok())
+        .or_else(|| {
+            headers
+                .get("authorization")
+                .and_then(|h| h.to_str().ok())
+                .and_then(|auth| auth.strip_prefix("Bearer "))
+        });
+
+    if let Some(key) = api_key {
+        match auth_manager.validate_api_key(key).await {
+            Ok(Some(info)) => {
+                request.extensions_mut().insert(info);
+            }
+            Ok(None) => return Err(StatusCode::UNAUTHORIZED),
+            Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// Only let through requests that `api_key_middleware` attached an admin key to:
+/// `401` without a key, `403` for a key lacking `ADMIN_PERMISSION`
+pub async fn admin_middleware(
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Result<Response, StatusCode> {
+    match request.extensions().get::<ApiKeyInfo>() {
+        None => Err(StatusCode::UNAUTHORIZED),
+        Some(info) if !info.is_admin() => Err(StatusCode::FORBIDDEN),
+        Some(_) => Ok(next.run(request).await),
+    }
+}
+
+pub async fn cors_middleware(
+    _headers: HeaderMap,
+    request: Request<Body>,
+    next: Next<Body>,
 ) -> Result<Response, StatusCode> {
     let response = next.run(request).await;
     
@@ -243,8 +422,8 @@ pub async fn cors_middleware(
 }
 
 pub async fn logging_middleware(
-    request: Request,
-    next: Next,
+    request: Request<Body>,
+    next: Next<Body>,
 ) -> Result<Response, StatusCode> {
     let start = Instant::now();
     let method = request.method().clone();
@@ -298,7 +477,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_auth_manager() {
-        let auth_manager = AuthManager::new("test_secret".to_string());
+        let auth_manager = AuthManager::new();
         
         let api_key = ApiKeyInfo {
             key: "test_key".to_string(),
@@ -308,6 +487,7 @@ mod tests {
             created_at: chrono::Utc::now(),
             last_used: None,
             is_active: true,
+            spending_limits: None,
         };
         
         auth_manager.add_api_key(api_key).await.unwrap();
@@ -318,6 +498,9 @@ mod tests {
         let stats = auth_manager.get_api_key_stats().await.unwrap();
         assert_eq!(stats.total_keys, 1);
         assert_eq!(stats.active_keys, 1);
+
+        assert_eq!(auth_manager.get_api_key_by_name("test").await.unwrap().key, "test_key");
+        assert!(auth_manager.get_api_key_by_name("other").await.is_none());
     }
 
     #[tokio::test]
@@ -349,4 +532,35 @@ mod tests {
         assert_eq!(stats.identifier, identifier);
         assert_eq!(stats.requests_last_minute, 0);
     }
+
+    #[tokio::test]
+    async fn test_rate_limiter_restrictions() {
+        let rate_limiter = RateLimiter::new(RateLimit::default());
+        let throttled = RateLimit {
+            requests_per_minute: 1,
+            requests_per_hour: 60,
+            requests_per_day: 1440,
+        };
+
+        rate_limiter.restrict(
+            "user:a",
+            Restriction::throttle(throttled, Duration::from_secs(60), "reverts".to_string()),
+        ).await;
+        assert!(rate_limiter.check_rate_limit("user:a").await.unwrap());
+        assert!(!rate_limiter.check_rate_limit("user:a").await.unwrap());
+        assert!(rate_limiter.check_rate_limit("user:b").await.unwrap());
+
+        rate_limiter.restrict("user:b", Restriction::ban(Duration::from_secs(60), "spray".to_string())).await;
+        assert!(!rate_limiter.check_rate_limit("user:b").await.unwrap());
+        assert_eq!(rate_limiter.get_restrictions().await.len(), 2);
+
+        assert!(rate_limiter.lift_restriction("user:b").await);
+        assert!(!rate_limiter.lift_restriction("user:b").await);
+        assert!(rate_limiter.check_rate_limit("user:b").await.unwrap());
+
+        // Expired restrictions no longer apply
+        rate_limiter.restrict("user:c", Restriction::ban(Duration::ZERO, "spray".to_string())).await;
+        assert!(rate_limiter.get_restriction("user:c").await.is_none());
+        assert!(rate_limiter.check_rate_limit("user:c").await.unwrap());
+    }
 }
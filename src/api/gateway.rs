@@ -1,5 +1,6 @@
+use alloy::providers::Provider;
 use axum::{
-    extract::{Path, Query, State},
+    extract::{Extension, Path, Query, State},
     http::StatusCode,
     response::{IntoResponse, Json},
     routing::{get, post},
@@ -8,17 +9,34 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
+use tower::ServiceBuilder;
+use tower_http::trace::TraceLayer;
 use uuid::Uuid;
 
-use crate::types::{RelayerError, Result, TransactionRequest, TransactionStatus};
-use crate::database::DatabaseManager;
+use crate::types::{
+    FacilitatorRequest, RelayerError, SettleResponse, SupportedResponse, TransactionRequest,
+    TransactionStatus, VerifyResponse,
+};
+use crate::database::{DatabaseManager, TransactionBroadcast};
 use crate::cache::CacheManager;
+use crate::wallet::keystore::{Keystore, KeystoreKdf, WalletKeyStore};
 use crate::wallet::pool::WalletPool;
+use crate::wallet::signer::KeystoreSigner;
 use crate::queue::scheduler::TaskScheduler;
-use crate::security::{SignatureVerifier, ReplayProtection};
-use crate::config::Config;
+use crate::queue::replacement::TransactionReplacer;
+use crate::queue::tracker::TransactionTracker;
+use crate::api::auth::{ApiKeyInfo, AuthManager, RateLimiter};
+use crate::security::{
+    api_key_budget_subject, api_key_reputation_subject, normalize_signature, parse_signature_bytes,
+    screening_reason, user_budget_subject, user_reputation_subject, AddressScreener, BudgetManager,
+    BudgetStatus, PolicyEngine, ReplayStore, ReputationEvent, ReputationManager, ReputationRejection,
+    ReputationStatus, SignatureVerifier, UserNonceManager, SCREENING_BLOCKED_EVENT,
+};
+use crate::config::{Config, NonceMode};
 use crate::services::EthereumProvider;
+use crate::settlement::Facilitator;
 use crate::utils::gas::GasPriceOracle;
+use crate::utils::simulation::TransactionSimulator;
 use crate::utils::validation::TransactionValidator;
 
 #[derive(Debug, Clone)]
@@ -27,10 +45,22 @@ pub struct ApiState {
     pub cache_manager: Arc<CacheManager<serde_json::Value>>,
     pub ethereum_provider: Arc<EthereumProvider>,
     pub wallet_pool: Arc<WalletPool>,
+    pub wallet_keystore: Arc<WalletKeyStore>,
     pub task_scheduler: Arc<TaskScheduler>,
     pub signature_verifier: Arc<SignatureVerifier>,
-    pub replay_protection: Arc<ReplayProtection>,
+    pub replay_store: Arc<dyn ReplayStore>,
+    pub user_nonces: Arc<UserNonceManager>,
+    pub policy_engine: Arc<PolicyEngine>,
+    pub budget_manager: Arc<BudgetManager>,
+    pub auth_manager: Arc<AuthManager>,
+    pub address_screener: Arc<AddressScreener>,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub reputation_manager: Arc<ReputationManager>,
+    pub transaction_tracker: Option<Arc<TransactionTracker>>,
+    pub transaction_replacer: Option<Arc<TransactionReplacer>>,
     pub gas_price_oracle: Option<Arc<GasPriceOracle>>,
+    pub transaction_simulator: Option<Arc<TransactionSimulator>>,
+    pub facilitator: Arc<Facilitator>,
     pub config: Arc<Config>,
 }
 
@@ -44,10 +74,24 @@ pub struct SubmitTransactionRequest {
     pub max_fee_per_gas: String,
     pub max_priority_fee_per_gas: String,
     pub nonce: String,
+    #[serde(default)]
     pub signature_r: String,
+    #[serde(default)]
     pub signature_s: String,
+    #[serde(default)]
     pub signature_v: u8,
+    /// Hex signature instead of r/s/v: 65-byte `r ‖ s ‖ v`, 64-byte EIP-2098 compact, or the raw
+    /// signature of a smart-contract wallet (EIP-1271 / ERC-6492)
+    #[serde(default)]
+    pub signature: Option<String>,
     pub priority: String,
+    /// Signed Unix time before which the transaction must not be sent; 0 or absent for none
+    #[serde(default)]
+    pub valid_after: Option<u64>,
+    /// Signed Unix time from which the transaction must not be sent, at most the signature
+    /// timeout ahead
+    #[serde(default)]
+    pub valid_before: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -65,6 +109,13 @@ pub struct TransactionStatusResponse {
     pub block_number: Option<u64>,
     pub gas_used: Option<String>,
     pub error_message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_amount: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settled_amount: Option<String>,
+    /// Every hash broadcast for the transaction, including fee-bumped replacements
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub broadcasts: Vec<TransactionBroadcast>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -77,6 +128,14 @@ pub struct UserTransactionsResponse {
     pub limit: u64,
 }
 
+/// Nonce a wallet should put in its next signed request
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserNonceResponse {
+    pub address: String,
+    pub mode: NonceMode,
+    pub next_nonce: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HealthCheckResponse {
     pub status: String,
@@ -137,17 +196,22 @@ pub fn create_router(state: ApiState) -> Router {
     use axum::middleware;
     use std::sync::Arc;
     
-    // Create auth manager and rate limiter for middleware
-    let auth_manager = Arc::new(crate::api::auth::AuthManager::new(
-        state.config.security.signature_timeout.to_string(),
-    ));
-    let rate_limiter = Arc::new(crate::api::auth::RateLimiter::new(
-        crate::api::auth::RateLimit {
-            requests_per_minute: state.config.server.rate_limit_per_minute,
-            requests_per_hour: state.config.server.rate_limit_per_minute * 60,
-            requests_per_day: state.config.server.rate_limit_per_minute * 60 * 24,
-        },
-    ));
+    let auth_manager = Arc::clone(&state.auth_manager);
+    let rate_limiter = Arc::clone(&state.rate_limiter);
+
+    // Admin routes need an API key with the admin permission
+    let admin_routes = Router::new()
+        .route("/admin/queue", get(get_queue_details))
+        .route("/admin/wallets", get(get_wallet_details))
+        .route("/admin/wallets/import", post(import_wallet))
+        .route("/admin/wallets/:address/export", post(export_wallet))
+        .route("/admin/config", get(get_config_info))
+        .route("/admin/budgets/users/:address", get(get_user_budget))
+        .route("/admin/budgets/api-keys/:name", get(get_api_key_budget))
+        .route("/admin/reputation", get(get_restricted_subjects))
+        .route("/admin/reputation/users/:address", get(get_user_reputation).delete(clear_user_reputation))
+        .route("/admin/reputation/api-keys/:name", get(get_api_key_reputation).delete(clear_api_key_reputation))
+        .route_layer(middleware::from_fn(crate::api::auth::admin_middleware));
     
     Router::new()
         // Public routes (no authentication required)
@@ -160,18 +224,29 @@ pub fn create_router(state: ApiState) -> Router {
         .route("/transactions/batch", post(submit_batch_transactions))
         .route("/transactions/:id", get(get_transaction_status))
         .route("/users/:address/transactions", get(get_user_transactions))
+        .route("/users/:address/nonce", get(get_user_nonce))
         .route("/transactions/:id/cancel", post(cancel_transaction))
+        // x402 facilitator routes
+        .route("/verify", post(verify_payment))
+        .route("/settle", post(settle_payment))
+        .route("/supported", get(get_supported))
         // Admin routes
-        .route("/admin/queue", get(get_queue_details))
-        .route("/admin/wallets", get(get_wallet_details))
-        .route("/admin/config", get(get_config_info))
+        .merge(admin_routes)
         // Search routes
         .route("/transactions/search", get(search_transactions))
-        // Apply rate limiting middleware to all routes
-        .layer(middleware::from_fn_with_state(
-            (auth_manager.clone(), rate_limiter),
-            rate_limit_middleware,
-        ))
+        // Apply request tracing and rate limiting middleware to all routes
+        .layer(
+            ServiceBuilder::new()
+                .layer(TraceLayer::new_for_http())
+                .layer(middleware::from_fn_with_state(
+                    (auth_manager.clone(), rate_limiter),
+                    rate_limit_middleware,
+                ))
+                .layer(middleware::from_fn_with_state(
+                    auth_manager,
+                    crate::api::auth::api_key_middleware,
+                )),
+        )
         .with_state(state)
 }
 
@@ -179,8 +254,8 @@ pub fn create_router(state: ApiState) -> Router {
 async fn rate_limit_middleware(
     axum::extract::State((_auth_manager, rate_limiter)): axum::extract::State<(Arc<crate::api::auth::AuthManager>, Arc<crate::api::auth::RateLimiter>)>,
     headers: axum::http::HeaderMap,
-    request: axum::extract::Request,
-    next: axum::middleware::Next,
+    request: axum::http::Request<axum::body::Body>,
+    next: axum::middleware::Next<axum::body::Body>,
 ) -> axum::response::Response {
     // Extract identifier (IP or API key)
     let identifier = headers
@@ -193,19 +268,15 @@ async fn rate_limit_middleware(
     // Check rate limit
     match rate_limiter.check_rate_limit(&identifier).await {
         Ok(true) => next.run(request).await,
-        Ok(false) => {
-            axum::response::Response::builder()
-                .status(axum::http::StatusCode::TOO_MANY_REQUESTS)
-                .header("retry-after", "60")
-                .body(axum::body::Body::from("Rate limit exceeded"))
-                .unwrap()
-        }
-        Err(_) => {
-            axum::response::Response::builder()
-                .status(axum::http::StatusCode::INTERNAL_SERVER_ERROR)
-                .body(axum::body::Body::from("Internal server error"))
-                .unwrap()
-        }
+        Ok(false) => (
+            StatusCode::TOO_MANY_REQUESTS,
+            [("retry-after", "60")],
+            "Rate limit exceeded",
+        ).into_response(),
+        Err(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error",
+        ).into_response(),
     }
 }
 
@@ -235,19 +306,19 @@ async fn health_check(
     // Check wallet pool health
     match state.wallet_pool.get_pool_stats().await {
         Ok(stats) => {
-            let status = if stats.healthy_wallets > 0 {
-                "healthy"
-            } else {
+            let status = if stats.healthy_wallets > 0 { "healthy" } else { "degraded" };
+            if status == "degraded" && overall_status == "healthy" {
                 overall_status = "degraded".to_string();
-                "degraded"
-            };
+            }
             services.insert("wallet_pool".to_string(), ServiceStatus {
                 status: status.to_string(),
                 message: Some(format!("Healthy wallets: {}/{}", stats.healthy_wallets, stats.total_wallets)),
             });
         }
         Err(e) => {
-            overall_status = "degraded".to_string();
+            if overall_status == "healthy" {
+                overall_status = "degraded".to_string();
+            }
             services.insert("wallet_pool".to_string(), ServiceStatus {
                 status: "unhealthy".to_string(),
                 message: Some(format!("Wallet pool error: {}", e)),
@@ -264,7 +335,9 @@ async fn health_check(
             });
         }
         Err(e) => {
-            overall_status = "degraded".to_string();
+            if overall_status == "healthy" {
+                overall_status = "degraded".to_string();
+            }
             services.insert("queue".to_string(), ServiceStatus {
                 status: "unhealthy".to_string(),
                 message: Some(format!("Queue error: {}", e)),
@@ -301,7 +374,9 @@ async fn health_check(
             });
         }
         Err(e) => {
-            overall_status = "degraded".to_string();
+            if overall_status == "healthy" {
+                overall_status = "degraded".to_string();
+            }
             services.insert("ethereum_provider".to_string(), ServiceStatus {
                 status: "unhealthy".to_string(),
                 message: Some(format!("Provider error: {}", e)),
@@ -402,9 +477,9 @@ async fn get_stats(
             overall_success_rate: wallet_stats.overall_success_rate,
         },
         cache_stats: CacheStatsResponse {
-            memory_entries: cache_stats.memory_stats.entry_count,
+            memory_entries: cache_stats.memory_stats.total_entries,
             redis_connected: cache_stats.use_redis,
-            hit_rate: cache_stats.memory_stats.hit_rate,
+            hit_rate: cache_stats.redis_stats.map(|stats| stats.hit_rate).unwrap_or(0.0),
         },
         transaction_stats,
     }).into_response()
@@ -585,8 +660,27 @@ async fn get_metrics(
 
 async fn submit_transaction(
     State(state): State<ApiState>,
+    api_key: Option<Extension<ApiKeyInfo>>,
     Json(payload): Json<SubmitTransactionRequest>,
 ) -> Result<Json<SubmitTransactionResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let api_key = api_key.map(|Extension(api_key)| api_key);
+    let task_id = relay_transaction(&state, &payload, api_key.as_ref()).await?;
+
+    Ok(Json(SubmitTransactionResponse {
+        transaction_id: task_id,
+        status: "pending".to_string(),
+        message: "Transaction submitted successfully".to_string(),
+    }))
+}
+
+/// Validate, authorize and queue one signed request: field validation, policy, signature,
+/// reputation, screening, simulation, budget, then the user nonce and replay claims.
+/// Single and batch submissions both go through here.
+async fn relay_transaction(
+    state: &ApiState,
+    payload: &SubmitTransactionRequest,
+    api_key: Option<&ApiKeyInfo>,
+) -> Result<Uuid, (StatusCode, Json<serde_json::Value>)> {
     // Validate the request
     if payload.user_address.is_empty() || payload.target_contract.is_empty() {
         return Err((
@@ -726,25 +820,6 @@ async fn submit_transaction(
             })),
         ))?;
 
-    // Parse signature
-    let signature_r = TransactionValidator::parse_u256(&payload.signature_r)
-        .map_err(|e| (
-            StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({
-                "error": e.to_string(),
-                "code": "INVALID_SIGNATURE"
-            })),
-        ))?;
-
-    let signature_s = TransactionValidator::parse_u256(&payload.signature_s)
-        .map_err(|e| (
-            StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({
-                "error": e.to_string(),
-                "code": "INVALID_SIGNATURE"
-            })),
-        ))?;
-
     // Parse and validate priority
     let priority = TransactionValidator::validate_priority(&payload.priority)
         .map_err(|e| (
@@ -755,23 +830,18 @@ async fn submit_transaction(
             })),
         ))?;
 
-    // Validate signature structure
-    let signature = crate::types::Signature {
-        r: signature_r,
-        s: signature_s,
-        v: payload.signature_v,
-    };
-    TransactionValidator::validate_signature(&signature)
+    // Normalize the signature; contract wallet signatures are checked on-chain
+    let (signature, signature_data) = parse_request_signature(payload)
         .map_err(|e| (
             StatusCode::BAD_REQUEST,
             Json(serde_json::json!({
-                "error": e.to_string(),
+                "error": e,
                 "code": "INVALID_SIGNATURE"
             })),
         ))?;
 
     // Create transaction request (all validations already done)
-    let transaction_request = TransactionRequest::new(
+    let mut transaction_request = TransactionRequest::new(
         user_address,
         target_contract,
         calldata,
@@ -783,6 +853,16 @@ async fn submit_transaction(
         signature,
         priority,
     );
+    transaction_request.signature_data = signature_data;
+    transaction_request.api_key_name = api_key.map(|api_key| api_key.name.clone());
+    set_validity_window(&mut transaction_request, payload)
+        .map_err(|e| (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e,
+                "code": "INVALID_VALIDITY_WINDOW"
+            })),
+        ))?;
 
     // Final comprehensive validation
     TransactionValidator::validate_transaction_params(
@@ -794,7 +874,7 @@ async fn submit_transaction(
         &transaction_request.max_fee_per_gas,
         &transaction_request.max_priority_fee_per_gas,
         &transaction_request.nonce,
-        &transaction_request.signature,
+        transaction_request.signature_data.is_none().then_some(&transaction_request.signature),
     ).map_err(|e| (
         StatusCode::BAD_REQUEST,
         Json(serde_json::json!({
@@ -803,14 +883,24 @@ async fn submit_transaction(
         })),
     ))?;
 
+    // Only relay calls the configured policy allows
+    if let Err(violation) = state.policy_engine.evaluate(&transaction_request) {
+        tracing::warn!("Transaction {} rejected by policy: {}", transaction_request.id, violation);
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({
+                "error": violation.to_string(),
+                "code": "POLICY_VIOLATION",
+                "rule": violation.rule
+            })),
+        ));
+    }
+
     // Verify transaction signature
     tracing::debug!("Verifying transaction signature for address: {:?}", user_address);
     let nonce_u64 = nonce.to::<u64>();
-    
-    // Clone signature verifier for mutable access
-    let mut verifier = (*state.signature_verifier).clone();
-    
-    match verifier.verify_transaction_signature(&transaction_request, nonce_u64) {
+
+    match state.signature_verifier.verify_transaction_signature(&transaction_request).await {
         Ok(true) => {
             tracing::info!("Signature verified successfully for transaction {}", transaction_request.id);
         }
@@ -824,6 +914,24 @@ async fn submit_transaction(
                 })),
             ));
         }
+        Err(RelayerError::Expired(e)) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": format!("Request expired: {}", e),
+                    "code": "REQUEST_EXPIRED"
+                })),
+            ));
+        }
+        Err(RelayerError::Validation(e)) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": e,
+                    "code": "INVALID_VALIDITY_WINDOW"
+                })),
+            ));
+        }
         Err(e) => {
             tracing::error!("Signature verification error: {}", e);
             return Err((
@@ -836,27 +944,24 @@ async fn submit_transaction(
         }
     }
 
-    // Check replay protection
-    if state.config.security.enable_replay_protection {
-        tracing::debug!("Checking replay protection for address: {:?}, nonce: {}", user_address, nonce_u64);
-        match state.replay_protection.check_and_record(user_address, nonce_u64, None) {
-            Ok(_) => {
-                tracing::debug!("Replay protection check passed");
-            }
-            Err(e) => {
-                tracing::warn!("Replay attack detected: {}", e);
-                return Err((
-                    StatusCode::BAD_REQUEST,
-                    Json(serde_json::json!({
-                        "error": format!("Replay attack detected: {}", e),
-                        "code": "REPLAY_ATTACK"
-                    })),
-                ));
-            }
-        }
+    // Gas prices are already validated by TransactionValidator
+
+    // Turn away users and API keys that are banned or throttled for abuse
+    let reputation_subjects = reputation_subjects(user_address, api_key);
+    if let Err(rejection) = state.reputation_manager.admit(&reputation_subjects).await {
+        return Err(reputation_rejection_response(rejection));
     }
 
-    // Gas prices are already validated by TransactionValidator
+    // Never relay for, to or through deny-listed addresses
+    if let Err(reason) = screen_transaction(state, &transaction_request).await {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({
+                "error": reason,
+                "code": "ADDRESS_BLOCKED"
+            })),
+        ));
+    }
 
     // Check wallet pool availability
     let wallet_stats = state.wallet_pool.get_pool_stats().await
@@ -898,41 +1003,301 @@ async fn submit_transaction(
         ));
     }
 
-    // Store transaction in database
-    tracing::info!("Storing transaction {} in database", transaction_request.id);
-    state.database_manager.create_transaction(&transaction_request).await
-        .map_err(|e| {
-            tracing::error!("Failed to store transaction in database: {}", e);
-            (
+    // Dry-run the call from the wallet that will send it, refusing requests that would revert
+    let simulation = match state.transaction_simulator {
+        Some(ref simulator) => {
+            let from = match transaction_request.sender {
+                Some(sender) => Some(sender),
+                None => state.wallet_pool.active_addresses().await.first().copied(),
+            };
+            let from = from.ok_or_else(|| (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(serde_json::json!({
+                    "error": "No healthy wallets available",
+                    "code": "WALLET_UNAVAILABLE"
+                })),
+            ))?;
+
+            let simulation = simulator.simulate(&transaction_request, from).await
+                .map_err(|e| {
+                    tracing::error!("Simulation of transaction {} failed: {}", transaction_request.id, e);
+                    (
+                        StatusCode::BAD_GATEWAY,
+                        Json(serde_json::json!({
+                            "error": format!("Failed to simulate transaction: {}", e),
+                            "code": "NETWORK_ERROR"
+                        })),
+                    )
+                })?;
+
+            if let Some(ref reason) = simulation.revert_reason {
+                tracing::warn!("Transaction {} would revert: {}", transaction_request.id, reason);
+                state.reputation_manager.record(
+                    &reputation_subjects,
+                    ReputationEvent::SimulationFailed { target: transaction_request.target_contract },
+                ).await;
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({
+                        "error": format!("Transaction would revert: {}", reason),
+                        "code": "SIMULATION_REVERTED",
+                        "revert_reason": reason
+                    })),
+                ));
+            }
+
+            Some(simulation)
+        }
+        None => None,
+    };
+
+    // Charge the user's and API key's rolling budgets with the estimated cost
+    let gas_estimate = simulation.as_ref().and_then(|simulation| simulation.gas_estimate);
+    match state.budget_manager.reserve(&transaction_request, gas_estimate, api_key).await {
+        Ok(()) => {}
+        Err(RelayerError::BudgetExceeded(e)) => {
+            tracing::warn!("Transaction {} over budget: {}", transaction_request.id, e);
+            return Err((
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(serde_json::json!({
+                    "error": format!("Budget exceeded: {}", e),
+                    "code": "BUDGET_EXCEEDED"
+                })),
+            ));
+        }
+        Err(e) => {
+            tracing::error!("Failed to reserve budget: {}", e);
+            return Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(serde_json::json!({
-                    "error": format!("Failed to store transaction: {}", e),
+                    "error": format!("Failed to check budget: {}", e),
                     "code": "DATABASE_ERROR"
                 })),
-            )
-        })?;
+            ));
+        }
+    }
+
+    // Consume the user's nonce last, so a request turned away for capacity can be
+    // retried, but before anything is stored or queued
+    match state.user_nonces.claim(user_address, nonce_u64).await {
+        Ok(()) => {}
+        Err(e @ (RelayerError::ReplayAttack(_) | RelayerError::Validation(_))) => {
+            tracing::warn!("Rejected nonce {} for {:?}: {}", nonce_u64, user_address, e);
+            release_budget(state, transaction_request.id).await;
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": e.to_string(),
+                    "code": "INVALID_NONCE"
+                })),
+            ));
+        }
+        Err(e) => {
+            tracing::error!("Failed to record nonce: {}", e);
+            release_budget(state, transaction_request.id).await;
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "error": format!("Failed to record nonce: {}", e),
+                    "code": "DATABASE_ERROR"
+                })),
+            ));
+        }
+    }
+
+    if state.config.security.enable_replay_protection {
+        tracing::debug!("Checking replay protection for address: {:?}, nonce: {}", user_address, nonce_u64);
+        match state.replay_store.check_and_record(state.config.ethereum.chain_id, user_address, nonce_u64).await {
+            Ok(()) => {
+                tracing::debug!("Replay protection check passed");
+            }
+            Err(RelayerError::ReplayAttack(e)) => {
+                tracing::warn!("Replay attack detected: {}", e);
+                release_budget(state, transaction_request.id).await;
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({
+                        "error": format!("Replay attack detected: {}", e),
+                        "code": "REPLAY_ATTACK"
+                    })),
+                ));
+            }
+            Err(e) => {
+                tracing::error!("Replay store unavailable: {}", e);
+                release_budget(state, transaction_request.id).await;
+                return Err((
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    Json(serde_json::json!({
+                        "error": format!("Replay protection unavailable: {}", e),
+                        "code": "REPLAY_STORE_ERROR"
+                    })),
+                ));
+            }
+        }
+    }
+
+    // Store transaction in database
+    tracing::info!("Storing transaction {} in database", transaction_request.id);
+    if let Err(e) = state.database_manager.create_transaction(&transaction_request).await {
+        tracing::error!("Failed to store transaction in database: {}", e);
+        release_budget(state, transaction_request.id).await;
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({
+                "error": format!("Failed to store transaction: {}", e),
+                "code": "DATABASE_ERROR"
+            })),
+        ));
+    }
+
+    if let Some(ref simulation) = simulation {
+        if let Err(e) = state.database_manager.record_simulation(transaction_request.id, simulation).await {
+            tracing::warn!("Failed to record simulation for transaction {}: {}", transaction_request.id, e);
+        }
+    }
 
     // Submit to task scheduler
-    tracing::info!("Submitting transaction {} to task scheduler", transaction_request.id);
-    let task_id = state.task_scheduler.schedule_task(transaction_request).await
-        .map_err(|e| {
+    let transaction_id = transaction_request.id;
+    let target_contract = transaction_request.target_contract;
+    tracing::info!("Submitting transaction {} to task scheduler", transaction_id);
+    let task_id = match state.task_scheduler.schedule_task(transaction_request).await {
+        Ok(task_id) => task_id,
+        Err(e) => {
             tracing::error!("Failed to schedule task: {}", e);
-            (
+            release_budget(state, transaction_id).await;
+            return Err((
                 StatusCode::SERVICE_UNAVAILABLE,
                 Json(serde_json::json!({
                     "error": format!("Failed to schedule transaction: {}", e),
                     "code": "SCHEDULER_ERROR"
                 })),
-            )
-        })?;
+            ));
+        }
+    };
 
-    tracing::info!("Transaction {} submitted successfully with task ID {}", transaction_request.id, task_id);
+    tracing::info!("Transaction {} submitted successfully with task ID {}", transaction_id, task_id);
+    state.reputation_manager.record(&reputation_subjects, ReputationEvent::Submitted { target: target_contract }).await;
+    state.reputation_manager.track(transaction_id, reputation_subjects).await;
 
-    Ok(Json(SubmitTransactionResponse {
-        transaction_id: task_id,
-        status: "pending".to_string(),
-        message: "Transaction submitted successfully".to_string(),
-    }))
+    Ok(task_id)
+}
+
+/// The request's signature in canonical form, plus the raw bytes of a smart-contract wallet
+/// signature. `signature` may hold a 65-byte `r ‖ s ‖ v` or 64-byte EIP-2098 compact ECDSA
+/// signature; any other length, or 65 bytes whose last byte is not a v value, is passed on
+/// as contract wallet bytes (EIP-1271 / ERC-6492). Without it, `signature_r`/`signature_s`
+/// are read as decimal or 0x-prefixed hex and `signature_v` may be 0/1 or 27/28.
+fn parse_request_signature(
+    payload: &SubmitTransactionRequest,
+) -> Result<(crate::types::Signature, Option<alloy::primitives::Bytes>), String> {
+    use std::str::FromStr;
+
+    if let Some(ref signature) = payload.signature {
+        let bytes = signature.strip_prefix("0x")
+            .and_then(|signature| hex::decode(signature).ok())
+            .filter(|signature| !signature.is_empty())
+            .ok_or_else(|| "Signature must be 0x-prefixed hex".to_string())?;
+
+        let is_ecdsa = match bytes.len() {
+            64 => true,
+            65 => matches!(bytes[64], 0 | 1 | 27 | 28),
+            _ => false,
+        };
+        if !is_ecdsa {
+            let placeholder = crate::types::Signature {
+                r: alloy::primitives::U256::ZERO,
+                s: alloy::primitives::U256::ZERO,
+                v: 0,
+            };
+            return Ok((placeholder, Some(bytes.into())));
+        }
+
+        return parse_signature_bytes(&bytes)
+            .map(|signature| (signature, None))
+            .map_err(|e| e.to_string());
+    }
+
+    let parse_component = |name: &str, value: &str| alloy::primitives::U256::from_str(value)
+        .map_err(|_| format!("Invalid {} format", name));
+
+    let signature = crate::types::Signature {
+        r: parse_component("signature_r", &payload.signature_r)?,
+        s: parse_component("signature_s", &payload.signature_s)?,
+        v: payload.signature_v,
+    };
+
+    normalize_signature(signature)
+        .map(|signature| (signature, None))
+        .map_err(|e| e.to_string())
+}
+
+/// Copy the signed validity window of `payload` onto `request`
+fn set_validity_window(request: &mut TransactionRequest, payload: &SubmitTransactionRequest) -> Result<(), String> {
+    let to_time = |name: &str, seconds: u64| chrono::DateTime::from_timestamp(seconds as i64, 0)
+        .ok_or_else(|| format!("Invalid {}", name));
+
+    request.valid_after = match payload.valid_after {
+        Some(0) | None => None,
+        Some(seconds) => Some(to_time("valid_after", seconds)?),
+    };
+    request.valid_before = payload.valid_before
+        .map(|seconds| to_time("valid_before", seconds))
+        .transpose()?;
+
+    Ok(())
+}
+
+/// Reputation subjects of a request: its user and, when one was sent, its API key
+fn reputation_subjects(user: alloy::primitives::Address, api_key: Option<&ApiKeyInfo>) -> Vec<String> {
+    let mut subjects = vec![user_reputation_subject(user)];
+    if let Some(api_key) = api_key {
+        subjects.push(api_key_reputation_subject(&api_key.name));
+    }
+    subjects
+}
+
+fn reputation_rejection_response(rejection: ReputationRejection) -> (StatusCode, Json<serde_json::Value>) {
+    tracing::warn!("Refused request from {}: {}", rejection.subject, rejection.reason);
+    let (status, code) = if rejection.banned {
+        (StatusCode::FORBIDDEN, "TEMPORARILY_BANNED")
+    } else {
+        (StatusCode::TOO_MANY_REQUESTS, "THROTTLED")
+    };
+
+    (status, Json(serde_json::json!({
+        "error": rejection.reason,
+        "code": code
+    })))
+}
+
+/// Check a request against the deny lists, storing it with a `screening_blocked` log entry
+/// when it is refused
+async fn screen_transaction(state: &ApiState, request: &TransactionRequest) -> Result<(), String> {
+    let matches = state.address_screener.screen(request).await;
+    if matches.is_empty() {
+        return Ok(());
+    }
+
+    let reason = screening_reason(&matches);
+    tracing::warn!("Transaction {} refused: {}", request.id, reason);
+
+    if let Err(e) = state.database_manager.record_rejected_transaction(
+        request,
+        &reason,
+        SCREENING_BLOCKED_EVENT,
+        serde_json::json!({ "matches": matches }),
+    ).await {
+        tracing::error!("Failed to record screening block of transaction {}: {}", request.id, e);
+    }
+
+    Err(reason)
+}
+
+/// Undo a budget reservation for a request turned away after it was charged
+async fn release_budget(state: &ApiState, transaction_id: Uuid) {
+    if let Err(e) = state.budget_manager.release(transaction_id).await {
+        tracing::warn!("Failed to release budget of transaction {}: {}", transaction_id, e);
+    }
 }
 
 async fn get_transaction_status(
@@ -950,6 +1315,12 @@ async fn get_transaction_status(
 
     match transaction {
         Some(tx) => {
+            let broadcasts = state.database_manager.get_transaction_broadcasts(tx.id).await
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Failed to load broadcasts of transaction {}: {}", tx.id, e);
+                    Vec::new()
+                });
+
             Ok(Json(TransactionStatusResponse {
                 transaction_id: tx.id,
                 status: tx.status,
@@ -957,6 +1328,9 @@ async fn get_transaction_status(
                 block_number: tx.block_number.map(|n| n as u64),
                 gas_used: tx.gas_used,
                 error_message: tx.error_message,
+                authorized_amount: tx.authorized_amount,
+                settled_amount: tx.settled_amount,
+                broadcasts,
                 created_at: tx.created_at.to_rfc3339(),
                 updated_at: tx.updated_at.to_rfc3339(),
             }))
@@ -1015,6 +1389,9 @@ async fn get_user_transactions(
             block_number: tx.block_number.map(|n| n as u64),
             gas_used: tx.gas_used,
             error_message: tx.error_message,
+            authorized_amount: tx.authorized_amount,
+            settled_amount: tx.settled_amount,
+            broadcasts: Vec::new(),
             created_at: tx.created_at.to_rfc3339(),
             updated_at: tx.updated_at.to_rfc3339(),
         }
@@ -1028,20 +1405,49 @@ async fn get_user_transactions(
     }))
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SearchTransactionsResponse {
-    pub transactions: Vec<TransactionStatusResponse>,
-    pub total: u64,
-    pub page: u64,
-    pub limit: u64,
-}
-
-async fn search_transactions(
+async fn get_user_nonce(
     State(state): State<ApiState>,
-    Query(params): Query<HashMap<String, String>>,
-) -> Result<Json<SearchTransactionsResponse>, (StatusCode, Json<serde_json::Value>)> {
-    use crate::database::TransactionFilters;
-
+    Path(address): Path<String>,
+) -> Result<Json<UserNonceResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let user_address = address.parse::<alloy::primitives::Address>()
+        .map_err(|_| (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "Invalid address format",
+                "code": "INVALID_ADDRESS"
+            })),
+        ))?;
+
+    let next_nonce = state.user_nonces.next_nonce(user_address).await
+        .map_err(|e| (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({
+                "error": format!("Database error: {}", e),
+                "code": "DATABASE_ERROR"
+            })),
+        ))?;
+
+    Ok(Json(UserNonceResponse {
+        address: user_address.to_string(),
+        mode: state.user_nonces.mode(),
+        next_nonce: next_nonce.to_string(),
+    }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchTransactionsResponse {
+    pub transactions: Vec<TransactionStatusResponse>,
+    pub total: u64,
+    pub page: u64,
+    pub limit: u64,
+}
+
+async fn search_transactions(
+    State(state): State<ApiState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<SearchTransactionsResponse>, (StatusCode, Json<serde_json::Value>)> {
+    use crate::database::TransactionFilters;
+
     // Parse query parameters
     let page = params.get("page")
         .and_then(|p| p.parse::<u64>().ok())
@@ -1129,6 +1535,9 @@ async fn search_transactions(
             block_number: tx.block_number.map(|n| n as u64),
             gas_used: tx.gas_used,
             error_message: tx.error_message,
+            authorized_amount: tx.authorized_amount,
+            settled_amount: tx.settled_amount,
+            broadcasts: Vec::new(),
             created_at: tx.created_at.to_rfc3339(),
             updated_at: tx.updated_at.to_rfc3339(),
         }
@@ -1144,8 +1553,45 @@ async fn search_transactions(
 
 async fn cancel_transaction(
     State(state): State<ApiState>,
+    api_key: Option<Extension<ApiKeyInfo>>,
     Path(transaction_id): Path<Uuid>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    // Only the API key that submitted the transaction, or an admin key, may cancel it
+    let Some(Extension(api_key)) = api_key else {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(serde_json::json!({
+                "error": "An API key is required to cancel a transaction",
+                "code": "UNAUTHORIZED"
+            }))
+        ));
+    };
+    if !api_key.is_admin() {
+        let transaction = state.database_manager.get_transaction(transaction_id).await
+            .map_err(|e| (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "error": format!("Database error: {}", e)
+                }))
+            ))?
+            .ok_or_else(|| (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({
+                    "error": "Transaction not found or cannot be cancelled"
+                }))
+            ))?;
+
+        if !may_cancel(transaction.api_key_name.as_deref(), &api_key) {
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(serde_json::json!({
+                    "error": "Transaction was not submitted with this API key",
+                    "code": "NOT_TRANSACTION_OWNER"
+                }))
+            ));
+        }
+    }
+
     // Cancel transaction in task scheduler
     let cancelled = state.task_scheduler.cancel_task(transaction_id).await
         .map_err(|e| (
@@ -1156,6 +1602,8 @@ async fn cancel_transaction(
         ))?;
 
     if cancelled {
+        state.reputation_manager.record_outcome(transaction_id, ReputationEvent::Cancelled).await;
+
         // Update transaction status in database
         state.database_manager.update_transaction_status(
             transaction_id,
@@ -1172,19 +1620,89 @@ async fn cancel_transaction(
             }))
         ))?;
 
-        Ok(Json(serde_json::json!({
+        return Ok(Json(serde_json::json!({
             "transaction_id": transaction_id,
             "status": "cancelled",
             "message": "Transaction cancelled successfully"
-        })))
-    } else {
-        Err((
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({
-                "error": "Transaction not found or cannot be cancelled"
-            }))
-        ))
+        })));
+    }
+
+    // Already broadcast: race it with a zero-value self-transfer of the same nonce
+    if let Some(ref replacer) = state.transaction_replacer {
+        match replacer.cancel(transaction_id).await {
+            Ok(Some(cancel_tx_hash)) => {
+                return Ok(Json(serde_json::json!({
+                    "transaction_id": transaction_id,
+                    "status": "cancelling",
+                    "cancel_tx_hash": cancel_tx_hash,
+                    "message": "Cancellation broadcast; the transaction ends cancelled or confirmed, whichever is mined"
+                })));
+            }
+            Ok(None) => {}
+            Err(RelayerError::Validation(message)) => {
+                return Err((
+                    StatusCode::CONFLICT,
+                    Json(serde_json::json!({
+                        "error": message,
+                        "code": "CANNOT_CANCEL"
+                    }))
+                ));
+            }
+            Err(e) => {
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({
+                        "error": format!("Failed to cancel transaction: {}", e),
+                        "code": "CANCEL_FAILED"
+                    }))
+                ));
+            }
+        }
+    }
+
+    Err((
+        StatusCode::NOT_FOUND,
+        Json(serde_json::json!({
+            "error": "Transaction not found or cannot be cancelled"
+        }))
+    ))
+}
+
+/// Whether `api_key` may cancel a transaction submitted with the key named `owner`
+fn may_cancel(owner: Option<&str>, api_key: &ApiKeyInfo) -> bool {
+    api_key.is_admin() || owner == Some(api_key.name.as_str())
+}
+
+// x402 facilitator endpoints. Invalid payments are reported in the body with a
+// 200 status, as resource servers expect from a facilitator.
+async fn verify_payment(
+    State(state): State<ApiState>,
+    Json(request): Json<FacilitatorRequest>,
+) -> Json<VerifyResponse> {
+    let response = state.facilitator.verify(&request).await;
+    if let Some(reason) = response.invalid_reason {
+        tracing::info!("x402 payment rejected: {:?}", reason);
+    }
+
+    Json(response)
+}
+
+async fn settle_payment(
+    State(state): State<ApiState>,
+    Json(request): Json<FacilitatorRequest>,
+) -> Json<SettleResponse> {
+    let response = state.facilitator.settle(&request).await;
+    if response.success {
+        tracing::info!("x402 payment settled: {}", response.transaction);
     }
+
+    Json(response)
+}
+
+async fn get_supported(
+    State(state): State<ApiState>,
+) -> Json<SupportedResponse> {
+    Json(state.facilitator.supported().await)
 }
 
 // Gas price endpoint
@@ -1229,6 +1747,7 @@ async fn get_gas_price(
         // Fallback: get current gas price from provider
         match state.ethereum_provider.get_gas_price().await {
             Ok(gas_price) => {
+                let gas_price = alloy::primitives::U256::from(gas_price);
                 let max_fee = gas_price * alloy::primitives::U256::from(110) / alloy::primitives::U256::from(100);
                 let priority_fee = gas_price * alloy::primitives::U256::from(10) / alloy::primitives::U256::from(100);
                 
@@ -1269,6 +1788,7 @@ pub struct BatchTransactionResponse {
 
 async fn submit_batch_transactions(
     State(state): State<ApiState>,
+    api_key: Option<Extension<ApiKeyInfo>>,
     Json(payload): Json<BatchTransactionRequest>,
 ) -> Result<Json<BatchTransactionResponse>, (StatusCode, Json<serde_json::Value>)> {
     if payload.transactions.is_empty() {
@@ -1289,18 +1809,19 @@ async fn submit_batch_transactions(
         ));
     }
 
+    let api_key = api_key.map(|Extension(api_key)| api_key);
     let batch_id = Uuid::new_v4();
     let mut transaction_ids = Vec::new();
     let mut errors = Vec::new();
 
     // Process each transaction in the batch
     for (index, tx_request) in payload.transactions.iter().enumerate() {
-        match process_single_transaction(&state, tx_request).await {
+        match relay_transaction(&state, tx_request, api_key.as_ref()).await {
             Ok(tx_id) => {
                 transaction_ids.push(tx_id);
             }
-            Err(e) => {
-                errors.push(format!("Transaction {}: {}", index, e));
+            Err((_, Json(error))) => {
+                errors.push(format!("Transaction {}: {}", index, error["error"].as_str().unwrap_or_default()));
             }
         }
     }
@@ -1330,86 +1851,6 @@ async fn submit_batch_transactions(
     }))
 }
 
-// Helper function to process a single transaction
-async fn process_single_transaction(
-    state: &ApiState,
-    payload: &SubmitTransactionRequest,
-) -> Result<Uuid, String> {
-    // Parse and validate addresses
-    let user_address = payload.user_address.parse::<alloy::primitives::Address>()
-        .map_err(|_| "Invalid user_address format".to_string())?;
-
-    let target_contract = payload.target_contract.parse::<alloy::primitives::Address>()
-        .map_err(|_| "Invalid target_contract format".to_string())?;
-
-    // Parse calldata
-    let calldata = if payload.calldata.starts_with("0x") {
-        hex::decode(&payload.calldata[2..])
-            .map_err(|_| "Invalid calldata format".to_string())?
-    } else {
-        hex::decode(&payload.calldata)
-            .map_err(|_| "Invalid calldata format".to_string())?
-    };
-
-    // Parse numeric values
-    let value = payload.value.parse::<alloy::primitives::U256>()
-        .map_err(|_| "Invalid value format".to_string())?;
-
-    let gas_limit = payload.gas_limit.parse::<alloy::primitives::U256>()
-        .map_err(|_| "Invalid gas_limit format".to_string())?;
-
-    let max_fee_per_gas = payload.max_fee_per_gas.parse::<alloy::primitives::U256>()
-        .map_err(|_| "Invalid max_fee_per_gas format".to_string())?;
-
-    let max_priority_fee_per_gas = payload.max_priority_fee_per_gas.parse::<alloy::primitives::U256>()
-        .map_err(|_| "Invalid max_priority_fee_per_gas format".to_string())?;
-
-    let nonce = payload.nonce.parse::<alloy::primitives::U256>()
-        .map_err(|_| "Invalid nonce format".to_string())?;
-
-    let signature_r = payload.signature_r.parse::<alloy::primitives::U256>()
-        .map_err(|_| "Invalid signature_r format".to_string())?;
-
-    let signature_s = payload.signature_s.parse::<alloy::primitives::U256>()
-        .map_err(|_| "Invalid signature_s format".to_string())?;
-
-    let priority = match payload.priority.as_str() {
-        "low" => crate::types::Priority::Low,
-        "normal" => crate::types::Priority::Normal,
-        "high" => crate::types::Priority::High,
-        "critical" => crate::types::Priority::Critical,
-        _ => return Err("Invalid priority".to_string()),
-    };
-
-    // Create transaction request
-    let transaction_request = TransactionRequest::new(
-        user_address,
-        target_contract,
-        alloy::primitives::Bytes::from(calldata),
-        value,
-        gas_limit,
-        max_fee_per_gas,
-        max_priority_fee_per_gas,
-        nonce,
-        crate::types::Signature {
-            r: signature_r,
-            s: signature_s,
-            v: payload.signature_v,
-        },
-        priority,
-    );
-
-    // Store in database
-    state.database_manager.create_transaction(&transaction_request).await
-        .map_err(|e| format!("Database error: {}", e))?;
-
-    // Submit to task scheduler
-    let task_id = state.task_scheduler.schedule_task(transaction_request).await
-        .map_err(|e| format!("Scheduler error: {}", e))?;
-
-    Ok(task_id)
-}
-
 // Admin endpoints
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -1510,6 +1951,150 @@ async fn get_wallet_details(
     }))
 }
 
+#[derive(Deserialize)]
+pub struct ImportWalletRequest {
+    pub keystore: Keystore,
+    /// Password the keystore is encrypted with
+    pub password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportWalletResponse {
+    pub address: String,
+    /// False when the wallet was already in the pool
+    pub added: bool,
+}
+
+#[derive(Deserialize)]
+pub struct ExportWalletRequest {
+    /// Password to encrypt the exported keystore with
+    pub password: String,
+    #[serde(default)]
+    pub kdf: ExportKdf,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportKdf {
+    #[default]
+    Scrypt,
+    Pbkdf2,
+}
+
+/// Store a v3 keystore's key, encrypted under the master passphrase, and add it to the pool
+async fn import_wallet(
+    State(state): State<ApiState>,
+    Json(payload): Json<ImportWalletRequest>,
+) -> Result<Json<ImportWalletResponse>, (StatusCode, Json<serde_json::Value>)> {
+    if !state.wallet_keystore.has_passphrase() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({
+                "error": "No wallet master passphrase configured",
+                "code": "KEYSTORE_LOCKED"
+            })),
+        ));
+    }
+
+    let ImportWalletRequest { keystore, password } = payload;
+    let private_key = state.wallet_keystore.import(keystore, zeroize::Zeroizing::new(password)).await
+        .map_err(|e| {
+            let (status, code) = match e {
+                RelayerError::Database(_) => (StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR"),
+                _ => (StatusCode::BAD_REQUEST, "INVALID_KEYSTORE"),
+            };
+            (status, Json(serde_json::json!({
+                "error": e.to_string(),
+                "code": code
+            })))
+        })?;
+
+    let address = alloy::primitives::Address::from_private_key(&private_key);
+    let exists = state.wallet_pool.wallets().read().await.iter().any(|wallet| wallet.address == address);
+    if !exists {
+        state.wallet_pool.add_signer(Arc::new(KeystoreSigner::new(private_key, "wallets table"))).await
+            .map_err(|e| (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "error": e.to_string(),
+                    "code": "WALLET_POOL_ERROR"
+                })),
+            ))?;
+    }
+    tracing::info!("Imported wallet {:?} from keystore", address);
+
+    Ok(Json(ImportWalletResponse {
+        address: address.to_string(),
+        added: !exists,
+    }))
+}
+
+/// Export a pool wallet as a v3 keystore encrypted under the given password
+async fn export_wallet(
+    State(state): State<ApiState>,
+    Path(address): Path<String>,
+    Json(payload): Json<ExportWalletRequest>,
+) -> Result<Json<Keystore>, (StatusCode, Json<serde_json::Value>)> {
+    let wallet_address = TransactionValidator::validate_address_string(&address)
+        .map_err(|e| (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string(),
+                "code": "INVALID_ADDRESS"
+            })),
+        ))?;
+
+    if payload.password.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "Export password must not be empty",
+                "code": "INVALID_PASSWORD"
+            })),
+        ));
+    }
+
+    let signer = state.wallet_pool.wallets().read().await
+        .iter()
+        .find(|wallet| wallet.address == wallet_address)
+        .and_then(|wallet| wallet.signer.clone())
+        .ok_or_else(|| (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "error": format!("Wallet {} not found", address),
+                "code": "WALLET_NOT_FOUND"
+            })),
+        ))?;
+    let private_key = signer.signing_key().cloned()
+        .ok_or_else(|| (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({
+                "error": format!("Wallet {} is held by a {} signer and cannot be exported", address, signer.kind()),
+                "code": "KEY_NOT_EXPORTABLE"
+            })),
+        ))?;
+
+    let kdf = match payload.kdf {
+        ExportKdf::Scrypt => KeystoreKdf::scrypt(),
+        ExportKdf::Pbkdf2 => KeystoreKdf::pbkdf2(),
+    };
+    let password = zeroize::Zeroizing::new(payload.password);
+    let keystore = tokio::task::spawn_blocking(move || Keystore::encrypt(&private_key, &password, kdf))
+        .await
+        .map_err(|e| RelayerError::Internal(e.to_string()))
+        .and_then(|keystore| keystore)
+        .map_err(|e| (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({
+                "error": e.to_string(),
+                "code": "INTERNAL_ERROR"
+            })),
+        ))?;
+    tracing::info!("Exported wallet {:?} as keystore", wallet_address);
+
+    Ok(Json(keystore))
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ConfigInfoResponse {
     pub server: ServerConfigInfo,
@@ -1554,6 +2139,7 @@ pub struct QueueConfigInfo {
 pub struct SecurityConfigInfo {
     pub signature_timeout: u64,
     pub nonce_window: u64,
+    pub nonce_mode: NonceMode,
     pub enable_replay_protection: bool,
 }
 
@@ -1591,11 +2177,140 @@ async fn get_config_info(
         security: SecurityConfigInfo {
             signature_timeout: state.config.security.signature_timeout,
             nonce_window: state.config.security.nonce_window,
+            nonce_mode: state.config.security.nonce_mode,
             enable_replay_protection: state.config.security.enable_replay_protection,
         },
     }))
 }
 
+async fn get_user_budget(
+    State(state): State<ApiState>,
+    Path(address): Path<String>,
+) -> Result<Json<BudgetStatus>, (StatusCode, Json<serde_json::Value>)> {
+    let user_address = TransactionValidator::validate_address_string(&address)
+        .map_err(|e| (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string(),
+                "code": "INVALID_ADDRESS"
+            })),
+        ))?;
+
+    let status = state.budget_manager
+        .status(&user_budget_subject(user_address), state.budget_manager.user_limits())
+        .await
+        .map_err(|e| (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({
+                "error": format!("Failed to get budget: {}", e),
+                "code": "DATABASE_ERROR"
+            })),
+        ))?;
+
+    Ok(Json(status))
+}
+
+async fn get_api_key_budget(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<Json<BudgetStatus>, (StatusCode, Json<serde_json::Value>)> {
+    let api_key = state.auth_manager.get_api_key_by_name(&name).await
+        .ok_or_else(|| (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "error": format!("API key '{}' not found", name),
+                "code": "API_KEY_NOT_FOUND"
+            })),
+        ))?;
+
+    let status = state.budget_manager
+        .status(&api_key_budget_subject(&api_key.name), state.budget_manager.api_key_limits(&api_key))
+        .await
+        .map_err(|e| (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({
+                "error": format!("Failed to get budget: {}", e),
+                "code": "DATABASE_ERROR"
+            })),
+        ))?;
+
+    Ok(Json(status))
+}
+
+async fn get_restricted_subjects(
+    State(state): State<ApiState>,
+) -> Json<serde_json::Value> {
+    let restrictions: Vec<serde_json::Value> = state.reputation_manager.restricted().await
+        .into_iter()
+        .map(|(subject, restriction)| serde_json::json!({
+            "subject": subject,
+            "restriction": restriction
+        }))
+        .collect();
+
+    Json(serde_json::json!({
+        "restrictions": restrictions,
+        "total": restrictions.len()
+    }))
+}
+
+async fn get_user_reputation(
+    State(state): State<ApiState>,
+    Path(address): Path<String>,
+) -> Result<Json<ReputationStatus>, (StatusCode, Json<serde_json::Value>)> {
+    let user_address = parse_reputation_address(&address)?;
+    Ok(Json(state.reputation_manager.status(&user_reputation_subject(user_address)).await))
+}
+
+async fn clear_user_reputation(
+    State(state): State<ApiState>,
+    Path(address): Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let user_address = parse_reputation_address(&address)?;
+    let subject = user_reputation_subject(user_address);
+    let cleared = state.reputation_manager.clear(&subject).await;
+    tracing::info!("Cleared reputation of {} (restriction lifted: {})", subject, cleared);
+
+    Ok(Json(serde_json::json!({
+        "subject": subject,
+        "cleared": cleared
+    })))
+}
+
+async fn get_api_key_reputation(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Json<ReputationStatus> {
+    Json(state.reputation_manager.status(&api_key_reputation_subject(&name)).await)
+}
+
+async fn clear_api_key_reputation(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Json<serde_json::Value> {
+    let subject = api_key_reputation_subject(&name);
+    let cleared = state.reputation_manager.clear(&subject).await;
+    tracing::info!("Cleared reputation of {} (restriction lifted: {})", subject, cleared);
+
+    Json(serde_json::json!({
+        "subject": subject,
+        "cleared": cleared
+    }))
+}
+
+fn parse_reputation_address(
+    address: &str,
+) -> Result<alloy::primitives::Address, (StatusCode, Json<serde_json::Value>)> {
+    TransactionValidator::validate_address_string(address)
+        .map_err(|e| (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.to_string(),
+                "code": "INVALID_ADDRESS"
+            })),
+        ))
+}
+
 fn hide_sensitive_info(url: &str) -> String {
     // Simple hiding of passwords/API keys in URLs
     if let Some(at_pos) = url.find('@') {
@@ -1629,10 +2344,102 @@ mod tests {
         http::{Request, StatusCode},
     };
     use tower::ServiceExt;
+    use crate::security::MemoryReplayStore;
 
-    #[tokio::test]
-    async fn test_health_check() {
-        let state = ApiState {};
+    // Services point at unreachable endpoints so handlers exercise their error paths
+    fn test_state() -> ApiState {
+        let mut config = Config::default();
+        config.database.url = "postgresql://127.0.0.1:1/express402_test".to_string();
+
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .acquire_timeout(std::time::Duration::from_secs(1))
+            .connect_lazy(&config.database.url)
+            .unwrap();
+        let provider = alloy::providers::ProviderBuilder::new()
+            .on_http("http://127.0.0.1:1".parse().unwrap());
+        let memory_cache = crate::cache::MemoryCache::new(
+            100,
+            std::time::Duration::from_secs(60),
+            std::time::Duration::from_secs(300),
+        );
+
+        let database_manager = Arc::new(DatabaseManager::from_pool(pool));
+        let ethereum_provider = Arc::new(provider);
+        let task_scheduler = Arc::new(TaskScheduler::new(4, 100, std::time::Duration::from_secs(30)));
+        let wallet_pool = Arc::new(WalletPool::new(Default::default()));
+        let config = Arc::new(config);
+        let facilitator = Arc::new(Facilitator::new(
+            Arc::clone(&config),
+            Arc::clone(&database_manager),
+            Arc::clone(&ethereum_provider),
+            Arc::clone(&task_scheduler),
+            Arc::clone(&wallet_pool),
+            None,
+        ));
+
+        let user_nonces = Arc::new(UserNonceManager::new(
+            Arc::clone(&database_manager),
+            config.security.nonce_mode,
+        ));
+
+        let policy_engine = Arc::new(PolicyEngine::new(
+            &config.policy,
+            &config.security.trusted_contracts,
+        ).unwrap());
+
+        let budget_manager = Arc::new(BudgetManager::new(
+            Arc::clone(&database_manager),
+            &config.budgets,
+        ).unwrap());
+
+        let rate_limiter = Arc::new(crate::api::auth::RateLimiter::new(Default::default()));
+        let wallet_keystore = Arc::new(WalletKeyStore::new(Arc::clone(&database_manager), None));
+
+        ApiState {
+            database_manager,
+            cache_manager: Arc::new(CacheManager::new(memory_cache, None, false)),
+            ethereum_provider,
+            wallet_pool,
+            wallet_keystore,
+            task_scheduler,
+            signature_verifier: Arc::new(SignatureVerifier::new(
+                alloy::primitives::U256::from(config.ethereum.chain_id),
+                alloy::primitives::Address::ZERO,
+            )),
+            replay_store: Arc::new(MemoryReplayStore::new(
+                std::time::Duration::from_secs(300),
+                std::time::Duration::from_secs(60),
+            )),
+            user_nonces,
+            policy_engine,
+            budget_manager,
+            auth_manager: Arc::new(AuthManager::new()),
+            address_screener: Arc::new(AddressScreener::new(vec![], std::time::Duration::from_secs(30))),
+            rate_limiter: Arc::clone(&rate_limiter),
+            reputation_manager: Arc::new(ReputationManager::new(config.reputation.clone(), rate_limiter)),
+            transaction_tracker: None,
+            transaction_replacer: None,
+            gas_price_oracle: None,
+            transaction_simulator: None,
+            facilitator,
+            config,
+        }
+    }
+
+    // Registers an API key with the admin permission and returns it
+    async fn admin_key(state: &ApiState) -> &'static str {
+        state.auth_manager.add_api_key(ApiKeyInfo::from(&crate::config::ApiKeyConfig {
+            key: "admin-key".to_string(),
+            name: "operator".to_string(),
+            permissions: vec![crate::api::auth::ADMIN_PERMISSION.to_string()],
+            spending_limits: None,
+        })).await.unwrap();
+        "admin-key"
+    }
+
+    #[tokio::test]
+    async fn test_health_check() {
+        let state = test_state();
         let app = create_router(state);
         
         let request = Request::builder()
@@ -1642,12 +2449,18 @@ mod tests {
             .unwrap();
         
         let response = app.oneshot(request).await.unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let health: HealthCheckResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(health.status, "unhealthy");
+        assert_eq!(health.services["database"].status, "unhealthy");
+        assert_eq!(health.services["queue"].status, "healthy");
     }
 
     #[tokio::test]
     async fn test_submit_transaction_invalid_address() {
-        let state = ApiState {};
+        let state = test_state();
         let app = create_router(state);
         
         let payload = SubmitTransactionRequest {
@@ -1662,7 +2475,10 @@ mod tests {
             signature_r: "0".to_string(),
             signature_s: "0".to_string(),
             signature_v: 27,
+            signature: None,
             priority: "normal".to_string(),
+            valid_after: None,
+            valid_before: None,
         };
         
         let request = Request::builder()
@@ -1678,7 +2494,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_get_transaction_status() {
-        let state = ApiState {};
+        let state = test_state();
         let app = create_router(state);
         
         let transaction_id = Uuid::new_v4();
@@ -1688,7 +2504,710 @@ mod tests {
             .body(Body::empty())
             .unwrap();
         
+        // Lookup fails without a reachable database
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn test_get_supported() {
+        let app = create_router(test_state());
+
+        let request = Request::builder()
+            .uri("/supported")
+            .method("GET")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let supported: SupportedResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(supported.kinds.len(), 1);
+        assert_eq!(supported.kinds[0].scheme, "exact");
+        assert_eq!(supported.kinds[0].network, "ethereum");
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_scheme_mismatch() {
+        let app = create_router(test_state());
+
+        let payload = serde_json::json!({
+            "x402Version": 1,
+            "paymentPayload": {
+                "x402Version": 1,
+                "scheme": "upto",
+                "network": "ethereum",
+                "payload": {}
+            },
+            "paymentRequirements": {
+                "scheme": "exact",
+                "network": "ethereum",
+                "maxAmountRequired": "10000",
+                "payTo": "0x2222222222222222222222222222222222222222",
+                "maxTimeoutSeconds": 60,
+                "asset": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+            }
+        });
+
+        let request = Request::builder()
+            .uri("/verify")
+            .method("POST")
+            .header("content-type", "application/json")
+            .body(Body::from(payload.to_string()))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let verify: VerifyResponse = serde_json::from_slice(&body).unwrap();
+        assert!(!verify.is_valid);
+        assert_eq!(verify.invalid_reason, Some(crate::types::InvalidReason::InvalidScheme));
+    }
+
+    #[tokio::test]
+    async fn test_settle_upto_requires_amount() {
+        let app = create_router(test_state());
+
+        let payload = serde_json::json!({
+            "x402Version": 1,
+            "paymentPayload": {
+                "x402Version": 1,
+                "scheme": "upto",
+                "network": "ethereum",
+                "payload": {}
+            },
+            "paymentRequirements": {
+                "scheme": "upto",
+                "network": "ethereum",
+                "maxAmountRequired": "10000",
+                "payTo": "0x2222222222222222222222222222222222222222",
+                "maxTimeoutSeconds": 60,
+                "asset": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+            }
+        });
+
+        let request = Request::builder()
+            .uri("/settle")
+            .method("POST")
+            .header("content-type", "application/json")
+            .body(Body::from(payload.to_string()))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let settle: SettleResponse = serde_json::from_slice(&body).unwrap();
+        assert!(!settle.success);
+        assert_eq!(settle.error_reason, Some(crate::types::InvalidReason::InvalidUptoSettleAmount));
+    }
+
+    #[tokio::test]
+    async fn test_get_user_nonce() {
+        let app = create_router(test_state());
+
+        let request = Request::builder()
+            .uri("/users/not-an-address/nonce")
+            .method("GET")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // Nonces live in Postgres, which is unreachable here
+        let request = Request::builder()
+            .uri("/users/0x1234567890123456789012345678901234567890/nonce")
+            .method("GET")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["code"], "DATABASE_ERROR");
+    }
+
+    #[tokio::test]
+    async fn test_submit_transaction_policy_violation() {
+        let mut state = test_state();
+        state.policy_engine = Arc::new(PolicyEngine::new(
+            &Default::default(),
+            &["0x0987654321098765432109876543210987654321".parse().unwrap()],
+        ).unwrap());
+        let app = create_router(state);
+
+        let payload = SubmitTransactionRequest {
+            user_address: "0x1234567890123456789012345678901234567890".to_string(),
+            target_contract: "0x1234567890123456789012345678901234567890".to_string(),
+            calldata: "0xa9059cbb".to_string(),
+            value: "0".to_string(),
+            // Numeric fields are parsed as hex first
+            gas_limit: "0x5208".to_string(),
+            max_fee_per_gas: "0x4a817c800".to_string(),
+            max_priority_fee_per_gas: "0x77359400".to_string(),
+            nonce: "0".to_string(),
+            signature_r: "1".to_string(),
+            signature_s: "1".to_string(),
+            signature_v: 27,
+            signature: None,
+            priority: "normal".to_string(),
+            valid_after: None,
+            valid_before: None,
+        };
+
+        let request = Request::builder()
+            .uri("/transactions")
+            .method("POST")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(&payload).unwrap()))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["code"], "POLICY_VIOLATION");
+    }
+
+    /// Submission of a zero-value call to `target`, signed by a fresh key with the state's verifier
+    fn signed_payload(
+        state: &ApiState,
+        target: alloy::primitives::Address,
+        calldata: Vec<u8>,
+    ) -> SubmitTransactionRequest {
+        use crate::security::TransactionMessage;
+        use crate::utils::CryptoUtils;
+
+        let key = CryptoUtils::generate_private_key();
+        let mut signed = TransactionRequest::new(
+            alloy::primitives::Address::from_private_key(&key),
+            target,
+            alloy::primitives::Bytes::from(calldata.clone()),
+            alloy::primitives::U256::ZERO,
+            alloy::primitives::U256::from(21000u64),
+            alloy::primitives::U256::from(20000000000u64),
+            alloy::primitives::U256::from(2000000000u64),
+            alloy::primitives::U256::ZERO,
+            crate::types::Signature { r: alloy::primitives::U256::ZERO, s: alloy::primitives::U256::ZERO, v: 27 },
+            crate::types::Priority::Normal,
+        );
+        signed.valid_before = chrono::DateTime::from_timestamp(chrono::Utc::now().timestamp() + 120, 0);
+        let message_hash = state.signature_verifier
+            .create_eip712_hash(&TransactionMessage::from_request(&signed))
+            .unwrap();
+        let signature = state.signature_verifier.sign_transaction(&key, &message_hash).unwrap();
+
+        SubmitTransactionRequest {
+            user_address: signed.user_address.to_string(),
+            target_contract: signed.target_contract.to_string(),
+            calldata: format!("0x{}", hex::encode(calldata)),
+            value: "0".to_string(),
+            gas_limit: "0x5208".to_string(),
+            max_fee_per_gas: "0x4a817c800".to_string(),
+            max_priority_fee_per_gas: "0x77359400".to_string(),
+            nonce: "0".to_string(),
+            signature_r: format!("{:#x}", signature.r),
+            signature_s: format!("{:#x}", signature.s),
+            signature_v: signature.v,
+            signature: None,
+            priority: "normal".to_string(),
+            valid_after: None,
+            valid_before: signed.valid_before.map(|time| time.timestamp() as u64),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_submit_transaction_simulation_reverted() {
+        use crate::utils::CryptoUtils;
+        use alloy::sol_types::{Revert, SolError};
+
+        // RPC stub whose eth_call reverts with Error("Pausable: paused")
+        let revert = format!("0x{}", hex::encode(Revert::from("Pausable: paused").abi_encode()));
+        let rpc = Router::new().route("/", post(move |Json(body): Json<serde_json::Value>| {
+            let revert = revert.clone();
+            async move {
+                Json(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": body["id"],
+                    "error": { "code": 3, "message": "execution reverted", "data": revert }
+                }))
+            }
+        }));
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(rpc.into_make_service()));
+
+        let mut state = test_state();
+        state.transaction_simulator = Some(Arc::new(TransactionSimulator::new(Arc::new(
+            alloy::providers::ProviderBuilder::new().on_http(url.parse().unwrap()),
+        ))));
+        state.wallet_pool.add_wallet(CryptoUtils::generate_private_key()).await.unwrap();
+
+        // A correctly signed request, so only the simulation can turn it away
+        let payload = signed_payload(&state, alloy::primitives::Address::repeat_byte(0x44), vec![0xa9, 0x05, 0x9c, 0xbb]);
+
+        let request = Request::builder()
+            .uri("/transactions")
+            .method("POST")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(&payload).unwrap()))
+            .unwrap();
+
+        let response = create_router(state).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["code"], "SIMULATION_REVERTED");
+        assert_eq!(error["revert_reason"], "revert: Pausable: paused");
+    }
+
+    #[tokio::test]
+    async fn test_unknown_api_key_rejected() {
+        let state = test_state();
+        state.auth_manager.add_api_key(ApiKeyInfo::from(&crate::config::ApiKeyConfig {
+            key: "partner-key".to_string(),
+            name: "partner".to_string(),
+            permissions: vec![],
+            spending_limits: None,
+        })).await.unwrap();
+        let app = create_router(state);
+
+        let request = Request::builder()
+            .uri("/supported")
+            .header("x-api-key", "not-a-key")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let request = Request::builder()
+            .uri("/supported")
+            .header("authorization", "Bearer partner-key")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_get_api_key_budget_unknown_key() {
+        let state = test_state();
+        let admin_key = admin_key(&state).await;
+        let app = create_router(state);
+
+        let request = Request::builder()
+            .uri("/admin/budgets/api-keys/nobody")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let request = Request::builder()
+            .uri("/admin/budgets/api-keys/nobody")
+            .header("x-api-key", admin_key)
+            .body(Body::empty())
+            .unwrap();
         let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["code"], "API_KEY_NOT_FOUND");
+    }
+
+    #[tokio::test]
+    async fn test_submit_transaction_address_blocked() {
+        use crate::security::IScreenedToken;
+        use alloy::sol_types::SolCall;
+
+        let denied = alloy::primitives::Address::repeat_byte(0xdd);
+        let path = std::env::temp_dir().join(format!("express402-deny-{}.json", Uuid::new_v4()));
+        std::fs::write(&path, format!(r#"["{}"]"#, denied)).unwrap();
+
+        let mut state = test_state();
+        let screener = AddressScreener::new(vec![path.clone()], std::time::Duration::from_secs(30));
+        screener.load().await.unwrap();
+        state.address_screener = Arc::new(screener);
+
+        // The recipient is only visible in the decoded transfer calldata
+        let transfer = IScreenedToken::transferCall { to: denied, amount: alloy::primitives::U256::from(5u64) };
+        let payload = signed_payload(&state, alloy::primitives::Address::repeat_byte(0x44), transfer.abi_encode());
+
+        let request = Request::builder()
+            .uri("/transactions")
+            .method("POST")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(&payload).unwrap()))
+            .unwrap();
+
+        let response = create_router(state).oneshot(request).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["code"], "ADDRESS_BLOCKED");
+        assert!(error["error"].as_str().unwrap().contains(&format!("recipient {}", denied)));
+    }
+
+    #[tokio::test]
+    async fn test_parse_request_signature_forms() {
+        let state = test_state();
+        let mut payload = signed_payload(&state, alloy::primitives::Address::repeat_byte(0x44), vec![]);
+        let (expected, _) = parse_request_signature(&payload).unwrap();
+        let full = expected.to_bytes();
+
+        // Decimal components with a raw recovery id
+        payload.signature_r = expected.r.to_string();
+        payload.signature_s = expected.s.to_string();
+        payload.signature_v = expected.v - 27;
+        assert_eq!(parse_request_signature(&payload).unwrap(), (expected.clone(), None));
+
+        payload.signature = Some(format!("0x{}", hex::encode(&full)));
+        assert_eq!(parse_request_signature(&payload).unwrap(), (expected.clone(), None));
+
+        let mut compact = full[..64].to_vec();
+        compact[32] |= (expected.v - 27) << 7;
+        payload.signature = Some(format!("0x{}", hex::encode(&compact)));
+        assert_eq!(parse_request_signature(&payload).unwrap(), (expected.clone(), None));
+
+        // The malleable high-s twin of a valid signature
+        let n = crate::security::SECP256K1_HALF_ORDER * alloy::primitives::U256::from(2) + alloy::primitives::U256::from(1);
+        let mut high_s = full.to_vec();
+        high_s[32..64].copy_from_slice(&(n - expected.s).to_be_bytes::<32>());
+        high_s[64] = 55 - high_s[64];
+        payload.signature = Some(format!("0x{}", hex::encode(&high_s)));
+        assert!(parse_request_signature(&payload).unwrap_err().contains("too high"));
+
+        // Anything else is left to the contract wallet verifier
+        payload.signature = Some("0xdeadbeef".to_string());
+        let (_, signature_data) = parse_request_signature(&payload).unwrap();
+        assert_eq!(signature_data.unwrap().to_vec(), vec![0xde, 0xad, 0xbe, 0xef]);
+    }
+
+    #[tokio::test]
+    async fn test_submit_transaction_compact_signature() {
+        let denied = alloy::primitives::Address::repeat_byte(0xdd);
+        let path = std::env::temp_dir().join(format!("express402-deny-{}.json", Uuid::new_v4()));
+        std::fs::write(&path, format!(r#"["{}"]"#, denied)).unwrap();
+
+        let mut state = test_state();
+        let screener = AddressScreener::new(vec![path.clone()], std::time::Duration::from_secs(30));
+        screener.load().await.unwrap();
+        state.address_screener = Arc::new(screener);
+
+        let mut payload = signed_payload(&state, denied, vec![0xa9, 0x05, 0x9c, 0xbb]);
+        let (signature, _) = parse_request_signature(&payload).unwrap();
+        let mut compact = signature.to_bytes()[..64].to_vec();
+        compact[32] |= (signature.v - 27) << 7;
+        payload.signature = Some(format!("0x{}", hex::encode(compact)));
+        payload.signature_r = String::new();
+        payload.signature_s = String::new();
+
+        let request = Request::builder()
+            .uri("/transactions")
+            .method("POST")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(&payload).unwrap()))
+            .unwrap();
+
+        // Screening runs after signature verification, so reaching it means the signature passed
+        let response = create_router(state).oneshot(request).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["code"], "ADDRESS_BLOCKED");
+    }
+
+    #[tokio::test]
+    async fn test_submit_transaction_expired() {
+        let state = test_state();
+        let mut payload = signed_payload(&state, alloy::primitives::Address::repeat_byte(0x44), vec![0xa9, 0x05, 0x9c, 0xbb]);
+        payload.valid_before = Some(chrono::Utc::now().timestamp() as u64 - 1);
+
+        let request = Request::builder()
+            .uri("/transactions")
+            .method("POST")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(&payload).unwrap()))
+            .unwrap();
+
+        let response = create_router(state).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["code"], "REQUEST_EXPIRED");
+    }
+
+    #[tokio::test]
+    async fn test_submit_transaction_oversized_gas_rejected() {
+        let state = test_state();
+        let oversized = format!("0x{}", "ff".repeat(32));
+
+        for (field, code) in [("gas_limit", "INVALID_GAS_LIMIT"), ("max_fee_per_gas", "INVALID_GAS_PRICE")] {
+            let mut payload = signed_payload(&state, alloy::primitives::Address::repeat_byte(0x44), vec![0xa9, 0x05, 0x9c, 0xbb]);
+            match field {
+                "gas_limit" => payload.gas_limit = oversized.clone(),
+                _ => payload.max_fee_per_gas = oversized.clone(),
+            }
+
+            let request = Request::builder()
+                .uri("/transactions")
+                .method("POST")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(&payload).unwrap()))
+                .unwrap();
+            let response = create_router(state.clone()).oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(error["code"], code);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_entries_are_signature_checked() {
+        let state = test_state();
+        let mut forged = signed_payload(&state, alloy::primitives::Address::repeat_byte(0x44), vec![0xa9, 0x05, 0x9c, 0xbb]);
+        forged.user_address = alloy::primitives::Address::repeat_byte(0x55).to_string();
+
+        let batch = BatchTransactionRequest { transactions: vec![forged] };
+        let request = Request::builder()
+            .uri("/transactions/batch")
+            .method("POST")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(&batch).unwrap()))
+            .unwrap();
+        let response = create_router(state.clone()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let response: BatchTransactionResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(response.status, "failed");
+        assert!(response.transaction_ids.is_empty());
+        assert!(response.message.contains("Signature verification failed"), "{}", response.message);
+        assert_eq!(state.task_scheduler.get_queue_stats().await.unwrap().pending_tasks, 0);
+    }
+
+    #[tokio::test]
+    async fn test_banned_user_rejected_until_cleared() {
+        let state = test_state();
+        let admin_key = admin_key(&state).await;
+        let payload = signed_payload(&state, alloy::primitives::Address::repeat_byte(0x44), vec![0xa9, 0x05, 0x9c, 0xbb]);
+        let user_address = payload.user_address.parse::<alloy::primitives::Address>().unwrap();
+        state.rate_limiter.restrict(
+            &user_reputation_subject(user_address),
+            crate::api::auth::Restriction::ban(std::time::Duration::from_secs(600), "Revert rate too high".to_string()),
+        ).await;
+
+        let request = Request::builder()
+            .uri("/transactions")
+            .method("POST")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(&payload).unwrap()))
+            .unwrap();
+        let response = create_router(state.clone()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["code"], "TEMPORARILY_BANNED");
+
+        // The banned user cannot lift its own ban
+        let request = Request::builder()
+            .uri(format!("/admin/reputation/users/{}", payload.user_address))
+            .method("DELETE")
+            .body(Body::empty())
+            .unwrap();
+        let response = create_router(state.clone()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let request = Request::builder()
+            .uri(format!("/admin/reputation/users/{}", payload.user_address))
+            .method("DELETE")
+            .header("x-api-key", admin_key)
+            .body(Body::empty())
+            .unwrap();
+        let response = create_router(state.clone()).oneshot(request).await.unwrap();
         assert_eq!(response.status(), StatusCode::OK);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let cleared: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(cleared["cleared"], true);
+
+        let request = Request::builder()
+            .uri(format!("/admin/reputation/users/{}", payload.user_address))
+            .header("x-api-key", admin_key)
+            .body(Body::empty())
+            .unwrap();
+        let response = create_router(state).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let status: ReputationStatus = serde_json::from_slice(&body).unwrap();
+        assert!(status.restriction.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_export_wallet_keystore() {
+        use crate::utils::CryptoUtils;
+
+        let state = test_state();
+        let admin_key = admin_key(&state).await;
+        let private_key = CryptoUtils::generate_private_key();
+        let address = state.wallet_pool.add_wallet(private_key.clone()).await.unwrap();
+
+        let request = Request::builder()
+            .uri(format!("/admin/wallets/{}/export", address))
+            .method("POST")
+            .header("x-api-key", admin_key)
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"password":"export-secret","kdf":"pbkdf2"}"#))
+            .unwrap();
+        let response = create_router(state.clone()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let keystore = Keystore::from_json(std::str::from_utf8(&body).unwrap()).unwrap();
+        assert_eq!(keystore.decrypt("export-secret").unwrap().to_bytes(), private_key.to_bytes());
+
+        let request = Request::builder()
+            .uri(format!("/admin/wallets/{}/export", alloy::primitives::Address::repeat_byte(0x11)))
+            .method("POST")
+            .header("x-api-key", admin_key)
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"password":"export-secret"}"#))
+            .unwrap();
+        let response = create_router(state.clone()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Importing needs a master passphrase to store the key under
+        let import = serde_json::json!({ "keystore": keystore, "password": "export-secret" });
+        let request = Request::builder()
+            .uri("/admin/wallets/import")
+            .method("POST")
+            .header("x-api-key", admin_key)
+            .header("content-type", "application/json")
+            .body(Body::from(import.to_string()))
+            .unwrap();
+        let response = create_router(state).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_admin_routes_require_admin_key() {
+        let state = test_state();
+        state.auth_manager.add_api_key(ApiKeyInfo::from(&crate::config::ApiKeyConfig {
+            key: "partner-key".to_string(),
+            name: "partner".to_string(),
+            permissions: vec!["read".to_string(), "write".to_string()],
+            spending_limits: None,
+        })).await.unwrap();
+        let address = state.wallet_pool.add_wallet(crate::utils::CryptoUtils::generate_private_key()).await.unwrap();
+        let app = create_router(state);
+
+        let export_uri = format!("/admin/wallets/{}/export", address);
+        for uri in ["/admin/wallets/import", export_uri.as_str()] {
+            let request = Request::builder()
+                .uri(uri)
+                .method("POST")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"password":"export-secret"}"#))
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+            let request = Request::builder()
+                .uri(uri)
+                .method("POST")
+                .header("x-api-key", "partner-key")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"password":"export-secret"}"#))
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancel_broadcast_transaction_needs_a_signed_broadcast() {
+        let mut state = test_state();
+        let tracker = Arc::new(TransactionTracker::new(
+            Arc::clone(&state.database_manager),
+            Arc::clone(&state.ethereum_provider),
+            std::time::Duration::from_secs(5),
+            1,
+        ));
+        state.transaction_replacer = Some(Arc::new(TransactionReplacer::new(
+            Arc::clone(&state.wallet_pool),
+            Arc::clone(&state.ethereum_provider),
+            Arc::clone(&state.database_manager),
+            Arc::clone(&tracker),
+            Default::default(),
+            u128::MAX,
+        )));
+        state.transaction_tracker = Some(Arc::clone(&tracker));
+        let admin_key = admin_key(&state).await;
+
+        // Tracked without what was signed, so there is nothing to replace
+        let transaction_id = Uuid::new_v4();
+        tracker.add_transaction(transaction_id, format!("0x{}", "ab".repeat(32))).await.unwrap();
+
+        let request = Request::builder()
+            .uri(format!("/transactions/{}/cancel", transaction_id))
+            .method("POST")
+            .header("x-api-key", admin_key)
+            .body(Body::empty())
+            .unwrap();
+        let response = create_router(state.clone()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "CANNOT_CANCEL");
+
+        let request = Request::builder()
+            .uri(format!("/transactions/{}/cancel", Uuid::new_v4()))
+            .method("POST")
+            .header("x-api-key", admin_key)
+            .body(Body::empty())
+            .unwrap();
+        let response = create_router(state).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_transaction_requires_owner_or_admin() {
+        let request = Request::builder()
+            .uri(format!("/transactions/{}/cancel", Uuid::new_v4()))
+            .method("POST")
+            .body(Body::empty())
+            .unwrap();
+        let response = create_router(test_state()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let key = |name: &str, permissions: Vec<String>| ApiKeyInfo::from(&crate::config::ApiKeyConfig {
+            key: format!("{}-key", name),
+            name: name.to_string(),
+            permissions,
+            spending_limits: None,
+        });
+        let partner = key("partner", vec![]);
+        let admin = key("operator", vec![crate::api::auth::ADMIN_PERMISSION.to_string()]);
+
+        assert!(may_cancel(Some("partner"), &partner));
+        assert!(!may_cancel(Some("other"), &partner));
+        assert!(!may_cancel(None, &partner));
+        assert!(may_cancel(Some("other"), &admin));
+        assert!(may_cancel(None, &admin));
     }
 }
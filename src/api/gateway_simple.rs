@@ -6,12 +6,10 @@ use axum::{
     Router,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::types::{Result, TransactionRequest, TransactionStatus, Priority};
 
 #[derive(Debug, Clone)]
 pub struct ApiState {
@@ -105,7 +103,7 @@ pub fn create_router() -> Router {
         .with_state(state)
 }
 
-async fn health_check() -> impl IntoResponse {
+pub async fn health_check() -> impl IntoResponse {
     (
         StatusCode::OK,
         Json(HealthCheckResponse {
@@ -116,7 +114,7 @@ async fn health_check() -> impl IntoResponse {
     )
 }
 
-async fn get_stats(State(state): State<ApiState>) -> impl IntoResponse {
+pub async fn get_stats(State(state): State<ApiState>) -> impl IntoResponse {
     let total_requests = state.total_requests.load(Ordering::Relaxed);
     let successful_requests = state.successful_requests.load(Ordering::Relaxed);
     let failed_requests = state.failed_requests.load(Ordering::Relaxed);
@@ -149,7 +147,7 @@ async fn get_stats(State(state): State<ApiState>) -> impl IntoResponse {
     )
 }
 
-async fn get_metrics(State(state): State<ApiState>) -> impl IntoResponse {
+pub async fn get_metrics(State(state): State<ApiState>) -> impl IntoResponse {
     // Prometheus format metrics
     let total_requests = state.total_requests.load(Ordering::Relaxed);
     let successful_requests = state.successful_requests.load(Ordering::Relaxed);
@@ -162,31 +160,31 @@ async fn get_metrics(State(state): State<ApiState>) -> impl IntoResponse {
     let metrics = format!(
         "# HELP express402_total_requests Total number of API requests
 # TYPE express402_total_requests counter
-express402_total_requests {{}} {total_requests}
+express402_total_requests {total_requests}
 
 # HELP express402_successful_requests Total number of successful API requests
 # TYPE express402_successful_requests counter
-express402_successful_requests {{}} {successful_requests}
+express402_successful_requests {successful_requests}
 
 # HELP express402_failed_requests Total number of failed API requests
 # TYPE express402_failed_requests counter
-express402_failed_requests {{}} {failed_requests}
+express402_failed_requests {failed_requests}
 
 # HELP express402_total_transactions Total number of transactions processed
 # TYPE express402_total_transactions counter
-express402_total_transactions {{}} {total_transactions}
+express402_total_transactions {total_transactions}
 
 # HELP express402_pending_transactions Current number of pending transactions
 # TYPE express402_pending_transactions gauge
-express402_pending_transactions {{}} {pending_transactions}
+express402_pending_transactions {pending_transactions}
 
 # HELP express402_completed_transactions Total number of completed transactions
 # TYPE express402_completed_transactions counter
-express402_completed_transactions {{}} {completed_transactions}
+express402_completed_transactions {completed_transactions}
 
 # HELP express402_failed_transactions Total number of failed transactions
 # TYPE express402_failed_transactions counter
-express402_failed_transactions {{}} {failed_transactions}
+express402_failed_transactions {failed_transactions}
 "
     );
 
@@ -196,9 +194,9 @@ express402_failed_transactions {{}} {failed_transactions}
     )
 }
 
-async fn submit_transaction(
+pub async fn submit_transaction(
     State(state): State<ApiState>,
-    Json(payload): Json<TransactionSubmitRequest>,
+    Json(_payload): Json<TransactionSubmitRequest>,
 ) -> impl IntoResponse {
     // Increment request counters
     state.total_requests.fetch_add(1, Ordering::Relaxed);
@@ -222,7 +220,7 @@ async fn submit_transaction(
     )
 }
 
-async fn get_transaction_status(
+pub async fn get_transaction_status(
     State(state): State<ApiState>,
     Path(transaction_id): Path<String>,
 ) -> impl IntoResponse {
@@ -243,7 +241,7 @@ async fn get_transaction_status(
     )
 }
 
-async fn cancel_transaction(
+pub async fn cancel_transaction(
     State(state): State<ApiState>,
     Path(transaction_id): Path<String>,
 ) -> impl IntoResponse {
@@ -1,6 +1,7 @@
 use axum::{
-    extract::{Request, State},
-    http::{HeaderMap, StatusCode},
+    body::Body,
+    extract::State,
+    http::{Request, StatusCode},
     middleware::Next,
     response::Response,
 };
@@ -34,6 +35,12 @@ pub struct ErrorCounter {
     pub last_error_time: Option<Instant>,
 }
 
+impl Default for MiddlewareManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl MiddlewareManager {
     pub fn new() -> Self {
         Self {
@@ -117,8 +124,8 @@ impl MiddlewareManager {
 
 pub async fn metrics_middleware(
     State(manager): State<Arc<MiddlewareManager>>,
-    request: Request,
-    next: Next,
+    request: Request<Body>,
+    next: Next<Body>,
 ) -> Result<Response, StatusCode> {
     let start = Instant::now();
     let identifier = extract_identifier(&request);
@@ -137,8 +144,8 @@ pub async fn metrics_middleware(
 }
 
 pub async fn request_id_middleware(
-    mut request: Request,
-    next: Next,
+    mut request: Request<Body>,
+    next: Next<Body>,
 ) -> Result<Response, StatusCode> {
     let request_id = uuid::Uuid::new_v4().to_string();
     request.headers_mut().insert(
@@ -150,8 +157,8 @@ pub async fn request_id_middleware(
 }
 
 pub async fn timeout_middleware(
-    request: Request,
-    next: Next,
+    request: Request<Body>,
+    next: Next<Body>,
 ) -> Result<Response, StatusCode> {
     let timeout_duration = Duration::from_secs(30);
     
@@ -162,8 +169,8 @@ pub async fn timeout_middleware(
 }
 
 pub async fn compression_middleware(
-    request: Request,
-    next: Next,
+    request: Request<Body>,
+    next: Next<Body>,
 ) -> Result<Response, StatusCode> {
     let response = next.run(request).await;
     
@@ -177,8 +184,8 @@ pub async fn compression_middleware(
 }
 
 pub async fn security_headers_middleware(
-    request: Request,
-    next: Next,
+    request: Request<Body>,
+    next: Next<Body>,
 ) -> Result<Response, StatusCode> {
     let response = next.run(request).await;
     
@@ -195,7 +202,7 @@ pub async fn security_headers_middleware(
     Ok(response)
 }
 
-fn extract_identifier(request: &Request) -> String {
+fn extract_identifier(request: &Request<Body>) -> String {
     // Extract identifier from request (IP, user agent, etc.)
     let headers = request.headers();
     
@@ -229,6 +236,7 @@ pub struct CircuitBreaker {
     failure_threshold: u32,
     recovery_timeout: Duration,
     state: Arc<RwLock<CircuitBreakerState>>,
+    failure_count: Arc<RwLock<u32>>,
 }
 
 #[derive(Debug, Clone)]
@@ -244,6 +252,7 @@ impl CircuitBreaker {
             failure_threshold,
             recovery_timeout,
             state: Arc::new(RwLock::new(CircuitBreakerState::Closed)),
+            failure_count: Arc::new(RwLock::new(0)),
         }
     }
 
@@ -255,7 +264,7 @@ impl CircuitBreaker {
         
         match current_state {
             CircuitBreakerState::Open => {
-                return Err(RelayerError::Internal("Circuit breaker is open".to_string()));
+                Err(RelayerError::Internal("Circuit breaker is open".to_string()))
             }
             CircuitBreakerState::HalfOpen => {
                 // Allow one request to test if service is back
@@ -264,6 +273,7 @@ impl CircuitBreaker {
                         // Success, close the circuit
                         let mut state = self.state.write().await;
                         *state = CircuitBreakerState::Closed;
+                        *self.failure_count.write().await = 0;
                         Ok(result)
                     }
                     Err(e) => {
@@ -276,7 +286,10 @@ impl CircuitBreaker {
             }
             CircuitBreakerState::Closed => {
                 match f() {
-                    Ok(result) => Ok(result),
+                    Ok(result) => {
+                        *self.failure_count.write().await = 0;
+                        Ok(result)
+                    }
                     Err(e) => {
                         // Increment failure count and potentially open circuit
                         self.handle_failure().await;
@@ -288,8 +301,16 @@ impl CircuitBreaker {
     }
 
     async fn handle_failure(&self) {
-        // This is a simplified implementation
-        // In a real implementation, you'd track failure counts per time window
+        // Only open the circuit after consecutive failures reach the threshold
+        {
+            let mut failure_count = self.failure_count.write().await;
+            *failure_count += 1;
+            if *failure_count < self.failure_threshold {
+                return;
+            }
+            *failure_count = 0;
+        }
+
         let mut state = self.state.write().await;
         *state = CircuitBreakerState::Open;
         
@@ -332,8 +353,19 @@ mod tests {
         assert!(result.is_ok());
         
         // Test failing call
-        let result = circuit_breaker.call(|| Err(RelayerError::Internal("test error".to_string()))).await;
+        let result: Result<&str, RelayerError> = circuit_breaker
+            .call(|| Err(RelayerError::Internal("test error".to_string())))
+            .await;
         assert!(result.is_err());
+
+        // Circuit stays closed until the failure threshold is reached
+        assert!(circuit_breaker.call(|| Ok("success")).await.is_ok());
+        for _ in 0..3 {
+            let _: Result<&str, RelayerError> = circuit_breaker
+                .call(|| Err(RelayerError::Internal("test error".to_string())))
+                .await;
+        }
+        assert!(circuit_breaker.call(|| Ok("success")).await.is_err());
     }
 
     #[tokio::test]
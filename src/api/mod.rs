@@ -1,6 +1,11 @@
+pub mod auth;
+pub mod middleware;
+pub mod gateway;
 pub mod gateway_simple;
+pub mod paywall;
 
 #[cfg(test)]
 mod tests;
 
-pub use gateway_simple::*;
+pub use gateway::{create_router, ApiState};
+pub use paywall::{MeteredUsage, Paywall, PaywallLayer};
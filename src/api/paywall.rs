@@ -0,0 +1,279 @@
+use axum::{
+    body::Body,
+    http::{HeaderValue, Request, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+use crate::settlement::Facilitator;
+use crate::types::{FacilitatorRequest, PaymentPayload, PaymentRequirements, X402_VERSION};
+
+pub const PAYMENT_HEADER: &str = "x-payment";
+pub const PAYMENT_RESPONSE_HEADER: &str = "x-payment-response";
+
+/// Body of an HTTP 402 response, listing the payments the resource accepts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentRequiredResponse {
+    pub x402_version: u8,
+    pub error: String,
+    pub accepts: Vec<PaymentRequirements>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payer: Option<String>,
+}
+
+/// Usage a handler reports for an "upto" payment, as a response extension.
+/// The paywall settles this amount, capped at what the client signed for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeteredUsage(pub String);
+
+/// Protects the wrapped routes with an x402 price.
+///
+/// Payments are verified and settled in-process with the relayer's own [`Facilitator`],
+/// and settlement only happens once the handler has returned a successful response.
+#[derive(Debug, Clone)]
+pub struct PaywallLayer {
+    facilitator: Arc<Facilitator>,
+    requirements: Arc<PaymentRequirements>,
+}
+
+impl PaywallLayer {
+    pub fn new(facilitator: Arc<Facilitator>, requirements: PaymentRequirements) -> Self {
+        Self {
+            facilitator,
+            requirements: Arc::new(requirements),
+        }
+    }
+}
+
+impl<S> Layer<S> for PaywallLayer {
+    type Service = Paywall<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Paywall {
+            inner,
+            facilitator: Arc::clone(&self.facilitator),
+            requirements: Arc::clone(&self.requirements),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Paywall<S> {
+    inner: S,
+    facilitator: Arc<Facilitator>,
+    requirements: Arc<PaymentRequirements>,
+}
+
+impl<S> Service<Request<Body>> for Paywall<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        // Use the service that was polled ready and leave a fresh clone in its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let facilitator = Arc::clone(&self.facilitator);
+
+        let mut requirements = (*self.requirements).clone();
+        if requirements.resource.is_empty() {
+            requirements.resource = request.uri().to_string();
+        }
+
+        Box::pin(async move {
+            let payment_payload = match request.headers().get(PAYMENT_HEADER) {
+                Some(header) => match decode_payment_header(header) {
+                    Some(payload) => payload,
+                    None => return Ok(payment_required(requirements, "Invalid or malformed payment header", None)),
+                },
+                None => return Ok(payment_required(requirements, "X-PAYMENT header is required", None)),
+            };
+
+            let mut facilitator_request = FacilitatorRequest {
+                x402_version: X402_VERSION,
+                payment_payload,
+                payment_requirements: requirements,
+                amount: None,
+            };
+
+            let verification = facilitator.verify(&facilitator_request).await;
+            if !verification.is_valid {
+                let reason = reason_string(&verification.invalid_reason);
+                return Ok(payment_required(facilitator_request.payment_requirements, &reason, verification.payer));
+            }
+
+            let mut response = inner.call(request).await?;
+
+            // Only charge for responses the client actually gets value from
+            if !response.status().is_success() {
+                return Ok(response);
+            }
+
+            if let Some(MeteredUsage(amount)) = response.extensions_mut().remove::<MeteredUsage>() {
+                facilitator_request.amount = Some(amount);
+            }
+
+            let settlement = facilitator.settle(&facilitator_request).await;
+            if !settlement.success {
+                let reason = reason_string(&settlement.error_reason);
+                return Ok(payment_required(facilitator_request.payment_requirements, &reason, settlement.payer));
+            }
+
+            let encoded = serde_json::to_vec(&settlement)
+                .map(|json| STANDARD.encode(json))
+                .ok()
+                .and_then(|header| HeaderValue::from_str(&header).ok());
+            if let Some(header) = encoded {
+                let headers = response.headers_mut();
+                headers.insert(PAYMENT_RESPONSE_HEADER, header);
+                headers.insert("access-control-expose-headers", HeaderValue::from_static("X-PAYMENT-RESPONSE"));
+            }
+
+            Ok(response)
+        })
+    }
+}
+
+/// Decode a base64 `X-PAYMENT` header into a payment payload
+pub fn decode_payment_header(header: &HeaderValue) -> Option<PaymentPayload> {
+    let decoded = STANDARD.decode(header.as_bytes()).ok()?;
+    serde_json::from_slice(&decoded).ok()
+}
+
+/// Encode a payment payload for the `X-PAYMENT` header
+pub fn encode_payment_header(payload: &PaymentPayload) -> String {
+    STANDARD.encode(serde_json::to_vec(payload).unwrap_or_default())
+}
+
+fn reason_string<T: Serialize>(reason: &Option<T>) -> String {
+    reason.as_ref()
+        .and_then(|reason| serde_json::to_value(reason).ok())
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_else(|| "payment_rejected".to_string())
+}
+
+fn payment_required(requirements: PaymentRequirements, error: &str, payer: Option<String>) -> Response {
+    (
+        StatusCode::PAYMENT_REQUIRED,
+        Json(PaymentRequiredResponse {
+            x402_version: X402_VERSION,
+            error: error.to_string(),
+            accepts: vec![requirements],
+            payer,
+        }),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::database::DatabaseManager;
+    use crate::queue::scheduler::TaskScheduler;
+    use axum::{routing::get, Router};
+    use tower::ServiceExt;
+
+    fn test_facilitator() -> Arc<Facilitator> {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .acquire_timeout(std::time::Duration::from_secs(1))
+            .connect_lazy("postgresql://127.0.0.1:1/express402_test")
+            .unwrap();
+        let provider = alloy::providers::ProviderBuilder::new()
+            .on_http("http://127.0.0.1:1".parse().unwrap());
+
+        Arc::new(Facilitator::new(
+            Arc::new(Config::default()),
+            Arc::new(DatabaseManager::from_pool(pool)),
+            Arc::new(provider),
+            Arc::new(TaskScheduler::new(4, 100, std::time::Duration::from_secs(30))),
+            Arc::new(crate::wallet::pool::WalletPool::new(Default::default())),
+            None,
+        ))
+    }
+
+    fn test_requirements() -> PaymentRequirements {
+        serde_json::from_value(serde_json::json!({
+            "scheme": "exact",
+            "network": "ethereum",
+            "maxAmountRequired": "10000",
+            "description": "Weather report",
+            "payTo": "0x2222222222222222222222222222222222222222",
+            "maxTimeoutSeconds": 60,
+            "asset": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+        }))
+        .unwrap()
+    }
+
+    fn test_app() -> Router {
+        Router::new().route(
+            "/weather",
+            get(|| async { "sunny" }).layer(PaywallLayer::new(test_facilitator(), test_requirements())),
+        )
+    }
+
+    async fn payment_required_body(response: Response) -> PaymentRequiredResponse {
+        assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_missing_payment_header() {
+        let request = Request::builder().uri("/weather").body(Body::empty()).unwrap();
+        let response = test_app().oneshot(request).await.unwrap();
+
+        let body = payment_required_body(response).await;
+        assert_eq!(body.x402_version, X402_VERSION);
+        assert_eq!(body.accepts.len(), 1);
+        assert_eq!(body.accepts[0].resource, "/weather");
+        assert_eq!(body.accepts[0].max_amount_required, "10000");
+    }
+
+    #[tokio::test]
+    async fn test_malformed_payment_header() {
+        let request = Request::builder()
+            .uri("/weather")
+            .header(PAYMENT_HEADER, "not base64!")
+            .body(Body::empty())
+            .unwrap();
+        let response = test_app().oneshot(request).await.unwrap();
+
+        let body = payment_required_body(response).await;
+        assert_eq!(body.error, "Invalid or malformed payment header");
+    }
+
+    #[tokio::test]
+    async fn test_rejected_payment() {
+        let payload = PaymentPayload {
+            x402_version: X402_VERSION,
+            scheme: "exact".to_string(),
+            network: "base".to_string(),
+            payload: serde_json::json!({}),
+        };
+        let request = Request::builder()
+            .uri("/weather")
+            .header(PAYMENT_HEADER, encode_payment_header(&payload))
+            .body(Body::empty())
+            .unwrap();
+        let response = test_app().oneshot(request).await.unwrap();
+
+        let body = payment_required_body(response).await;
+        assert_eq!(body.error, "invalid_network");
+    }
+}
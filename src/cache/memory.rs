@@ -4,7 +4,7 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use tokio::time::{Duration, Instant};
 
-use crate::types::{RelayerError, Result};
+use crate::types::Result;
 use super::redis::{RedisCache, RedisCacheStats};
 
 #[derive(Debug)]
@@ -286,7 +286,7 @@ pub struct MemoryCacheStats {
     pub default_ttl_seconds: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
 pub struct CacheEntryInfo {
     pub key: String,
     pub created_at: Instant,
@@ -308,6 +308,7 @@ impl<T> Clone for MemoryCache<T> {
     }
 }
 
+#[derive(Debug)]
 pub struct CacheManager<T> {
     memory_cache: MemoryCache<T>,
     redis_cache: Option<RedisCache>,
@@ -416,10 +417,9 @@ where
 
     pub async fn get_stats(&self) -> Result<CacheManagerStats> {
         let memory_stats = self.memory_cache.get_stats().await?;
-        let redis_stats = if self.use_redis && self.redis_cache.is_some() {
-            self.redis_cache.as_ref().unwrap().get_stats().await.ok()
-        } else {
-            None
+        let redis_stats = match &self.redis_cache {
+            Some(redis_cache) if self.use_redis => redis_cache.get_stats().await.ok(),
+            _ => None,
         };
 
         Ok(CacheManagerStats {
@@ -453,7 +453,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_memory_cache_creation() {
-        let cache = MemoryCache::new(
+        let cache: MemoryCache<String> = MemoryCache::new(
             100,
             Duration::from_secs(60),
             Duration::from_secs(300),
@@ -466,7 +466,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_memory_cache_set_get() {
-        let cache = MemoryCache::new(
+        let cache: MemoryCache<String> = MemoryCache::new(
             100,
             Duration::from_secs(60),
             Duration::from_secs(300),
@@ -480,7 +480,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_memory_cache_expiration() {
-        let cache = MemoryCache::new(
+        let cache: MemoryCache<String> = MemoryCache::new(
             100,
             Duration::from_secs(60),
             Duration::from_secs(300),
@@ -497,7 +497,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_memory_cache_lru_eviction() {
-        let cache = MemoryCache::new(
+        let cache: MemoryCache<String> = MemoryCache::new(
             2, // Very small cache
             Duration::from_secs(60),
             Duration::from_secs(300),
@@ -1,7 +1,7 @@
 pub mod redis;
 pub mod memory;
-pub mod persistence;
+// Uses compile-time checked queries; needs DATABASE_URL or offline sqlx data
+// pub mod persistence;
 
 pub use redis::*;
 pub use memory::*;
-pub use persistence::*;
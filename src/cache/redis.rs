@@ -3,11 +3,12 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tokio::time::{Duration, Instant};
+use tokio::time::Duration;
 
 use crate::types::{RelayerError, Result};
+#[cfg(test)]
+use super::memory::CacheEntry;
 
-#[derive(Debug)]
 pub struct RedisCache {
     client: Client,
     connection: Arc<RwLock<Option<Connection>>>,
@@ -15,41 +16,6 @@ pub struct RedisCache {
     default_ttl: Duration,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CacheEntry<T> {
-    pub value: T,
-    pub created_at: Instant,
-    pub expires_at: Option<Instant>,
-    pub access_count: u64,
-    pub last_accessed: Instant,
-}
-
-impl<T> CacheEntry<T> {
-    pub fn new(value: T, ttl: Option<Duration>) -> Self {
-        let now = Instant::now();
-        Self {
-            value,
-            created_at: now,
-            expires_at: ttl.map(|ttl| now + ttl),
-            access_count: 0,
-            last_accessed: now,
-        }
-    }
-
-    pub fn is_expired(&self) -> bool {
-        if let Some(expires_at) = self.expires_at {
-            Instant::now() > expires_at
-        } else {
-            false
-        }
-    }
-
-    pub fn access(&mut self) {
-        self.access_count += 1;
-        self.last_accessed = Instant::now();
-    }
-}
-
 impl RedisCache {
     pub fn new(url: &str, key_prefix: String, default_ttl: Duration) -> Result<Self> {
         let client = Client::open(url)?;
@@ -170,15 +136,9 @@ impl RedisCache {
         let result: RedisResult<i64> = redis::cmd("TTL").arg(&full_key).query(conn);
 
         match result {
-            Ok(ttl) => {
-                if ttl == -1 {
-                    Ok(None) // No expiration
-                } else if ttl == -2 {
-                    Ok(None) // Key doesn't exist
-                } else {
-                    Ok(Some(Duration::from_secs(ttl as u64)))
-                }
-            }
+            // -1 means no expiration, -2 means the key doesn't exist
+            Ok(ttl) if ttl < 0 => Ok(None),
+            Ok(ttl) => Ok(Some(Duration::from_secs(ttl as u64))),
             Err(e) => Err(e.into()),
         }
     }
@@ -331,6 +291,15 @@ pub struct RedisCacheStats {
     pub hit_rate: f64,
 }
 
+impl std::fmt::Debug for RedisCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisCache")
+            .field("key_prefix", &self.key_prefix)
+            .field("default_ttl", &self.default_ttl)
+            .finish()
+    }
+}
+
 impl Clone for RedisCache {
     fn clone(&self) -> Self {
         Self {
@@ -345,7 +314,7 @@ impl Clone for RedisCache {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
+    
 
     #[tokio::test]
     async fn test_redis_cache_creation() {
@@ -1,8 +1,9 @@
-use alloy::primitives::Address;
+use alloy::primitives::{Address, U256};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::str::FromStr;
 
-use crate::types::{Result, RelayerError};
+use crate::types::{network_for_chain_id, Result, RelayerError};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerConfig {
@@ -94,7 +95,23 @@ impl Default for EthereumConfig {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WalletConfig {
+    /// Plaintext hex keys; prefer `keystore_paths` or `load_from_database`
     pub private_keys: Vec<String>,
+    /// v3 keystore files, or directories of them, encrypted under the master passphrase
+    #[serde(default)]
+    pub keystore_paths: Vec<String>,
+    /// Load keys from `wallets.encrypted_private_key`, encrypted under the master passphrase
+    #[serde(default)]
+    pub load_from_database: bool,
+    /// File holding the master passphrase; read before `passphrase_env`
+    #[serde(default)]
+    pub passphrase_file: Option<String>,
+    /// Environment variable holding the master passphrase
+    #[serde(default = "default_passphrase_env")]
+    pub passphrase_env: String,
+    /// Web3Signer-compatible signers holding wallet keys outside the relayer
+    #[serde(default)]
+    pub remote_signers: Vec<RemoteSignerConfig>,
     pub min_balance: u64, // wei
     pub max_concurrent_transactions: u32,
     pub transaction_timeout: u64, // seconds
@@ -102,10 +119,19 @@ pub struct WalletConfig {
     pub retry_delay: u64, // seconds
 }
 
+fn default_passphrase_env() -> String {
+    "EXPRESS402_WALLET_PASSPHRASE".to_string()
+}
+
 impl Default for WalletConfig {
     fn default() -> Self {
         Self {
             private_keys: vec![],
+            keystore_paths: vec![],
+            load_from_database: false,
+            passphrase_file: None,
+            passphrase_env: default_passphrase_env(),
+            remote_signers: vec![],
             min_balance: 1000000000000000000, // 1 ETH
             max_concurrent_transactions: 5,
             transaction_timeout: 60,
@@ -115,6 +141,26 @@ impl Default for WalletConfig {
     }
 }
 
+/// A remote signer speaking the Web3Signer eth1 JSON-RPC API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteSignerConfig {
+    pub url: String,
+    /// Accounts to add to the pool; every account the signer manages when empty
+    pub addresses: Vec<Address>,
+    pub timeout: u64, // seconds
+}
+
+impl Default for RemoteSignerConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://localhost:9000".to_string(),
+            addresses: vec![],
+            timeout: 10,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SecurityConfig {
     pub signature_timeout: u64, // seconds
@@ -122,6 +168,45 @@ pub struct SecurityConfig {
     pub max_pending_transactions: u32,
     pub enable_replay_protection: bool,
     pub trusted_contracts: Vec<Address>,
+    /// ERC-6492 universal validator used for signatures from not-yet-deployed smart accounts
+    #[serde(default)]
+    pub universal_signature_validator: Option<Address>,
+    #[serde(default = "default_signature_cache_ttl")]
+    pub signature_cache_ttl: u64, // seconds
+    #[serde(default)]
+    pub nonce_mode: NonceMode,
+    /// Dry-run every relayed call with `eth_call` / `eth_estimateGas` and refuse ones that revert
+    #[serde(default = "default_simulate_transactions")]
+    pub simulate_transactions: bool,
+    /// JSON or CSV files of addresses the relayer refuses to transact with
+    #[serde(default)]
+    pub deny_lists: Vec<String>,
+    #[serde(default = "default_deny_list_reload_interval")]
+    pub deny_list_reload_interval: u64, // seconds
+}
+
+fn default_deny_list_reload_interval() -> u64 {
+    30
+}
+
+fn default_simulate_transactions() -> bool {
+    true
+}
+
+/// How relayer nonces signed by users are consumed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NonceMode {
+    /// Each nonce must be exactly one more than the previous one
+    #[default]
+    Sequential,
+    /// Any unused nonce is accepted, tracked in Permit2-style bitmaps, so a user can
+    /// have several requests in flight at once
+    Unordered,
+}
+
+fn default_signature_cache_ttl() -> u64 {
+    30
 }
 
 impl Default for SecurityConfig {
@@ -132,6 +217,12 @@ impl Default for SecurityConfig {
             max_pending_transactions: 1000,
             enable_replay_protection: true,
             trusted_contracts: vec![],
+            universal_signature_validator: None,
+            signature_cache_ttl: default_signature_cache_ttl(),
+            nonce_mode: NonceMode::default(),
+            simulate_transactions: default_simulate_transactions(),
+            deny_lists: vec![],
+            deny_list_reload_interval: default_deny_list_reload_interval(),
         }
     }
 }
@@ -163,6 +254,256 @@ impl Default for QueueConfig {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct X402Config {
+    pub network: Option<String>, // x402 network name, derived from ethereum.chain_id when unset
+    pub schemes: Vec<String>,
+    pub settlement_gas_limit: u64,
+    pub settlement_timeout: u64, // seconds
+}
+
+impl Default for X402Config {
+    fn default() -> Self {
+        Self {
+            network: None,
+            schemes: vec!["exact".to_string()],
+            settlement_gas_limit: 150000,
+            settlement_timeout: 30,
+        }
+    }
+}
+
+/// Allowlist of contracts and functions the relayer will pay gas for. Enforced as soon as
+/// any rule or `security.trusted_contracts` entry is configured.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PolicyConfig {
+    #[serde(default)]
+    pub rules: Vec<PolicyRuleConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyRuleConfig {
+    pub name: String,
+    pub contract: Address,
+    /// 4-byte selectors (`0xa9059cbb`) or function signatures (`transfer(address,uint256)`);
+    /// empty allows any call, including plain transfers
+    #[serde(default)]
+    pub selectors: Vec<String>,
+    #[serde(default)]
+    pub max_value: Option<String>, // wei
+    #[serde(default)]
+    pub max_gas_limit: Option<u64>,
+    #[serde(default)]
+    pub args: Vec<ArgumentConstraintConfig>,
+}
+
+/// Constraint on a static ABI argument, addressed by its position after the selector
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArgumentConstraintConfig {
+    pub index: usize,
+    /// Address or integer the argument must equal
+    #[serde(default)]
+    pub eq: Option<String>,
+    #[serde(default)]
+    pub min: Option<String>,
+    #[serde(default)]
+    pub max: Option<String>,
+}
+
+/// Rolling spending caps for relayed transactions, keyed by user address and by API key
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BudgetConfig {
+    /// Applied to every `user_address`
+    #[serde(default)]
+    pub user: SpendingLimits,
+    /// Applied to API keys that do not set their own `spending_limits`
+    #[serde(default)]
+    pub api_key: SpendingLimits,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpendingLimits {
+    #[serde(default)]
+    pub hourly: BudgetLimit,
+    #[serde(default)]
+    pub daily: BudgetLimit,
+    /// Rolling 30 days
+    #[serde(default)]
+    pub monthly: BudgetLimit,
+}
+
+/// Caps for one rolling window; unset caps are unlimited
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BudgetLimit {
+    #[serde(default)]
+    pub max_value: Option<String>, // wei
+    #[serde(default)]
+    pub max_gas_cost: Option<String>, // wei
+    #[serde(default)]
+    pub max_transactions: Option<u64>,
+}
+
+/// API key accepted in `X-API-Key` or `Authorization: Bearer`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyConfig {
+    pub key: String,
+    pub name: String,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    #[serde(default)]
+    pub spending_limits: Option<SpendingLimits>,
+}
+
+/// Thresholds for throttling and temporarily banning users and API keys whose traffic
+/// costs gas without result. Rates are only judged once a subject has `min_transactions`
+/// in the window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReputationConfig {
+    pub enabled: bool,
+    pub window: u64, // seconds
+    pub min_transactions: u32,
+    pub max_revert_rate: f64,
+    pub max_simulation_failure_rate: f64,
+    pub max_cancellation_rate: f64,
+    /// Most requests in any 60 seconds
+    pub max_burst: u32,
+    /// Most distinct target contracts in the window
+    pub max_distinct_targets: u32,
+    pub throttle_requests_per_minute: u32,
+    pub throttle_duration: u64, // seconds, doubled for every further strike
+    /// Strikes, counting this one, at which a throttle becomes a ban
+    pub ban_after_strikes: u32,
+    pub ban_duration: u64, // seconds, doubled for every further strike
+    /// Strikes are forgotten after this long without a new one
+    pub strike_decay: u64, // seconds
+}
+
+impl Default for ReputationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window: 3600,
+            min_transactions: 10,
+            max_revert_rate: 0.5,
+            max_simulation_failure_rate: 0.5,
+            max_cancellation_rate: 0.5,
+            max_burst: 30,
+            max_distinct_targets: 25,
+            throttle_requests_per_minute: 5,
+            throttle_duration: 300,
+            ban_after_strikes: 3,
+            ban_duration: 3600,
+            strike_decay: 86400,
+        }
+    }
+}
+
+/// Replacement of relayed transactions that stay pending too long, by re-signing the same
+/// wallet nonce with higher fees
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpeedUpConfig {
+    pub enabled: bool,
+    pub check_interval: u64, // seconds
+    /// Seconds a transaction of each priority may stay pending before it is replaced
+    pub low_after: u64,
+    pub normal_after: u64,
+    pub high_after: u64,
+    pub critical_after: u64,
+    /// Percent both fees rise by per replacement; nodes require at least 10
+    pub fee_bump_percent: u32,
+    /// Highest max fee per gas a replacement may offer, in wei; `ethereum.max_gas_price` when unset
+    pub max_fee_per_gas: Option<u64>,
+    pub max_replacements: u32,
+}
+
+impl Default for SpeedUpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            check_interval: 15,
+            low_after: 600,
+            normal_after: 180,
+            high_after: 90,
+            critical_after: 45,
+            fee_bump_percent: 12,
+            max_fee_per_gas: None,
+            max_replacements: 5,
+        }
+    }
+}
+
+/// Automatic top-up of pool wallets that fall below `wallets.min_balance` from a treasury wallet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TreasuryConfig {
+    pub enabled: bool,
+    /// Plaintext hex key of the treasury wallet; prefer `keystore_path`
+    pub private_key: Option<String>,
+    /// v3 keystore of the treasury wallet, encrypted under the wallet master passphrase
+    pub keystore_path: Option<String>,
+    /// Balance a low wallet is topped up to, in wei
+    pub target_balance: String,
+    /// Most the treasury may send in any 24 hours, in wei
+    pub daily_cap: String,
+    pub check_interval: u64, // seconds
+    /// Seconds to wait for a sent top-up to land before sending another to the same wallet
+    pub retry_after: u64,
+}
+
+impl Default for TreasuryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            private_key: None,
+            keystore_path: None,
+            target_balance: "2000000000000000000".to_string(), // 2 ETH
+            daily_cap: "10000000000000000000".to_string(), // 10 ETH
+            check_interval: 60,
+            retry_after: 600,
+        }
+    }
+}
+
+/// Pool wallets derived from a BIP-39 mnemonic, scaled within the wallet pool's size bounds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HdWalletConfig {
+    pub enabled: bool,
+    /// Plaintext mnemonic; prefer `mnemonic_file` or `mnemonic_env`
+    pub mnemonic: Option<String>,
+    /// File holding the mnemonic; read before `mnemonic` and `mnemonic_env`
+    pub mnemonic_file: Option<String>,
+    /// Environment variable holding the mnemonic
+    pub mnemonic_env: String,
+    /// Optional BIP-39 passphrase
+    pub password: Option<String>,
+    /// BIP-44 path wallets are derived under; the wallet index is appended
+    pub derivation_path: String,
+    /// Pending tasks per wallet in rotation above which another wallet is derived
+    pub scale_up_queue_depth: usize,
+    /// Seconds a derived wallet must go unused before it may be retired
+    pub idle_timeout: u64,
+    pub check_interval: u64, // seconds
+}
+
+impl Default for HdWalletConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mnemonic: None,
+            mnemonic_file: None,
+            mnemonic_env: "EXPRESS402_WALLET_MNEMONIC".to_string(),
+            password: None,
+            derivation_path: "m/44'/60'/0'/0".to_string(),
+            scale_up_queue_depth: 5,
+            idle_timeout: 600, // 10 minutes
+            check_interval: 30,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub server: ServerConfig,
@@ -172,6 +513,22 @@ pub struct Config {
     pub wallets: WalletConfig,
     pub security: SecurityConfig,
     pub queue: QueueConfig,
+    #[serde(default)]
+    pub x402: X402Config,
+    #[serde(default)]
+    pub policy: PolicyConfig,
+    #[serde(default)]
+    pub budgets: BudgetConfig,
+    #[serde(default)]
+    pub api_keys: Vec<ApiKeyConfig>,
+    #[serde(default)]
+    pub reputation: ReputationConfig,
+    #[serde(default)]
+    pub speed_up: SpeedUpConfig,
+    #[serde(default)]
+    pub treasury: TreasuryConfig,
+    #[serde(default)]
+    pub hd_wallets: HdWalletConfig,
     pub log_level: String,
     pub environment: String,
 }
@@ -186,6 +543,14 @@ impl Default for Config {
             wallets: WalletConfig::default(),
             security: SecurityConfig::default(),
             queue: QueueConfig::default(),
+            x402: X402Config::default(),
+            policy: PolicyConfig::default(),
+            budgets: BudgetConfig::default(),
+            api_keys: Vec::new(),
+            reputation: ReputationConfig::default(),
+            speed_up: SpeedUpConfig::default(),
+            treasury: TreasuryConfig::default(),
+            hd_wallets: HdWalletConfig::default(),
             log_level: "info".to_string(),
             environment: "development".to_string(),
         }
@@ -200,7 +565,7 @@ pub struct ValidationError {
 
 impl Config {
     pub fn from_env() -> Result<Self> {
-        let mut settings = config::Config::builder()
+        let settings = config::Config::builder()
             .add_source(config::Environment::with_prefix("EXPRESS402"))
             .build()?;
 
@@ -208,7 +573,7 @@ impl Config {
     }
 
     pub fn from_file(path: &str) -> Result<Self> {
-        let mut settings = config::Config::builder()
+        let settings = config::Config::builder()
             .add_source(config::File::with_name(path))
             .add_source(config::Environment::with_prefix("EXPRESS402"))
             .build()?;
@@ -224,6 +589,12 @@ impl Config {
         self.environment == "development"
     }
 
+    /// x402 network name payments are settled on
+    pub fn x402_network(&self) -> Option<String> {
+        self.x402.network.clone()
+            .or_else(|| network_for_chain_id(self.ethereum.chain_id).map(str::to_string))
+    }
+
     /// Validate configuration and return any errors found
     pub fn validate(&self) -> Vec<ValidationError> {
         let mut errors = Vec::new();
@@ -302,13 +673,27 @@ impl Config {
         }
 
         // Validate wallet config
-        if self.wallets.private_keys.is_empty() {
+        if self.wallets.private_keys.is_empty()
+            && self.wallets.keystore_paths.is_empty()
+            && !self.wallets.load_from_database
+            && self.wallets.remote_signers.is_empty()
+            && !self.hd_wallets.enabled
+        {
             errors.push(ValidationError {
                 field: "wallets.private_keys".to_string(),
-                message: "At least one wallet private key is required".to_string(),
+                message: "At least one wallet private key, keystore path, load_from_database, remote signer or HD wallets is required".to_string(),
             });
         }
 
+        for (i, signer) in self.wallets.remote_signers.iter().enumerate() {
+            if !signer.url.starts_with("http://") && !signer.url.starts_with("https://") {
+                errors.push(ValidationError {
+                    field: format!("wallets.remote_signers[{}].url", i),
+                    message: "Remote signer URL must be http or https".to_string(),
+                });
+            }
+        }
+
         if self.wallets.max_concurrent_transactions == 0 {
             errors.push(ValidationError {
                 field: "wallets.max_concurrent_transactions".to_string(),
@@ -353,6 +738,121 @@ impl Config {
             });
         }
 
+        // Validate reputation config
+        for (field, rate) in [
+            ("reputation.max_revert_rate", self.reputation.max_revert_rate),
+            ("reputation.max_simulation_failure_rate", self.reputation.max_simulation_failure_rate),
+            ("reputation.max_cancellation_rate", self.reputation.max_cancellation_rate),
+        ] {
+            if !(0.0..=1.0).contains(&rate) {
+                errors.push(ValidationError {
+                    field: field.to_string(),
+                    message: "Rate must be between 0 and 1".to_string(),
+                });
+            }
+        }
+
+        if self.reputation.ban_after_strikes == 0 {
+            errors.push(ValidationError {
+                field: "reputation.ban_after_strikes".to_string(),
+                message: "Ban threshold must be greater than 0".to_string(),
+            });
+        }
+
+        // Validate speed-up config
+        if self.speed_up.fee_bump_percent < 10 {
+            errors.push(ValidationError {
+                field: "speed_up.fee_bump_percent".to_string(),
+                message: "Fee bump must be at least 10 percent for nodes to accept a replacement".to_string(),
+            });
+        }
+
+        if self.speed_up.enabled && self.speed_up.check_interval == 0 {
+            errors.push(ValidationError {
+                field: "speed_up.check_interval".to_string(),
+                message: "Check interval must be greater than 0".to_string(),
+            });
+        }
+
+        // Validate treasury config
+        if self.treasury.enabled {
+            if self.treasury.private_key.is_none() && self.treasury.keystore_path.is_none() {
+                errors.push(ValidationError {
+                    field: "treasury.private_key".to_string(),
+                    message: "Treasury needs a private_key or keystore_path".to_string(),
+                });
+            }
+
+            match U256::from_str(&self.treasury.target_balance) {
+                Ok(target) if target <= U256::from(self.wallets.min_balance) => {
+                    errors.push(ValidationError {
+                        field: "treasury.target_balance".to_string(),
+                        message: "Target balance must be above wallets.min_balance".to_string(),
+                    });
+                }
+                Ok(_) => {}
+                Err(_) => {
+                    errors.push(ValidationError {
+                        field: "treasury.target_balance".to_string(),
+                        message: "Target balance must be a wei amount".to_string(),
+                    });
+                }
+            }
+
+            if U256::from_str(&self.treasury.daily_cap).is_err() {
+                errors.push(ValidationError {
+                    field: "treasury.daily_cap".to_string(),
+                    message: "Daily cap must be a wei amount".to_string(),
+                });
+            }
+
+            if self.treasury.check_interval == 0 {
+                errors.push(ValidationError {
+                    field: "treasury.check_interval".to_string(),
+                    message: "Check interval must be greater than 0".to_string(),
+                });
+            }
+        }
+
+        // Validate HD wallet config
+        if self.hd_wallets.enabled {
+            if !self.hd_wallets.derivation_path.starts_with("m/") {
+                errors.push(ValidationError {
+                    field: "hd_wallets.derivation_path".to_string(),
+                    message: "Derivation path must start with m/".to_string(),
+                });
+            }
+
+            if self.hd_wallets.scale_up_queue_depth == 0 {
+                errors.push(ValidationError {
+                    field: "hd_wallets.scale_up_queue_depth".to_string(),
+                    message: "Scale-up queue depth must be greater than 0".to_string(),
+                });
+            }
+
+            if self.hd_wallets.check_interval == 0 {
+                errors.push(ValidationError {
+                    field: "hd_wallets.check_interval".to_string(),
+                    message: "Check interval must be greater than 0".to_string(),
+                });
+            }
+        }
+
+        // Validate x402 config
+        if self.x402_network().is_none() {
+            errors.push(ValidationError {
+                field: "x402.network".to_string(),
+                message: format!("No x402 network name known for chain ID {}", self.ethereum.chain_id),
+            });
+        }
+
+        if self.x402.settlement_gas_limit == 0 {
+            errors.push(ValidationError {
+                field: "x402.settlement_gas_limit".to_string(),
+                message: "Settlement gas limit must be greater than 0".to_string(),
+            });
+        }
+
         // Validate log level
         let valid_log_levels = ["trace", "debug", "info", "warn", "error"];
         if !valid_log_levels.contains(&self.log_level.as_str()) {
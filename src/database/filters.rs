@@ -2,7 +2,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 /// Transaction search filters
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TransactionFilters {
     pub status: Option<String>,
     pub priority: Option<String>,
@@ -12,18 +12,6 @@ pub struct TransactionFilters {
     pub end_time: Option<DateTime<Utc>>,
 }
 
-impl Default for TransactionFilters {
-    fn default() -> Self {
-        Self {
-            status: None,
-            priority: None,
-            user_address: None,
-            target_contract: None,
-            start_time: None,
-            end_time: None,
-        }
-    }
-}
 
 impl TransactionFilters {
     pub fn new() -> Self {
@@ -1,16 +1,18 @@
-use sqlx::{PgPool, postgres::PgPoolOptions};
+use sqlx::{Executor, PgPool, Postgres, postgres::{PgArguments, PgPoolOptions}, query::Query};
 use std::time::Duration;
 use tokio::fs;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
-use crate::types::{RelayerError, Result, TransactionRequest, TransactionStatus};
+use crate::types::{RelayerError, Result, SimulationResult, TransactionRequest, TransactionStatus};
 use crate::config::Config;
+use crate::queue::{BroadcastKind, SentTransaction};
 
 mod filters;
 pub use filters::TransactionFilters;
 
+#[derive(Debug)]
 pub struct DatabaseManager {
     pool: PgPool,
 }
@@ -28,13 +30,46 @@ pub struct TransactionRecord {
     pub nonce: String,
     pub signature_r: String,
     pub signature_s: String,
-    pub signature_v: u8,
+    pub signature_v: i16,
     pub priority: String,
     pub status: String,
     pub tx_hash: Option<String>,
     pub block_number: Option<i64>,
     pub gas_used: Option<String>,
     pub error_message: Option<String>,
+    pub authorized_amount: Option<String>,
+    pub settled_amount: Option<String>,
+    pub simulation_from: Option<String>,
+    pub simulation_success: Option<bool>,
+    pub simulation_gas_estimate: Option<i64>,
+    pub simulation_revert_reason: Option<String>,
+    pub simulated_at: Option<DateTime<Utc>>,
+    pub valid_after: Option<DateTime<Utc>>,
+    pub valid_before: Option<DateTime<Utc>>,
+    pub api_key_name: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One signed broadcast of a transaction; replacements share the transaction's id
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TransactionBroadcast {
+    pub tx_hash: String,
+    pub kind: String,
+    pub wallet_address: String,
+    pub nonce: i64,
+    pub max_fee_per_gas: String,
+    pub max_priority_fee_per_gas: String,
+    pub mined: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A wallet derived from the HD mnemonic at `derivation_index`
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct HdWalletIndex {
+    pub derivation_index: i32,
+    pub address: String,
+    pub retired: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -52,21 +87,70 @@ impl DatabaseManager {
         Ok(Self { pool })
     }
 
+    pub fn from_pool(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
     pub async fn run_migrations(&self) -> Result<()> {
         tracing::info!("Running database migrations...");
-        
-        // Read migration files
+
         let migration_files = vec![
-            "migrations/001_initial_schema.sql",
+            "001_initial_schema.sql",
+            "002_settlement_amounts.sql",
+            "003_user_nonces.sql",
+            "004_simulation_results.sql",
+            "005_spending_ledger.sql",
+            "006_validity_windows.sql",
+            "007_transaction_broadcasts.sql",
+            "008_cancel_broadcasts.sql",
+            "009_treasury_top_ups.sql",
+            "010_hd_wallet_indexes.sql",
+            "011_transaction_api_keys.sql",
+            "012_settlement_claims.sql",
         ];
 
+        // Applied migrations are recorded so later boots skip them
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS schema_migrations (
+                name VARCHAR(255) PRIMARY KEY,
+                applied_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
         for migration_file in migration_files {
-            let migration_sql = fs::read_to_string(migration_file)
+            let mut tx = self.pool.begin().await?;
+
+            // Instances booting together apply each migration once
+            sqlx::query("SELECT pg_advisory_xact_lock(hashtext('schema_migrations'))")
+                .execute(&mut tx)
+                .await?;
+
+            let applied: Option<(String,)> = sqlx::query_as(
+                "SELECT name FROM schema_migrations WHERE name = $1",
+            )
+            .bind(migration_file)
+            .fetch_optional(&mut tx)
+            .await?;
+            if applied.is_some() {
+                tracing::debug!("Migration already applied: {}", migration_file);
+                continue;
+            }
+
+            let migration_sql = fs::read_to_string(format!("migrations/{}", migration_file))
                 .await?;
 
-            sqlx::query(&migration_sql)
-                .execute(&self.pool)
+            // Plain `&str` goes over the simple query protocol, which allows several statements
+            tx.execute(migration_sql.as_str()).await?;
+
+            sqlx::query("INSERT INTO schema_migrations (name) VALUES ($1)")
+                .bind(migration_file)
+                .execute(&mut tx)
                 .await?;
+            tx.commit().await?;
 
             tracing::info!("Applied migration: {}", migration_file);
         }
@@ -102,7 +186,7 @@ impl DatabaseManager {
             .await
             ?;
 
-        let connection_count_u32 = connection_count as u32;
+        let connection_count_u32 = connection_count;
         let idle_connections_u32 = idle_connections as u32;
         Ok(DatabaseStats {
             connection_count: connection_count_u32,
@@ -115,37 +199,9 @@ impl DatabaseManager {
 
     // Transaction operations
     pub async fn create_transaction(&self, request: &TransactionRequest) -> Result<Uuid> {
-        let result = sqlx::query!(
-            r#"
-            INSERT INTO transactions (
-                id, user_address, target_contract, calldata, value, gas_limit,
-                max_fee_per_gas, max_priority_fee_per_gas, nonce,
-                signature_r, signature_s, signature_v, priority, status,
-                created_at, updated_at
-            ) VALUES (
-                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16
-            )
-            "#,
-            request.id,
-            request.user_address.to_string(),
-            request.target_contract.to_string(),
-            request.calldata.as_ref(),
-            request.value.to_string(),
-            request.gas_limit.to_string(),
-            request.max_fee_per_gas.to_string(),
-            request.max_priority_fee_per_gas.to_string(),
-            request.nonce.to_string(),
-            request.signature.r.to_string(),
-            request.signature.s.to_string(),
-            request.signature.v,
-            request.priority.to_string(),
-            "pending",
-            request.timestamp,
-            request.timestamp
-        )
-        .execute(&self.pool)
-        .await
-        ?;
+        let result = insert_transaction(request)
+            .execute(&self.pool)
+            .await?;
 
         if result.rows_affected() == 0 {
             return Err(RelayerError::Database("No rows affected".to_string()));
@@ -154,17 +210,53 @@ impl DatabaseManager {
         Ok(request.id)
     }
 
+    /// Create an x402 settlement transaction and claim the authorization it spends, keyed by
+    /// its target (the token or Permit2 contract), payer and nonce. Returns false, creating
+    /// nothing, while another transaction holding the claim is not failed, cancelled or expired.
+    pub async fn create_settlement_transaction(&self, request: &TransactionRequest) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        insert_transaction(request)
+            .execute(&mut tx)
+            .await?;
+
+        let claimed = sqlx::query(
+            r#"
+            INSERT INTO settlement_claims (nonce_contract, payer, nonce, transaction_id)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (nonce_contract, payer, nonce) DO UPDATE
+            SET transaction_id = $4, created_at = NOW()
+            WHERE NOT EXISTS (
+                SELECT 1 FROM transactions
+                WHERE id = settlement_claims.transaction_id
+                AND status NOT IN ('failed', 'cancelled', 'expired')
+            )
+            "#,
+        )
+        .bind(request.target_contract.to_string())
+        .bind(request.user_address.to_string())
+        .bind(request.nonce.to_string())
+        .bind(request.id)
+        .execute(&mut tx)
+        .await?;
+
+        if claimed.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
     pub async fn get_transaction(&self, transaction_id: Uuid) -> Result<Option<TransactionRecord>> {
-        let record = sqlx::query_as!(
-            TransactionRecord,
+        let record = sqlx::query_as::<_, TransactionRecord>(
             r#"
             SELECT * FROM transactions WHERE id = $1
             "#,
-            transaction_id
         )
+        .bind(transaction_id)
         .fetch_optional(&self.pool)
-        .await
-        ?;
+        .await?;
 
         Ok(record)
     }
@@ -178,28 +270,130 @@ impl DatabaseManager {
         gas_used: Option<String>,
         error_message: Option<String>,
     ) -> Result<()> {
-        sqlx::query!(
+        sqlx::query(
             r#"
             UPDATE transactions 
             SET status = $2, tx_hash = $3, block_number = $4, gas_used = $5, 
                 error_message = $6, updated_at = $7
             WHERE id = $1
             "#,
-            transaction_id,
-            status.to_string(),
-            tx_hash,
-            block_number.map(|n| n as i64),
-            gas_used,
-            error_message,
-            Utc::now()
         )
+        .bind(transaction_id)
+        .bind(status.to_string())
+        .bind(tx_hash)
+        .bind(block_number.map(|n| n as i64))
+        .bind(gas_used)
+        .bind(error_message)
+        .bind(Utc::now())
         .execute(&self.pool)
-        .await
-        ?;
+        .await?;
 
         Ok(())
     }
 
+    /// Record the signed maximum and the charged amount of a metered payment
+    pub async fn record_settlement_amounts(
+        &self,
+        transaction_id: Uuid,
+        authorized_amount: String,
+        settled_amount: String,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE transactions
+            SET authorized_amount = $2, settled_amount = $3, updated_at = $4
+            WHERE id = $1
+            "#,
+        )
+        .bind(transaction_id)
+        .bind(authorized_amount)
+        .bind(settled_amount)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record the pre-flight simulation that admitted a transaction
+    pub async fn record_simulation(&self, transaction_id: Uuid, simulation: &SimulationResult) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE transactions
+            SET simulation_from = $2, simulation_success = $3, simulation_gas_estimate = $4,
+                simulation_revert_reason = $5, simulated_at = $6, updated_at = $7
+            WHERE id = $1
+            "#,
+        )
+        .bind(transaction_id)
+        .bind(simulation.from.to_string())
+        .bind(simulation.success)
+        .bind(simulation.gas_estimate.map(|gas| gas as i64))
+        .bind(simulation.revert_reason.clone())
+        .bind(simulation.simulated_at)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Store a request refused before queuing as a failed transaction, with an audit log entry
+    pub async fn record_rejected_transaction(
+        &self,
+        request: &TransactionRequest,
+        error_message: &str,
+        event_type: &str,
+        event_data: serde_json::Value,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO transactions (
+                id, user_address, target_contract, calldata, value, gas_limit,
+                max_fee_per_gas, max_priority_fee_per_gas, nonce,
+                signature_r, signature_s, signature_v, priority, status,
+                error_message, created_at, updated_at, valid_after, valid_before
+            ) VALUES (
+                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, 'failed', $14, $15, $16, $17, $18
+            )
+            "#,
+        )
+        .bind(request.id)
+        .bind(request.user_address.to_string())
+        .bind(request.target_contract.to_string())
+        .bind(request.calldata.to_vec())
+        .bind(request.value.to_string())
+        .bind(request.gas_limit.to_string())
+        .bind(request.max_fee_per_gas.to_string())
+        .bind(request.max_priority_fee_per_gas.to_string())
+        .bind(request.nonce.to_string())
+        .bind(request.signature.r.to_string())
+        .bind(request.signature.s.to_string())
+        .bind(request.signature.v as i16)
+        .bind(request.priority.to_string())
+        .bind(error_message)
+        .bind(request.timestamp)
+        .bind(Utc::now())
+        .bind(request.valid_after)
+        .bind(request.valid_before)
+        .execute(&mut tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO transaction_logs (transaction_id, event_type, event_data) VALUES ($1, $2, $3::JSONB)",
+        )
+        .bind(request.id)
+        .bind(event_type)
+        .bind(event_data.to_string())
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
     pub async fn get_user_transactions(
         &self,
         user_address: &str,
@@ -208,35 +402,30 @@ impl DatabaseManager {
     ) -> Result<(Vec<TransactionRecord>, u64)> {
         let offset = (page - 1) * limit;
 
-        let records = sqlx::query_as!(
-            TransactionRecord,
+        let records = sqlx::query_as::<_, TransactionRecord>(
             r#"
             SELECT * FROM transactions 
             WHERE user_address = $1 
             ORDER BY created_at DESC 
             LIMIT $2 OFFSET $3
             "#,
-            user_address,
-            limit as i64,
-            offset as i64
         )
+        .bind(user_address)
+        .bind(limit as i64)
+        .bind(offset as i64)
         .fetch_all(&self.pool)
-        .await
-        ?;
+        .await?;
 
-        let total = sqlx::query!(
+        let total: (i64,) = sqlx::query_as(
             r#"
-            SELECT COUNT(*) as count FROM transactions WHERE user_address = $1
+            SELECT COUNT(*) FROM transactions WHERE user_address = $1
             "#,
-            user_address
         )
+        .bind(user_address)
         .fetch_one(&self.pool)
-        .await
-        ?
-        .count
-        .unwrap_or(0) as u64;
+        .await?;
 
-        Ok((records, total))
+        Ok((records, total.0 as u64))
     }
 
     /// Search transactions with filters
@@ -249,143 +438,538 @@ impl DatabaseManager {
         let offset = (page - 1) * limit;
 
         // If only user_address filter, use optimized existing method
-        if filters.status.is_none() 
-            && filters.priority.is_none() 
-            && filters.target_contract.is_none()
-            && filters.start_time.is_none()
-            && filters.end_time.is_none()
-            && filters.user_address.is_some() {
-            return self.get_user_transactions(
-                filters.user_address.as_ref().unwrap(),
-                page,
-                limit
-            ).await;
+        if let Some(user_address) = &filters.user_address {
+            if filters.status.is_none()
+                && filters.priority.is_none()
+                && filters.target_contract.is_none()
+                && filters.start_time.is_none()
+                && filters.end_time.is_none() {
+                return self.get_user_transactions(user_address, page, limit).await;
+            }
+        }
+
+        let mut records_query = sqlx::QueryBuilder::<Postgres>::new("SELECT * FROM transactions WHERE TRUE");
+        let mut count_query = sqlx::QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM transactions WHERE TRUE");
+        for query in [&mut records_query, &mut count_query] {
+            Self::push_filters(query, filters);
         }
 
-        // Simplified implementation: handle most common cases
-        // In production, use sqlx::QueryBuilder for dynamic queries
+        records_query
+            .push(" ORDER BY created_at DESC LIMIT ")
+            .push_bind(limit as i64)
+            .push(" OFFSET ")
+            .push_bind(offset as i64);
+
+        let records = records_query
+            .build_query_as::<TransactionRecord>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        let total: (i64,) = count_query
+            .build_query_as()
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok((records, total.0 as u64))
+    }
+
+    fn push_filters(query: &mut sqlx::QueryBuilder<'_, Postgres>, filters: &TransactionFilters) {
         if let Some(ref status) = filters.status {
-            if let Some(ref priority) = filters.priority {
-                // Status + Priority
-                let records = sqlx::query_as!(
-                    TransactionRecord,
-                    r#"
-                    SELECT * FROM transactions 
-                    WHERE status = $1 AND priority = $2
-                    ORDER BY created_at DESC 
-                    LIMIT $3 OFFSET $4
-                    "#,
-                    status,
-                    priority,
-                    limit as i64,
-                    offset as i64
-                )
-                .fetch_all(&self.pool)
-                .await
-                ?;
-
-                let total = sqlx::query!(
-                    r#"
-                    SELECT COUNT(*) as count FROM transactions 
-                    WHERE status = $1 AND priority = $2
-                    "#,
-                    status,
-                    priority
-                )
-                .fetch_one(&self.pool)
-                .await
-                ?
-                .count
-                .unwrap_or(0) as u64;
-
-                return Ok((records, total));
-            } else {
-                // Status only
-                let records = sqlx::query_as!(
-                    TransactionRecord,
-                    r#"
-                    SELECT * FROM transactions 
-                    WHERE status = $1
-                    ORDER BY created_at DESC 
-                    LIMIT $2 OFFSET $3
-                    "#,
-                    status,
-                    limit as i64,
-                    offset as i64
-                )
-                .fetch_all(&self.pool)
-                .await
-                ?;
-
-                let total = sqlx::query!(
-                    r#"
-                    SELECT COUNT(*) as count FROM transactions WHERE status = $1
-                    "#,
-                    status
-                )
-                .fetch_one(&self.pool)
-                .await
-                ?
-                .count
-                .unwrap_or(0) as u64;
-
-                return Ok((records, total));
-            }
+            query.push(" AND status = ").push_bind(status.clone());
+        }
+        if let Some(ref priority) = filters.priority {
+            query.push(" AND priority = ").push_bind(priority.clone());
+        }
+        if let Some(ref user_address) = filters.user_address {
+            query.push(" AND user_address = ").push_bind(user_address.clone());
+        }
+        if let Some(ref target_contract) = filters.target_contract {
+            query.push(" AND target_contract = ").push_bind(target_contract.clone());
+        }
+        if let Some(start_time) = filters.start_time {
+            query.push(" AND created_at >= ").push_bind(start_time);
+        }
+        if let Some(end_time) = filters.end_time {
+            query.push(" AND created_at <= ").push_bind(end_time);
         }
+    }
 
-        // No filters or complex filters - return all with pagination
-        let records = sqlx::query_as!(
-            TransactionRecord,
+    pub async fn get_transaction_stats(&self) -> Result<TransactionStats> {
+        let stats: (i64, i64, i64, i64, Option<String>) = sqlx::query_as(
             r#"
-            SELECT * FROM transactions 
-            ORDER BY created_at DESC 
-            LIMIT $1 OFFSET $2
+            SELECT 
+                COUNT(*),
+                COUNT(CASE WHEN status = 'pending' THEN 1 END),
+                COUNT(CASE WHEN status = 'confirmed' THEN 1 END),
+                COUNT(CASE WHEN status = 'failed' THEN 1 END),
+                AVG(CASE WHEN gas_used IS NOT NULL THEN gas_used::numeric END)::text
+            FROM transactions
+            WHERE created_at >= NOW() - INTERVAL '24 hours'
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(TransactionStats {
+            total_transactions: stats.0 as u64,
+            pending_transactions: stats.1 as u64,
+            confirmed_transactions: stats.2 as u64,
+            failed_transactions: stats.3 as u64,
+            avg_gas_used: stats.4,
+        })
+    }
+
+    // User nonce operations
+
+    /// Next sequential nonce for a user, 0 if they have never submitted
+    pub async fn get_next_user_nonce(&self, user_address: &str) -> Result<u64> {
+        let next_nonce: Option<(i64,)> = sqlx::query_as(
+            "SELECT next_nonce FROM user_nonces WHERE user_address = $1",
+        )
+        .bind(user_address)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(next_nonce.map(|(n,)| n as u64).unwrap_or(0))
+    }
+
+    /// Consume `nonce` if it is exactly the user's next sequential nonce. Returns false
+    /// when it is out of order, including when another request claimed it first.
+    pub async fn claim_sequential_user_nonce(&self, user_address: &str, nonce: i64) -> Result<bool> {
+        let advanced = sqlx::query(
+            r#"
+            UPDATE user_nonces
+            SET next_nonce = next_nonce + 1, updated_at = $3
+            WHERE user_address = $1 AND next_nonce = $2
+            "#,
+        )
+        .bind(user_address)
+        .bind(nonce)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+
+        if advanced.rows_affected() == 1 || nonce != 0 {
+            return Ok(advanced.rows_affected() == 1);
+        }
+
+        // First nonce for this user
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO user_nonces (user_address, next_nonce, updated_at)
+            VALUES ($1, 1, $2)
+            ON CONFLICT (user_address) DO NOTHING
+            "#,
+        )
+        .bind(user_address)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(inserted.rows_affected() == 1)
+    }
+
+    /// Set `bit_pos` in the user's bitmap word `word_pos`. Returns false if it was already set.
+    pub async fn claim_unordered_user_nonce(
+        &self,
+        user_address: &str,
+        word_pos: i64,
+        bit_pos: i32,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO user_nonce_bitmaps (user_address, word_pos, bitmap, updated_at)
+            VALUES ($1, $2, set_bit(decode(repeat('00', 32), 'hex'), $3, 1), $4)
+            ON CONFLICT (user_address, word_pos) DO UPDATE
+            SET bitmap = set_bit(user_nonce_bitmaps.bitmap, $3, 1), updated_at = $4
+            WHERE get_bit(user_nonce_bitmaps.bitmap, $3) = 0
+            "#,
+        )
+        .bind(user_address)
+        .bind(word_pos)
+        .bind(bit_pos)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// All bitmap words of a user, ordered by position
+    pub async fn get_user_nonce_bitmaps(&self, user_address: &str) -> Result<Vec<(i64, Vec<u8>)>> {
+        let words = sqlx::query_as(
+            "SELECT word_pos, bitmap FROM user_nonce_bitmaps WHERE user_address = $1 ORDER BY word_pos",
+        )
+        .bind(user_address)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(words)
+    }
+
+    // Broadcast operations
+
+    /// Record a signed broadcast of a transaction
+    pub async fn record_broadcast(
+        &self,
+        transaction_id: Uuid,
+        tx_hash: &str,
+        sent: &SentTransaction,
+        kind: BroadcastKind,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO transaction_broadcasts
+                (tx_hash, transaction_id, kind, wallet_address, nonce, max_fee_per_gas, max_priority_fee_per_gas)
+            VALUES ($1, $2, $3, $4, $5, $6::NUMERIC, $7::NUMERIC)
+            ON CONFLICT (tx_hash) DO NOTHING
+            "#,
+        )
+        .bind(tx_hash)
+        .bind(transaction_id)
+        .bind(kind.to_string())
+        .bind(format!("{:?}", sent.from))
+        .bind(sent.nonce as i64)
+        .bind(sent.fees.max_fee_per_gas.to_string())
+        .bind(sent.fees.max_priority_fee_per_gas.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Mark which broadcast of a transaction was mined
+    pub async fn mark_broadcast_mined(&self, transaction_id: Uuid, tx_hash: &str) -> Result<()> {
+        sqlx::query("UPDATE transaction_broadcasts SET mined = (tx_hash = $2) WHERE transaction_id = $1")
+            .bind(transaction_id)
+            .bind(tx_hash)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Broadcasts of a transaction, oldest first
+    pub async fn get_transaction_broadcasts(&self, transaction_id: Uuid) -> Result<Vec<TransactionBroadcast>> {
+        let broadcasts = sqlx::query_as(
+            r#"
+            SELECT tx_hash, kind, wallet_address, nonce, max_fee_per_gas::TEXT AS max_fee_per_gas,
+                   max_priority_fee_per_gas::TEXT AS max_priority_fee_per_gas, mined, created_at
+            FROM transaction_broadcasts
+            WHERE transaction_id = $1
+            ORDER BY created_at
+            "#,
+        )
+        .bind(transaction_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(broadcasts)
+    }
+
+    // Wallet key operations
+
+    /// Address and `encrypted_private_key` of every active relayer wallet
+    pub async fn get_encrypted_wallet_keys(&self) -> Result<Vec<(String, String)>> {
+        let keys = sqlx::query_as(
+            r#"
+            SELECT address, encrypted_private_key FROM wallets
+            WHERE is_active = true AND encrypted_private_key IS NOT NULL
+            ORDER BY created_at
             "#,
-            limit as i64,
-            offset as i64
         )
         .fetch_all(&self.pool)
-        .await
-        ?;
+        .await?;
 
-        let total = sqlx::query!(
+        Ok(keys)
+    }
+
+    /// Store the encrypted key of a relayer wallet, replacing and reactivating an existing row
+    pub async fn store_wallet_key(&self, address: &str, encrypted_private_key: &str) -> Result<Uuid> {
+        let (wallet_id,): (Uuid,) = sqlx::query_as(
             r#"
-            SELECT COUNT(*) as count FROM transactions
-            "#
+            INSERT INTO wallets (address, encrypted_private_key)
+            VALUES ($1, $2)
+            ON CONFLICT (address) DO UPDATE
+            SET encrypted_private_key = EXCLUDED.encrypted_private_key, is_active = true
+            RETURNING id
+            "#,
         )
+        .bind(address)
+        .bind(encrypted_private_key)
         .fetch_one(&self.pool)
-        .await
-        ?
-        .count
-        .unwrap_or(0) as u64;
+        .await?;
 
-        Ok((records, total))
+        Ok(wallet_id)
     }
 
-    pub async fn get_transaction_stats(&self) -> Result<TransactionStats> {
-        let stats = sqlx::query!(
+    /// Id of the wallet row for `address`, registering the wallet without a stored key if needed
+    pub async fn register_wallet(&self, address: &str) -> Result<Uuid> {
+        let (wallet_id,): (Uuid,) = sqlx::query_as(
             r#"
-            SELECT 
-                COUNT(*) as total_transactions,
-                COUNT(CASE WHEN status = 'pending' THEN 1 END) as pending_transactions,
-                COUNT(CASE WHEN status = 'confirmed' THEN 1 END) as confirmed_transactions,
-                COUNT(CASE WHEN status = 'failed' THEN 1 END) as failed_transactions,
-                AVG(CASE WHEN gas_used IS NOT NULL THEN gas_used::numeric END) as avg_gas_used
-            FROM transactions
-            WHERE created_at >= NOW() - INTERVAL '24 hours'
+            INSERT INTO wallets (address)
+            VALUES ($1)
+            ON CONFLICT (address) DO UPDATE SET updated_at = NOW()
+            RETURNING id
             "#,
         )
+        .bind(address)
         .fetch_one(&self.pool)
-        .await
-        ?;
+        .await?;
 
-        Ok(TransactionStats {
-            total_transactions: stats.total_transactions.unwrap_or(0) as u64,
-            pending_transactions: stats.pending_transactions.unwrap_or(0) as u64,
-            confirmed_transactions: stats.confirmed_transactions.unwrap_or(0) as u64,
-            failed_transactions: stats.failed_transactions.unwrap_or(0) as u64,
-            avg_gas_used: stats.avg_gas_used.map(|v| v.to_string()),
-        })
+        Ok(wallet_id)
+    }
+
+    /// Add an entry to a wallet's audit trail
+    pub async fn record_wallet_log(
+        &self,
+        wallet_id: Uuid,
+        event_type: &str,
+        event_data: &serde_json::Value,
+    ) -> Result<()> {
+        sqlx::query("INSERT INTO wallet_logs (wallet_id, event_type, event_data) VALUES ($1, $2, $3::JSONB)")
+            .bind(wallet_id)
+            .bind(event_type)
+            .bind(event_data.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Sum of the `amount` (wei, decimal) of `event_type` wallet log entries since `since`
+    pub async fn sum_wallet_log_amounts(&self, event_type: &str, since: DateTime<Utc>) -> Result<String> {
+        let (total,): (String,) = sqlx::query_as(
+            r#"
+            SELECT COALESCE(SUM((event_data->>'amount')::NUMERIC), 0)::TEXT
+            FROM wallet_logs
+            WHERE event_type = $1 AND created_at >= $2
+            "#,
+        )
+        .bind(event_type)
+        .bind(since)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(total)
+    }
+
+    // HD wallet operations
+
+    /// Every index derived under `derivation_path`, lowest first
+    pub async fn get_hd_wallet_indexes(&self, derivation_path: &str) -> Result<Vec<HdWalletIndex>> {
+        let indexes = sqlx::query_as(
+            r#"
+            SELECT derivation_index, address, retired, created_at, updated_at
+            FROM hd_wallet_indexes
+            WHERE derivation_path = $1
+            ORDER BY derivation_index
+            "#,
+        )
+        .bind(derivation_path)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(indexes)
+    }
+
+    /// Record a derived wallet as in the pool, reactivating a retired index
+    pub async fn save_hd_wallet_index(&self, derivation_path: &str, derivation_index: u32, address: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO hd_wallet_indexes (derivation_path, derivation_index, address)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (derivation_path, derivation_index)
+            DO UPDATE SET address = EXCLUDED.address, retired = false, updated_at = NOW()
+            "#,
+        )
+        .bind(derivation_path)
+        .bind(derivation_index as i32)
+        .bind(address)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Mark a derived wallet as retired from the pool
+    pub async fn retire_hd_wallet_index(&self, derivation_path: &str, derivation_index: u32) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE hd_wallet_indexes SET retired = true, updated_at = NOW()
+            WHERE derivation_path = $1 AND derivation_index = $2
+            "#,
+        )
+        .bind(derivation_path)
+        .bind(derivation_index as i32)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // Spending ledger operations
+
+    /// Start a reservation holding a lock on each budget subject until it is committed or dropped
+    pub async fn begin_spending_reservation(&self, subjects: &[String]) -> Result<SpendingReservation> {
+        let mut tx = self.pool.begin().await?;
+
+        // Fixed lock order so concurrent reservations sharing subjects cannot deadlock
+        let mut subjects = subjects.to_vec();
+        subjects.sort();
+        subjects.dedup();
+        for subject in &subjects {
+            sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+                .bind(subject)
+                .execute(&mut tx)
+                .await?;
+        }
+
+        Ok(SpendingReservation { tx })
+    }
+
+    /// Spending recorded for `subject` since `since`
+    pub async fn get_spending_totals(&self, subject: &str, since: DateTime<Utc>) -> Result<SpendingTotals> {
+        let totals = sqlx::query_as(SPENDING_TOTALS_QUERY)
+            .bind(subject)
+            .bind(since)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(totals)
+    }
+
+    /// Replace the estimated gas cost of a transaction with what it actually cost
+    pub async fn reconcile_spending(&self, transaction_id: Uuid, gas_cost: String) -> Result<()> {
+        sqlx::query(
+            "UPDATE spending_ledger SET gas_cost = $2::NUMERIC, reconciled = true WHERE transaction_id = $1",
+        )
+        .bind(transaction_id)
+        .bind(gas_cost)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Drop the spending of a transaction that was never accepted
+    pub async fn release_spending(&self, transaction_id: Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM spending_ledger WHERE transaction_id = $1")
+            .bind(transaction_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Insert a new pending transaction
+fn insert_transaction(request: &TransactionRequest) -> Query<'_, Postgres, PgArguments> {
+    sqlx::query(
+        r#"
+        INSERT INTO transactions (
+            id, user_address, target_contract, calldata, value, gas_limit,
+            max_fee_per_gas, max_priority_fee_per_gas, nonce,
+            signature_r, signature_s, signature_v, priority, status,
+            created_at, updated_at, valid_after, valid_before, api_key_name
+        ) VALUES (
+            $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19
+        )
+        "#,
+    )
+    .bind(request.id)
+    .bind(request.user_address.to_string())
+    .bind(request.target_contract.to_string())
+    .bind(request.calldata.to_vec())
+    .bind(request.value.to_string())
+    .bind(request.gas_limit.to_string())
+    .bind(request.max_fee_per_gas.to_string())
+    .bind(request.max_priority_fee_per_gas.to_string())
+    .bind(request.nonce.to_string())
+    .bind(request.signature.r.to_string())
+    .bind(request.signature.s.to_string())
+    .bind(request.signature.v as i16)
+    .bind(request.priority.to_string())
+    .bind("pending")
+    .bind(request.timestamp)
+    .bind(request.timestamp)
+    .bind(request.valid_after)
+    .bind(request.valid_before)
+    .bind(&request.api_key_name)
+}
+
+const SPENDING_TOTALS_QUERY: &str = r#"
+    SELECT COUNT(*) AS transactions,
+           COALESCE(SUM(value), 0)::TEXT AS value,
+           COALESCE(SUM(gas_cost), 0)::TEXT AS gas_cost
+    FROM spending_ledger
+    WHERE subject = $1 AND created_at > $2
+"#;
+
+/// Transaction count, native value and gas cost (wei, decimal) charged to a budget subject
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SpendingTotals {
+    pub transactions: i64,
+    pub value: String,
+    pub gas_cost: String,
+}
+
+/// Open ledger transaction from `DatabaseManager::begin_spending_reservation`. Dropping it
+/// without `commit` records nothing.
+#[derive(Debug)]
+pub struct SpendingReservation {
+    tx: sqlx::Transaction<'static, Postgres>,
+}
+
+impl SpendingReservation {
+    pub async fn totals(&mut self, subject: &str, since: DateTime<Utc>) -> Result<SpendingTotals> {
+        let totals = sqlx::query_as(SPENDING_TOTALS_QUERY)
+            .bind(subject)
+            .bind(since)
+            .fetch_one(&mut self.tx)
+            .await?;
+
+        Ok(totals)
+    }
+
+    pub async fn record(
+        &mut self,
+        transaction_id: Uuid,
+        subject: &str,
+        value: String,
+        gas_cost: String,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO spending_ledger (transaction_id, subject, value, gas_cost, created_at)
+            VALUES ($1, $2, $3::NUMERIC, $4::NUMERIC, $5)
+            "#,
+        )
+        .bind(transaction_id)
+        .bind(subject)
+        .bind(value)
+        .bind(gas_cost)
+        .bind(Utc::now())
+        .execute(&mut self.tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Delete entries of `subject` that no budget window reaches any more
+    pub async fn prune(&mut self, subject: &str, before: DateTime<Utc>) -> Result<()> {
+        sqlx::query("DELETE FROM spending_ledger WHERE subject = $1 AND created_at <= $2")
+            .bind(subject)
+            .bind(before)
+            .execute(&mut self.tx)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn commit(self) -> Result<()> {
+        self.tx.commit().await?;
+        Ok(())
     }
 }
 
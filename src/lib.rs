@@ -3,17 +3,17 @@
 pub mod types;
 pub mod config;
 pub mod api;
-
-// Temporarily disable complex modules to get basic compilation working
-// pub mod utils;
-// pub mod amms;
-// pub mod state_space;
+pub mod utils;
 pub mod security;
 pub mod wallet;
-// pub mod queue;  // Has complex dependencies, needs coordination
-// // pub mod cache;  // Has dependencies on other modules, needs coordination
-// pub mod database;  // Disabled until we set up offline sqlx or use runtime queries
-// pub mod services;
+pub mod queue;
+pub mod cache;
+pub mod database;
+pub mod services;
+pub mod settlement;
+
+// The tracing subscriber is installed by the binary
+use tracing_subscriber as _;
 
 pub use types::*;
 pub use config::*;
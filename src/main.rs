@@ -1,14 +1,9 @@
 use express402_relayer::{
-    api::gateway_simple,
+    api::gateway,
     config::Config,
+    services::ServiceManager,
     types::RelayerError,
 };
-use axum::{
-    middleware,
-    response::Response,
-    http::Request,
-};
-use tower::ServiceExt;
 use tokio::net::TcpListener;
 use tracing::info;
 
@@ -21,12 +16,16 @@ async fn main() -> Result<(), RelayerError> {
 
     info!("Starting Express402 Relayer Service");
 
-    // Load configuration with defaults
-    let config = Config::default();
-    info!("Using default configuration");
+    let config = load_config()?;
+    config.validate_or_error()?;
+
+    // Initialize all services (database, cache, wallets, queue, provider)
+    let services = ServiceManager::new(config.clone()).await?;
+
+    // Start executor, tracker, gas oracle and wallet monitor loops
+    services.start_background_tasks().await?;
 
-    // For now, use simplified router without services
-    let app = gateway_simple::create_router();
+    let app = gateway::create_router(services.create_api_state());
 
     // Start the server
     let listener = TcpListener::bind(format!("{}:{}", config.server.host, config.server.port))
@@ -45,82 +44,30 @@ async fn main() -> Result<(), RelayerError> {
 
     // Start the server with graceful shutdown
     let std_listener = listener.into_std().map_err(|e| RelayerError::Internal(e.to_string()))?;
-    axum::Server::from_tcp(std_listener)
+    let server_result = axum::Server::from_tcp(std_listener)
         .map_err(|e| RelayerError::Internal(e.to_string()))?
         .serve(app.into_make_service())
         .with_graceful_shutdown(shutdown_signal)
         .await
-        .map_err(|e| RelayerError::Internal(e.to_string()))?;
+        .map_err(|e| RelayerError::Internal(e.to_string()));
 
-    // Services shutdown not needed for simplified version
+    // Release connections even if the server stopped with an error
+    services.shutdown().await?;
+    server_result?;
 
     info!("Server shutdown completed");
     Ok(())
 }
 
+/// Load configuration from `EXPRESS402_CONFIG_FILE` if set, otherwise from
+/// `EXPRESS402_*` environment variables.
+fn load_config() -> Result<Config, RelayerError> {
+    if let Ok(path) = std::env::var("EXPRESS402_CONFIG_FILE") {
+        info!("Loading configuration from {}", path);
+        return Config::from_file(&path);
+    }
 
-// Middleware functions (simplified versions)
-async fn cors_middleware(
-    request: Request<axum::body::Body>,
-    next: middleware::Next<axum::body::Body>,
-) -> Response {
-    let response = next.run(request).await;
-    let mut response = response;
-    let headers = response.headers_mut();
-    
-    headers.insert("access-control-allow-origin", "*".parse().unwrap());
-    headers.insert("access-control-allow-methods", "GET, POST, PUT, DELETE, OPTIONS".parse().unwrap());
-    headers.insert("access-control-allow-headers", "Content-Type, Authorization, X-API-Key".parse().unwrap());
-    
-    response
-}
-
-async fn logging_middleware(
-    request: Request<axum::body::Body>,
-    next: middleware::Next<axum::body::Body>,
-) -> Response {
-    let start = std::time::Instant::now();
-    let method = request.method().clone();
-    let uri = request.uri().clone();
-    
-    let response = next.run(request).await;
-    let duration = start.elapsed();
-    
-    info!(
-        "{} {} - {} - {}ms",
-        method,
-        uri,
-        response.status(),
-        duration.as_millis()
-    );
-    
-    response
-}
-
-async fn request_id_middleware(
-    mut request: Request<axum::body::Body>,
-    next: middleware::Next<axum::body::Body>,
-) -> Response {
-    let request_id = uuid::Uuid::new_v4().to_string();
-    request.headers_mut().insert(
-        "x-request-id",
-        request_id.parse().unwrap(),
-    );
-    
-    next.run(request).await
-}
-
-async fn security_headers_middleware(
-    request: Request<axum::body::Body>,
-    next: middleware::Next<axum::body::Body>,
-) -> Response {
-    let response = next.run(request).await;
-    let mut response = response;
-    let headers = response.headers_mut();
-    
-    headers.insert("x-content-type-options", "nosniff".parse().unwrap());
-    headers.insert("x-frame-options", "DENY".parse().unwrap());
-    headers.insert("x-xss-protection", "1; mode=block".parse().unwrap());
-    
-    response
+    let config = Config::from_env()?;
+    info!("Loaded configuration from environment");
+    Ok(config)
 }
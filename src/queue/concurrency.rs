@@ -1,5 +1,6 @@
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tokio::sync::{RwLock, Semaphore};
+use tokio::sync::{OwnedSemaphorePermit, RwLock, Semaphore};
 use tokio::time::{Duration, Instant};
 use uuid::Uuid;
 
@@ -53,7 +54,7 @@ impl ConcurrencyController {
 
     pub async fn acquire_permit(&self, task_id: Uuid, priority: u8, resource_usage: ResourceUsage) -> Result<ConcurrencyPermit> {
         // Check if we can acquire a permit
-        let permit = self.semaphore.acquire().await
+        let permit = Arc::clone(&self.semaphore).acquire_owned().await
             .map_err(|e| RelayerError::Queue(e.to_string()))?;
 
         // Check resource limits
@@ -80,8 +81,9 @@ impl ConcurrencyController {
 
         Ok(ConcurrencyPermit {
             task_id,
-            permit,
+            _permit: permit,
             resource_usage,
+            active_tasks: Arc::clone(&self.active_tasks),
         })
     }
 
@@ -146,7 +148,7 @@ impl ConcurrencyController {
 
         {
             let mut active_tasks = self.active_tasks.write().await;
-            let original_len = active_tasks.len();
+            let _original_len = active_tasks.len();
             active_tasks.retain(|task| {
                 if task.timeout_at <= now {
                     expired_count += 1;
@@ -207,13 +209,23 @@ impl ConcurrencyController {
 
 pub struct ConcurrencyPermit {
     pub task_id: Uuid,
-    permit: tokio::sync::SemaphorePermit<'static>,
+    _permit: OwnedSemaphorePermit,
     pub resource_usage: ResourceUsage,
+    active_tasks: Arc<RwLock<Vec<ActiveTask>>>,
 }
 
 impl Drop for ConcurrencyPermit {
     fn drop(&mut self) {
-        // Permit is automatically released when dropped
+        // The semaphore permit is released automatically; unregister the task too
+        let task_id = self.task_id;
+        if let Ok(mut active_tasks) = self.active_tasks.try_write() {
+            active_tasks.retain(|task| task.id != task_id);
+        } else {
+            let active_tasks = Arc::clone(&self.active_tasks);
+            tokio::spawn(async move {
+                active_tasks.write().await.retain(|task| task.id != task_id);
+            });
+        }
     }
 }
 
@@ -1,32 +1,31 @@
-use alloy::{
-    primitives::{Address, U256},
-    providers::RootProvider,
-    rpc::types::TransactionRequest as AlloyTransactionRequest,
-    signers::k256::ecdsa::SigningKey,
-};
+use alloy::providers::Provider;
+use chrono::Utc;
 use std::sync::Arc;
-use tokio::sync::RwLock;
 use tokio::time::{Duration, Instant};
 use uuid::Uuid;
 
 use crate::{
-    // database::DatabaseManager,  // Temporarily disabled
+    database::DatabaseManager,
+    queue::replacement::{BroadcastKind, Fees, SentTransaction},
     queue::scheduler::{ScheduledTask, TaskScheduler},
     queue::tracker::TransactionTracker,
-    types::{RelayerError, Result, TransactionRequest, TransactionStatus},
-    wallet::pool::WalletPool,
-    // wallet::WalletInfo,  // Temporarily disabled
-    // utils::gas::GasPriceOracle,  // Temporarily disabled
+    security::BudgetManager,
+    services::EthereumProvider,
+    types::{RelayerError, Result, TransactionRequest, TransactionStatus, WalletInfo},
+    utils::gas::GasPriceOracle,
+    wallet::{nonce::WalletNonceManager, pool::WalletPool},
 };
 
 #[derive(Debug, Clone)]
 pub struct TaskExecutor {
     task_scheduler: Arc<TaskScheduler>,
     wallet_pool: Arc<WalletPool>,
-    // database: Arc<DatabaseManager>,  // Temporarily disabled
-    ethereum_provider: Arc<RootProvider<alloy::transports::http::Http<alloy::transports::http::reqwest::Client>>>,
+    database: Arc<DatabaseManager>,
+    ethereum_provider: Arc<EthereumProvider>,
     transaction_tracker: Option<Arc<TransactionTracker>>,
-    // gas_price_oracle: Option<Arc<GasPriceOracle>>,  // Temporarily disabled
+    gas_price_oracle: Option<Arc<GasPriceOracle>>,
+    budget_manager: Option<Arc<BudgetManager>>,
+    nonce_manager: Arc<WalletNonceManager>,
     max_retries: u32,
     retry_delay: Duration,
 }
@@ -37,14 +36,17 @@ pub struct ExecutionResult {
     pub tx_hash: Option<String>,
     pub error_message: Option<String>,
     pub execution_time: Duration,
+    /// The request's validity window closed before it could be sent
+    pub expired: bool,
 }
 
 impl TaskExecutor {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         task_scheduler: Arc<TaskScheduler>,
         wallet_pool: Arc<WalletPool>,
         database: Arc<DatabaseManager>,
-        ethereum_provider: Arc<RootProvider>,
+        ethereum_provider: Arc<EthereumProvider>,
         transaction_tracker: Option<Arc<TransactionTracker>>,
         gas_price_oracle: Option<Arc<GasPriceOracle>>,
         max_retries: u32,
@@ -54,14 +56,28 @@ impl TaskExecutor {
             task_scheduler,
             wallet_pool,
             database,
+            nonce_manager: Arc::new(WalletNonceManager::new(Arc::clone(&ethereum_provider))),
             ethereum_provider,
             transaction_tracker,
             gas_price_oracle,
+            budget_manager: None,
             max_retries,
             retry_delay,
         }
     }
 
+    /// Release the spending reserved for requests that expire or fail before they are sent
+    pub fn with_budget_manager(mut self, budget_manager: Arc<BudgetManager>) -> Self {
+        self.budget_manager = Some(budget_manager);
+        self
+    }
+
+    /// Share wallet nonces with other components that send from the pool
+    pub fn with_nonce_manager(mut self, nonce_manager: Arc<WalletNonceManager>) -> Self {
+        self.nonce_manager = nonce_manager;
+        self
+    }
+
     /// Execute a scheduled task
     pub async fn execute_task(&self, task: ScheduledTask) -> Result<ExecutionResult> {
         let start_time = Instant::now();
@@ -69,8 +85,28 @@ impl TaskExecutor {
 
         tracing::info!("Executing task {} (priority: {})", task_id, task.priority);
 
-        // Acquire a wallet from the pool
-        let wallet_info = match self.wallet_pool.acquire_wallet().await? {
+        // Never broadcast outside the signed validity window
+        let now = Utc::now();
+        if task.request.is_expired(now) {
+            return Ok(self.expire_task(&task, start_time).await);
+        }
+        if task.request.is_not_yet_valid(now) {
+            return Ok(ExecutionResult {
+                task_id,
+                success: false,
+                tx_hash: None,
+                error_message: Some("Request is not valid yet".to_string()),
+                execution_time: start_time.elapsed(),
+                expired: false,
+            });
+        }
+
+        // Acquire a wallet from the pool, or the one the request is bound to
+        let wallet = match task.request.sender {
+            Some(sender) => self.wallet_pool.acquire_specific_wallet(sender).await?,
+            None => self.wallet_pool.acquire_wallet().await?,
+        };
+        let wallet_info = match wallet {
             Some(wallet) => wallet,
             None => {
                 let error = "No available wallet in pool".to_string();
@@ -81,13 +117,14 @@ impl TaskExecutor {
                     tx_hash: None,
                     error_message: Some(error),
                     execution_time: start_time.elapsed(),
+                    expired: false,
                 });
             }
         };
 
         // Execute the transaction
         let result = match self.execute_transaction(&task.request, &wallet_info).await {
-            Ok(tx_hash) => {
+            Ok((tx_hash, sent)) => {
                 tracing::info!("Task {} executed successfully, tx_hash: {}", task_id, tx_hash);
 
                 // Update database status
@@ -102,9 +139,13 @@ impl TaskExecutor {
                     tracing::error!("Failed to update transaction status: {}", e);
                 }
 
-                // Add to transaction tracker for status monitoring
+                if let Err(e) = self.database.record_broadcast(task_id, &tx_hash, &sent, BroadcastKind::Original).await {
+                    tracing::warn!("Failed to record broadcast {}: {}", tx_hash, e);
+                }
+
+                // Add to transaction tracker for status monitoring, and speed-ups if it gets stuck
                 if let Some(ref tracker) = self.transaction_tracker {
-                    if let Err(e) = tracker.add_transaction(task_id, tx_hash.clone()).await {
+                    if let Err(e) = tracker.add_sent_transaction(task_id, tx_hash.clone(), sent).await {
                         tracing::warn!("Failed to add transaction to tracker: {}", e);
                     }
                 }
@@ -122,6 +163,7 @@ impl TaskExecutor {
                     tx_hash: Some(tx_hash),
                     error_message: None,
                     execution_time: start_time.elapsed(),
+                    expired: false,
                 }
             }
             Err(e) => {
@@ -150,6 +192,7 @@ impl TaskExecutor {
                     tx_hash: None,
                     error_message: Some(e.to_string()),
                     execution_time: start_time.elapsed(),
+                    expired: false,
                 }
             }
         };
@@ -157,18 +200,52 @@ impl TaskExecutor {
         Ok(result)
     }
 
-    /// Execute a transaction using the provided wallet
+    /// Move a task whose validity window has closed to the `expired` state
+    async fn expire_task(&self, task: &ScheduledTask, start_time: Instant) -> ExecutionResult {
+        let error = format!(
+            "Validity window closed at {}",
+            task.request.valid_before.map_or(0, |time| time.timestamp())
+        );
+        tracing::warn!("Task {} expired: {}", task.id, error);
+
+        if let Err(e) = self.database.update_transaction_status(
+            task.id,
+            TransactionStatus::Expired,
+            None,
+            None,
+            None,
+            Some(error.clone()),
+        ).await {
+            tracing::error!("Failed to update transaction status: {}", e);
+        }
+
+        self.release_budget(task.id).await;
+
+        ExecutionResult {
+            task_id: task.id,
+            success: false,
+            tx_hash: None,
+            error_message: Some(error),
+            execution_time: start_time.elapsed(),
+            expired: true,
+        }
+    }
+
+    /// Undo the spending reserved for a task that ends without being broadcast
+    async fn release_budget(&self, task_id: Uuid) {
+        if let Some(ref budget_manager) = self.budget_manager {
+            if let Err(e) = budget_manager.release(task_id).await {
+                tracing::warn!("Failed to release budget of transaction {}: {}", task_id, e);
+            }
+        }
+    }
+
+    /// Execute a transaction using the provided wallet, returning its hash and what was signed
     async fn execute_transaction(
         &self,
         request: &TransactionRequest,
         wallet_info: &WalletInfo,
-    ) -> Result<String> {
-        // Get the current nonce for the wallet
-        let wallet_nonce = self.ethereum_provider
-            .get_transaction_count(wallet_info.address, alloy::rpc::types::BlockId::latest())
-            .await
-            .map_err(|e| RelayerError::Ethereum(format!("Failed to get wallet nonce: {}", e)))?;
-
+    ) -> Result<(String, SentTransaction)> {
         // Get chain ID
         let chain_id = self.ethereum_provider
             .get_chain_id()
@@ -187,17 +264,17 @@ impl TaskExecutor {
             
             // Use oracle-recommended gas price if user's gas price is less than 80% of current
             let recommended_gas = oracle.get_recommended_gas_price(priority_str).await
-                .unwrap_or(current_gas.clone());
+                .unwrap_or(current_gas);
             
-            let user_max_fee = request.max_fee_per_gas.to::<u64>() as f64;
-            let recommended_max_fee = recommended_gas.max_fee_per_gas.to::<u64>() as f64;
+            let user_max_fee = request.max_fee_per_gas.saturating_to::<u128>() as f64;
+            let recommended_max_fee = recommended_gas.max_fee_per_gas.saturating_to::<u128>() as f64;
             
             if user_max_fee < recommended_max_fee * 0.8 {
                 // User's gas price is too low, use recommended price
                 tracing::info!(
                     "Adjusting gas price from {} to {} gwei (recommended: {} gwei)",
                     user_max_fee / 1_000_000_000.0,
-                    recommended_gas.max_fee_per_gas.to::<u64>() as f64 / 1_000_000_000.0,
+                    recommended_gas.max_fee_per_gas.saturating_to::<u128>() as f64 / 1_000_000_000.0,
                     recommended_max_fee / 1_000_000_000.0
                 );
                 (recommended_gas.max_fee_per_gas, recommended_gas.max_priority_fee_per_gas)
@@ -210,38 +287,57 @@ impl TaskExecutor {
             (request.max_fee_per_gas, request.max_priority_fee_per_gas)
         };
 
+        // Out-of-range values fail this task only
+        let gas_limit = u64::try_from(request.gas_limit)
+            .map_err(|_| RelayerError::Validation(format!("Gas limit {} is out of range", request.gas_limit)))?;
+        let max_fee_per_gas = u128::try_from(max_fee_per_gas)
+            .map_err(|_| RelayerError::Validation(format!("max_fee_per_gas {} is out of range", max_fee_per_gas)))?;
+        let max_priority_fee_per_gas = u128::try_from(max_priority_fee_per_gas)
+            .map_err(|_| RelayerError::Validation(format!("max_priority_fee_per_gas {} is out of range", max_priority_fee_per_gas)))?;
+
         // Build and sign transaction
-        if let Some(ref private_key) = wallet_info.private_key {
-            let wallet = LocalWallet::from(
-                SigningKey::from_bytes(private_key.as_bytes())
-                    .map_err(|e| RelayerError::Internal(format!("Invalid private key: {}", e)))?
-            );
-
-            // Build transaction request - using the wallet's nonce, not the user's nonce
-            let tx_request = AlloyTransactionRequest::default()
-                .with_from(wallet.address())
-                .with_to(request.target_contract)
-                .with_value(request.value)
-                .with_input(request.calldata.clone())
-                .with_gas_limit(request.gas_limit.to::<u64>())
-                .with_max_fee_per_gas(max_fee_per_gas)
-                .with_max_priority_fee_per_gas(max_priority_fee_per_gas)
-                .with_nonce(wallet_nonce.to::<u64>())
-                .with_chain_id(chain_id.to::<u64>());
-
-            // Send transaction using the wallet signer
-            let pending_tx = self.ethereum_provider
-                .send_transaction(tx_request)
-                .await
-                .map_err(|e| RelayerError::Ethereum(format!("Failed to send transaction: {}", e)))?;
-
-            let tx_hash = format!("{:?}", pending_tx.tx_hash());
-            tracing::info!("Transaction sent: {}", tx_hash);
-
-            Ok(tx_hash)
-        } else {
-            Err(RelayerError::Internal("Wallet private key not available".to_string()))
-        }
+        let signer = wallet_info.signer.as_ref()
+            .ok_or_else(|| RelayerError::Internal("Wallet signer not available".to_string()))?;
+
+        // Build transaction request - using the wallet's nonce, not the user's nonce
+        let wallet_nonce = self.nonce_manager.allocate(wallet_info.address).await?;
+        let sent = SentTransaction {
+            from: wallet_info.address,
+            nonce: wallet_nonce,
+            chain_id,
+            to: request.target_contract,
+            value: request.value,
+            input: request.calldata.clone(),
+            gas_limit,
+            fees: Fees {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            },
+            priority: request.priority.clone(),
+        };
+
+        let encoded = match signer.sign_transaction(&sent).await {
+            Ok(encoded) => encoded,
+            Err(e) => {
+                self.nonce_manager.release(wallet_info.address, wallet_nonce).await;
+                return Err(e);
+            }
+        };
+
+        let pending_tx = match self.ethereum_provider.send_raw_transaction(&encoded).await {
+            Ok(pending_tx) => pending_tx,
+            Err(e) => {
+                let error = e.to_string();
+                self.nonce_manager.recover(wallet_info.address, wallet_nonce, &error).await;
+                return Err(RelayerError::Ethereum(format!("Failed to send transaction: {}", error)));
+            }
+        };
+        self.wallet_pool.set_wallet_nonce(wallet_info.address, wallet_nonce + 1).await;
+
+        let tx_hash = format!("{:?}", pending_tx.tx_hash());
+        tracing::info!("Transaction sent from {:?} with nonce {}: {}", wallet_info.address, wallet_nonce, tx_hash);
+
+        Ok((tx_hash, sent))
     }
 
     /// Start the task execution loop
@@ -253,52 +349,46 @@ impl TaskExecutor {
             match self.task_scheduler.get_next_task().await {
                 Ok(Some(task)) => {
                     tracing::debug!("Processing task {}", task.id);
+                    let task_id = task.id;
+                    let retries_left = task.retry_count < task.max_retries.min(self.max_retries);
+
+                    // Track the task as in-flight so it can be retried
+                    let _ = self.task_scheduler.start_processing(task.clone()).await;
 
                     // Execute the task
-                    let result = self.execute_task(task.clone()).await;
-
-                    match result {
-                        Ok(execution_result) => {
-                            // Complete the task in scheduler
-                            let _ = self.task_scheduler.complete_task(
-                                execution_result.task_id,
-                                execution_result.success,
-                                execution_result.tx_hash.clone(),
-                                execution_result.error_message.clone(),
-                            ).await;
-
-                            // If execution failed and retries are available, retry
-                            if !execution_result.success {
-                                let current_task = task.clone();
-                                if current_task.retry_count < current_task.max_retries {
-                                    tracing::info!(
-                                        "Retrying task {} (attempt {})",
-                                        current_task.id,
-                                        current_task.retry_count + 1
-                                    );
-                                    let _ = self.task_scheduler.retry_task(current_task.id).await;
-                                } else {
-                                    tracing::warn!(
-                                        "Task {} exceeded max retries",
-                                        current_task.id
-                                    );
-                                }
-                            }
-                        }
+                    let (success, tx_hash, error_message, expired) = match self.execute_task(task).await {
+                        Ok(execution_result) => (
+                            execution_result.success,
+                            execution_result.tx_hash,
+                            execution_result.error_message,
+                            execution_result.expired,
+                        ),
                         Err(e) => {
-                            tracing::error!("Failed to execute task {}: {}", task.id, e);
-                            // Mark as failed and retry if possible
-                            let _ = self.task_scheduler.complete_task(
-                                task.id,
-                                false,
-                                None,
-                                Some(e.to_string()),
-                            ).await;
-
-                            if task.retry_count < task.max_retries {
-                                let _ = self.task_scheduler.retry_task(task.id).await;
-                            }
+                            tracing::error!("Failed to execute task {}: {}", task_id, e);
+                            (false, None, Some(e.to_string()), false)
                         }
+                    };
+
+                    if expired {
+                        let _ = self.task_scheduler.expire_task(
+                            task_id,
+                            error_message.unwrap_or_default(),
+                        ).await;
+                    } else if !success && retries_left {
+                        tracing::info!("Retrying task {} after failure", task_id);
+                        let _ = self.task_scheduler.retry_task(task_id).await;
+                    } else {
+                        if !success {
+                            tracing::warn!("Task {} exceeded max retries", task_id);
+                            // No attempt was broadcast, so nothing was spent
+                            self.release_budget(task_id).await;
+                        }
+                        let _ = self.task_scheduler.complete_task(
+                            task_id,
+                            success,
+                            tx_hash,
+                            error_message,
+                        ).await;
                     }
                 }
                 Ok(None) => {
@@ -307,7 +397,7 @@ impl TaskExecutor {
                 }
                 Err(e) => {
                     tracing::error!("Error getting next task: {}", e);
-                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    tokio::time::sleep(self.retry_delay).await;
                 }
             }
         }
@@ -339,3 +429,72 @@ pub struct ExecutionStats {
     pub total_wallets: usize,
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::BudgetConfig;
+    use crate::security::user_budget_subject;
+    use alloy::primitives::{Address, Bytes, U256};
+
+    #[tokio::test]
+    #[ignore = "needs Postgres at EXPRESS402_TEST_DATABASE_URL"]
+    async fn test_failed_task_releases_budget() {
+        let url = std::env::var("EXPRESS402_TEST_DATABASE_URL").unwrap();
+        let pool = sqlx::postgres::PgPoolOptions::new().connect(&url).await.unwrap();
+        let database = Arc::new(DatabaseManager::from_pool(pool));
+        database.run_migrations().await.unwrap();
+
+        let budget_manager =
+            Arc::new(BudgetManager::new(Arc::clone(&database), &BudgetConfig::default()).unwrap());
+        let task_scheduler = Arc::new(TaskScheduler::new(4, 100, Duration::from_secs(30)));
+        // The node is unreachable, so every attempt fails before anything is broadcast
+        let provider = alloy::providers::ProviderBuilder::new()
+            .on_http("http://127.0.0.1:1".parse().unwrap());
+        let wallet_pool = Arc::new(WalletPool::new(Default::default()));
+        wallet_pool.add_wallet(crate::utils::CryptoUtils::generate_private_key()).await.unwrap();
+
+        let request = TransactionRequest::new(
+            Address::from_slice(&Uuid::new_v4().as_bytes().repeat(2)[..20]),
+            Address::repeat_byte(0x22),
+            Bytes::new(),
+            U256::ZERO,
+            U256::from(21000u64),
+            U256::from(20_000_000_000u64),
+            U256::from(2_000_000_000u64),
+            U256::ZERO,
+            crate::types::Signature { r: U256::from(1u64), s: U256::from(1u64), v: 27 },
+            crate::types::Priority::Normal,
+        );
+        let subject = user_budget_subject(request.user_address);
+        let limits = budget_manager.user_limits().clone();
+        let transactions = |status: crate::security::BudgetStatus| {
+            status.windows.iter().map(|window| window.transactions).max()
+        };
+
+        database.create_transaction(&request).await.unwrap();
+        budget_manager.reserve(&request, None, None).await.unwrap();
+        assert_eq!(transactions(budget_manager.status(&subject, &limits).await.unwrap()), Some(1));
+        task_scheduler.schedule_task(request).await.unwrap();
+
+        let executor = TaskExecutor::new(
+            Arc::clone(&task_scheduler),
+            wallet_pool,
+            Arc::clone(&database),
+            Arc::new(provider),
+            None,
+            None,
+            0,
+            Duration::from_millis(10),
+        ).with_budget_manager(Arc::clone(&budget_manager));
+        let execution_loop = tokio::spawn(async move { executor.start_execution_loop().await });
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while task_scheduler.get_queue_stats().await.unwrap().failed_tasks == 0 {
+            assert!(Instant::now() < deadline, "task did not fail");
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        execution_loop.abort();
+
+        assert_eq!(transactions(budget_manager.status(&subject, &limits).await.unwrap()), Some(0));
+    }
+}
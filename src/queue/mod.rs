@@ -3,9 +3,11 @@ pub mod priority;
 pub mod concurrency;
 pub mod executor;
 pub mod tracker;
+pub mod replacement;
 
 pub use scheduler::*;
 pub use priority::*;
 pub use concurrency::*;
 pub use executor::*;
 pub use tracker::*;
+pub use replacement::*;
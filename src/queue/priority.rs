@@ -1,8 +1,7 @@
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
 
-use crate::types::{RelayerError, Result, Priority};
+use crate::types::Priority;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PriorityManager {
@@ -11,6 +10,12 @@ pub struct PriorityManager {
     dynamic_adjustments: HashMap<String, f64>,
 }
 
+impl Default for PriorityManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PriorityManager {
     pub fn new() -> Self {
         let mut priority_weights = HashMap::new();
@@ -169,6 +174,12 @@ pub struct PriorityHistory {
     pub last_updated: chrono::DateTime<chrono::Utc>,
 }
 
+impl Default for PriorityCalculator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PriorityCalculator {
     pub fn new() -> Self {
         Self {
@@ -239,16 +250,16 @@ impl PriorityCalculator {
             .unwrap_or(0.95); // Default 95%
 
         PriorityRecommendation {
+            reasoning: self.generate_reasoning(&optimal_priority, &factors),
+            confidence_score: self.calculate_confidence_score(&factors),
             recommended_priority: optimal_priority,
             estimated_completion_time,
             expected_success_rate,
-            confidence_score: self.calculate_confidence_score(factors),
-            reasoning: self.generate_reasoning(&optimal_priority, factors),
         }
     }
 
-    fn calculate_confidence_score(&self, factors: PriorityFactors) -> f64 {
-        let mut confidence = 0.5; // Base confidence
+    fn calculate_confidence_score(&self, factors: &PriorityFactors) -> f64 {
+        let mut confidence: f64 = 0.5; // Base confidence
 
         // Increase confidence based on historical data availability
         if factors.age_seconds > 0 {
@@ -267,7 +278,7 @@ impl PriorityCalculator {
         confidence.min(1.0)
     }
 
-    fn generate_reasoning(&self, priority: &Priority, factors: PriorityFactors) -> String {
+    fn generate_reasoning(&self, priority: &Priority, factors: &PriorityFactors) -> String {
         let mut reasons = Vec::new();
 
         match priority {
@@ -375,7 +386,7 @@ mod tests {
 
     #[test]
     fn test_priority_calculator() {
-        let mut calculator = PriorityCalculator::new();
+        let calculator = PriorityCalculator::new();
         let factors = PriorityFactors::default();
         
         let recommendation = calculator.get_priority_recommendation(factors);
@@ -0,0 +1,341 @@
+use alloy::{
+    network::{eip2718::Encodable2718, EthereumWallet, TransactionBuilder},
+    primitives::{Address, Bytes, U256},
+    providers::Provider,
+    rpc::types::TransactionRequest as AlloyTransactionRequest,
+    signers::{k256::ecdsa::SigningKey, local::PrivateKeySigner},
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::{
+    config::SpeedUpConfig,
+    database::DatabaseManager,
+    queue::tracker::TransactionTracker,
+    services::EthereumProvider,
+    types::{Priority, RelayerError, Result},
+    utils::gas::GasPriceOracle,
+    wallet::pool::WalletPool,
+};
+
+/// Gas of a plain value transfer, all a cancellation needs
+const TRANSFER_GAS: u64 = 21_000;
+
+/// Why a wallet nonce was (re)broadcast
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BroadcastKind {
+    Original,
+    SpeedUp,
+    /// Zero-value self-transfer taking the nonce of a transaction the user cancelled
+    Cancel,
+}
+
+impl std::fmt::Display for BroadcastKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BroadcastKind::Original => write!(f, "original"),
+            BroadcastKind::SpeedUp => write!(f, "speed_up"),
+            BroadcastKind::Cancel => write!(f, "cancel"),
+        }
+    }
+}
+
+/// Fees of an EIP-1559 transaction, in wei per gas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fees {
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+}
+
+/// Everything a relayer wallet signed for one broadcast, kept so the same nonce can be
+/// signed again with other fees
+#[derive(Debug, Clone)]
+pub struct SentTransaction {
+    pub from: Address,
+    pub nonce: u64,
+    pub chain_id: u64,
+    pub to: Address,
+    pub value: U256,
+    pub input: Bytes,
+    pub gas_limit: u64,
+    pub fees: Fees,
+    pub priority: Priority,
+}
+
+impl SentTransaction {
+    pub fn with_fees(&self, fees: Fees) -> Self {
+        Self { fees, ..self.clone() }
+    }
+
+    /// Zero-value transfer to the sending wallet itself with the same nonce, which voids this
+    /// transaction if it is mined first
+    pub fn cancellation(&self, fees: Fees) -> Self {
+        Self {
+            to: self.from,
+            value: U256::ZERO,
+            input: Bytes::new(),
+            gas_limit: TRANSFER_GAS,
+            fees,
+            ..self.clone()
+        }
+    }
+
+    pub fn to_alloy_request(&self) -> AlloyTransactionRequest {
+        AlloyTransactionRequest::default()
+            .with_from(self.from)
+            .with_to(self.to)
+            .with_value(self.value)
+            .with_input(self.input.clone())
+            .with_gas_limit(self.gas_limit)
+            .with_max_fee_per_gas(self.fees.max_fee_per_gas)
+            .with_max_priority_fee_per_gas(self.fees.max_priority_fee_per_gas)
+            .with_nonce(self.nonce)
+            .with_chain_id(self.chain_id)
+    }
+}
+
+/// How long a transaction of `priority` may stay pending before it is sped up
+pub fn stuck_after(config: &SpeedUpConfig, priority: &Priority) -> Duration {
+    Duration::from_secs(match priority {
+        Priority::Low => config.low_after,
+        Priority::Normal => config.normal_after,
+        Priority::High => config.high_after,
+        Priority::Critical => config.critical_after,
+    })
+}
+
+/// Fees for a replacement of a transaction sent with `current` fees. Both fees rise by at
+/// least `bump_percent` (nodes only accept a replacement that raises both), and to
+/// `market` when that is higher. Returns `None` when the raise would exceed `cap`.
+pub fn replacement_fees(current: Fees, bump_percent: u32, market: Option<Fees>, cap: u128) -> Option<Fees> {
+    let bump = |fee: u128| -> u128 {
+        let raised = fee.saturating_mul(100 + bump_percent as u128).div_ceil(100);
+        // A zero fee still has to rise for the replacement to be accepted
+        raised.max(fee.saturating_add(1))
+    };
+
+    let mut max_priority_fee_per_gas = bump(current.max_priority_fee_per_gas);
+    let mut max_fee_per_gas = bump(current.max_fee_per_gas);
+    if let Some(market) = market {
+        max_priority_fee_per_gas = max_priority_fee_per_gas.max(market.max_priority_fee_per_gas);
+        max_fee_per_gas = max_fee_per_gas.max(market.max_fee_per_gas);
+    }
+    // The tip never exceeds the max fee; raising the max fee keeps the replacement valid
+    max_fee_per_gas = max_fee_per_gas.max(max_priority_fee_per_gas);
+
+    if max_fee_per_gas > cap {
+        return None;
+    }
+
+    Some(Fees { max_fee_per_gas, max_priority_fee_per_gas })
+}
+
+/// Sign `sent` with a key held in process, returning the raw transaction
+pub(crate) async fn sign_transaction(sent: &SentTransaction, private_key: &SigningKey) -> Result<Vec<u8>> {
+    let wallet = EthereumWallet::from(PrivateKeySigner::from_signing_key(private_key.clone()));
+    let envelope = sent.to_alloy_request().build(&wallet).await
+        .map_err(|e| RelayerError::Ethereum(format!("Failed to sign transaction: {}", e)))?;
+    Ok(envelope.encoded_2718())
+}
+
+/// Re-signs the nonce of a broadcast transaction: with higher fees when it is stuck, or as a
+/// zero-value self-transfer when the user cancels it. Replacements never offer a max fee per
+/// gas above `max_fee_cap`.
+#[derive(Debug, Clone)]
+pub struct TransactionReplacer {
+    wallet_pool: Arc<WalletPool>,
+    ethereum_provider: Arc<EthereumProvider>,
+    database: Arc<DatabaseManager>,
+    tracker: Arc<TransactionTracker>,
+    gas_price_oracle: Option<Arc<GasPriceOracle>>,
+    config: SpeedUpConfig,
+    max_fee_cap: u128,
+}
+
+impl TransactionReplacer {
+    pub fn new(
+        wallet_pool: Arc<WalletPool>,
+        ethereum_provider: Arc<EthereumProvider>,
+        database: Arc<DatabaseManager>,
+        tracker: Arc<TransactionTracker>,
+        config: SpeedUpConfig,
+        max_fee_cap: u128,
+    ) -> Self {
+        Self {
+            wallet_pool,
+            ethereum_provider,
+            database,
+            tracker,
+            gas_price_oracle: None,
+            config,
+            max_fee_cap,
+        }
+    }
+
+    /// Raise replacement fees to the oracle's recommendation when the market moved further
+    /// than the minimum bump
+    pub fn with_gas_price_oracle(mut self, gas_price_oracle: Arc<GasPriceOracle>) -> Self {
+        self.gas_price_oracle = Some(gas_price_oracle);
+        self
+    }
+
+    /// Start replacing transactions that stay pending longer than their priority allows
+    pub async fn start_speed_up_loop(&self) -> Result<()> {
+        tracing::info!("Starting speed-up loop...");
+        let mut interval = tokio::time::interval(Duration::from_secs(self.config.check_interval));
+
+        loop {
+            interval.tick().await;
+
+            for stuck in self.tracker.stuck_transactions(&self.config).await {
+                let transaction_id = stuck.transaction_id;
+                // A stuck cancellation is sped up as a cancellation
+                let kind = if stuck.cancelling { BroadcastKind::Cancel } else { BroadcastKind::SpeedUp };
+
+                let result = match self.replacement_fees(&stuck.sent).await {
+                    Ok(fees) => self.broadcast(transaction_id, stuck.sent.with_fees(fees), kind).await,
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(tx_hash) => {
+                        tracing::info!(
+                            "Sped up transaction {} after {}s: {}",
+                            transaction_id,
+                            stuck.pending_for.as_secs(),
+                            tx_hash
+                        );
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to speed up transaction {}: {}", transaction_id, e);
+                        self.tracker.mark_replacement_attempt(transaction_id).await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Cancel a broadcast transaction by racing it with a zero-value self-transfer of the
+    /// same nonce. Returns the cancellation's hash, or `None` when the transaction is not
+    /// being tracked. Whichever of the two is mined decides the final status.
+    pub async fn cancel(&self, transaction_id: Uuid) -> Result<Option<String>> {
+        let Some(sent) = self.tracker.cancel_target(transaction_id).await? else {
+            return Ok(None);
+        };
+
+        let fees = self.replacement_fees(&sent).await?;
+        let tx_hash = self.broadcast(transaction_id, sent.cancellation(fees), BroadcastKind::Cancel).await?;
+        tracing::info!("Cancelling transaction {} with {}", transaction_id, tx_hash);
+
+        Ok(Some(tx_hash))
+    }
+
+    async fn replacement_fees(&self, sent: &SentTransaction) -> Result<Fees> {
+        let market = match self.gas_price_oracle {
+            Some(ref oracle) => oracle.get_recommended_gas_price(&sent.priority.to_string()).await
+                .ok()
+                .map(|gas| Fees {
+                    max_fee_per_gas: gas.max_fee_per_gas.to::<u128>(),
+                    max_priority_fee_per_gas: gas.max_priority_fee_per_gas.to::<u128>(),
+                }),
+            None => None,
+        };
+
+        replacement_fees(sent.fees, self.config.fee_bump_percent, market, self.max_fee_cap)
+            .ok_or_else(|| RelayerError::Ethereum(format!(
+                "Replacement fees would exceed the cap of {} wei per gas",
+                self.max_fee_cap
+            )))
+    }
+
+    /// Sign and send a replacement, then track and record it under the same transaction
+    async fn broadcast(&self, transaction_id: Uuid, replacement: SentTransaction, kind: BroadcastKind) -> Result<String> {
+        let signer = {
+            let wallets = self.wallet_pool.wallets();
+            let wallets = wallets.read().await;
+            wallets.iter()
+                .find(|wallet| wallet.address == replacement.from)
+                .and_then(|wallet| wallet.signer.clone())
+                .ok_or_else(|| RelayerError::WalletPool(format!("Wallet {:?} is no longer in the pool", replacement.from)))?
+        };
+
+        let encoded = signer.sign_transaction(&replacement).await?;
+        let pending_tx = self.ethereum_provider.send_raw_transaction(&encoded).await
+            .map_err(|e| RelayerError::Ethereum(format!("Failed to send replacement: {}", e)))?;
+        let tx_hash = format!("{:?}", pending_tx.tx_hash());
+
+        if let Err(e) = self.database.record_broadcast(transaction_id, &tx_hash, &replacement, kind).await {
+            tracing::warn!("Failed to record broadcast {}: {}", tx_hash, e);
+        }
+        self.tracker.add_replacement(transaction_id, tx_hash.clone(), replacement, kind).await?;
+
+        Ok(tx_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GWEI: u128 = 1_000_000_000;
+
+    #[test]
+    fn test_replacement_fees_bump_both_fees() {
+        let current = Fees { max_fee_per_gas: 30 * GWEI, max_priority_fee_per_gas: 2 * GWEI };
+
+        let fees = replacement_fees(current, 12, None, 100 * GWEI).unwrap();
+        assert_eq!(fees.max_fee_per_gas, 33_600_000_000);
+        assert_eq!(fees.max_priority_fee_per_gas, 2_240_000_000);
+
+        // Rounds up so the 10% minimum is never missed by a wei
+        let fees = replacement_fees(Fees { max_fee_per_gas: 15, max_priority_fee_per_gas: 0 }, 10, None, u128::MAX).unwrap();
+        assert_eq!(fees, Fees { max_fee_per_gas: 17, max_priority_fee_per_gas: 1 });
+    }
+
+    #[test]
+    fn test_replacement_fees_follow_market_and_cap() {
+        let current = Fees { max_fee_per_gas: 30 * GWEI, max_priority_fee_per_gas: 2 * GWEI };
+        let market = Fees { max_fee_per_gas: 50 * GWEI, max_priority_fee_per_gas: GWEI };
+
+        let fees = replacement_fees(current, 12, Some(market), 100 * GWEI).unwrap();
+        assert_eq!(fees.max_fee_per_gas, 50 * GWEI);
+        assert_eq!(fees.max_priority_fee_per_gas, 2_240_000_000);
+
+        assert!(replacement_fees(current, 12, Some(market), 40 * GWEI).is_none());
+        assert!(replacement_fees(current, 12, None, 33 * GWEI).is_none());
+    }
+
+    #[test]
+    fn test_cancellation_is_a_self_transfer() {
+        let sent = SentTransaction {
+            from: Address::repeat_byte(0x01),
+            nonce: 42,
+            chain_id: 8453,
+            to: Address::repeat_byte(0x02),
+            value: U256::from(5),
+            input: Bytes::from(vec![0xa9, 0x05, 0x9c, 0xbb]),
+            gas_limit: 120_000,
+            fees: Fees { max_fee_per_gas: 30 * GWEI, max_priority_fee_per_gas: 2 * GWEI },
+            priority: Priority::High,
+        };
+        let fees = replacement_fees(sent.fees, 12, None, u128::MAX).unwrap();
+
+        let cancel = sent.cancellation(fees);
+        assert_eq!(cancel.to, sent.from);
+        assert_eq!(cancel.value, U256::ZERO);
+        assert!(cancel.input.is_empty());
+        assert_eq!(cancel.gas_limit, 21_000);
+        assert_eq!((cancel.nonce, cancel.chain_id), (42, 8453));
+        assert_eq!(cancel.fees, fees);
+    }
+
+    #[test]
+    fn test_stuck_after_per_priority() {
+        let config = SpeedUpConfig::default();
+        assert_eq!(stuck_after(&config, &Priority::Critical), Duration::from_secs(config.critical_after));
+        assert_eq!(stuck_after(&config, &Priority::Low), Duration::from_secs(config.low_after));
+        assert!(stuck_after(&config, &Priority::High) < stuck_after(&config, &Priority::Normal));
+    }
+}
@@ -1,10 +1,11 @@
+use serde::{Deserialize, Serialize};
 use std::collections::{BinaryHeap, HashMap, VecDeque};
 use std::sync::Arc;
 use tokio::sync::{RwLock, Semaphore};
 use tokio::time::{Duration, Instant};
 use uuid::Uuid;
 
-use crate::types::{RelayerError, Result, TransactionRequest, Priority};
+use crate::types::{RelayerError, Result, TransactionRequest};
 
 #[derive(Debug)]
 pub struct TaskScheduler {
@@ -12,12 +13,13 @@ pub struct TaskScheduler {
     processing_queue: Arc<RwLock<VecDeque<ScheduledTask>>>,
     completed_tasks: Arc<RwLock<HashMap<Uuid, TaskResult>>>,
     failed_tasks: Arc<RwLock<HashMap<Uuid, TaskResult>>>,
+    expired_tasks: Arc<RwLock<HashMap<Uuid, TaskResult>>>,
     semaphore: Arc<Semaphore>,
     max_queue_size: usize,
     processing_timeout: Duration,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub struct ScheduledTask {
     pub id: Uuid,
     pub request: TransactionRequest,
@@ -28,11 +30,19 @@ pub struct ScheduledTask {
     pub max_retries: u32,
 }
 
+impl PartialEq for ScheduledTask {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for ScheduledTask {}
+
 impl Ord for ScheduledTask {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        // Higher priority first, then by scheduled time
-        other.priority.cmp(&self.priority)
-            .then_with(|| self.scheduled_at.cmp(&other.scheduled_at))
+        // BinaryHeap is a max-heap: higher priority first, then earliest scheduled time
+        self.priority.cmp(&other.priority)
+            .then_with(|| other.scheduled_at.cmp(&self.scheduled_at))
     }
 }
 
@@ -59,6 +69,7 @@ impl TaskScheduler {
             processing_queue: Arc::new(RwLock::new(VecDeque::new())),
             completed_tasks: Arc::new(RwLock::new(HashMap::new())),
             failed_tasks: Arc::new(RwLock::new(HashMap::new())),
+            expired_tasks: Arc::new(RwLock::new(HashMap::new())),
             semaphore: Arc::new(Semaphore::new(max_concurrent)),
             max_queue_size,
             processing_timeout,
@@ -75,8 +86,9 @@ impl TaskScheduler {
         }
 
         let priority = request.priority.weight();
+        let task_id = request.id;
         let task = ScheduledTask {
-            id: request.id,
+            id: task_id,
             request,
             priority,
             created_at: Instant::now(),
@@ -90,8 +102,8 @@ impl TaskScheduler {
             queue.push(task);
         }
 
-        tracing::info!("Scheduled task {} with priority {}", task.id, priority);
-        Ok(task.id)
+        tracing::info!("Scheduled task {} with priority {}", task_id, priority);
+        Ok(task_id)
     }
 
     pub async fn get_next_task(&self) -> Result<Option<ScheduledTask>> {
@@ -142,6 +154,27 @@ impl TaskScheduler {
         Ok(())
     }
 
+    /// Finish a task whose validity window closed before it was sent. It is not retried.
+    pub async fn expire_task(&self, task_id: Uuid, error_message: String) -> Result<()> {
+        let result = TaskResult {
+            id: task_id,
+            success: false,
+            tx_hash: None,
+            error_message: Some(error_message),
+            processing_time: Duration::ZERO,
+            completed_at: Instant::now(),
+        };
+        self.expired_tasks.write().await.insert(task_id, result);
+
+        {
+            let mut processing_queue = self.processing_queue.write().await;
+            processing_queue.retain(|task| task.id != task_id);
+        }
+
+        tracing::info!("Expired task {}", task_id);
+        Ok(())
+    }
+
     pub async fn retry_task(&self, task_id: Uuid) -> Result<()> {
         // Find the task in processing queue
         let mut task_to_retry = None;
@@ -158,13 +191,14 @@ impl TaskScheduler {
             if task.retry_count <= task.max_retries {
                 // Add delay before retry
                 task.scheduled_at = Instant::now() + Duration::from_secs(5 * task.retry_count as u64);
-                
+                let attempt = task.retry_count;
+
                 {
                     let mut queue = self.priority_queue.write().await;
                     queue.push(task);
                 }
 
-                tracing::info!("Retrying task {} (attempt {})", task_id, task.retry_count);
+                tracing::info!("Retrying task {} (attempt {})", task_id, attempt);
             } else {
                 // Max retries exceeded, mark as failed
                 self.complete_task(task_id, false, None, Some("Max retries exceeded".to_string())).await?;
@@ -179,6 +213,7 @@ impl TaskScheduler {
         let processing_queue = self.processing_queue.read().await;
         let completed_tasks = self.completed_tasks.read().await;
         let failed_tasks = self.failed_tasks.read().await;
+        let expired_tasks = self.expired_tasks.read().await;
 
         let pending_count = priority_queue.len();
         let processing_count = processing_queue.len();
@@ -191,6 +226,7 @@ impl TaskScheduler {
             processing_tasks: processing_count,
             completed_tasks: completed_count,
             failed_tasks: failed_count,
+            expired_tasks: expired_tasks.len(),
             available_permits,
             max_queue_size: self.max_queue_size,
             processing_timeout_seconds: self.processing_timeout.as_secs(),
@@ -230,9 +266,42 @@ impl TaskScheduler {
             }
         }
 
+        if self.expired_tasks.read().await.contains_key(&task_id) {
+            return Ok(TaskStatus::Expired);
+        }
+
         Ok(TaskStatus::NotFound)
     }
 
+    pub async fn get_task_result(&self, task_id: Uuid) -> Option<TaskResult> {
+        if let Some(result) = self.completed_tasks.read().await.get(&task_id) {
+            return Some(result.clone());
+        }
+
+        if let Some(result) = self.failed_tasks.read().await.get(&task_id) {
+            return Some(result.clone());
+        }
+
+        self.expired_tasks.read().await.get(&task_id).cloned()
+    }
+
+    /// Wait until a task has completed or failed, polling its result
+    pub async fn wait_for_result(&self, task_id: Uuid, timeout: Duration) -> Result<TaskResult> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if let Some(result) = self.get_task_result(task_id).await {
+                return Ok(result);
+            }
+
+            if Instant::now() >= deadline {
+                return Err(RelayerError::Timeout(format!("Task {} did not complete in time", task_id)));
+            }
+
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
     pub async fn cancel_task(&self, task_id: Uuid) -> Result<bool> {
         let mut cancelled = false;
 
@@ -279,6 +348,14 @@ impl TaskScheduler {
             removed_count += original_len - failed.len();
         }
 
+        // Clear old expired tasks
+        {
+            let mut expired = self.expired_tasks.write().await;
+            let original_len = expired.len();
+            expired.retain(|_, result| result.completed_at > cutoff_time);
+            removed_count += original_len - expired.len();
+        }
+
         tracing::info!("Cleared {} old tasks", removed_count);
         Ok(removed_count)
     }
@@ -290,15 +367,17 @@ pub enum TaskStatus {
     Processing,
     Completed,
     Failed,
+    Expired,
     NotFound,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct QueueStats {
     pub pending_tasks: usize,
     pub processing_tasks: usize,
     pub completed_tasks: usize,
     pub failed_tasks: usize,
+    pub expired_tasks: usize,
     pub available_permits: usize,
     pub max_queue_size: usize,
     pub processing_timeout_seconds: u64,
@@ -311,6 +390,7 @@ impl Clone for TaskScheduler {
             processing_queue: Arc::clone(&self.processing_queue),
             completed_tasks: Arc::clone(&self.completed_tasks),
             failed_tasks: Arc::clone(&self.failed_tasks),
+            expired_tasks: Arc::clone(&self.expired_tasks),
             semaphore: Arc::clone(&self.semaphore),
             max_queue_size: self.max_queue_size,
             processing_timeout: self.processing_timeout,
@@ -402,4 +482,34 @@ mod tests {
         let stats = scheduler.get_queue_stats().await.unwrap();
         assert_eq!(stats.completed_tasks, 1);
     }
+
+    #[tokio::test]
+    async fn test_wait_for_result() {
+        let scheduler = TaskScheduler::new(5, 1000, Duration::from_secs(300));
+        let task_id = scheduler.schedule_task(create_test_request()).await.unwrap();
+
+        assert!(scheduler.wait_for_result(task_id, Duration::from_millis(150)).await.is_err());
+
+        scheduler.complete_task(task_id, false, None, Some("reverted".to_string())).await.unwrap();
+        let result = scheduler.wait_for_result(task_id, Duration::from_secs(1)).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error_message.as_deref(), Some("reverted"));
+    }
+
+    #[tokio::test]
+    async fn test_expire_task() {
+        let scheduler = TaskScheduler::new(5, 1000, Duration::from_secs(300));
+        let task_id = scheduler.schedule_task(create_test_request()).await.unwrap();
+
+        let task = scheduler.get_next_task().await.unwrap().unwrap();
+        scheduler.start_processing(task).await.unwrap();
+        scheduler.expire_task(task_id, "valid_before has passed".to_string()).await.unwrap();
+
+        assert!(matches!(scheduler.get_task_status(task_id).await.unwrap(), TaskStatus::Expired));
+        let stats = scheduler.get_queue_stats().await.unwrap();
+        assert_eq!((stats.processing_tasks, stats.failed_tasks, stats.expired_tasks), (0, 0, 1));
+
+        let result = scheduler.wait_for_result(task_id, Duration::from_secs(1)).await.unwrap();
+        assert!(!result.success);
+    }
 }
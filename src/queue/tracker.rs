@@ -1,9 +1,7 @@
 use alloy::{
-    primitives::{B256, U256},
-    providers::RootProvider,
+    primitives::B256,
+    providers::Provider,
 };
-use chrono::Utc;
-use hex;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -11,32 +9,58 @@ use tokio::time::{Duration, Instant};
 use uuid::Uuid;
 
 use crate::{
-    // database::DatabaseManager,  // Temporarily disabled
+    config::SpeedUpConfig,
+    database::DatabaseManager,
+    queue::{stuck_after, BroadcastKind, SentTransaction},
+    security::{BudgetManager, ReputationEvent, ReputationManager},
+    services::EthereumProvider,
     types::{RelayerError, Result, TransactionStatus},
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct TransactionTracker {
-    // database: Arc<DatabaseManager>,  // Temporarily disabled
-    ethereum_provider: Arc<RootProvider<alloy::transports::http::Http<alloy::transports::http::reqwest::Client>>>,
-    pending_transactions: Arc<RwLock<HashMap<String, PendingTransaction>>>,
+    database: Arc<DatabaseManager>,
+    ethereum_provider: Arc<EthereumProvider>,
+    pending_transactions: Arc<RwLock<HashMap<Uuid, PendingTransaction>>>,
     check_interval: Duration,
     confirmation_blocks: u64,
+    budget_manager: Option<Arc<BudgetManager>>,
+    reputation_manager: Option<Arc<ReputationManager>>,
 }
 
 #[derive(Debug, Clone)]
 struct PendingTransaction {
     transaction_id: Uuid,
-    tx_hash: String,
+    /// Every hash broadcast for this transaction, oldest first
+    broadcasts: Vec<(String, BroadcastKind)>,
+    /// Last signed broadcast, needed to replace it
+    sent: Option<SentTransaction>,
     submitted_at: Instant,
+    last_broadcast_at: Instant,
+    replacements: u32,
+    /// One of the hashes is mined and waiting for confirmations
+    mined: bool,
+    /// A cancellation was broadcast and races the transaction for its nonce
+    cancelling: bool,
     last_checked: Instant,
     check_count: u32,
 }
 
+/// A transaction pending longer than its priority allows
+#[derive(Debug, Clone)]
+pub struct StuckTransaction {
+    pub transaction_id: Uuid,
+    pub sent: SentTransaction,
+    pub replacements: u32,
+    pub pending_for: Duration,
+    /// The last broadcast is a cancellation
+    pub cancelling: bool,
+}
+
 impl TransactionTracker {
     pub fn new(
         database: Arc<DatabaseManager>,
-        ethereum_provider: Arc<RootProvider>,
+        ethereum_provider: Arc<EthereumProvider>,
         check_interval: Duration,
         confirmation_blocks: u64,
     ) -> Self {
@@ -46,26 +70,162 @@ impl TransactionTracker {
             pending_transactions: Arc::new(RwLock::new(HashMap::new())),
             check_interval,
             confirmation_blocks,
+            budget_manager: None,
+            reputation_manager: None,
+        }
+    }
+
+    /// Reconcile spending budgets with the actual gas cost of mined transactions
+    pub fn with_budget_manager(mut self, budget_manager: Arc<BudgetManager>) -> Self {
+        self.budget_manager = Some(budget_manager);
+        self
+    }
+
+    /// Feed mined outcomes into the submitters' reputation
+    pub fn with_reputation_manager(mut self, reputation_manager: Arc<ReputationManager>) -> Self {
+        self.reputation_manager = Some(reputation_manager);
+        self
+    }
+
+    async fn record_outcome(&self, transaction_id: Uuid, event: ReputationEvent) {
+        if let Some(ref reputation_manager) = self.reputation_manager {
+            reputation_manager.record_outcome(transaction_id, event).await;
+        }
+    }
+
+    async fn reconcile_budget(&self, transaction_id: Uuid, gas_used: &str, effective_gas_price: u128) {
+        let Some(ref budget_manager) = self.budget_manager else {
+            return;
+        };
+
+        let gas_used = gas_used.parse::<u64>().unwrap_or_default();
+        if let Err(e) = budget_manager.reconcile(transaction_id, gas_used, effective_gas_price).await {
+            tracing::warn!("Failed to reconcile budget for transaction {}: {}", transaction_id, e);
         }
     }
 
     /// Add a transaction to track
     pub async fn add_transaction(&self, transaction_id: Uuid, tx_hash: String) -> Result<()> {
+        self.insert_pending(transaction_id, tx_hash, None).await;
+        Ok(())
+    }
+
+    /// Add a transaction to track together with what was signed, so it can be sped up
+    pub async fn add_sent_transaction(&self, transaction_id: Uuid, tx_hash: String, sent: SentTransaction) -> Result<()> {
+        self.insert_pending(transaction_id, tx_hash, Some(sent)).await;
+        Ok(())
+    }
+
+    async fn insert_pending(&self, transaction_id: Uuid, tx_hash: String, sent: Option<SentTransaction>) {
+        let now = Instant::now();
         let pending = PendingTransaction {
             transaction_id,
-            tx_hash: tx_hash.clone(),
-            submitted_at: Instant::now(),
-            last_checked: Instant::now(),
+            broadcasts: vec![(tx_hash.clone(), BroadcastKind::Original)],
+            sent,
+            submitted_at: now,
+            last_broadcast_at: now,
+            replacements: 0,
+            mined: false,
+            cancelling: false,
+            last_checked: now,
             check_count: 0,
         };
 
         let mut pending_map = self.pending_transactions.write().await;
-        pending_map.insert(tx_hash.clone(), pending);
+        pending_map.insert(transaction_id, pending);
 
         tracing::info!("Added transaction {} ({}) to tracker", transaction_id, tx_hash);
+    }
+
+    /// Track a replacement broadcast of a pending transaction alongside its earlier hashes.
+    /// The first cancellation does not count against the speed-up limit.
+    pub async fn add_replacement(
+        &self,
+        transaction_id: Uuid,
+        tx_hash: String,
+        sent: SentTransaction,
+        kind: BroadcastKind,
+    ) -> Result<()> {
+        let mut pending_map = self.pending_transactions.write().await;
+        let pending = pending_map.get_mut(&transaction_id)
+            .ok_or_else(|| RelayerError::Queue(format!("Transaction {} is not tracked", transaction_id)))?;
+
+        if kind == BroadcastKind::Cancel && !pending.cancelling {
+            pending.cancelling = true;
+        } else {
+            pending.replacements += 1;
+        }
+        pending.broadcasts.push((tx_hash.clone(), kind));
+        pending.sent = Some(sent);
+        pending.last_broadcast_at = Instant::now();
+
+        tracing::info!(
+            "Tracking {} {} of transaction {} ({} hashes)",
+            kind,
+            tx_hash,
+            transaction_id,
+            pending.broadcasts.len()
+        );
         Ok(())
     }
 
+    /// What a cancellation of a tracked transaction has to replace, or `None` when the
+    /// transaction is not tracked. Fails when it can no longer be cancelled.
+    pub async fn cancel_target(&self, transaction_id: Uuid) -> Result<Option<SentTransaction>> {
+        let pending_map = self.pending_transactions.read().await;
+        let Some(pending) = pending_map.get(&transaction_id) else {
+            return Ok(None);
+        };
+
+        if pending.mined {
+            return Err(RelayerError::Validation("Transaction is already mined".to_string()));
+        }
+        if pending.cancelling {
+            return Err(RelayerError::Validation("Transaction is already being cancelled".to_string()));
+        }
+        pending.sent.clone()
+            .map(Some)
+            .ok_or_else(|| RelayerError::Validation("Transaction was not sent by this relayer".to_string()))
+    }
+
+    /// Count a replacement that could not be broadcast, so the transaction waits a full
+    /// threshold before the next attempt
+    pub async fn mark_replacement_attempt(&self, transaction_id: Uuid) {
+        let mut pending_map = self.pending_transactions.write().await;
+        if let Some(pending) = pending_map.get_mut(&transaction_id) {
+            pending.replacements += 1;
+            pending.last_broadcast_at = Instant::now();
+        }
+    }
+
+    /// Hashes broadcast for a tracked transaction, oldest first
+    pub async fn transaction_hashes(&self, transaction_id: Uuid) -> Vec<String> {
+        self.pending_transactions.read().await
+            .get(&transaction_id)
+            .map(|pending| pending.broadcasts.iter().map(|(tx_hash, _)| tx_hash.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Transactions not mined within their priority's threshold since the last broadcast
+    pub async fn stuck_transactions(&self, config: &SpeedUpConfig) -> Vec<StuckTransaction> {
+        let pending_map = self.pending_transactions.read().await;
+
+        pending_map.values()
+            .filter(|pending| !pending.mined && pending.replacements < config.max_replacements)
+            .filter_map(|pending| {
+                let sent = pending.sent.as_ref()?;
+                let pending_for = pending.last_broadcast_at.elapsed();
+                (pending_for >= stuck_after(config, &sent.priority)).then(|| StuckTransaction {
+                    transaction_id: pending.transaction_id,
+                    sent: sent.clone(),
+                    replacements: pending.replacements,
+                    pending_for,
+                    cancelling: pending.cancelling,
+                })
+            })
+            .collect()
+    }
+
     /// Check transaction status on blockchain
     pub async fn check_transaction(&self, tx_hash: &str) -> Result<TransactionCheckResult> {
         // Parse transaction hash
@@ -105,19 +265,19 @@ impl TransactionTracker {
         let block_number = receipt.block_number
             .ok_or_else(|| RelayerError::Ethereum("Receipt missing block number".to_string()))?;
 
-        let block_number_u64 = block_number.to::<u64>();
-        let current_block_u64 = current_block.to::<u64>();
+        let block_number_u64 = block_number;
+        let current_block_u64 = current_block;
         let confirmations = current_block_u64.saturating_sub(block_number_u64);
 
-        let gas_used = receipt.gas_used.unwrap_or(U256::ZERO);
-        let gas_used_str = gas_used.to_string();
+        let gas_used_str = receipt.gas_used.to_string();
 
-        if receipt.status == Some(1) {
+        if receipt.status() {
             // Transaction successful
             if confirmations >= self.confirmation_blocks {
                 Ok(TransactionCheckResult::Confirmed {
                     block_number: block_number_u64,
                     gas_used: gas_used_str,
+                    effective_gas_price: receipt.effective_gas_price,
                     confirmations,
                 })
             } else {
@@ -132,6 +292,7 @@ impl TransactionTracker {
             Ok(TransactionCheckResult::Failed {
                 block_number: block_number_u64,
                 gas_used: gas_used_str,
+                effective_gas_price: receipt.effective_gas_price,
             })
         }
     }
@@ -146,92 +307,146 @@ impl TransactionTracker {
         // Get pending transactions
         let pending_map = self.pending_transactions.read().await.clone();
         
-        for (tx_hash, pending) in pending_map.iter() {
+        for pending in pending_map.values() {
             // Check if enough time has passed since last check
             if pending.last_checked.elapsed() < self.check_interval {
                 continue;
             }
 
-            match self.check_transaction(tx_hash).await {
-                Ok(result) => {
-                    processed += 1;
-
-                    match result {
-                        TransactionCheckResult::Confirmed { block_number, gas_used, .. } => {
-                            confirmed += 1;
-                            
-                            // Update database
-                            if let Err(e) = self.database.update_transaction_status(
-                                pending.transaction_id,
-                                TransactionStatus::Confirmed,
-                                Some(tx_hash.clone()),
-                                Some(block_number),
-                                Some(gas_used),
-                                None,
-                            ).await {
-                                tracing::error!("Failed to update transaction {}: {}", pending.transaction_id, e);
-                                errors += 1;
-                            } else {
-                                tracing::info!("Transaction {} confirmed in block {}", pending.transaction_id, block_number);
-                                
-                                // Remove from pending
-                                let mut pending_map = self.pending_transactions.write().await;
-                                pending_map.remove(tx_hash);
-                            }
-                        }
-                        TransactionCheckResult::Failed { block_number, gas_used } => {
-                            failed += 1;
-                            
-                            // Update database
-                            if let Err(e) = self.database.update_transaction_status(
-                                pending.transaction_id,
-                                TransactionStatus::Failed,
-                                Some(tx_hash.clone()),
-                                Some(block_number),
-                                Some(gas_used),
-                                Some("Transaction reverted".to_string()),
-                            ).await {
-                                tracing::error!("Failed to update transaction {}: {}", pending.transaction_id, e);
-                                errors += 1;
-                            } else {
-                                tracing::warn!("Transaction {} failed in block {}", pending.transaction_id, block_number);
-                                
-                                // Remove from pending
-                                let mut pending_map = self.pending_transactions.write().await;
-                                pending_map.remove(tx_hash);
-                            }
-                        }
-                        TransactionCheckResult::Processing { .. } => {
-                            // Update last checked time
-                            let mut pending_map = self.pending_transactions.write().await;
-                            if let Some(pending) = pending_map.get_mut(tx_hash) {
-                                pending.last_checked = Instant::now();
-                                pending.check_count += 1;
-                                
-                                // Update database status to processing
-                                let _ = self.database.update_transaction_status(
-                                    pending.transaction_id,
-                                    TransactionStatus::Processing,
-                                    Some(tx_hash.clone()),
-                                    None,
-                                    None,
-                                    None,
-                                ).await;
-                            }
-                        }
-                        TransactionCheckResult::Pending => {
-                            // Still pending, update last checked time
-                            let mut pending_map = self.pending_transactions.write().await;
-                            if let Some(pending) = pending_map.get_mut(tx_hash) {
-                                pending.last_checked = Instant::now();
-                                pending.check_count += 1;
-                            }
+            // At most one broadcast of a nonce can be mined; the newest is the likeliest
+            let mut mined = None;
+            let mut check_error = false;
+            for (tx_hash, kind) in pending.broadcasts.iter().rev() {
+                match self.check_transaction(tx_hash).await {
+                    Ok(TransactionCheckResult::Pending) => {}
+                    Ok(result) => {
+                        mined = Some((tx_hash.clone(), *kind, result));
+                        break;
+                    }
+                    Err(e) => {
+                        tracing::error!("Error checking transaction {}: {}", tx_hash, e);
+                        check_error = true;
+                    }
+                }
+            }
+
+            if check_error && mined.is_none() {
+                errors += 1;
+                continue;
+            }
+            processed += 1;
+
+            let transaction_id = pending.transaction_id;
+            match mined {
+                Some((tx_hash, BroadcastKind::Cancel, TransactionCheckResult::Confirmed { block_number, gas_used, effective_gas_price, .. })) => {
+                    // The cancellation won the nonce, so the transaction itself never ran
+                    if let Err(e) = self.database.update_transaction_status(
+                        transaction_id,
+                        TransactionStatus::Cancelled,
+                        Some(tx_hash.clone()),
+                        Some(block_number),
+                        Some(gas_used.clone()),
+                        Some("Cancelled by user".to_string()),
+                    ).await {
+                        tracing::error!("Failed to update transaction {}: {}", transaction_id, e);
+                        errors += 1;
+                    } else {
+                        tracing::info!("Transaction {} cancelled in block {} by {}", transaction_id, block_number, tx_hash);
+                        self.record_mined_broadcast(transaction_id, &tx_hash).await;
+                        // The relayer still paid for the cancellation's gas
+                        self.reconcile_budget(transaction_id, &gas_used, effective_gas_price).await;
+                        self.record_outcome(transaction_id, ReputationEvent::Cancelled).await;
+
+                        let mut pending_map = self.pending_transactions.write().await;
+                        pending_map.remove(&transaction_id);
+                    }
+                }
+                Some((tx_hash, _, TransactionCheckResult::Confirmed { block_number, gas_used, effective_gas_price, .. })) => {
+                    confirmed += 1;
+                    
+                    // Update database
+                    if let Err(e) = self.database.update_transaction_status(
+                        transaction_id,
+                        TransactionStatus::Confirmed,
+                        Some(tx_hash.clone()),
+                        Some(block_number),
+                        Some(gas_used.clone()),
+                        None,
+                    ).await {
+                        tracing::error!("Failed to update transaction {}: {}", transaction_id, e);
+                        errors += 1;
+                    } else {
+                        tracing::info!("Transaction {} confirmed in block {} as {}", transaction_id, block_number, tx_hash);
+                        self.record_mined_broadcast(transaction_id, &tx_hash).await;
+                        self.reconcile_budget(transaction_id, &gas_used, effective_gas_price).await;
+                        self.record_outcome(transaction_id, ReputationEvent::Confirmed).await;
+                        
+                        // Remove from pending
+                        let mut pending_map = self.pending_transactions.write().await;
+                        pending_map.remove(&transaction_id);
+                    }
+                }
+                Some((tx_hash, _, TransactionCheckResult::Failed { block_number, gas_used, effective_gas_price })) => {
+                    failed += 1;
+                    
+                    // Update database
+                    if let Err(e) = self.database.update_transaction_status(
+                        transaction_id,
+                        TransactionStatus::Failed,
+                        Some(tx_hash.clone()),
+                        Some(block_number),
+                        Some(gas_used.clone()),
+                        Some("Transaction reverted".to_string()),
+                    ).await {
+                        tracing::error!("Failed to update transaction {}: {}", transaction_id, e);
+                        errors += 1;
+                    } else {
+                        tracing::warn!("Transaction {} failed in block {} as {}", transaction_id, block_number, tx_hash);
+                        self.record_mined_broadcast(transaction_id, &tx_hash).await;
+                        // A reverted transaction still paid for its gas
+                        self.reconcile_budget(transaction_id, &gas_used, effective_gas_price).await;
+                        self.record_outcome(transaction_id, ReputationEvent::Reverted).await;
+                        
+                        // Remove from pending
+                        let mut pending_map = self.pending_transactions.write().await;
+                        pending_map.remove(&transaction_id);
+                    }
+                }
+                Some((tx_hash, kind, TransactionCheckResult::Processing { .. })) => {
+                    // Update last checked time
+                    let mut pending_map = self.pending_transactions.write().await;
+                    if let Some(pending) = pending_map.get_mut(&transaction_id) {
+                        pending.last_checked = Instant::now();
+                        pending.check_count += 1;
+                        // Mined, so it must no longer be replaced
+                        pending.mined = true;
+
+                        // A mined cancellation leaves the transaction submitted until it is
+                        // confirmed as cancelled; processing would report the payment going through
+                        if kind == BroadcastKind::Cancel {
+                            continue;
                         }
+
+                        // Update database status to processing
+                        let _ = self.database.update_transaction_status(
+                            transaction_id,
+                            TransactionStatus::Processing,
+                            Some(tx_hash),
+                            None,
+                            None,
+                            None,
+                        ).await;
                     }
                 }
-                Err(e) => {
-                    tracing::error!("Error checking transaction {}: {}", tx_hash, e);
-                    errors += 1;
+                Some((_, _, TransactionCheckResult::Pending)) | None => {
+                    // Still pending, update last checked time
+                    let mut pending_map = self.pending_transactions.write().await;
+                    if let Some(pending) = pending_map.get_mut(&transaction_id) {
+                        pending.last_checked = Instant::now();
+                        pending.check_count += 1;
+                        // A mined broadcast can drop out again in a reorg
+                        pending.mined = false;
+                    }
                 }
             }
         }
@@ -245,6 +460,12 @@ impl TransactionTracker {
         })
     }
 
+    async fn record_mined_broadcast(&self, transaction_id: Uuid, tx_hash: &str) {
+        if let Err(e) = self.database.mark_broadcast_mined(transaction_id, tx_hash).await {
+            tracing::warn!("Failed to record mined broadcast {} of transaction {}: {}", tx_hash, transaction_id, e);
+        }
+    }
+
     /// Start the tracking loop
     pub async fn start_tracking_loop(&self) -> Result<()> {
         tracing::info!("Starting transaction tracking loop...");
@@ -298,10 +519,13 @@ impl TransactionTracker {
         })
     }
 
-    /// Remove a transaction from tracking (e.g., if manually cancelled)
+    /// Remove a transaction from tracking (e.g., if manually cancelled) by any of its hashes
     pub async fn remove_transaction(&self, tx_hash: &str) -> Result<bool> {
         let mut pending_map = self.pending_transactions.write().await;
-        Ok(pending_map.remove(tx_hash).is_some())
+        let transaction_id = pending_map.values()
+            .find(|pending| pending.broadcasts.iter().any(|(hash, _)| hash == tx_hash))
+            .map(|pending| pending.transaction_id);
+        Ok(transaction_id.and_then(|id| pending_map.remove(&id)).is_some())
     }
 }
 
@@ -313,6 +537,8 @@ impl Clone for TransactionTracker {
             pending_transactions: Arc::clone(&self.pending_transactions),
             check_interval: self.check_interval,
             confirmation_blocks: self.confirmation_blocks,
+            budget_manager: self.budget_manager.clone(),
+            reputation_manager: self.reputation_manager.clone(),
         }
     }
 }
@@ -328,11 +554,13 @@ pub enum TransactionCheckResult {
     Confirmed {
         block_number: u64,
         gas_used: String,
+        effective_gas_price: u128,
         confirmations: u64,
     },
     Failed {
         block_number: u64,
         gas_used: String,
+        effective_gas_price: u128,
     },
 }
 
@@ -353,3 +581,113 @@ pub struct TrackingStats {
     pub confirmation_blocks: u64,
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::Fees;
+    use crate::types::Priority;
+    use alloy::primitives::{Address, Bytes, U256};
+
+    fn test_tracker() -> TransactionTracker {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .acquire_timeout(std::time::Duration::from_secs(1))
+            .connect_lazy("postgresql://127.0.0.1:1/express402_test")
+            .unwrap();
+        let provider = alloy::providers::ProviderBuilder::new()
+            .on_http("http://127.0.0.1:1".parse().unwrap());
+        TransactionTracker::new(
+            Arc::new(DatabaseManager::from_pool(pool)),
+            Arc::new(provider),
+            Duration::from_secs(5),
+            1,
+        )
+    }
+
+    fn sent(priority: Priority) -> SentTransaction {
+        SentTransaction {
+            from: Address::repeat_byte(0x01),
+            nonce: 7,
+            chain_id: 1,
+            to: Address::repeat_byte(0x02),
+            value: U256::ZERO,
+            input: Bytes::new(),
+            gas_limit: 100_000,
+            fees: Fees { max_fee_per_gas: 30_000_000_000, max_priority_fee_per_gas: 2_000_000_000 },
+            priority,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stuck_transactions_use_priority_thresholds() {
+        let tracker = test_tracker();
+        let critical = Uuid::new_v4();
+        let low = Uuid::new_v4();
+        let untracked_fees = Uuid::new_v4();
+        tracker.add_sent_transaction(critical, "0xaa".to_string(), sent(Priority::Critical)).await.unwrap();
+        tracker.add_sent_transaction(low, "0xbb".to_string(), sent(Priority::Low)).await.unwrap();
+        tracker.add_transaction(untracked_fees, "0xcc".to_string()).await.unwrap();
+
+        let config = SpeedUpConfig { critical_after: 0, ..SpeedUpConfig::default() };
+        let stuck = tracker.stuck_transactions(&config).await;
+        assert_eq!(stuck.len(), 1);
+        assert_eq!(stuck[0].transaction_id, critical);
+        assert_eq!(stuck[0].replacements, 0);
+    }
+
+    #[tokio::test]
+    async fn test_replacements_share_the_transaction() {
+        let tracker = test_tracker();
+        let transaction_id = Uuid::new_v4();
+        let original = sent(Priority::Normal);
+        tracker.add_sent_transaction(transaction_id, "0xaa".to_string(), original.clone()).await.unwrap();
+
+        let config = SpeedUpConfig { normal_after: 0, max_replacements: 2, ..SpeedUpConfig::default() };
+        let bumped = original.with_fees(Fees { max_fee_per_gas: 34_000_000_000, max_priority_fee_per_gas: 2_300_000_000 });
+        tracker.add_replacement(transaction_id, "0xbb".to_string(), bumped, BroadcastKind::SpeedUp).await.unwrap();
+
+        assert_eq!(tracker.transaction_hashes(transaction_id).await, vec!["0xaa", "0xbb"]);
+        let stuck = tracker.stuck_transactions(&config).await;
+        assert_eq!(stuck[0].replacements, 1);
+        assert_eq!(stuck[0].sent.fees.max_fee_per_gas, 34_000_000_000);
+        assert_eq!(tracker.get_tracking_stats().await.unwrap().total_pending, 1);
+
+        // A failed attempt counts against the limit too
+        tracker.mark_replacement_attempt(transaction_id).await;
+        assert!(tracker.stuck_transactions(&config).await.is_empty());
+
+        assert!(tracker.add_replacement(Uuid::new_v4(), "0xcc".to_string(), original, BroadcastKind::SpeedUp).await.is_err());
+
+        // Any of the hashes identifies the transaction
+        assert!(tracker.remove_transaction("0xaa").await.unwrap());
+        assert!(tracker.transaction_hashes(transaction_id).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_races_the_transaction() {
+        let tracker = test_tracker();
+        let transaction_id = Uuid::new_v4();
+        let original = sent(Priority::Normal);
+        tracker.add_sent_transaction(transaction_id, "0xaa".to_string(), original.clone()).await.unwrap();
+
+        assert!(tracker.cancel_target(Uuid::new_v4()).await.unwrap().is_none());
+        let target = tracker.cancel_target(transaction_id).await.unwrap().unwrap();
+        assert_eq!(target.nonce, original.nonce);
+
+        let cancel = target.cancellation(Fees { max_fee_per_gas: 34_000_000_000, max_priority_fee_per_gas: 2_300_000_000 });
+        tracker.add_replacement(transaction_id, "0xbb".to_string(), cancel, BroadcastKind::Cancel).await.unwrap();
+        assert_eq!(tracker.transaction_hashes(transaction_id).await, vec!["0xaa", "0xbb"]);
+        assert!(tracker.cancel_target(transaction_id).await.is_err());
+
+        // A stuck cancellation is sped up like any other transaction
+        let config = SpeedUpConfig { normal_after: 0, ..SpeedUpConfig::default() };
+        let stuck = tracker.stuck_transactions(&config).await;
+        assert!(stuck[0].cancelling);
+        assert_eq!(stuck[0].replacements, 0);
+        assert_eq!(stuck[0].sent.to, original.from);
+
+        // Transactions the relayer did not sign cannot be cancelled on-chain
+        let unsigned = Uuid::new_v4();
+        tracker.add_transaction(unsigned, "0xcc".to_string()).await.unwrap();
+        assert!(tracker.cancel_target(unsigned).await.is_err());
+    }
+}
@@ -1,7 +1,7 @@
 use alloy::{
     primitives::{Address, U256},
-    providers::{Provider, RootProvider},
-    rpc::types::BlockId,
+    providers::Provider,
+    transports::http::{reqwest::Client, Http},
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -27,7 +27,7 @@ pub struct BalanceChecker<P> {
 
 impl<P> BalanceChecker<P>
 where
-    P: Provider + Send + Sync + 'static,
+    P: Provider<Http<Client>> + Send + Sync + 'static,
 {
     pub fn new(provider: Arc<P>, min_balance_threshold: U256, cache_ttl: std::time::Duration) -> Self {
         Self {
@@ -147,7 +147,7 @@ where
 
 impl<P> Clone for BalanceChecker<P>
 where
-    P: Provider + Send + Sync + 'static,
+    P: Provider<Http<Client>> + Send + Sync + 'static,
 {
     fn clone(&self) -> Self {
         Self {
@@ -174,7 +174,7 @@ pub struct BalanceMonitor<P> {
 
 impl<P> BalanceMonitor<P>
 where
-    P: Provider + Send + Sync + 'static,
+    P: Provider<Http<Client>> + Send + Sync + 'static,
 {
     pub fn new(
         checker: BalanceChecker<P>,
@@ -241,7 +241,7 @@ where
 
 impl<P> Clone for BalanceMonitor<P>
 where
-    P: Provider + Send + Sync + 'static,
+    P: Provider<Http<Client>> + Send + Sync + 'static,
 {
     fn clone(&self) -> Self {
         Self {
@@ -255,7 +255,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use alloy::primitives::Address;
+    
 
     #[tokio::test]
     async fn test_balance_checker_creation() {
@@ -0,0 +1,331 @@
+use alloy::primitives::{Address, U256};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::api::auth::ApiKeyInfo;
+use crate::config::{BudgetConfig, BudgetLimit, SpendingLimits};
+use crate::database::{DatabaseManager, SpendingTotals};
+use crate::types::{RelayerError, Result, TransactionRequest};
+
+/// Rolling window a budget applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetWindow {
+    Hourly,
+    Daily,
+    Monthly,
+}
+
+impl BudgetWindow {
+    pub const ALL: [BudgetWindow; 3] = [BudgetWindow::Hourly, BudgetWindow::Daily, BudgetWindow::Monthly];
+
+    pub fn duration(&self) -> Duration {
+        match self {
+            BudgetWindow::Hourly => Duration::hours(1),
+            BudgetWindow::Daily => Duration::days(1),
+            BudgetWindow::Monthly => Duration::days(30),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BudgetWindow::Hourly => "hourly",
+            BudgetWindow::Daily => "daily",
+            BudgetWindow::Monthly => "monthly",
+        }
+    }
+
+    fn limit<'a>(&self, limits: &'a SpendingLimits) -> &'a BudgetLimit {
+        match self {
+            BudgetWindow::Hourly => &limits.hourly,
+            BudgetWindow::Daily => &limits.daily,
+            BudgetWindow::Monthly => &limits.monthly,
+        }
+    }
+}
+
+/// Ledger subject for a user's budget
+pub fn user_budget_subject(user: Address) -> String {
+    format!("user:{:#x}", user)
+}
+
+/// Ledger subject for an API key's budget, by key name so the key itself is never stored
+pub fn api_key_budget_subject(name: &str) -> String {
+    format!("api_key:{}", name)
+}
+
+/// What a transaction adds to, or a window already holds in, a budget
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Spending {
+    pub transactions: u64,
+    pub value: U256,
+    pub gas_cost: U256,
+}
+
+impl Spending {
+    fn from_totals(totals: &SpendingTotals) -> Result<Self> {
+        let parse = |amount: &str| U256::from_str(amount)
+            .map_err(|_| RelayerError::Database(format!("Invalid spending total '{}'", amount)));
+
+        Ok(Self {
+            transactions: totals.transactions as u64,
+            value: parse(&totals.value)?,
+            gas_cost: parse(&totals.gas_cost)?,
+        })
+    }
+}
+
+/// Gas cost charged before a transaction is mined: the simulated estimate, or the gas limit,
+/// at the signed fee cap
+pub fn estimated_gas_cost(request: &TransactionRequest, gas_estimate: Option<u64>) -> U256 {
+    let gas = gas_estimate.map(U256::from).unwrap_or(request.gas_limit);
+    gas.saturating_mul(request.max_fee_per_gas)
+}
+
+fn parse_cap(what: &str, cap: &Option<String>) -> Result<Option<U256>> {
+    cap.as_deref()
+        .map(|amount| U256::from_str(amount)
+            .map_err(|_| RelayerError::Config(format!("Invalid budget {} '{}'", what, amount))))
+        .transpose()
+}
+
+/// Check every cap of `limits`, failing if any is not a wei amount
+pub fn validate_spending_limits(limits: &SpendingLimits) -> Result<()> {
+    for window in BudgetWindow::ALL {
+        let limit = window.limit(limits);
+        parse_cap("max_value", &limit.max_value)?;
+        parse_cap("max_gas_cost", &limit.max_gas_cost)?;
+    }
+    Ok(())
+}
+
+/// First cap of `limit` that `charge` would push `spent` over, as a readable reason
+pub fn exceeded_cap(limit: &BudgetLimit, spent: &Spending, charge: &Spending) -> Result<Option<String>> {
+    if let Some(max) = limit.max_transactions {
+        if spent.transactions + charge.transactions > max {
+            return Ok(Some(format!("transaction count limit of {} reached", max)));
+        }
+    }
+
+    if let Some(max) = parse_cap("max_value", &limit.max_value)? {
+        if spent.value.saturating_add(charge.value) > max {
+            return Ok(Some(format!(
+                "value {} wei would exceed the limit of {} wei ({} wei used)",
+                charge.value, max, spent.value
+            )));
+        }
+    }
+
+    if let Some(max) = parse_cap("max_gas_cost", &limit.max_gas_cost)? {
+        if spent.gas_cost.saturating_add(charge.gas_cost) > max {
+            return Ok(Some(format!(
+                "gas cost {} wei would exceed the limit of {} wei ({} wei used)",
+                charge.gas_cost, max, spent.gas_cost
+            )));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Usage and caps of one budget window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetWindowStatus {
+    pub window: BudgetWindow,
+    pub transactions: u64,
+    pub value: String,
+    pub gas_cost: String,
+    pub limit: BudgetLimit,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetStatus {
+    pub subject: String,
+    pub windows: Vec<BudgetWindowStatus>,
+}
+
+/// Rolling hourly, daily and monthly budgets per user address and API key, charged in the
+/// `spending_ledger` table so every relayer instance sees the same totals
+#[derive(Debug, Clone)]
+pub struct BudgetManager {
+    database: Arc<DatabaseManager>,
+    user_limits: SpendingLimits,
+    api_key_limits: SpendingLimits,
+}
+
+impl BudgetManager {
+    pub fn new(database: Arc<DatabaseManager>, config: &BudgetConfig) -> Result<Self> {
+        validate_spending_limits(&config.user)?;
+        validate_spending_limits(&config.api_key)?;
+
+        Ok(Self {
+            database,
+            user_limits: config.user.clone(),
+            api_key_limits: config.api_key.clone(),
+        })
+    }
+
+    pub fn user_limits(&self) -> &SpendingLimits {
+        &self.user_limits
+    }
+
+    /// The key's own limits, or the configured default for API keys
+    pub fn api_key_limits<'a>(&'a self, api_key: &'a ApiKeyInfo) -> &'a SpendingLimits {
+        api_key.spending_limits.as_ref().unwrap_or(&self.api_key_limits)
+    }
+
+    /// Charge a transaction to its user and API key budgets, failing with `BudgetExceeded`
+    /// if any window would go over its caps. Totals are read and written under a lock per
+    /// subject, so concurrent requests cannot both squeeze under the same cap.
+    pub async fn reserve(
+        &self,
+        request: &TransactionRequest,
+        gas_estimate: Option<u64>,
+        api_key: Option<&ApiKeyInfo>,
+    ) -> Result<()> {
+        let charge = Spending {
+            transactions: 1,
+            value: request.value,
+            gas_cost: estimated_gas_cost(request, gas_estimate),
+        };
+
+        let mut subjects = vec![(user_budget_subject(request.user_address), &self.user_limits)];
+        if let Some(api_key) = api_key {
+            subjects.push((api_key_budget_subject(&api_key.name), self.api_key_limits(api_key)));
+        }
+
+        let subject_names: Vec<String> = subjects.iter().map(|(subject, _)| subject.clone()).collect();
+        let mut reservation = self.database.begin_spending_reservation(&subject_names).await?;
+        let now = Utc::now();
+
+        for (subject, limits) in &subjects {
+            for window in BudgetWindow::ALL {
+                let limit = window.limit(limits);
+                if *limit == BudgetLimit::default() {
+                    continue;
+                }
+
+                let spent = Spending::from_totals(&reservation.totals(subject, now - window.duration()).await?)?;
+                if let Some(reason) = exceeded_cap(limit, &spent, &charge)? {
+                    return Err(RelayerError::BudgetExceeded(format!(
+                        "{} budget of {}: {}",
+                        window.name(), subject, reason
+                    )));
+                }
+            }
+        }
+
+        for (subject, _) in &subjects {
+            reservation.prune(subject, now - BudgetWindow::Monthly.duration()).await?;
+            reservation.record(request.id, subject, charge.value.to_string(), charge.gas_cost.to_string()).await?;
+        }
+
+        reservation.commit().await
+    }
+
+    /// Replace a transaction's estimated gas cost with the cost of its receipt
+    pub async fn reconcile(&self, transaction_id: Uuid, gas_used: u64, effective_gas_price: u128) -> Result<()> {
+        let gas_cost = U256::from(gas_used).saturating_mul(U256::from(effective_gas_price));
+        self.database.reconcile_spending(transaction_id, gas_cost.to_string()).await
+    }
+
+    /// Undo `reserve` for a transaction that was rejected before it was queued
+    pub async fn release(&self, transaction_id: Uuid) -> Result<()> {
+        self.database.release_spending(transaction_id).await
+    }
+
+    /// Current usage of `subject` in every window, alongside its caps
+    pub async fn status(&self, subject: &str, limits: &SpendingLimits) -> Result<BudgetStatus> {
+        let now: DateTime<Utc> = Utc::now();
+        let mut windows = Vec::with_capacity(BudgetWindow::ALL.len());
+
+        for window in BudgetWindow::ALL {
+            let totals = self.database.get_spending_totals(subject, now - window.duration()).await?;
+            windows.push(BudgetWindowStatus {
+                window,
+                transactions: totals.transactions as u64,
+                value: totals.value,
+                gas_cost: totals.gas_cost,
+                limit: window.limit(limits).clone(),
+            });
+        }
+
+        Ok(BudgetStatus {
+            subject: subject.to_string(),
+            windows,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spending(transactions: u64, value: u64, gas_cost: u64) -> Spending {
+        Spending {
+            transactions,
+            value: U256::from(value),
+            gas_cost: U256::from(gas_cost),
+        }
+    }
+
+    #[test]
+    fn test_exceeded_cap() {
+        let limit = BudgetLimit {
+            max_value: Some("1000".to_string()),
+            max_gas_cost: Some("500".to_string()),
+            max_transactions: Some(3),
+        };
+        let charge = spending(1, 100, 50);
+
+        assert_eq!(exceeded_cap(&limit, &spending(0, 0, 0), &charge).unwrap(), None);
+        // Reaching a cap exactly is still allowed
+        assert_eq!(exceeded_cap(&limit, &spending(2, 900, 450), &charge).unwrap(), None);
+
+        let reason = exceeded_cap(&limit, &spending(3, 0, 0), &charge).unwrap().unwrap();
+        assert!(reason.contains("transaction count"));
+
+        let reason = exceeded_cap(&limit, &spending(0, 901, 0), &charge).unwrap().unwrap();
+        assert!(reason.starts_with("value 100 wei"));
+
+        let reason = exceeded_cap(&limit, &spending(0, 0, 451), &charge).unwrap().unwrap();
+        assert!(reason.starts_with("gas cost 50 wei"));
+
+        assert_eq!(exceeded_cap(&BudgetLimit::default(), &spending(u64::MAX - 1, 0, 0), &charge).unwrap(), None);
+    }
+
+    #[test]
+    fn test_validate_spending_limits() {
+        let mut limits = SpendingLimits::default();
+        limits.daily.max_gas_cost = Some("10000000000000000".to_string());
+        assert!(validate_spending_limits(&limits).is_ok());
+
+        limits.monthly.max_value = Some("1 ether".to_string());
+        assert!(matches!(validate_spending_limits(&limits), Err(RelayerError::Config(_))));
+    }
+
+    #[test]
+    fn test_budget_subjects_and_estimate() {
+        let user = Address::repeat_byte(0xAB);
+        assert_eq!(user_budget_subject(user), format!("user:0x{}", "ab".repeat(20)));
+        assert_eq!(api_key_budget_subject("partner"), "api_key:partner");
+
+        let request = TransactionRequest::new(
+            user,
+            Address::repeat_byte(0x44),
+            alloy::primitives::Bytes::new(),
+            U256::ZERO,
+            U256::from(100000u64),
+            U256::from(20000000000u64),
+            U256::from(2000000000u64),
+            U256::ZERO,
+            crate::types::Signature { r: U256::ZERO, s: U256::ZERO, v: 27 },
+            crate::types::Priority::Normal,
+        );
+        assert_eq!(estimated_gas_cost(&request, None), U256::from(2000000000000000u64));
+        assert_eq!(estimated_gas_cost(&request, Some(46000)), U256::from(920000000000000u64));
+    }
+}
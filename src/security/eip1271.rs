@@ -0,0 +1,431 @@
+use alloy::{
+    network::TransactionBuilder,
+    primitives::{b256, Address, Bytes, B256},
+    providers::Provider,
+    rpc::types::TransactionRequest as AlloyTransactionRequest,
+    sol,
+    sol_types::{SolCall, SolValue},
+    transports::RpcError,
+};
+use sha3::{Digest, Keccak256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::{
+    services::EthereumProvider,
+    types::{RelayerError, Result, Signature},
+};
+
+use super::signature::ecrecover;
+
+sol! {
+    interface IERC1271 {
+        function isValidSignature(bytes32 hash, bytes signature) external view returns (bytes4 magicValue);
+    }
+
+    /// ERC-6492 universal validator: deploys counterfactual accounts in the call, then checks the signature
+    interface IUniversalSigValidator {
+        function isValidSig(address signer, bytes32 hash, bytes signature) external returns (bool);
+    }
+}
+
+/// Returned by `isValidSignature` when the signature is valid
+pub const EIP1271_MAGIC_VALUE: [u8; 4] = [0x16, 0x26, 0xba, 0x7e];
+
+/// Suffix marking an ERC-6492 signature for an account that may not be deployed yet
+pub const ERC6492_MAGIC_SUFFIX: B256 = b256!("6492649264926492649264926492649264926492649264926492649264926492");
+
+/// An ERC-6492 signature: `abi.encode(factory, factoryCalldata, signature) ‖ magicSuffix`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Erc6492Signature {
+    pub factory: Address,
+    pub factory_calldata: Bytes,
+    pub signature: Bytes,
+}
+
+impl Erc6492Signature {
+    /// Unwrap an ERC-6492 signature, or `None` if `signature` is not wrapped
+    pub fn unwrap(signature: &[u8]) -> Option<Self> {
+        let wrapped = signature.strip_suffix(ERC6492_MAGIC_SUFFIX.as_slice())?;
+        let (factory, factory_calldata, signature) = <(Address, Bytes, Bytes)>::abi_decode_params(wrapped, true).ok()?;
+
+        Some(Self {
+            factory,
+            factory_calldata,
+            signature,
+        })
+    }
+
+    pub fn wrap(&self) -> Bytes {
+        let mut wrapped = (self.factory, self.factory_calldata.clone(), self.signature.clone()).abi_encode_params();
+        wrapped.extend_from_slice(ERC6492_MAGIC_SUFFIX.as_slice());
+        wrapped.into()
+    }
+}
+
+const MAX_CACHED_RESULTS: usize = 10_000;
+
+#[derive(Debug, Clone)]
+struct CodeInfo {
+    has_code: bool,
+    last_updated: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone)]
+struct SignatureCheck {
+    is_valid: bool,
+    last_updated: chrono::DateTime<chrono::Utc>,
+}
+
+/// Verifies signatures from EOAs and smart-contract wallets.
+///
+/// Accounts with code are asked through EIP-1271 `isValidSignature`. ERC-6492 signatures from
+/// accounts that are not deployed yet are checked by the universal validator, when one is configured.
+#[derive(Debug, Clone)]
+pub struct ContractSignatureVerifier {
+    provider: Arc<EthereumProvider>,
+    universal_validator: Option<Address>,
+    code_cache: Arc<RwLock<HashMap<Address, CodeInfo>>>,
+    result_cache: Arc<RwLock<HashMap<B256, SignatureCheck>>>,
+    cache_ttl: std::time::Duration,
+}
+
+impl ContractSignatureVerifier {
+    pub fn new(
+        provider: Arc<EthereumProvider>,
+        universal_validator: Option<Address>,
+        cache_ttl: std::time::Duration,
+    ) -> Self {
+        Self {
+            provider,
+            universal_validator,
+            code_cache: Arc::new(RwLock::new(HashMap::new())),
+            result_cache: Arc::new(RwLock::new(HashMap::new())),
+            cache_ttl,
+        }
+    }
+
+    /// Whether `signature` is a valid signature of `hash` by `signer`
+    pub async fn is_valid_signature(&self, signer: Address, hash: B256, signature: &[u8]) -> Result<bool> {
+        let cache_key = Self::cache_key(signer, hash, signature);
+
+        // Check cache first
+        {
+            let cache = self.result_cache.read().await;
+            if let Some(cached) = cache.get(&cache_key) {
+                if self.is_fresh(cached.last_updated) {
+                    return Ok(cached.is_valid);
+                }
+            }
+        }
+
+        let is_valid = self.check_signature(signer, hash, signature).await?;
+
+        // Update cache, dropping expired entries once it grows large
+        {
+            let mut cache = self.result_cache.write().await;
+            if cache.len() >= MAX_CACHED_RESULTS {
+                cache.retain(|_, check| self.is_fresh(check.last_updated));
+            }
+            cache.insert(cache_key, SignatureCheck {
+                is_valid,
+                last_updated: chrono::Utc::now(),
+            });
+        }
+
+        Ok(is_valid)
+    }
+
+    async fn check_signature(&self, signer: Address, hash: B256, signature: &[u8]) -> Result<bool> {
+        if let Some(wrapped) = Erc6492Signature::unwrap(signature) {
+            // Deployed accounts validate the inner signature themselves
+            if self.has_code(signer).await? {
+                return self.is_valid_eip1271(signer, hash, &wrapped.signature).await;
+            }
+
+            let validator = self.universal_validator.ok_or_else(|| {
+                RelayerError::SignatureVerification(
+                    "ERC-6492 signatures require a universal signature validator".to_string(),
+                )
+            })?;
+            let call = IUniversalSigValidator::isValidSigCall {
+                signer,
+                hash,
+                signature: signature.to_vec().into(),
+            };
+            return Ok(self.call(validator, call).await?.map(|valid| valid._0).unwrap_or(false));
+        }
+
+        if self.has_code(signer).await? {
+            return self.is_valid_eip1271(signer, hash, signature).await;
+        }
+
+        Ok(recover_ecdsa(hash, signature) == Some(signer))
+    }
+
+    async fn is_valid_eip1271(&self, signer: Address, hash: B256, signature: &[u8]) -> Result<bool> {
+        let call = IERC1271::isValidSignatureCall {
+            hash,
+            signature: signature.to_vec().into(),
+        };
+
+        Ok(self.call(signer, call).await?
+            .is_some_and(|result| result.magicValue.0 == EIP1271_MAGIC_VALUE))
+    }
+
+    /// Whether `address` has deployed code, cached like the signature results
+    pub async fn has_code(&self, address: Address) -> Result<bool> {
+        {
+            let cache = self.code_cache.read().await;
+            if let Some(cached) = cache.get(&address) {
+                if self.is_fresh(cached.last_updated) {
+                    return Ok(cached.has_code);
+                }
+            }
+        }
+
+        let code = self.provider.get_code_at(address).await
+            .map_err(|e| RelayerError::Ethereum(format!("Failed to get code of {:?}: {}", address, e)))?;
+        let has_code = !code.is_empty();
+
+        {
+            let mut cache = self.code_cache.write().await;
+            cache.insert(address, CodeInfo {
+                has_code,
+                last_updated: chrono::Utc::now(),
+            });
+        }
+
+        Ok(has_code)
+    }
+
+    /// `eth_call` that treats a revert or undecodable result as `None` rather than an error
+    async fn call<C: SolCall>(&self, to: Address, call: C) -> Result<Option<C::Return>> {
+        let tx = AlloyTransactionRequest::default()
+            .with_to(to)
+            .with_input(call.abi_encode());
+
+        match self.provider.call(&tx).await {
+            Ok(output) => Ok(C::abi_decode_returns(&output, true).ok()),
+            Err(RpcError::ErrorResp(e)) => {
+                tracing::debug!("{} on {:?} reverted: {}", C::SIGNATURE, to, e);
+                Ok(None)
+            }
+            Err(e) => Err(RelayerError::Ethereum(format!("eth_call to {:?} failed: {}", to, e))),
+        }
+    }
+
+    pub async fn clear_cache(&self) {
+        self.code_cache.write().await.clear();
+        self.result_cache.write().await.clear();
+    }
+
+    pub async fn cleanup_expired_cache(&self) {
+        let now = chrono::Utc::now();
+        let ttl = chrono::Duration::from_std(self.cache_ttl).unwrap_or_default();
+
+        self.code_cache.write().await.retain(|_, info| now - info.last_updated < ttl);
+        self.result_cache.write().await.retain(|_, check| now - check.last_updated < ttl);
+    }
+
+    fn is_fresh(&self, last_updated: chrono::DateTime<chrono::Utc>) -> bool {
+        chrono::Utc::now() - last_updated < chrono::Duration::from_std(self.cache_ttl).unwrap_or_default()
+    }
+
+    fn cache_key(signer: Address, hash: B256, signature: &[u8]) -> B256 {
+        let mut hasher = Keccak256::new();
+        hasher.update(signer);
+        hasher.update(hash);
+        hasher.update(signature);
+        B256::from_slice(&hasher.finalize())
+    }
+}
+
+/// Recover the signer of a 65-byte `r ‖ s ‖ v` ECDSA signature
+fn recover_ecdsa(hash: B256, signature: &[u8]) -> Option<Address> {
+    if signature.len() != 65 {
+        return None;
+    }
+
+    let signature = Signature {
+        r: alloy::primitives::U256::from_be_slice(&signature[..32]),
+        s: alloy::primitives::U256::from_be_slice(&signature[32..64]),
+        v: signature[64],
+    };
+    ecrecover(&hash, &signature).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::{SignatureVerifier, TransactionMessage};
+    use crate::types::{Priority, TransactionRequest};
+    use crate::utils::CryptoUtils;
+    use alloy::primitives::U256;
+    use axum::{routing::post, Json, Router};
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const WALLET: Address = Address::repeat_byte(0xaa);
+    const BROKEN_WALLET: Address = Address::repeat_byte(0xbb);
+    const COUNTERFACTUAL_WALLET: Address = Address::repeat_byte(0xcc);
+    const VALIDATOR: Address = Address::repeat_byte(0xdd);
+
+    /// JSON-RPC node where WALLET accepts every signature, BROKEN_WALLET reverts,
+    /// and VALIDATOR approves every ERC-6492 signature. Returns the URL and an `eth_call` counter.
+    async fn rpc_stub() -> (String, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+
+        let app = Router::new().route(
+            "/",
+            post(move |Json(request): Json<Value>| {
+                let counter = Arc::clone(&counter);
+                async move {
+                    let params = &request["params"];
+                    let outcome = match request["method"].as_str().unwrap_or_default() {
+                        "eth_getCode" => {
+                            let address: Address = params[0].as_str().unwrap().parse().unwrap();
+                            let has_code = address == WALLET || address == BROKEN_WALLET;
+                            Ok(json!(if has_code { "0x6001" } else { "0x" }))
+                        }
+                        "eth_call" => {
+                            counter.fetch_add(1, Ordering::SeqCst);
+                            let to: Address = params[0]["to"].as_str().unwrap().parse().unwrap();
+                            match to {
+                                WALLET => Ok(json!(format!("0x1626ba7e{}", "00".repeat(28)))),
+                                VALIDATOR => Ok(json!(format!("0x{}01", "00".repeat(31)))),
+                                _ => Err(json!({ "code": 3, "message": "execution reverted" })),
+                            }
+                        }
+                        method => Err(json!({ "code": -32601, "message": format!("{} not found", method) })),
+                    };
+
+                    Json(match outcome {
+                        Ok(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+                        Err(error) => json!({ "jsonrpc": "2.0", "id": request["id"], "error": error }),
+                    })
+                }
+            }),
+        );
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service()));
+
+        (url, calls)
+    }
+
+    async fn test_verifier(universal_validator: Option<Address>) -> (ContractSignatureVerifier, Arc<AtomicUsize>) {
+        let (url, calls) = rpc_stub().await;
+        let provider = alloy::providers::ProviderBuilder::new().on_http(url.parse().unwrap());
+
+        let verifier = ContractSignatureVerifier::new(
+            Arc::new(provider),
+            universal_validator,
+            std::time::Duration::from_secs(30),
+        );
+        (verifier, calls)
+    }
+
+    fn wrapped_signature() -> Bytes {
+        Erc6492Signature {
+            factory: Address::repeat_byte(0xfa),
+            factory_calldata: Bytes::from(vec![0x12, 0x34]),
+            signature: Bytes::from(vec![0x56; 65]),
+        }
+        .wrap()
+    }
+
+    #[test]
+    fn test_erc6492_unwrap() {
+        let wrapped = wrapped_signature();
+        assert!(wrapped.ends_with(ERC6492_MAGIC_SUFFIX.as_slice()));
+
+        let unwrapped = Erc6492Signature::unwrap(&wrapped).unwrap();
+        assert_eq!(unwrapped.factory, Address::repeat_byte(0xfa));
+        assert_eq!(unwrapped.signature, Bytes::from(vec![0x56; 65]));
+
+        assert_eq!(Erc6492Signature::unwrap(&[0x56; 65]), None);
+    }
+
+    #[tokio::test]
+    async fn test_eoa_signature() {
+        let (verifier, calls) = test_verifier(None).await;
+        let key = CryptoUtils::generate_private_key();
+        let hash = B256::repeat_byte(0x42);
+
+        let signature = Signature::from(key.sign_prehash_recoverable(hash.as_slice()).unwrap()).to_bytes();
+        assert!(verifier.is_valid_signature(Address::from_private_key(&key), hash, &signature).await.unwrap());
+        assert!(!verifier.is_valid_signature(Address::repeat_byte(0x01), hash, &signature).await.unwrap());
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_eip1271_signature() {
+        let (verifier, calls) = test_verifier(None).await;
+        let hash = B256::repeat_byte(0x42);
+
+        assert!(verifier.is_valid_signature(WALLET, hash, &[0x56; 65]).await.unwrap());
+        // A reverting wallet rejects the signature rather than failing verification
+        assert!(!verifier.is_valid_signature(BROKEN_WALLET, hash, &[0x56; 65]).await.unwrap());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // Results are cached
+        assert!(verifier.is_valid_signature(WALLET, hash, &[0x56; 65]).await.unwrap());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        verifier.clear_cache().await;
+        assert!(verifier.is_valid_signature(WALLET, hash, &[0x56; 65]).await.unwrap());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_erc6492_signature() {
+        let hash = B256::repeat_byte(0x42);
+
+        let (verifier, _) = test_verifier(Some(VALIDATOR)).await;
+        assert!(verifier.is_valid_signature(COUNTERFACTUAL_WALLET, hash, &wrapped_signature()).await.unwrap());
+
+        // Once deployed, the wallet checks the unwrapped signature itself
+        assert!(verifier.is_valid_signature(WALLET, hash, &wrapped_signature()).await.unwrap());
+
+        let (without_validator, _) = test_verifier(None).await;
+        assert!(matches!(
+            without_validator.is_valid_signature(COUNTERFACTUAL_WALLET, hash, &wrapped_signature()).await,
+            Err(RelayerError::SignatureVerification(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_signature_verifier_accepts_contract_wallet() {
+        let (contract_verifier, _) = test_verifier(None).await;
+        let verifier = SignatureVerifier::new(U256::from(1u64), Address::ZERO)
+            .with_contract_verifier(contract_verifier);
+
+        let mut request = TransactionRequest::new(
+            WALLET,
+            Address::repeat_byte(0x09),
+            Bytes::from(vec![0x12, 0x34]),
+            U256::ZERO,
+            U256::from(21000u64),
+            U256::from(20000000000u64),
+            U256::from(2000000000u64),
+            U256::from(1u64),
+            Signature { r: U256::ZERO, s: U256::ZERO, v: 0 },
+            Priority::Normal,
+        );
+        request.valid_before = Some(chrono::Utc::now() + chrono::Duration::seconds(60));
+        request.signature_data = Some(Bytes::from(vec![0x56; 130]));
+        assert!(verifier.verify_transaction_signature(&request).await.unwrap());
+
+        // EOAs still verify with plain ECDSA
+        let key = CryptoUtils::generate_private_key();
+        request.user_address = Address::from_private_key(&key);
+        request.signature_data = None;
+        let message_hash = verifier.create_eip712_hash(&TransactionMessage::from_request(&request)).unwrap();
+        request.signature = verifier.sign_transaction(&key, &message_hash).unwrap();
+        assert!(verifier.verify_transaction_signature(&request).await.unwrap());
+    }
+}
@@ -0,0 +1,616 @@
+use alloy::primitives::{Address, Sign, B256, I256, U256};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha3::{Digest, Keccak256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+use crate::types::{RelayerError, Result};
+
+/// A named member of an EIP-712 struct type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TypedDataField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub r#type: String,
+}
+
+/// Struct definitions keyed by type name
+pub type TypedDataTypes = BTreeMap<String, Vec<TypedDataField>>;
+
+/// Typed data in the `eth_signTypedData_v4` JSON format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedData {
+    pub types: TypedDataTypes,
+    pub primary_type: String,
+    pub domain: Value,
+    pub message: Value,
+}
+
+/// Domain fields in the order EIP-712 lists them, used when `types` has no `EIP712Domain`
+const DOMAIN_FIELDS: [(&str, &str); 5] = [
+    ("name", "string"),
+    ("version", "string"),
+    ("chainId", "uint256"),
+    ("verifyingContract", "address"),
+    ("salt", "bytes32"),
+];
+
+impl TypedData {
+    pub fn new(types: TypedDataTypes, primary_type: &str, domain: Value, message: Value) -> Self {
+        Self {
+            types,
+            primary_type: primary_type.to_string(),
+            domain,
+            message,
+        }
+    }
+
+    /// Digest a wallet signs: `keccak256("\x19\x01" ‖ domainSeparator ‖ hashStruct(message))`
+    pub fn signing_hash(&self) -> Result<B256> {
+        let mut hasher = Keccak256::new();
+        hasher.update(b"\x19\x01");
+        hasher.update(self.domain_separator()?);
+        hasher.update(self.hash_struct(&self.primary_type, &self.message)?);
+
+        Ok(B256::from_slice(&hasher.finalize()))
+    }
+
+    /// `hashStruct` of the domain. When `types` omits `EIP712Domain`, the type is built
+    /// from the domain fields that are present.
+    pub fn domain_separator(&self) -> Result<B256> {
+        if self.types.contains_key("EIP712Domain") {
+            return self.hash_struct("EIP712Domain", &self.domain);
+        }
+
+        let domain_fields = DOMAIN_FIELDS
+            .iter()
+            .filter(|(name, _)| self.domain.get(name).is_some_and(|value| !value.is_null()))
+            .map(|(name, r#type)| TypedDataField {
+                name: name.to_string(),
+                r#type: r#type.to_string(),
+            })
+            .collect();
+
+        let mut with_domain = self.clone();
+        with_domain.types.insert("EIP712Domain".to_string(), domain_fields);
+        with_domain.hash_struct("EIP712Domain", &self.domain)
+    }
+
+    /// `encodeType`: the struct signature followed by every referenced struct, sorted by name
+    pub fn encode_type(&self, type_name: &str) -> Result<String> {
+        let mut dependencies = BTreeSet::new();
+        self.collect_dependencies(type_name, &mut dependencies);
+        dependencies.remove(type_name);
+
+        let mut encoded = self.struct_signature(type_name)?;
+        for dependency in dependencies {
+            encoded.push_str(&self.struct_signature(&dependency)?);
+        }
+
+        Ok(encoded)
+    }
+
+    pub fn type_hash(&self, type_name: &str) -> Result<B256> {
+        Ok(keccak256(self.encode_type(type_name)?.as_bytes()))
+    }
+
+    /// `hashStruct(s) = keccak256(typeHash ‖ encodeData(s))`
+    pub fn hash_struct(&self, type_name: &str, data: &Value) -> Result<B256> {
+        let mut encoded = self.type_hash(type_name)?.to_vec();
+        encoded.extend_from_slice(&self.encode_data(type_name, data)?);
+
+        Ok(keccak256(&encoded))
+    }
+
+    /// `encodeData`: each member encoded to 32 bytes, in declaration order
+    pub fn encode_data(&self, type_name: &str, data: &Value) -> Result<Vec<u8>> {
+        let fields = self.fields(type_name)?;
+        if !data.is_object() {
+            return Err(eip712_error(format!("Value of {} must be an object", type_name)));
+        }
+
+        let mut encoded = Vec::with_capacity(32 * fields.len());
+        for field in fields {
+            let value = data.get(&field.name).ok_or_else(|| {
+                eip712_error(format!("Missing value for field '{}' of {}", field.name, type_name))
+            })?;
+            encoded.extend_from_slice(self.encode_value(&field.r#type, value)?.as_slice());
+        }
+
+        Ok(encoded)
+    }
+
+    fn encode_value(&self, r#type: &str, value: &Value) -> Result<B256> {
+        if let Some((item_type, length)) = split_array_type(r#type)? {
+            let items = value
+                .as_array()
+                .ok_or_else(|| eip712_error(format!("Value of {} must be an array", r#type)))?;
+            if length.is_some_and(|length| length != items.len()) {
+                return Err(eip712_error(format!("{} has {} items", r#type, items.len())));
+            }
+
+            let mut encoded = Vec::with_capacity(32 * items.len());
+            for item in items {
+                encoded.extend_from_slice(self.encode_value(item_type, item)?.as_slice());
+            }
+            return Ok(keccak256(&encoded));
+        }
+
+        if self.types.contains_key(r#type) {
+            return self.hash_struct(r#type, value);
+        }
+
+        encode_atomic(r#type, value)
+    }
+
+    fn fields(&self, type_name: &str) -> Result<&Vec<TypedDataField>> {
+        self.types
+            .get(type_name)
+            .ok_or_else(|| eip712_error(format!("Unknown struct type {}", type_name)))
+    }
+
+    fn struct_signature(&self, type_name: &str) -> Result<String> {
+        let members = self
+            .fields(type_name)?
+            .iter()
+            .map(|field| format!("{} {}", field.r#type, field.name))
+            .collect::<Vec<_>>();
+
+        Ok(format!("{}({})", type_name, members.join(",")))
+    }
+
+    fn collect_dependencies(&self, type_name: &str, found: &mut BTreeSet<String>) {
+        let base_type = type_name.split('[').next().unwrap_or(type_name);
+        if found.contains(base_type) {
+            return;
+        }
+
+        if let Some(fields) = self.types.get(base_type) {
+            found.insert(base_type.to_string());
+            for field in fields {
+                self.collect_dependencies(&field.r#type, found);
+            }
+        }
+    }
+}
+
+/// Split `T[]` / `T[n]` into the item type and fixed length, or `None` for non-array types
+fn split_array_type(r#type: &str) -> Result<Option<(&str, Option<usize>)>> {
+    let Some(inner) = r#type.strip_suffix(']') else {
+        return Ok(None);
+    };
+    let open = inner
+        .rfind('[')
+        .ok_or_else(|| eip712_error(format!("Malformed array type {}", r#type)))?;
+
+    let length = match &inner[open + 1..] {
+        "" => None,
+        length => Some(
+            length
+                .parse()
+                .map_err(|_| eip712_error(format!("Malformed array type {}", r#type)))?,
+        ),
+    };
+
+    Ok(Some((&inner[..open], length)))
+}
+
+fn encode_atomic(r#type: &str, value: &Value) -> Result<B256> {
+    match r#type {
+        "string" => {
+            let string = value
+                .as_str()
+                .ok_or_else(|| eip712_error(format!("Value of string must be a string, got {}", value)))?;
+            Ok(keccak256(string.as_bytes()))
+        }
+        "bytes" => Ok(keccak256(&parse_hex(r#type, value)?)),
+        "address" => {
+            let address = value
+                .as_str()
+                .and_then(|address| Address::from_str(address).ok())
+                .ok_or_else(|| eip712_error(format!("Invalid address {}", value)))?;
+            Ok(address.into_word())
+        }
+        "bool" => {
+            let flag = value
+                .as_bool()
+                .ok_or_else(|| eip712_error(format!("Invalid bool {}", value)))?;
+            Ok(B256::from(U256::from(flag as u8)))
+        }
+        _ => {
+            if let Some(size) = r#type.strip_prefix("bytes") {
+                encode_fixed_bytes(r#type, size, value)
+            } else if let Some(bits) = r#type.strip_prefix("uint") {
+                encode_uint(r#type, bits, value)
+            } else if let Some(bits) = r#type.strip_prefix("int") {
+                encode_int(r#type, bits, value)
+            } else {
+                Err(eip712_error(format!("Unknown type {}", r#type)))
+            }
+        }
+    }
+}
+
+fn encode_fixed_bytes(r#type: &str, size: &str, value: &Value) -> Result<B256> {
+    let size: usize = size
+        .parse()
+        .ok()
+        .filter(|size| (1..=32).contains(size))
+        .ok_or_else(|| eip712_error(format!("Unknown type {}", r#type)))?;
+
+    let bytes = parse_hex(r#type, value)?;
+    if bytes.len() != size {
+        return Err(eip712_error(format!("Value of {} has {} bytes", r#type, bytes.len())));
+    }
+
+    let mut word = B256::ZERO;
+    word[..size].copy_from_slice(&bytes);
+    Ok(word)
+}
+
+fn encode_uint(r#type: &str, bits: &str, value: &Value) -> Result<B256> {
+    let bits = parse_bits(r#type, bits)?;
+
+    let number = integer_string(value)
+        .and_then(|string| parse_magnitude(&string))
+        .ok_or_else(|| eip712_error(format!("Invalid {} {}", r#type, value)))?;
+
+    if bits < 256 && number >> bits != U256::ZERO {
+        return Err(eip712_error(format!("{} out of range for {}", number, r#type)));
+    }
+
+    Ok(B256::from(number))
+}
+
+fn encode_int(r#type: &str, bits: &str, value: &Value) -> Result<B256> {
+    let bits = parse_bits(r#type, bits)?;
+
+    let number = integer_string(value)
+        .and_then(|string| match string.strip_prefix('-') {
+            Some(magnitude) => I256::checked_from_sign_and_abs(Sign::Negative, parse_magnitude(magnitude)?),
+            None => I256::checked_from_sign_and_abs(Sign::Positive, parse_magnitude(&string)?),
+        })
+        .ok_or_else(|| eip712_error(format!("Invalid {} {}", r#type, value)))?;
+
+    // In range when sign-extending from `bits` leaves the value unchanged
+    if bits < 256 && number.asr(bits - 1) != number.asr(255) {
+        return Err(eip712_error(format!("{} out of range for {}", number, r#type)));
+    }
+
+    Ok(B256::from(number.into_raw()))
+}
+
+/// Integers arrive as JSON numbers or as decimal / `0x` hex strings.
+/// Numbers too large for `u64`/`i64` have already lost precision, so they are rejected.
+fn integer_string(value: &Value) -> Option<String> {
+    match value {
+        Value::Number(number) if number.is_i64() || number.is_u64() => Some(number.to_string()),
+        Value::String(string) => Some(string.clone()),
+        _ => None,
+    }
+}
+
+fn parse_magnitude(string: &str) -> Option<U256> {
+    match string.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16).ok(),
+        None => U256::from_str_radix(string, 10).ok(),
+    }
+}
+
+fn parse_bits(r#type: &str, bits: &str) -> Result<usize> {
+    bits.parse()
+        .ok()
+        .filter(|bits| (8..=256).contains(bits) && bits % 8 == 0)
+        .ok_or_else(|| eip712_error(format!("Unknown type {}", r#type)))
+}
+
+fn parse_hex(r#type: &str, value: &Value) -> Result<Vec<u8>> {
+    value
+        .as_str()
+        .and_then(|string| string.strip_prefix("0x"))
+        .and_then(|hex| hex::decode(hex).ok())
+        .ok_or_else(|| eip712_error(format!("Value of {} must be 0x-prefixed hex, got {}", r#type, value)))
+}
+
+fn keccak256(data: &[u8]) -> B256 {
+    B256::from_slice(&Keccak256::digest(data))
+}
+
+fn eip712_error(message: String) -> RelayerError {
+    RelayerError::Validation(format!("Invalid EIP-712 typed data: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn b256(hex: &str) -> B256 {
+        B256::from_str(hex).unwrap()
+    }
+
+    fn domain_type() -> Value {
+        json!([
+            { "name": "name", "type": "string" },
+            { "name": "version", "type": "string" },
+            { "name": "chainId", "type": "uint256" },
+            { "name": "verifyingContract", "type": "address" }
+        ])
+    }
+
+    /// The `Mail` example from the EIP-712 specification
+    fn mail() -> TypedData {
+        serde_json::from_value(json!({
+            "types": {
+                "EIP712Domain": domain_type(),
+                "Person": [
+                    { "name": "name", "type": "string" },
+                    { "name": "wallet", "type": "address" }
+                ],
+                "Mail": [
+                    { "name": "from", "type": "Person" },
+                    { "name": "to", "type": "Person" },
+                    { "name": "contents", "type": "string" }
+                ]
+            },
+            "primaryType": "Mail",
+            "domain": {
+                "name": "Ether Mail",
+                "version": "1",
+                "chainId": 1,
+                "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+            },
+            "message": {
+                "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+                "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+                "contents": "Hello, Bob!"
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_spec_mail_vector() {
+        let typed_data = mail();
+
+        assert_eq!(
+            typed_data.encode_type("Mail").unwrap(),
+            "Mail(Person from,Person to,string contents)Person(string name,address wallet)"
+        );
+        assert_eq!(
+            typed_data.type_hash("Mail").unwrap(),
+            b256("0xa0cedeb2dc280ba39b857546d74f5549c3a1d7bdc2dd96bf881f76108e23dac2")
+        );
+        assert_eq!(
+            typed_data.domain_separator().unwrap(),
+            b256("0xf2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f")
+        );
+        assert_eq!(
+            typed_data.hash_struct("Mail", &typed_data.message).unwrap(),
+            b256("0xc52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e")
+        );
+        assert_eq!(
+            typed_data.signing_hash().unwrap(),
+            b256("0xbe609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2")
+        );
+    }
+
+    #[test]
+    fn test_nested_arrays_vector() {
+        // eth-sig-util's signTypedData_v4 example; `Group` is unreferenced and stays out of encodeType
+        let typed_data: TypedData = serde_json::from_value(json!({
+            "types": {
+                "EIP712Domain": domain_type(),
+                "Person": [
+                    { "name": "name", "type": "string" },
+                    { "name": "wallets", "type": "address[]" }
+                ],
+                "Mail": [
+                    { "name": "from", "type": "Person" },
+                    { "name": "to", "type": "Person[]" },
+                    { "name": "contents", "type": "string" }
+                ],
+                "Group": [
+                    { "name": "name", "type": "string" },
+                    { "name": "members", "type": "Person[]" }
+                ]
+            },
+            "primaryType": "Mail",
+            "domain": {
+                "name": "Ether Mail",
+                "version": "1",
+                "chainId": 1,
+                "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+            },
+            "message": {
+                "from": {
+                    "name": "Cow",
+                    "wallets": [
+                        "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826",
+                        "0xDeaDbeefdEAdbeefdEadbEEFdeadbeEFdEaDbeeF"
+                    ]
+                },
+                "to": [{
+                    "name": "Bob",
+                    "wallets": [
+                        "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB",
+                        "0xB0BdaBea57B0BDABeA57b0bdABEA57b0BDabEa57",
+                        "0xB0B0b0b0b0b0B000000000000000000000000000"
+                    ]
+                }],
+                "contents": "Hello, Bob!"
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            typed_data.encode_type("Mail").unwrap(),
+            "Mail(Person from,Person[] to,string contents)Person(string name,address[] wallets)"
+        );
+        assert_eq!(
+            typed_data.hash_struct("Mail", &typed_data.message).unwrap(),
+            b256("0xeb4221181ff3f1a83ea7313993ca9218496e424604ba9492bb4052c03d5c3df8")
+        );
+        assert_eq!(
+            typed_data.signing_hash().unwrap(),
+            b256("0xa85c2e2b118698e88db68a8105b794a8cc7cec074e89ef991cb4f5f533819cc2")
+        );
+    }
+
+    fn widths() -> TypedData {
+        serde_json::from_value(json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "salt", "type": "bytes32" }
+                ],
+                "Widths": [
+                    { "name": "small", "type": "int8" },
+                    { "name": "byte", "type": "uint8" },
+                    { "name": "minusOne", "type": "int16" },
+                    { "name": "triple", "type": "uint24" },
+                    { "name": "halfMin", "type": "int128" },
+                    { "name": "max", "type": "uint256" },
+                    { "name": "min", "type": "int256" },
+                    { "name": "flag", "type": "bool" },
+                    { "name": "tag", "type": "bytes1" },
+                    { "name": "id", "type": "bytes32" },
+                    { "name": "empty", "type": "bytes" },
+                    { "name": "blob", "type": "bytes" },
+                    { "name": "note", "type": "string" },
+                    { "name": "fixed", "type": "uint16[3]" },
+                    { "name": "grid", "type": "int32[][]" }
+                ]
+            },
+            "primaryType": "Widths",
+            "domain": {
+                "name": "Widths",
+                "chainId": "0x2105",
+                "salt": "0xf2d857f4a3edcb9b78b4d503bfe733db1e3f6cdc2b7971ee739626c97e86a558"
+            },
+            "message": {
+                "small": -128,
+                "byte": 255,
+                "minusOne": "-1",
+                "triple": "0xffffff",
+                "halfMin": "-170141183460469231731687303715884105728",
+                "max": "115792089237316195423570985008687907853269984665640564039457584007913129639935",
+                "min": "-57896044618658097711785492504343953926634992332820282019728792003956564819968",
+                "flag": true,
+                "tag": "0x01",
+                "id": "0x00000000000000000000000000000000000000000000000000000000deadbeef",
+                "empty": "0x",
+                "blob": "0x1234567890",
+                "note": "héllo wörld",
+                "fixed": [1, 2, 65535],
+                "grid": [[-1, 2], [], [2147483647]]
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_atomic_widths_vector() {
+        let typed_data = widths();
+
+        assert_eq!(
+            typed_data.domain_separator().unwrap(),
+            b256("0x840adb3ef6a99fea290736276c257d3711d8e5c4c8930bfbcc52b9f047793d2d")
+        );
+        assert_eq!(
+            typed_data.hash_struct("Widths", &typed_data.message).unwrap(),
+            b256("0x99ed005e3b3a169c1107f4e8c8e113fdc7ba68829f284fbcbc8f1091e0e8766c")
+        );
+        assert_eq!(
+            typed_data.signing_hash().unwrap(),
+            b256("0x5eb89c924ff11a574679f2c80f419b9e21aecca09294792e797e48ec64b9d0f2")
+        );
+    }
+
+    #[test]
+    fn test_forward_request_vector() {
+        // ERC-2771 forward request as signed for OpenZeppelin's MinimalForwarder
+        let typed_data: TypedData = serde_json::from_value(json!({
+            "types": {
+                "EIP712Domain": domain_type(),
+                "ForwardRequest": [
+                    { "name": "from", "type": "address" },
+                    { "name": "to", "type": "address" },
+                    { "name": "value", "type": "uint256" },
+                    { "name": "gas", "type": "uint256" },
+                    { "name": "nonce", "type": "uint256" },
+                    { "name": "data", "type": "bytes" }
+                ]
+            },
+            "primaryType": "ForwardRequest",
+            "domain": {
+                "name": "MinimalForwarder",
+                "version": "0.0.1",
+                "chainId": 84532,
+                "verifyingContract": "0x1111111111111111111111111111111111111111"
+            },
+            "message": {
+                "from": "0x2222222222222222222222222222222222222222",
+                "to": "0x3333333333333333333333333333333333333333",
+                "value": "0",
+                "gas": "100000",
+                "nonce": "7",
+                "data": "0xa9059cbb0000000000000000000000004444444444444444444444444444444444444444000000000000000000000000000000000000000000000000000000000000000a"
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            typed_data.signing_hash().unwrap(),
+            b256("0x26c45f049f2499b5ffa5e6218dbecfb73dd4c7e5a998a4914322b05615f31531")
+        );
+    }
+
+    #[test]
+    fn test_domain_type_inferred_from_fields() {
+        let mut typed_data = mail();
+        let separator = typed_data.domain_separator().unwrap();
+
+        typed_data.types.remove("EIP712Domain");
+        assert_eq!(typed_data.domain_separator().unwrap(), separator);
+    }
+
+    #[test]
+    fn test_rejects_malformed_values() {
+        let cases = [
+            ("small", json!(-129)),
+            ("byte", json!(256)),
+            ("byte", json!(-1)),
+            ("triple", json!("0x1000000")),
+            ("halfMin", json!("-170141183460469231731687303715884105729")),
+            ("max", json!(1.5)),
+            ("flag", json!("true")),
+            ("tag", json!("0x0102")),
+            ("blob", json!("1234")),
+            ("fixed", json!([1, 2])),
+            ("grid", json!([1, 2])),
+        ];
+
+        for (field, value) in cases {
+            let mut typed_data = widths();
+            typed_data.message[field] = value.clone();
+            assert!(
+                matches!(typed_data.signing_hash(), Err(RelayerError::Validation(_))),
+                "{} = {} should be rejected",
+                field,
+                value
+            );
+        }
+
+        let mut missing = widths();
+        missing.message.as_object_mut().unwrap().remove("note");
+        assert!(missing.signing_hash().is_err());
+
+        let mut unknown = widths();
+        unknown.types.get_mut("Widths").unwrap()[0].r#type = "int7".to_string();
+        assert!(unknown.signing_hash().is_err());
+    }
+}
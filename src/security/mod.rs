@@ -1,6 +1,13 @@
 pub mod signature;
 pub mod replay;
 pub mod balance;
+pub mod eip1271;
+pub mod eip712;
+pub mod nonce;
+pub mod policy;
+pub mod budget;
+pub mod screening;
+pub mod reputation;
 
 #[cfg(test)]
 mod tests;
@@ -8,3 +15,10 @@ mod tests;
 pub use signature::*;
 pub use replay::*;
 pub use balance::*;
+pub use eip1271::*;
+pub use eip712::*;
+pub use nonce::*;
+pub use policy::*;
+pub use budget::*;
+pub use screening::*;
+pub use reputation::*;
@@ -0,0 +1,137 @@
+use alloy::primitives::Address;
+use std::sync::Arc;
+
+use crate::config::NonceMode;
+use crate::database::DatabaseManager;
+use crate::types::{RelayerError, Result};
+
+/// Nonces tracked by one bitmap word, as in Permit2's `nonceBitmap`
+pub const NONCE_BITMAP_WORD_BITS: u64 = 256;
+
+/// Bitmap word and bit holding `nonce`
+pub fn nonce_bitmap_position(nonce: u64) -> (i64, i32) {
+    (
+        (nonce / NONCE_BITMAP_WORD_BITS) as i64,
+        (nonce % NONCE_BITMAP_WORD_BITS) as i32,
+    )
+}
+
+/// Lowest nonce not set in `words`, which must be ordered by position. Bits are numbered
+/// like Postgres `set_bit` on `bytea`: bit n is bit n % 8 of byte n / 8.
+pub fn lowest_unused_nonce(words: &[(i64, Vec<u8>)]) -> u64 {
+    let mut expected_pos = 0i64;
+
+    for (word_pos, bitmap) in words {
+        if *word_pos != expected_pos {
+            break;
+        }
+
+        let free_bit = (0..NONCE_BITMAP_WORD_BITS as usize).find(|bit| {
+            bitmap.get(bit / 8).is_none_or(|byte| byte & (1 << (bit % 8)) == 0)
+        });
+        if let Some(bit) = free_bit {
+            return expected_pos as u64 * NONCE_BITMAP_WORD_BITS + bit as u64;
+        }
+
+        expected_pos += 1;
+    }
+
+    expected_pos as u64 * NONCE_BITMAP_WORD_BITS
+}
+
+/// Durable relayer nonces signed by users, persisted in Postgres
+#[derive(Debug, Clone)]
+pub struct UserNonceManager {
+    database: Arc<DatabaseManager>,
+    mode: NonceMode,
+}
+
+impl UserNonceManager {
+    pub fn new(database: Arc<DatabaseManager>, mode: NonceMode) -> Self {
+        Self { database, mode }
+    }
+
+    pub fn mode(&self) -> NonceMode {
+        self.mode
+    }
+
+    /// Nonce the user should sign next. In unordered mode this is the lowest unused one,
+    /// though any other unused nonce is accepted too.
+    pub async fn next_nonce(&self, user: Address) -> Result<u64> {
+        let user = user.to_string();
+
+        match self.mode {
+            NonceMode::Sequential => self.database.get_next_user_nonce(&user).await,
+            NonceMode::Unordered => {
+                let words = self.database.get_user_nonce_bitmaps(&user).await?;
+                Ok(lowest_unused_nonce(&words))
+            }
+        }
+    }
+
+    /// Consume `nonce` for `user`, failing with `ReplayAttack` if it cannot be used
+    pub async fn claim(&self, user: Address, nonce: u64) -> Result<()> {
+        let user_key = user.to_string();
+
+        match self.mode {
+            NonceMode::Sequential => {
+                let nonce_i64 = i64::try_from(nonce)
+                    .map_err(|_| RelayerError::Validation(format!("Nonce {} is too large", nonce)))?;
+
+                if !self.database.claim_sequential_user_nonce(&user_key, nonce_i64).await? {
+                    let expected = self.database.get_next_user_nonce(&user_key).await?;
+                    return Err(RelayerError::ReplayAttack(format!(
+                        "Nonce {} for address {:?} is out of sequence, expected {}",
+                        nonce, user, expected
+                    )));
+                }
+            }
+            NonceMode::Unordered => {
+                let (word_pos, bit_pos) = nonce_bitmap_position(nonce);
+
+                if !self.database.claim_unordered_user_nonce(&user_key, word_pos, bit_pos).await? {
+                    return Err(RelayerError::ReplayAttack(format!(
+                        "Nonce {} already used for address {:?}",
+                        nonce, user
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nonce_bitmap_position() {
+        assert_eq!(nonce_bitmap_position(0), (0, 0));
+        assert_eq!(nonce_bitmap_position(255), (0, 255));
+        assert_eq!(nonce_bitmap_position(256), (1, 0));
+        assert_eq!(nonce_bitmap_position(u64::MAX), ((u64::MAX >> 8) as i64, 255));
+    }
+
+    #[test]
+    fn test_lowest_unused_nonce() {
+        assert_eq!(lowest_unused_nonce(&[]), 0);
+
+        // Nonces 0..=8 used: first byte full, bit 0 of the second byte set
+        let mut word = vec![0u8; 32];
+        word[0] = 0xff;
+        word[1] = 0x01;
+        assert_eq!(lowest_unused_nonce(&[(0, word.clone())]), 9);
+
+        // A gap below a used nonce is reported first
+        word[0] = 0xfb;
+        assert_eq!(lowest_unused_nonce(&[(0, word)]), 2);
+
+        // Full words roll over to the next, and missing words are entirely free
+        let full = vec![0xffu8; 32];
+        assert_eq!(lowest_unused_nonce(&[(0, full.clone())]), 256);
+        assert_eq!(lowest_unused_nonce(&[(0, full.clone()), (2, vec![0u8; 32])]), 256);
+        assert_eq!(lowest_unused_nonce(&[(1, full)]), 0);
+    }
+}
@@ -0,0 +1,368 @@
+use alloy::primitives::{keccak256, Address, B256, U256};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::config::{ArgumentConstraintConfig, PolicyConfig, PolicyRuleConfig};
+use crate::types::{RelayerError, Result, TransactionRequest};
+
+/// Name reported for requests allowed or rejected through `security.trusted_contracts`
+pub const TRUSTED_CONTRACTS_RULE: &str = "trusted_contracts";
+
+/// Why a request was refused, naming the rule that failed when one applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyViolation {
+    pub rule: Option<String>,
+    pub reason: String,
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.rule {
+            Some(ref rule) => write!(f, "Policy rule '{}' failed: {}", rule, self.reason),
+            None => write!(f, "Policy violation: {}", self.reason),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ArgumentConstraint {
+    index: usize,
+    eq: Option<(B256, String)>,
+    min: Option<U256>,
+    max: Option<U256>,
+}
+
+#[derive(Debug, Clone)]
+struct PolicyRule {
+    name: String,
+    contract: Address,
+    selectors: Vec<[u8; 4]>,
+    max_value: Option<U256>,
+    max_gas_limit: Option<U256>,
+    args: Vec<ArgumentConstraint>,
+}
+
+impl PolicyRule {
+    fn from_config(config: &PolicyRuleConfig) -> Result<Self> {
+        let invalid = |what: &str, value: &str| {
+            RelayerError::Config(format!("Policy rule '{}': invalid {} '{}'", config.name, what, value))
+        };
+
+        let selectors = config.selectors.iter()
+            .map(|selector| parse_selector(selector).ok_or_else(|| invalid("selector", selector)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let max_value = config.max_value.as_deref()
+            .map(|value| U256::from_str(value).map_err(|_| invalid("max_value", value)))
+            .transpose()?;
+
+        let args = config.args.iter()
+            .map(|arg| ArgumentConstraint::from_config(arg).map_err(|value| invalid("argument bound", &value)))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            name: config.name.clone(),
+            contract: config.contract,
+            selectors,
+            max_value,
+            max_gas_limit: config.max_gas_limit.map(U256::from),
+            args,
+        })
+    }
+
+    fn allowing(name: &str, contract: Address) -> Self {
+        Self {
+            name: name.to_string(),
+            contract,
+            selectors: vec![],
+            max_value: None,
+            max_gas_limit: None,
+            args: vec![],
+        }
+    }
+
+    fn matches(&self, target: Address, selector: Option<[u8; 4]>) -> bool {
+        self.contract == target
+            && (self.selectors.is_empty() || selector.is_some_and(|s| self.selectors.contains(&s)))
+    }
+
+    fn check(&self, request: &TransactionRequest) -> std::result::Result<(), String> {
+        if let Some(max_value) = self.max_value {
+            if request.value > max_value {
+                return Err(format!("value {} exceeds maximum {}", request.value, max_value));
+            }
+        }
+
+        if let Some(max_gas_limit) = self.max_gas_limit {
+            if request.gas_limit > max_gas_limit {
+                return Err(format!("gas limit {} exceeds maximum {}", request.gas_limit, max_gas_limit));
+            }
+        }
+
+        for arg in &self.args {
+            arg.check(&request.calldata)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ArgumentConstraint {
+    /// Returns the offending bound on error
+    fn from_config(config: &ArgumentConstraintConfig) -> std::result::Result<Self, String> {
+        let parse_uint = |value: &String| U256::from_str(value).map_err(|_| value.clone());
+
+        let eq = match config.eq {
+            Some(ref value) => {
+                let word = match Address::from_str(value) {
+                    Ok(address) => address.into_word(),
+                    Err(_) => B256::from(parse_uint(value)?),
+                };
+                Some((word, value.clone()))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            index: config.index,
+            eq,
+            min: config.min.as_ref().map(parse_uint).transpose()?,
+            max: config.max.as_ref().map(parse_uint).transpose()?,
+        })
+    }
+
+    fn check(&self, calldata: &[u8]) -> std::result::Result<(), String> {
+        let start = 4 + 32 * self.index;
+        let word = calldata.get(start..start + 32)
+            .map(B256::from_slice)
+            .ok_or_else(|| format!("argument {} is missing", self.index))?;
+        let value = U256::from_be_bytes(word.0);
+
+        if let Some((expected, ref display)) = self.eq {
+            if word != expected {
+                return Err(format!("argument {} must equal {}", self.index, display));
+            }
+        }
+
+        if let Some(min) = self.min {
+            if value < min {
+                return Err(format!("argument {} is {}, below minimum {}", self.index, value, min));
+            }
+        }
+
+        if let Some(max) = self.max {
+            if value > max {
+                return Err(format!("argument {} is {}, above maximum {}", self.index, value, max));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Accept `0x`-prefixed 4-byte selectors or function signatures such as `transfer(address,uint256)`
+fn parse_selector(selector: &str) -> Option<[u8; 4]> {
+    if selector.contains('(') {
+        let signature: String = selector.chars().filter(|c| !c.is_whitespace()).collect();
+        return Some(keccak256(signature.as_bytes())[..4].try_into().unwrap());
+    }
+
+    let bytes = hex::decode(selector.strip_prefix("0x").unwrap_or(selector)).ok()?;
+    bytes.try_into().ok()
+}
+
+/// Contract and function allowlist evaluated against every relayed request
+#[derive(Debug, Clone, Default)]
+pub struct PolicyEngine {
+    rules: Vec<PolicyRule>,
+}
+
+impl PolicyEngine {
+    /// Each trusted contract becomes a rule allowing any call to it
+    pub fn new(config: &PolicyConfig, trusted_contracts: &[Address]) -> Result<Self> {
+        let mut rules = config.rules.iter()
+            .map(PolicyRule::from_config)
+            .collect::<Result<Vec<_>>>()?;
+
+        rules.extend(
+            trusted_contracts.iter().map(|&contract| PolicyRule::allowing(TRUSTED_CONTRACTS_RULE, contract)),
+        );
+
+        Ok(Self { rules })
+    }
+
+    /// Without any rule every request is allowed
+    pub fn is_enforced(&self) -> bool {
+        !self.rules.is_empty()
+    }
+
+    /// Allow the request if any rule matching its contract and selector accepts it
+    pub fn evaluate(&self, request: &TransactionRequest) -> std::result::Result<(), PolicyViolation> {
+        if !self.is_enforced() {
+            return Ok(());
+        }
+
+        let target = request.target_contract;
+        let selector: Option<[u8; 4]> = request.calldata.get(..4).map(|s| s.try_into().unwrap());
+
+        if !self.rules.iter().any(|rule| rule.contract == target) {
+            return Err(PolicyViolation {
+                rule: None,
+                reason: format!("contract {} is not allowlisted", target),
+            });
+        }
+
+        let mut first_failure = None;
+        for rule in self.rules.iter().filter(|rule| rule.matches(target, selector)) {
+            match rule.check(request) {
+                Ok(()) => return Ok(()),
+                Err(reason) => {
+                    first_failure.get_or_insert(PolicyViolation {
+                        rule: Some(rule.name.clone()),
+                        reason,
+                    });
+                }
+            }
+        }
+
+        Err(first_failure.unwrap_or_else(|| PolicyViolation {
+            rule: None,
+            reason: match selector {
+                Some(selector) => format!(
+                    "function selector 0x{} is not allowed on {}",
+                    hex::encode(selector),
+                    target
+                ),
+                None => format!("calls without a function selector are not allowed on {}", target),
+            },
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Priority, Signature};
+    use alloy::primitives::Bytes;
+    use serde_json::json;
+
+    const TOKEN: Address = Address::repeat_byte(0x44);
+    const TREASURY: Address = Address::repeat_byte(0x22);
+
+    fn test_engine() -> PolicyEngine {
+        let config: PolicyConfig = serde_json::from_value(json!({
+            "rules": [{
+                "name": "usdc-transfer-to-treasury",
+                "contract": TOKEN,
+                "selectors": ["transfer(address, uint256)"],
+                "max_value": "0",
+                "max_gas_limit": 100000,
+                "args": [
+                    { "index": 0, "eq": TREASURY.to_string() },
+                    { "index": 1, "max": "1000000" }
+                ]
+            }, {
+                "name": "usdc-approve",
+                "contract": TOKEN,
+                "selectors": ["0x095ea7b3"]
+            }]
+        }))
+        .unwrap();
+
+        PolicyEngine::new(&config, &[Address::repeat_byte(0x77)]).unwrap()
+    }
+
+    fn request(target: Address, calldata: Vec<u8>, value: u64, gas_limit: u64) -> TransactionRequest {
+        TransactionRequest::new(
+            Address::repeat_byte(0x11),
+            target,
+            Bytes::from(calldata),
+            U256::from(value),
+            U256::from(gas_limit),
+            U256::from(20000000000u64),
+            U256::from(2000000000u64),
+            U256::ZERO,
+            Signature { r: U256::ZERO, s: U256::ZERO, v: 27 },
+            Priority::Normal,
+        )
+    }
+
+    fn transfer_calldata(to: Address, amount: u64) -> Vec<u8> {
+        let mut calldata = hex::decode("a9059cbb").unwrap();
+        calldata.extend_from_slice(to.into_word().as_slice());
+        calldata.extend_from_slice(&U256::from(amount).to_be_bytes::<32>());
+        calldata
+    }
+
+    fn failed_rule(result: std::result::Result<(), PolicyViolation>) -> Option<String> {
+        result.unwrap_err().rule
+    }
+
+    #[test]
+    fn test_allows_matching_request() {
+        let engine = test_engine();
+        assert!(engine.evaluate(&request(TOKEN, transfer_calldata(TREASURY, 500_000), 0, 60_000)).is_ok());
+        assert!(engine.evaluate(&request(TOKEN, hex::decode("095ea7b3").unwrap(), 0, 60_000)).is_ok());
+    }
+
+    #[test]
+    fn test_rejections_name_failed_rule() {
+        let engine = test_engine();
+        let rule = Some("usdc-transfer-to-treasury".to_string());
+
+        let wrong_recipient = engine.evaluate(&request(TOKEN, transfer_calldata(Address::repeat_byte(0x99), 1), 0, 60_000));
+        assert_eq!(wrong_recipient.clone().unwrap_err().reason, format!("argument 0 must equal {}", TREASURY));
+        assert_eq!(failed_rule(wrong_recipient), rule);
+
+        let too_much = engine.evaluate(&request(TOKEN, transfer_calldata(TREASURY, 1_000_001), 0, 60_000));
+        assert_eq!(failed_rule(too_much), rule);
+
+        let with_value = engine.evaluate(&request(TOKEN, transfer_calldata(TREASURY, 1), 1, 60_000));
+        assert_eq!(failed_rule(with_value), rule);
+
+        let high_gas = engine.evaluate(&request(TOKEN, transfer_calldata(TREASURY, 1), 0, 200_000));
+        assert_eq!(failed_rule(high_gas), rule);
+
+        let truncated = engine.evaluate(&request(TOKEN, transfer_calldata(TREASURY, 1)[..36].to_vec(), 0, 60_000));
+        assert_eq!(truncated.unwrap_err().reason, "argument 1 is missing");
+    }
+
+    #[test]
+    fn test_rejects_unlisted_contract_and_selector() {
+        let engine = test_engine();
+
+        let unknown_contract = engine.evaluate(&request(Address::repeat_byte(0x55), vec![], 0, 21_000)).unwrap_err();
+        assert_eq!(unknown_contract.rule, None);
+        assert!(unknown_contract.reason.contains("not allowlisted"));
+
+        // transferFrom is not covered by any USDC rule
+        let unknown_selector = engine.evaluate(&request(TOKEN, hex::decode("23b872dd").unwrap(), 0, 21_000)).unwrap_err();
+        assert_eq!(unknown_selector.rule, None);
+        assert!(unknown_selector.reason.contains("0x23b872dd"));
+
+        assert!(engine.evaluate(&request(TOKEN, vec![], 0, 21_000)).is_err());
+    }
+
+    #[test]
+    fn test_trusted_contracts_and_empty_policy() {
+        let engine = test_engine();
+        assert!(engine.evaluate(&request(Address::repeat_byte(0x77), vec![0xde, 0xad, 0xbe, 0xef], 5, 1_000_000)).is_ok());
+
+        let open = PolicyEngine::new(&PolicyConfig::default(), &[]).unwrap();
+        assert!(!open.is_enforced());
+        assert!(open.evaluate(&request(Address::repeat_byte(0x55), vec![], 0, 21_000)).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_rule_config() {
+        let config: PolicyConfig = serde_json::from_value(json!({
+            "rules": [{ "name": "bad", "contract": TOKEN, "selectors": ["0x1234"] }]
+        }))
+        .unwrap();
+
+        match PolicyEngine::new(&config, &[]) {
+            Err(RelayerError::Config(message)) => assert!(message.contains("'bad'")),
+            other => panic!("expected config error, got {:?}", other),
+        }
+    }
+}
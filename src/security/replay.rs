@@ -1,9 +1,11 @@
 use alloy::primitives::Address;
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use redis::aio::MultiplexedConnection;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
-use tokio::time::{Duration, Instant};
+use tokio::time::Duration;
 
 use crate::types::{RelayerError, Result};
 
@@ -14,6 +16,7 @@ pub struct ReplayProtectionEntry {
     pub tx_hash: Option<String>,
 }
 
+#[derive(Debug)]
 pub struct ReplayProtection {
     entries: Arc<RwLock<HashMap<Address, Vec<ReplayProtectionEntry>>>>,
     window_duration: Duration,
@@ -167,6 +170,16 @@ pub struct ReplayProtectionStats {
     pub window_duration_seconds: u64,
 }
 
+impl Clone for ReplayProtection {
+    fn clone(&self) -> Self {
+        Self {
+            entries: Arc::clone(&self.entries),
+            window_duration: self.window_duration,
+            cleanup_interval: self.cleanup_interval,
+        }
+    }
+}
+
 impl Default for ReplayProtection {
     fn default() -> Self {
         Self::new(
@@ -176,6 +189,131 @@ impl Default for ReplayProtection {
     }
 }
 
+/// Shared record of (chain, signer, nonce) triples seen within the nonce window
+#[async_trait]
+pub trait ReplayStore: std::fmt::Debug + Send + Sync {
+    /// Atomically claim a nonce, failing with `ReplayAttack` if it was already claimed
+    async fn check_and_record(&self, chain_id: u64, signer: Address, nonce: u64) -> Result<()>;
+
+    async fn is_nonce_used(&self, chain_id: u64, signer: Address, nonce: u64) -> Result<bool>;
+}
+
+/// Process-local store, one `ReplayProtection` per chain. Entries are lost on restart and
+/// are not shared between replicas.
+#[derive(Debug, Clone)]
+pub struct MemoryReplayStore {
+    chains: Arc<RwLock<HashMap<u64, ReplayProtection>>>,
+    window_duration: Duration,
+    cleanup_interval: Duration,
+}
+
+impl MemoryReplayStore {
+    pub fn new(window_duration: Duration, cleanup_interval: Duration) -> Self {
+        Self {
+            chains: Arc::new(RwLock::new(HashMap::new())),
+            window_duration,
+            cleanup_interval,
+        }
+    }
+
+    fn chain(&self, chain_id: u64) -> Result<ReplayProtection> {
+        if let Some(protection) = self.chains.read()
+            .map_err(|e| RelayerError::Internal(e.to_string()))?
+            .get(&chain_id)
+        {
+            return Ok(protection.clone());
+        }
+
+        let mut chains = self.chains.write()
+            .map_err(|e| RelayerError::Internal(e.to_string()))?;
+        Ok(chains
+            .entry(chain_id)
+            .or_insert_with(|| ReplayProtection::new(self.window_duration, self.cleanup_interval))
+            .clone())
+    }
+}
+
+#[async_trait]
+impl ReplayStore for MemoryReplayStore {
+    async fn check_and_record(&self, chain_id: u64, signer: Address, nonce: u64) -> Result<()> {
+        self.chain(chain_id)?.check_and_record(signer, nonce, None)?;
+        Ok(())
+    }
+
+    async fn is_nonce_used(&self, chain_id: u64, signer: Address, nonce: u64) -> Result<bool> {
+        self.chain(chain_id)?.is_nonce_used(signer, nonce)
+    }
+}
+
+/// Redis-backed store shared by every relayer instance. Each nonce is claimed with
+/// `SET key 1 NX PX <window>`, so exactly one replica wins and the key expires with the window.
+#[derive(Clone)]
+pub struct RedisReplayStore {
+    connection: MultiplexedConnection,
+    key_prefix: String,
+    window_duration: Duration,
+}
+
+impl RedisReplayStore {
+    pub async fn connect(url: &str, key_prefix: String, window_duration: Duration) -> Result<Self> {
+        let client = redis::Client::open(url)?;
+        let connection = client.get_multiplexed_tokio_connection().await?;
+
+        Ok(Self {
+            connection,
+            key_prefix,
+            window_duration,
+        })
+    }
+
+    fn key(&self, chain_id: u64, signer: Address, nonce: u64) -> String {
+        format!("{}replay:{}:{:#x}:{}", self.key_prefix, chain_id, signer, nonce)
+    }
+}
+
+impl std::fmt::Debug for RedisReplayStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisReplayStore")
+            .field("key_prefix", &self.key_prefix)
+            .field("window_duration", &self.window_duration)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl ReplayStore for RedisReplayStore {
+    async fn check_and_record(&self, chain_id: u64, signer: Address, nonce: u64) -> Result<()> {
+        let mut conn = self.connection.clone();
+        let claimed: Option<String> = redis::cmd("SET")
+            .arg(self.key(chain_id, signer, nonce))
+            .arg(1)
+            .arg("NX")
+            .arg("PX")
+            .arg(self.window_duration.as_millis() as u64)
+            .query_async(&mut conn)
+            .await?;
+
+        if claimed.is_none() {
+            return Err(RelayerError::ReplayAttack(format!(
+                "Nonce {} already used recently for address {:?}",
+                nonce, signer
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn is_nonce_used(&self, chain_id: u64, signer: Address, nonce: u64) -> Result<bool> {
+        let mut conn = self.connection.clone();
+        let exists: bool = redis::cmd("EXISTS")
+            .arg(self.key(chain_id, signer, nonce))
+            .query_async(&mut conn)
+            .await?;
+
+        Ok(exists)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -235,4 +373,91 @@ mod tests {
         protection.check_and_record(address, 1, None).unwrap();
         assert_eq!(protection.get_next_nonce(address).unwrap(), 2);
     }
+
+    #[tokio::test]
+    async fn test_memory_store_keys_by_chain() {
+        let store = MemoryReplayStore::new(Duration::from_secs(60), Duration::from_secs(10));
+        let signer = Address::repeat_byte(0x11);
+
+        store.check_and_record(1, signer, 7).await.unwrap();
+        assert!(matches!(
+            store.check_and_record(1, signer, 7).await,
+            Err(RelayerError::ReplayAttack(_))
+        ));
+
+        // The same nonce on another chain is a different authorization
+        assert!(!store.is_nonce_used(8453, signer, 7).await.unwrap());
+        store.check_and_record(8453, signer, 7).await.unwrap();
+        assert!(store.is_nonce_used(1, signer, 7).await.unwrap());
+    }
+
+    /// Minimal RESP server implementing `SET ... NX PX` and `EXISTS` over a shared key set
+    async fn spawn_redis_stub() -> String {
+        use std::collections::HashSet;
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("redis://{}", listener.local_addr().unwrap());
+        let keys = Arc::new(std::sync::Mutex::new(HashSet::<String>::new()));
+
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                let keys = Arc::clone(&keys);
+                tokio::spawn(async move {
+                    let (read, mut write) = socket.into_split();
+                    let mut lines = BufReader::new(read).lines();
+                    while let Ok(Some(header)) = lines.next_line().await {
+                        let argc: usize = header.trim_start_matches('*').parse().unwrap();
+                        let mut args = Vec::with_capacity(argc);
+                        for _ in 0..argc {
+                            lines.next_line().await.unwrap();
+                            args.push(lines.next_line().await.unwrap().unwrap());
+                        }
+
+                        let reply = match args[0].to_uppercase().as_str() {
+                            "SET" => {
+                                assert_eq!(&args[3..5], ["NX", "PX"]);
+                                if keys.lock().unwrap().insert(args[1].clone()) {
+                                    "+OK\r\n".to_string()
+                                } else {
+                                    "$-1\r\n".to_string()
+                                }
+                            }
+                            "EXISTS" => format!(":{}\r\n", keys.lock().unwrap().contains(&args[1]) as u8),
+                            _ => "+OK\r\n".to_string(),
+                        };
+                        write.write_all(reply.as_bytes()).await.unwrap();
+                    }
+                });
+            }
+        });
+
+        url
+    }
+
+    #[tokio::test]
+    async fn test_redis_store_shared_between_instances() {
+        let url = spawn_redis_stub().await;
+        let window = Duration::from_secs(60);
+        let first = RedisReplayStore::connect(&url, "express402:".to_string(), window).await.unwrap();
+        let second = RedisReplayStore::connect(&url, "express402:".to_string(), window).await.unwrap();
+        let signer = Address::repeat_byte(0x22);
+
+        assert_eq!(
+            first.key(1, signer, 3),
+            "express402:replay:1:0x2222222222222222222222222222222222222222:3"
+        );
+
+        first.check_and_record(1, signer, 3).await.unwrap();
+
+        // A replay sent to another replica is rejected
+        assert!(second.is_nonce_used(1, signer, 3).await.unwrap());
+        assert!(matches!(
+            second.check_and_record(1, signer, 3).await,
+            Err(RelayerError::ReplayAttack(_))
+        ));
+        second.check_and_record(1, signer, 4).await.unwrap();
+        assert!(!first.is_nonce_used(10, signer, 3).await.unwrap());
+    }
 }
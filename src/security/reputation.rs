@@ -0,0 +1,451 @@
+use alloy::primitives::Address;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::api::auth::{RateLimit, RateLimiter, Restriction};
+use crate::config::ReputationConfig;
+
+/// Rate limiter identifier of a user address
+pub fn user_reputation_subject(user: Address) -> String {
+    format!("user:{:#x}", user)
+}
+
+/// Rate limiter identifier of an API key, by key name so the key itself is never exposed
+pub fn api_key_reputation_subject(name: &str) -> String {
+    format!("api_key:{}", name)
+}
+
+/// Something a subject did that bears on its reputation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReputationEvent {
+    /// A request was accepted into the queue
+    Submitted { target: Address },
+    /// A request was refused because its call reverts in simulation
+    SimulationFailed { target: Address },
+    Confirmed,
+    /// The transaction was mined but reverted
+    Reverted,
+    Cancelled,
+}
+
+/// A subject's behaviour over the reputation window
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReputationSignals {
+    pub submitted: u32,
+    pub simulation_failures: u32,
+    pub confirmed: u32,
+    pub reverted: u32,
+    pub cancelled: u32,
+    /// Requests in the last 60 seconds
+    pub burst: u32,
+    pub distinct_targets: u32,
+}
+
+impl ReputationSignals {
+    fn rate(count: u32, total: u32) -> f64 {
+        if total == 0 { 0.0 } else { count as f64 / total as f64 }
+    }
+
+    pub fn revert_rate(&self) -> f64 {
+        Self::rate(self.reverted, self.reverted + self.confirmed)
+    }
+
+    pub fn simulation_failure_rate(&self) -> f64 {
+        Self::rate(self.simulation_failures, self.submitted + self.simulation_failures)
+    }
+
+    pub fn cancellation_rate(&self) -> f64 {
+        Self::rate(self.cancelled, self.submitted)
+    }
+}
+
+/// First threshold of `config` that `signals` breaks, as a readable reason
+pub fn reputation_violation(config: &ReputationConfig, signals: &ReputationSignals) -> Option<String> {
+    if signals.burst > config.max_burst {
+        return Some(format!("{} requests in one minute (limit {})", signals.burst, config.max_burst));
+    }
+
+    if signals.distinct_targets > config.max_distinct_targets {
+        return Some(format!(
+            "{} distinct target contracts (limit {})",
+            signals.distinct_targets, config.max_distinct_targets
+        ));
+    }
+
+    let min = config.min_transactions;
+    let rates = [
+        ("revert", signals.reverted + signals.confirmed, signals.revert_rate(), config.max_revert_rate),
+        (
+            "simulation failure",
+            signals.submitted + signals.simulation_failures,
+            signals.simulation_failure_rate(),
+            config.max_simulation_failure_rate,
+        ),
+        ("cancellation", signals.submitted, signals.cancellation_rate(), config.max_cancellation_rate),
+    ];
+    for (name, total, rate, max) in rates {
+        if total >= min && rate > max {
+            return Some(format!("{} rate {:.0}% over {} transactions (limit {:.0}%)", name, rate * 100.0, total, max * 100.0));
+        }
+    }
+
+    None
+}
+
+/// Why a request was refused admission
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReputationRejection {
+    pub subject: String,
+    /// Refused outright by a ban, rather than over a throttled rate
+    pub banned: bool,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReputationStatus {
+    pub subject: String,
+    pub signals: ReputationSignals,
+    pub strikes: u32,
+    pub restriction: Option<Restriction>,
+}
+
+#[derive(Debug)]
+struct TrackedTransaction {
+    tracked_at: Instant,
+    subjects: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct SubjectHistory {
+    events: VecDeque<(Instant, ReputationEvent)>,
+    strikes: u32,
+    last_strike: Option<Instant>,
+}
+
+/// Tracks revert, simulation failure and cancellation rates, burst velocity and target
+/// spread per user address and API key, and throttles or bans subjects that cross the
+/// configured thresholds through the `RateLimiter`. Penalties escalate with every strike.
+#[derive(Debug, Clone)]
+pub struct ReputationManager {
+    config: ReputationConfig,
+    rate_limiter: Arc<RateLimiter>,
+    subjects: Arc<RwLock<HashMap<String, SubjectHistory>>>,
+    /// Subjects of queued transactions, so their outcomes can be attributed
+    transactions: Arc<RwLock<HashMap<Uuid, TrackedTransaction>>>,
+}
+
+impl ReputationManager {
+    pub fn new(config: ReputationConfig, rate_limiter: Arc<RateLimiter>) -> Self {
+        Self {
+            config,
+            rate_limiter,
+            subjects: Arc::new(RwLock::new(HashMap::new())),
+            transactions: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    fn window(&self) -> Duration {
+        Duration::from_secs(self.config.window)
+    }
+
+    /// Refuse a request from any subject that is banned or over its (throttled) rate
+    pub async fn admit(&self, subjects: &[String]) -> Result<(), ReputationRejection> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        for subject in subjects {
+            let restriction = self.rate_limiter.get_restriction(subject).await;
+            if let Some(ref restriction) = restriction {
+                if restriction.is_ban() {
+                    return Err(ReputationRejection {
+                        subject: subject.clone(),
+                        banned: true,
+                        reason: format!("{} is banned until {}: {}", subject, restriction.expires_at, restriction.reason),
+                    });
+                }
+            }
+
+            // Only errors on a poisoned lock, which cannot happen with tokio's RwLock
+            if !self.rate_limiter.check_rate_limit(subject).await.unwrap_or(true) {
+                let reason = match restriction {
+                    Some(restriction) => format!(
+                        "{} is throttled until {}: {}",
+                        subject, restriction.expires_at, restriction.reason
+                    ),
+                    None => format!("{} is over its rate limit", subject),
+                };
+                return Err(ReputationRejection { subject: subject.clone(), banned: false, reason });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remember who submitted a queued transaction, for `record_outcome`
+    pub async fn track(&self, transaction_id: Uuid, subjects: Vec<String>) {
+        if !self.config.enabled {
+            return;
+        }
+
+        let now = Instant::now();
+        let mut transactions = self.transactions.write().await;
+        // Transactions that never reach an outcome are dropped with the window
+        let window = self.window();
+        transactions.retain(|_, tracked| now.duration_since(tracked.tracked_at) < window);
+        transactions.insert(transaction_id, TrackedTransaction { tracked_at: now, subjects });
+    }
+
+    /// Attribute the outcome of a tracked transaction to its subjects
+    pub async fn record_outcome(&self, transaction_id: Uuid, event: ReputationEvent) {
+        let subjects = match self.transactions.write().await.remove(&transaction_id) {
+            Some(tracked) => tracked.subjects,
+            None => return,
+        };
+        self.record(&subjects, event).await;
+    }
+
+    /// Add an event to each subject's history, penalizing subjects that now break a threshold
+    pub async fn record(&self, subjects: &[String], event: ReputationEvent) {
+        if !self.config.enabled {
+            return;
+        }
+
+        let now = Instant::now();
+        let mut penalties = Vec::new();
+        {
+            let mut histories = self.subjects.write().await;
+            for subject in subjects {
+                let history = histories.entry(subject.clone()).or_default();
+                history.events.push_back((now, event));
+                self.prune(history, now);
+
+                let signals = Self::signals(&history.events, now);
+                if let Some(reason) = reputation_violation(&self.config, &signals) {
+                    penalties.push((subject.clone(), self.strike(history, now, reason)));
+                }
+            }
+        }
+
+        for (subject, restriction) in penalties {
+            tracing::warn!(
+                "{} {}: {}",
+                if restriction.is_ban() { "Banning" } else { "Throttling" },
+                subject,
+                restriction.reason
+            );
+            self.rate_limiter.restrict(&subject, restriction).await;
+        }
+    }
+
+    fn prune(&self, history: &mut SubjectHistory, now: Instant) {
+        let window = self.window();
+        while history.events.front().is_some_and(|(at, _)| now.duration_since(*at) >= window) {
+            history.events.pop_front();
+        }
+
+        let decay = Duration::from_secs(self.config.strike_decay);
+        if history.last_strike.is_some_and(|at| now.duration_since(at) >= decay) {
+            history.strikes = 0;
+            history.last_strike = None;
+        }
+    }
+
+    /// Count a strike and build the penalty for it: a throttle, or a ban from
+    /// `ban_after_strikes` on, each twice as long as the one before
+    fn strike(&self, history: &mut SubjectHistory, now: Instant, reason: String) -> Restriction {
+        history.strikes += 1;
+        history.last_strike = Some(now);
+        // The same events must not count towards the next strike
+        history.events.clear();
+
+        let ban_after = self.config.ban_after_strikes.max(1);
+        if history.strikes >= ban_after {
+            let duration = escalated(self.config.ban_duration, history.strikes - ban_after);
+            Restriction::ban(duration, reason)
+        } else {
+            let rpm = self.config.throttle_requests_per_minute;
+            let limits = RateLimit {
+                requests_per_minute: rpm,
+                requests_per_hour: rpm.saturating_mul(60),
+                requests_per_day: rpm.saturating_mul(60 * 24),
+            };
+            let duration = escalated(self.config.throttle_duration, history.strikes - 1);
+            Restriction::throttle(limits, duration, reason)
+        }
+    }
+
+    fn signals(events: &VecDeque<(Instant, ReputationEvent)>, now: Instant) -> ReputationSignals {
+        let mut signals = ReputationSignals::default();
+        let mut targets = HashSet::new();
+
+        for (at, event) in events {
+            let requested = match event {
+                ReputationEvent::Submitted { target } => {
+                    signals.submitted += 1;
+                    Some(target)
+                }
+                ReputationEvent::SimulationFailed { target } => {
+                    signals.simulation_failures += 1;
+                    Some(target)
+                }
+                ReputationEvent::Confirmed => {
+                    signals.confirmed += 1;
+                    None
+                }
+                ReputationEvent::Reverted => {
+                    signals.reverted += 1;
+                    None
+                }
+                ReputationEvent::Cancelled => {
+                    signals.cancelled += 1;
+                    None
+                }
+            };
+
+            if let Some(target) = requested {
+                targets.insert(*target);
+                if now.duration_since(*at) < Duration::from_secs(60) {
+                    signals.burst += 1;
+                }
+            }
+        }
+
+        signals.distinct_targets = targets.len() as u32;
+        signals
+    }
+
+    /// Current signals, strikes and restriction of `subject`
+    pub async fn status(&self, subject: &str) -> ReputationStatus {
+        let now = Instant::now();
+        let (signals, strikes) = {
+            let mut histories = self.subjects.write().await;
+            match histories.get_mut(subject) {
+                Some(history) => {
+                    self.prune(history, now);
+                    (Self::signals(&history.events, now), history.strikes)
+                }
+                None => (ReputationSignals::default(), 0),
+            }
+        };
+
+        ReputationStatus {
+            subject: subject.to_string(),
+            signals,
+            strikes,
+            restriction: self.rate_limiter.get_restriction(subject).await,
+        }
+    }
+
+    /// Every subject currently throttled or banned
+    pub async fn restricted(&self) -> Vec<(String, Restriction)> {
+        self.rate_limiter.get_restrictions().await
+    }
+
+    /// Lift any throttle or ban on `subject` and forget its history, returning whether a
+    /// restriction was in force
+    pub async fn clear(&self, subject: &str) -> bool {
+        self.subjects.write().await.remove(subject);
+        self.rate_limiter.lift_restriction(subject).await
+    }
+}
+
+fn escalated(base_secs: u64, doublings: u32) -> Duration {
+    Duration::from_secs(base_secs.saturating_mul(1u64 << doublings.min(16)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_manager(config: ReputationConfig) -> ReputationManager {
+        ReputationManager::new(config, Arc::new(RateLimiter::new(RateLimit::default())))
+    }
+
+    #[test]
+    fn test_reputation_violation() {
+        let config = ReputationConfig::default();
+        let mut signals = ReputationSignals {
+            confirmed: 4,
+            reverted: 5,
+            ..Default::default()
+        };
+        // Rates are not judged on fewer than `min_transactions`
+        assert_eq!(reputation_violation(&config, &signals), None);
+
+        signals.reverted = 6;
+        let reason = reputation_violation(&config, &signals).unwrap();
+        assert!(reason.starts_with("revert rate 60% over 10 transactions"));
+
+        let signals = ReputationSignals { submitted: 26, distinct_targets: 26, ..Default::default() };
+        assert!(reputation_violation(&config, &signals).unwrap().contains("distinct target"));
+
+        let signals = ReputationSignals { submitted: 31, burst: 31, distinct_targets: 1, ..Default::default() };
+        assert!(reputation_violation(&config, &signals).unwrap().contains("in one minute"));
+
+        let signals = ReputationSignals { submitted: 10, cancelled: 6, distinct_targets: 1, ..Default::default() };
+        assert!(reputation_violation(&config, &signals).unwrap().starts_with("cancellation rate"));
+    }
+
+    #[tokio::test]
+    async fn test_reverts_escalate_to_ban() {
+        let manager = test_manager(ReputationConfig {
+            min_transactions: 2,
+            ban_after_strikes: 2,
+            ..Default::default()
+        });
+        let user = user_reputation_subject(Address::repeat_byte(0x11));
+        let subjects = vec![user.clone()];
+
+        for _ in 0..2 {
+            let id = Uuid::new_v4();
+            manager.track(id, subjects.clone()).await;
+            manager.record_outcome(id, ReputationEvent::Reverted).await;
+        }
+
+        let status = manager.status(&user).await;
+        assert_eq!(status.strikes, 1);
+        let throttle = status.restriction.unwrap();
+        assert!(!throttle.is_ban());
+        assert_eq!(throttle.limits.unwrap().requests_per_minute, 5);
+
+        // The throttle allows 5 requests a minute
+        for _ in 0..5 {
+            assert!(manager.admit(&subjects).await.is_ok());
+        }
+        let rejection = manager.admit(&subjects).await.unwrap_err();
+        assert!(!rejection.banned);
+
+        manager.record(&subjects, ReputationEvent::Reverted).await;
+        manager.record(&subjects, ReputationEvent::Reverted).await;
+        let rejection = manager.admit(&subjects).await.unwrap_err();
+        assert!(rejection.banned);
+        assert!(rejection.reason.contains("revert rate 100%"));
+
+        // Other subjects are unaffected, and a cleared ban admits again
+        assert!(manager.admit(&[api_key_reputation_subject("partner")]).await.is_ok());
+        assert!(manager.clear(&user).await);
+        assert!(manager.admit(&subjects).await.is_ok());
+        assert_eq!(manager.status(&user).await.strikes, 0);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_manager_admits_everything() {
+        let manager = test_manager(ReputationConfig { enabled: false, ..Default::default() });
+        let subjects = vec![user_reputation_subject(Address::repeat_byte(0x22))];
+
+        for _ in 0..50 {
+            manager.record(&subjects, ReputationEvent::SimulationFailed { target: Address::repeat_byte(0x44) }).await;
+        }
+        assert!(manager.admit(&subjects).await.is_ok());
+        assert_eq!(manager.status(&subjects[0]).await.signals, ReputationSignals::default());
+    }
+}
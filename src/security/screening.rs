@@ -0,0 +1,419 @@
+use alloy::{primitives::Address, sol, sol_types::SolCall};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::sync::RwLock;
+use tokio::time::Duration;
+
+use crate::types::{RelayerError, Result, TransactionRequest};
+
+/// `transaction_logs.event_type` of requests refused by address screening
+pub const SCREENING_BLOCKED_EVENT: &str = "screening_blocked";
+
+sol! {
+    /// Token calls whose counterparties are screened
+    interface IScreenedToken {
+        function transfer(address to, uint256 amount) external returns (bool);
+        function transferFrom(address from, address to, uint256 amount) external returns (bool);
+        function approve(address spender, uint256 amount) external returns (bool);
+        function transferWithAuthorization(
+            address from,
+            address to,
+            uint256 value,
+            uint256 validAfter,
+            uint256 validBefore,
+            bytes32 nonce,
+            uint8 v,
+            bytes32 r,
+            bytes32 s
+        );
+        function receiveWithAuthorization(
+            address from,
+            address to,
+            uint256 value,
+            uint256 validAfter,
+            uint256 validBefore,
+            bytes32 nonce,
+            uint8 v,
+            bytes32 r,
+            bytes32 s
+        );
+    }
+}
+
+/// Where a screened address appears in a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreenedRole {
+    User,
+    TargetContract,
+    /// Token holder whose funds a `transferFrom` or EIP-3009 authorization moves
+    Payer,
+    Recipient,
+    Spender,
+}
+
+impl ScreenedRole {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScreenedRole::User => "user",
+            ScreenedRole::TargetContract => "target_contract",
+            ScreenedRole::Payer => "payer",
+            ScreenedRole::Recipient => "recipient",
+            ScreenedRole::Spender => "spender",
+        }
+    }
+}
+
+/// A screened address found on a deny list
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ScreeningMatch {
+    pub address: Address,
+    pub role: ScreenedRole,
+    pub list: String,
+}
+
+/// Every address a request involves: its user, target contract, and the parties of an
+/// ERC-20 or EIP-3009 call decoded from the calldata
+pub fn screened_addresses(request: &TransactionRequest) -> Vec<(Address, ScreenedRole)> {
+    use IScreenedToken::*;
+
+    let mut addresses = vec![
+        (request.user_address, ScreenedRole::User),
+        (request.target_contract, ScreenedRole::TargetContract),
+    ];
+
+    let calldata = request.calldata.as_ref();
+    let selector = match calldata.get(..4) {
+        Some(selector) => selector,
+        None => return addresses,
+    };
+
+    if selector == transferCall::SELECTOR {
+        if let Ok(call) = transferCall::abi_decode(calldata, false) {
+            addresses.push((call.to, ScreenedRole::Recipient));
+        }
+    } else if selector == transferFromCall::SELECTOR {
+        if let Ok(call) = transferFromCall::abi_decode(calldata, false) {
+            addresses.push((call.from, ScreenedRole::Payer));
+            addresses.push((call.to, ScreenedRole::Recipient));
+        }
+    } else if selector == approveCall::SELECTOR {
+        if let Ok(call) = approveCall::abi_decode(calldata, false) {
+            addresses.push((call.spender, ScreenedRole::Spender));
+        }
+    } else if selector == transferWithAuthorizationCall::SELECTOR {
+        if let Ok(call) = transferWithAuthorizationCall::abi_decode(calldata, false) {
+            addresses.push((call.from, ScreenedRole::Payer));
+            addresses.push((call.to, ScreenedRole::Recipient));
+        }
+    } else if selector == receiveWithAuthorizationCall::SELECTOR {
+        if let Ok(call) = receiveWithAuthorizationCall::abi_decode(calldata, false) {
+            addresses.push((call.from, ScreenedRole::Payer));
+            addresses.push((call.to, ScreenedRole::Recipient));
+        }
+    }
+
+    addresses
+}
+
+/// Why a request was refused, for the API error and the stored transaction
+pub fn screening_reason(matches: &[ScreeningMatch]) -> String {
+    let parties = matches.iter()
+        .map(|m| format!("{} {} ({})", m.role.name(), m.address, m.list))
+        .collect::<Vec<_>>();
+    format!("Blocked by address screening: {}", parties.join(", "))
+}
+
+/// Addresses in a deny list file. JSON files hold an array of addresses or of objects with
+/// an `address` field, optionally under an `addresses` key; CSV files hold one address per
+/// row, in the `address` column when there is a header naming one, otherwise the first.
+pub fn parse_deny_list(path: &Path, contents: &str) -> Result<Vec<Address>> {
+    let invalid = |detail: String| RelayerError::Config(format!("Deny list {}: {}", path.display(), detail));
+
+    let is_json = path.extension().and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+
+    if is_json {
+        let value: serde_json::Value = serde_json::from_str(contents)
+            .map_err(|e| invalid(format!("invalid JSON: {}", e)))?;
+        let entries = match value {
+            serde_json::Value::Array(entries) => entries,
+            serde_json::Value::Object(mut object) => match object.remove("addresses") {
+                Some(serde_json::Value::Array(entries)) => entries,
+                _ => return Err(invalid("expected an `addresses` array".to_string())),
+            },
+            _ => return Err(invalid("expected an array of addresses".to_string())),
+        };
+
+        return entries.iter()
+            .map(|entry| {
+                let address = entry.as_str()
+                    .or_else(|| entry.get("address").and_then(|address| address.as_str()))
+                    .ok_or_else(|| invalid(format!("entry without an address: {}", entry)))?;
+                Address::from_str(address.trim()).map_err(|_| invalid(format!("invalid address '{}'", address)))
+            })
+            .collect();
+    }
+
+    let mut rows = contents.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .peekable();
+
+    // A header row is any first row whose cells are not all addresses
+    let mut column = 0;
+    if let Some(header) = rows.peek() {
+        let cells: Vec<&str> = header.split(',').map(|cell| cell.trim().trim_matches('"')).collect();
+        if Address::from_str(cells[0]).is_err() {
+            column = cells.iter().position(|cell| cell.eq_ignore_ascii_case("address")).unwrap_or(0);
+            rows.next();
+        }
+    }
+
+    rows.map(|row| {
+        let cell = row.split(',').nth(column).unwrap_or_default().trim().trim_matches('"');
+        Address::from_str(cell).map_err(|_| invalid(format!("invalid address '{}'", cell)))
+    })
+    .collect()
+}
+
+#[derive(Debug, Default)]
+struct DenyLists {
+    /// Denied address and the file that lists it
+    entries: HashMap<Address, String>,
+    modified: HashMap<PathBuf, Option<SystemTime>>,
+}
+
+/// Refuses requests involving addresses on local deny lists, re-reading the files whenever
+/// they change
+#[derive(Debug, Clone)]
+pub struct AddressScreener {
+    paths: Vec<PathBuf>,
+    lists: Arc<RwLock<DenyLists>>,
+    reload_interval: Duration,
+}
+
+impl AddressScreener {
+    pub fn new(paths: Vec<PathBuf>, reload_interval: Duration) -> Self {
+        Self {
+            paths,
+            lists: Arc::new(RwLock::new(DenyLists::default())),
+            reload_interval,
+        }
+    }
+
+    pub fn is_enforced(&self) -> bool {
+        !self.paths.is_empty()
+    }
+
+    pub async fn denied_count(&self) -> usize {
+        self.lists.read().await.entries.len()
+    }
+
+    /// Read every deny list, replacing the loaded ones only if all of them parse
+    pub async fn load(&self) -> Result<usize> {
+        let mut lists = DenyLists::default();
+
+        for path in &self.paths {
+            let contents = tokio::fs::read_to_string(path).await
+                .map_err(|e| RelayerError::Config(format!("Failed to read deny list {}: {}", path.display(), e)))?;
+            let modified = tokio::fs::metadata(path).await.ok().and_then(|metadata| metadata.modified().ok());
+            let name = path.file_name().map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.display().to_string());
+
+            for address in parse_deny_list(path, &contents)? {
+                lists.entries.entry(address).or_insert_with(|| name.clone());
+            }
+            lists.modified.insert(path.clone(), modified);
+        }
+
+        let count = lists.entries.len();
+        *self.lists.write().await = lists;
+        Ok(count)
+    }
+
+    /// Reload the lists if any file's modification time changed since the last load
+    pub async fn reload_if_changed(&self) -> Result<bool> {
+        let changed = {
+            let lists = self.lists.read().await;
+            let mut changed = false;
+            for path in &self.paths {
+                let modified = tokio::fs::metadata(path).await.ok().and_then(|metadata| metadata.modified().ok());
+                if lists.modified.get(path) != Some(&modified) {
+                    changed = true;
+                    break;
+                }
+            }
+            changed
+        };
+
+        if changed {
+            let count = self.load().await?;
+            tracing::info!("Reloaded deny lists: {} addresses", count);
+        }
+        Ok(changed)
+    }
+
+    /// Start watching the deny list files. A list that fails to parse keeps the previous
+    /// lists in force.
+    pub async fn start_reload_loop(&self) -> Result<()> {
+        let screener = Arc::new(self.clone());
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(screener.reload_interval);
+
+            loop {
+                interval.tick().await;
+
+                if let Err(e) = screener.reload_if_changed().await {
+                    tracing::error!("Failed to reload deny lists, keeping the previous ones: {}", e);
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Deny-listed addresses involved in `request`; empty when it may be relayed
+    pub async fn screen(&self, request: &TransactionRequest) -> Vec<ScreeningMatch> {
+        let lists = self.lists.read().await;
+        if lists.entries.is_empty() {
+            return Vec::new();
+        }
+
+        screened_addresses(request).into_iter()
+            .filter_map(|(address, role)| lists.entries.get(&address).map(|list| ScreeningMatch {
+                address,
+                role,
+                list: list.clone(),
+            }))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Priority, Signature};
+    use alloy::primitives::{Bytes, B256, U256};
+
+    const DENIED: Address = Address::repeat_byte(0xdd);
+
+    fn request(calldata: Vec<u8>) -> TransactionRequest {
+        TransactionRequest::new(
+            Address::repeat_byte(0x11),
+            Address::repeat_byte(0x44),
+            Bytes::from(calldata),
+            U256::ZERO,
+            U256::from(100000u64),
+            U256::from(20000000000u64),
+            U256::from(2000000000u64),
+            U256::ZERO,
+            Signature { r: U256::ZERO, s: U256::ZERO, v: 27 },
+            Priority::Normal,
+        )
+    }
+
+    #[test]
+    fn test_screened_addresses() {
+        let transfer = IScreenedToken::transferCall { to: DENIED, amount: U256::from(5u64) }.abi_encode();
+        assert_eq!(
+            screened_addresses(&request(transfer)),
+            vec![
+                (Address::repeat_byte(0x11), ScreenedRole::User),
+                (Address::repeat_byte(0x44), ScreenedRole::TargetContract),
+                (DENIED, ScreenedRole::Recipient),
+            ]
+        );
+
+        let authorization = IScreenedToken::transferWithAuthorizationCall {
+            from: Address::repeat_byte(0x22),
+            to: DENIED,
+            value: U256::from(5u64),
+            validAfter: U256::ZERO,
+            validBefore: U256::MAX,
+            nonce: B256::ZERO,
+            v: 27,
+            r: B256::ZERO,
+            s: B256::ZERO,
+        }
+        .abi_encode();
+        let addresses = screened_addresses(&request(authorization));
+        assert!(addresses.contains(&(Address::repeat_byte(0x22), ScreenedRole::Payer)));
+        assert!(addresses.contains(&(DENIED, ScreenedRole::Recipient)));
+
+        // Unknown or malformed calls only screen the request itself
+        assert_eq!(screened_addresses(&request(vec![0xa9, 0x05, 0x9c, 0xbb, 0x01])).len(), 2);
+        assert_eq!(screened_addresses(&request(vec![])).len(), 2);
+    }
+
+    #[test]
+    fn test_parse_deny_list() {
+        let json = format!(r#"["{}", {{"address": "{}"}}]"#, DENIED, Address::repeat_byte(0xee));
+        assert_eq!(
+            parse_deny_list(Path::new("sdn.json"), &json).unwrap(),
+            vec![DENIED, Address::repeat_byte(0xee)]
+        );
+
+        let json = format!(r#"{{"addresses": ["{}"]}}"#, DENIED);
+        assert_eq!(parse_deny_list(Path::new("sdn.JSON"), &json).unwrap(), vec![DENIED]);
+
+        let csv = format!("name,address\n\"Some entity\",{}\n\n# comment\nOther,\"{}\"\n", DENIED, Address::repeat_byte(0xee));
+        assert_eq!(
+            parse_deny_list(Path::new("sdn.csv"), &csv).unwrap(),
+            vec![DENIED, Address::repeat_byte(0xee)]
+        );
+
+        let csv = format!("{}\n{}\n", DENIED, Address::repeat_byte(0xee));
+        assert_eq!(parse_deny_list(Path::new("list.csv"), &csv).unwrap().len(), 2);
+
+        assert!(matches!(
+            parse_deny_list(Path::new("list.csv"), "address\nnot-an-address\n"),
+            Err(RelayerError::Config(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_screen_and_hot_reload() {
+        let dir = std::env::temp_dir().join(format!("express402-screening-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("deny.csv");
+        std::fs::write(&path, "address\n").unwrap();
+
+        let screener = AddressScreener::new(vec![path.clone()], Duration::from_secs(60));
+        assert_eq!(screener.load().await.unwrap(), 0);
+
+        let transfer = IScreenedToken::transferCall { to: DENIED, amount: U256::from(5u64) }.abi_encode();
+        assert!(screener.screen(&request(transfer.clone())).await.is_empty());
+        assert!(!screener.reload_if_changed().await.unwrap());
+
+        std::fs::write(&path, format!("address\n{}\n", DENIED)).unwrap();
+        // Make the change visible even on filesystems with coarse timestamps
+        let later = SystemTime::now() + std::time::Duration::from_secs(5);
+        std::fs::File::options().write(true).open(&path).unwrap().set_modified(later).unwrap();
+        assert!(screener.reload_if_changed().await.unwrap());
+
+        let matches = screener.screen(&request(transfer)).await;
+        assert_eq!(
+            matches,
+            vec![ScreeningMatch { address: DENIED, role: ScreenedRole::Recipient, list: "deny.csv".to_string() }]
+        );
+        assert_eq!(
+            screening_reason(&matches),
+            format!("Blocked by address screening: recipient {} (deny.csv)", DENIED)
+        );
+
+        // A broken list leaves the loaded one in force
+        std::fs::write(&path, "address\nnot-an-address\n").unwrap();
+        std::fs::File::options().write(true).open(&path).unwrap()
+            .set_modified(later + std::time::Duration::from_secs(5)).unwrap();
+        assert!(screener.reload_if_changed().await.is_err());
+        assert_eq!(screener.denied_count().await, 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
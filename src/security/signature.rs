@@ -1,13 +1,107 @@
 use alloy::{
-    primitives::{Address, U256},
+    primitives::{uint, Address, Bytes, PrimitiveSignature, B256, U256},
     signers::k256::ecdsa::SigningKey,
 };
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 
 use crate::types::{RelayerError, Result, TransactionRequest, Signature};
 
+use super::eip1271::ContractSignatureVerifier;
+use super::eip712::{TypedData, TypedDataField, TypedDataTypes};
+
+/// Half the secp256k1 group order. Signatures with a larger `s` are malleable copies
+/// of a low-s signature (EIP-2) and are rejected.
+pub const SECP256K1_HALF_ORDER: U256 =
+    uint!(0x7FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF5D576E7357A4501DDFE92F46681B20A0_U256);
+
+/// Recover the address that produced `signature` over `message_hash`.
+/// Only canonical signatures are accepted: v must be 27/28 and s in the lower half of the curve order.
+pub fn ecrecover(message_hash: &B256, signature: &Signature) -> Result<Address> {
+    let y_parity = match signature.v {
+        27 => false,
+        28 => true,
+        _ => {
+            return Err(RelayerError::SignatureVerification(
+                "Invalid signature v value".to_string(),
+            ));
+        }
+    };
+
+    if signature.r.is_zero() || signature.s.is_zero() {
+        return Err(RelayerError::SignatureVerification(
+            "Invalid signature r or s value".to_string(),
+        ));
+    }
+
+    if signature.s > SECP256K1_HALF_ORDER {
+        return Err(RelayerError::SignatureVerification(
+            "Signature s value is too high".to_string(),
+        ));
+    }
+
+    PrimitiveSignature::new(signature.r, signature.s, y_parity)
+        .recover_address_from_prehash(message_hash)
+        .map_err(|e| RelayerError::SignatureVerification(format!("Failed to recover signer: {}", e)))
+}
+
+/// Bring an r/s/v signature into canonical form: v of 0/1 becomes 27/28, and zero r/s
+/// or a high s is rejected rather than flipped, since the signer never produced it.
+pub fn normalize_signature(signature: Signature) -> Result<Signature> {
+    let v = match signature.v {
+        0 | 1 => signature.v + 27,
+        27 | 28 => signature.v,
+        v => {
+            return Err(RelayerError::SignatureVerification(format!(
+                "Invalid signature v value: {}",
+                v
+            )));
+        }
+    };
+
+    if signature.r.is_zero() || signature.s.is_zero() {
+        return Err(RelayerError::SignatureVerification(
+            "Invalid signature r or s value".to_string(),
+        ));
+    }
+
+    if signature.s > SECP256K1_HALF_ORDER {
+        return Err(RelayerError::SignatureVerification(
+            "Signature s value is too high".to_string(),
+        ));
+    }
+
+    Ok(Signature { v, ..signature })
+}
+
+/// Parse a 65-byte `r ‖ s ‖ v` or 64-byte EIP-2098 compact `r ‖ yParityAndS` signature
+/// into canonical form
+pub fn parse_signature_bytes(bytes: &[u8]) -> Result<Signature> {
+    let signature = match bytes.len() {
+        65 => Signature {
+            r: U256::from_be_slice(&bytes[..32]),
+            s: U256::from_be_slice(&bytes[32..64]),
+            v: bytes[64],
+        },
+        64 => {
+            let y_parity_and_s = U256::from_be_slice(&bytes[32..]);
+            Signature {
+                r: U256::from_be_slice(&bytes[..32]),
+                s: y_parity_and_s & (U256::MAX >> 1),
+                v: 27 + y_parity_and_s.bit(255) as u8,
+            }
+        }
+        len => {
+            return Err(RelayerError::SignatureVerification(format!(
+                "Signature must be 64 or 65 bytes, got {}",
+                len
+            )));
+        }
+    };
+
+    normalize_signature(signature)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EIP712Domain {
     pub name: String,
@@ -16,22 +110,116 @@ pub struct EIP712Domain {
     pub verifying_contract: Address,
 }
 
+impl EIP712Domain {
+    /// keccak256 of the encoded `EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)`
+    pub fn separator(&self) -> [u8; 32] {
+        use sha3::{Digest, Keccak256};
+
+        let domain_type_hash = Keccak256::digest(b"EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)");
+        let name_hash = Keccak256::digest(self.name.as_bytes());
+        let version_hash = Keccak256::digest(self.version.as_bytes());
+
+        let mut hasher = Keccak256::new();
+        hasher.update(domain_type_hash);
+        hasher.update(name_hash);
+        hasher.update(version_hash);
+        hasher.update(self.chain_id.to_be_bytes::<32>());
+        hasher.update(self.verifying_contract.into_word());
+
+        hasher.finalize().into()
+    }
+
+    /// The domain as `eth_signTypedData_v4` JSON
+    pub fn to_value(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": self.name,
+            "version": self.version,
+            "chainId": self.chain_id.to_string(),
+            "verifyingContract": self.verifying_contract.to_string(),
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransactionMessage {
     pub user_address: Address,
     pub target_contract: Address,
-    pub calldata: String,
+    pub calldata: Bytes,
     pub value: U256,
     pub gas_limit: U256,
     pub max_fee_per_gas: U256,
     pub max_priority_fee_per_gas: U256,
     pub nonce: U256,
-    pub timestamp: u64,
+    pub valid_after: u64,
+    pub valid_before: u64,
+}
+
+impl TransactionMessage {
+    /// The message a user signs for a relayed transaction
+    pub fn from_request(request: &TransactionRequest) -> Self {
+        Self {
+            user_address: request.user_address,
+            target_contract: request.target_contract,
+            calldata: request.calldata.clone(),
+            value: request.value,
+            gas_limit: request.gas_limit,
+            max_fee_per_gas: request.max_fee_per_gas,
+            max_priority_fee_per_gas: request.max_priority_fee_per_gas,
+            nonce: request.nonce,
+            valid_after: request.valid_after.map_or(0, |time| time.timestamp() as u64),
+            valid_before: request.valid_before.map_or(0, |time| time.timestamp() as u64),
+        }
+    }
+
+    /// The message as `eth_signTypedData_v4` typed data under `domain`
+    pub fn typed_data(&self, domain: &EIP712Domain) -> TypedData {
+        let fields = [
+            ("user_address", "address"),
+            ("target_contract", "address"),
+            ("calldata", "bytes"),
+            ("value", "uint256"),
+            ("gas_limit", "uint256"),
+            ("max_fee_per_gas", "uint256"),
+            ("max_priority_fee_per_gas", "uint256"),
+            ("nonce", "uint256"),
+            ("valid_after", "uint256"),
+            ("valid_before", "uint256"),
+        ];
+
+        let mut types = TypedDataTypes::new();
+        types.insert(
+            "TransactionMessage".to_string(),
+            fields
+                .iter()
+                .map(|(name, r#type)| TypedDataField {
+                    name: name.to_string(),
+                    r#type: r#type.to_string(),
+                })
+                .collect(),
+        );
+
+        let message = serde_json::json!({
+            "user_address": self.user_address.to_string(),
+            "target_contract": self.target_contract.to_string(),
+            "calldata": self.calldata.to_string(),
+            "value": self.value.to_string(),
+            "gas_limit": self.gas_limit.to_string(),
+            "max_fee_per_gas": self.max_fee_per_gas.to_string(),
+            "max_priority_fee_per_gas": self.max_priority_fee_per_gas.to_string(),
+            "nonce": self.nonce.to_string(),
+            "valid_after": self.valid_after.to_string(),
+            "valid_before": self.valid_before.to_string(),
+        });
+
+        TypedData::new(types, "TransactionMessage", domain.to_value(), message)
+    }
 }
 
+#[derive(Debug)]
 pub struct SignatureVerifier {
     domain: EIP712Domain,
-    nonce_cache: HashMap<Address, u64>,
+    contract_verifier: Option<ContractSignatureVerifier>,
+    max_validity_window: Duration,
 }
 
 impl SignatureVerifier {
@@ -43,79 +231,103 @@ impl SignatureVerifier {
                 chain_id,
                 verifying_contract,
             },
-            nonce_cache: HashMap::new(),
+            contract_verifier: None,
+            max_validity_window: Duration::seconds(300),
         }
     }
 
-    pub fn verify_transaction_signature(
-        &mut self,
-        request: &TransactionRequest,
-        expected_nonce: u64,
-    ) -> Result<bool> {
-        // Check if signature is recent enough
-        let now = Utc::now().timestamp() as u64;
-        let signature_age = now - request.timestamp.timestamp() as u64;
-        
-        if signature_age > 300 { // 5 minutes
-            return Err(RelayerError::SignatureVerification(
-                "Signature too old".to_string(),
-            ));
-        }
+    /// Longest a signed request may stay valid, counted from when it is received
+    pub fn with_max_validity_window(mut self, max_validity_window: std::time::Duration) -> Self {
+        self.max_validity_window = Duration::from_std(max_validity_window).unwrap_or(Duration::MAX);
+        self
+    }
 
-        // Verify nonce
-        if !self.verify_nonce(request.user_address, expected_nonce) {
-            return Err(RelayerError::SignatureVerification(
-                "Invalid nonce".to_string(),
-            ));
-        }
+    /// Also accept EIP-1271 / ERC-6492 signatures from smart-contract wallets
+    pub fn with_contract_verifier(mut self, contract_verifier: ContractSignatureVerifier) -> Self {
+        self.contract_verifier = Some(contract_verifier);
+        self
+    }
 
-        // Create the message that was signed
-        let message = TransactionMessage {
-            user_address: request.user_address,
-            target_contract: request.target_contract,
-            calldata: format!("0x{}", hex::encode(&request.calldata)),
-            value: request.value,
-            gas_limit: request.gas_limit,
-            max_fee_per_gas: request.max_fee_per_gas,
-            max_priority_fee_per_gas: request.max_priority_fee_per_gas,
-            nonce: request.nonce,
-            timestamp: request.timestamp.timestamp() as u64,
-        };
+    pub fn domain(&self) -> &EIP712Domain {
+        &self.domain
+    }
+
+    /// Check the request is inside its signed validity window and signed by `user_address`.
+    /// The signed nonce is consumed separately by `UserNonceManager`.
+    pub async fn verify_transaction_signature(&self, request: &TransactionRequest) -> Result<bool> {
+        self.check_validity_window(request, Utc::now())?;
 
         // Verify EIP-712 signature
-        self.verify_eip712_signature(&message, &request.signature, request.user_address)
+        let message = TransactionMessage::from_request(request);
+        self.verify_eip712_signature(&message, request).await
     }
 
-    fn verify_nonce(&mut self, address: Address, nonce: u64) -> bool {
-        let current_nonce = self.nonce_cache.get(&address).copied().unwrap_or(0);
-        
-        if nonce <= current_nonce {
-            return false;
+    /// Check that `now` falls in the request's `[valid_after, valid_before)` window and that
+    /// the window ends no more than `max_validity_window` after `now`
+    pub fn check_validity_window(&self, request: &TransactionRequest, now: DateTime<Utc>) -> Result<()> {
+        let valid_before = request.valid_before.ok_or_else(|| {
+            RelayerError::Validation("valid_before is required".to_string())
+        })?;
+
+        if let Some(valid_after) = request.valid_after {
+            if valid_after >= valid_before {
+                return Err(RelayerError::Validation(
+                    "valid_after must be before valid_before".to_string(),
+                ));
+            }
+        }
+
+        if request.is_expired(now) {
+            return Err(RelayerError::Expired(format!(
+                "valid_before {} has passed",
+                valid_before.timestamp()
+            )));
+        }
+
+        if request.is_not_yet_valid(now) {
+            return Err(RelayerError::Validation(format!(
+                "Request is not valid until {}",
+                request.valid_after.map_or(0, |time| time.timestamp())
+            )));
+        }
+
+        if valid_before > now + self.max_validity_window {
+            return Err(RelayerError::Validation(format!(
+                "valid_before may be at most {} seconds in the future",
+                self.max_validity_window.num_seconds()
+            )));
         }
 
-        self.nonce_cache.insert(address, nonce);
-        true
+        Ok(())
     }
 
-    fn verify_eip712_signature(
+    async fn verify_eip712_signature(
         &self,
-        _message: &TransactionMessage,
-        signature: &crate::types::Signature,
-        _expected_address: Address,
+        message: &TransactionMessage,
+        request: &TransactionRequest,
     ) -> Result<bool> {
-        // Basic signature format check
-        if signature.v != 27 && signature.v != 28 {
-            return Err(RelayerError::SignatureVerification(
-                "Invalid signature v value".to_string(),
-            ));
+        let message_hash = self.create_eip712_hash(message)?;
+
+        // Plain ECDSA first, so EOAs never cost an RPC round trip
+        if request.signature_data.is_none() {
+            match self.recover_address_from_signature(&message_hash, &request.signature) {
+                Ok(recovered_address) if recovered_address == request.user_address => return Ok(true),
+                Err(e) if self.contract_verifier.is_none() => return Err(e),
+                _ => {}
+            }
         }
 
-        // TODO: Implement proper EIP-712 signature verification
-        // For now, return true as placeholder
-        Ok(true)
+        match self.contract_verifier {
+            Some(ref contract_verifier) => {
+                contract_verifier
+                    .is_valid_signature(request.user_address, B256::from(message_hash), &request.signature_bytes())
+                    .await
+            }
+            None => Ok(false),
+        }
     }
 
-    fn create_eip712_hash(&self, message: &TransactionMessage) -> Result<[u8; 32]> {
+    pub fn create_eip712_hash(&self, message: &TransactionMessage) -> Result<[u8; 32]> {
         use sha3::{Digest, Keccak256};
 
         // Create domain separator
@@ -138,111 +350,33 @@ impl SignatureVerifier {
     }
 
     fn create_domain_separator(&self) -> Result<[u8; 32]> {
-        use sha3::{Digest, Keccak256};
-
-        // EIP-712 domain separator: keccak256(keccak256("EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)") + keccak256(name) + keccak256(version) + chainId + verifyingContract)
-        
-        let domain_type_hash = Keccak256::digest(b"EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)");
-        let name_hash = Keccak256::digest(self.domain.name.as_bytes());
-        let version_hash = Keccak256::digest(self.domain.version.as_bytes());
-        
-        let mut hasher = Keccak256::new();
-        hasher.update(domain_type_hash);
-        hasher.update(name_hash);
-        hasher.update(version_hash);
-        hasher.update(self.domain.chain_id.to_be_bytes::<32>());
-        hasher.update(self.domain.verifying_contract.as_slice());
-        
-        let hash = hasher.finalize();
-        let mut result = [0u8; 32];
-        result.copy_from_slice(&hash);
-        
-        Ok(result)
+        Ok(self.domain.separator())
     }
 
     fn create_struct_hash(&self, message: &TransactionMessage) -> Result<[u8; 32]> {
-        use sha3::{Digest, Keccak256};
-
-        // TransactionMessage type hash
-        let type_hash = Keccak256::digest(b"TransactionMessage(address user_address,address target_contract,string calldata,uint256 value,uint256 gas_limit,uint256 max_fee_per_gas,uint256 max_priority_fee_per_gas,uint256 nonce,uint256 timestamp)");
-        
-        // Encode struct data
-        let mut encoded_data = Vec::new();
-        
-        // user_address (32 bytes)
-        encoded_data.extend_from_slice(&message.user_address.into_array());
-        
-        // target_contract (32 bytes)
-        encoded_data.extend_from_slice(&message.target_contract.into_array());
-        
-        // calldata (keccak256 hash)
-        let calldata_hash = Keccak256::digest(message.calldata.as_bytes());
-        encoded_data.extend_from_slice(&calldata_hash);
-        
-        // value (32 bytes)
-        let mut value_be = [0u8; 32];
-        value_be[24..32].copy_from_slice(&message.value.to_be_bytes::<8>());
-        encoded_data.extend_from_slice(&value_be);
-        
-        // gas_limit (32 bytes)
-        let mut gas_limit_be = [0u8; 32];
-        gas_limit_be[24..32].copy_from_slice(&message.gas_limit.to_be_bytes::<8>());
-        encoded_data.extend_from_slice(&gas_limit_be);
-        
-        // max_fee_per_gas (32 bytes)
-        let mut max_fee_be = [0u8; 32];
-        max_fee_be[24..32].copy_from_slice(&message.max_fee_per_gas.to_be_bytes::<8>());
-        encoded_data.extend_from_slice(&max_fee_be);
-        
-        // max_priority_fee_per_gas (32 bytes)
-        let mut max_priority_fee_be = [0u8; 32];
-        max_priority_fee_be[24..32].copy_from_slice(&message.max_priority_fee_per_gas.to_be_bytes::<8>());
-        encoded_data.extend_from_slice(&max_priority_fee_be);
-        
-        // nonce (32 bytes)
-        let mut nonce_be = [0u8; 32];
-        nonce_be[24..32].copy_from_slice(&message.nonce.to_be_bytes::<8>());
-        encoded_data.extend_from_slice(&nonce_be);
-        
-        // timestamp (32 bytes)
-        let mut timestamp_be = [0u8; 32];
-        timestamp_be[24..32].copy_from_slice(&message.timestamp.to_be_bytes());
-        encoded_data.extend_from_slice(&timestamp_be);
-        
-        // Create struct hash
-        let mut hasher = Keccak256::new();
-        hasher.update(type_hash);
-        hasher.update(encoded_data);
-        
-        let hash = hasher.finalize();
-        let mut result = [0u8; 32];
-        result.copy_from_slice(&hash);
-        
-        Ok(result)
+        let typed_data = message.typed_data(&self.domain);
+        Ok(typed_data.hash_struct(&typed_data.primary_type, &typed_data.message)?.0)
     }
 
     pub fn recover_address_from_signature(
         &self,
-        _message_hash: &[u8; 32],
-        _signature: &Signature,
+        message_hash: &[u8; 32],
+        signature: &Signature,
     ) -> Result<Address> {
-        // TODO: Implement proper address recovery
-        // For now, return a placeholder address
-        Ok(Address::ZERO)
+        ecrecover(&B256::from(*message_hash), signature)
     }
 
     pub fn sign_transaction(
         &self,
-        _private_key: &SigningKey,
-        _message_hash: &[u8; 32],
+        private_key: &SigningKey,
+        message_hash: &[u8; 32],
     ) -> Result<Signature> {
-        // TODO: Implement proper signature creation
-        // For now, return a placeholder signature
-        Ok(Signature {
-            r: U256::ZERO,
-            s: U256::ZERO,
-            v: 0,
-        })
+        // k256 always produces low-s signatures
+        let (signature, recovery_id) = private_key
+            .sign_prehash_recoverable(message_hash)
+            .map_err(|e| RelayerError::Internal(format!("Signing failed: {}", e)))?;
+
+        Ok(Signature::from((signature, recovery_id)))
     }
 }
 
@@ -250,7 +384,8 @@ impl Clone for SignatureVerifier {
     fn clone(&self) -> Self {
         Self {
             domain: self.domain.clone(),
-            nonce_cache: self.nonce_cache.clone(),
+            contract_verifier: self.contract_verifier.clone(),
+            max_validity_window: self.max_validity_window,
         }
     }
 }
@@ -271,20 +406,41 @@ mod tests {
     }
 
     #[test]
-    fn test_nonce_verification() {
-        let mut verifier = SignatureVerifier::new(U256::from(1), Address::ZERO);
-        let address = Address::ZERO;
-        
-        // First nonce should be valid
-        assert!(verifier.verify_nonce(address, 1));
-        
-        // Same nonce should be invalid
-        assert!(!verifier.verify_nonce(address, 1));
-        
-        // Lower nonce should be invalid
-        assert!(!verifier.verify_nonce(address, 0));
-        
-        // Higher nonce should be valid
-        assert!(verifier.verify_nonce(address, 2));
+    fn test_parse_signature_bytes_forms() {
+        let key = SigningKey::from_slice(&[0x42; 32]).unwrap();
+        let verifier = SignatureVerifier::new(U256::from(1), Address::ZERO);
+        let hash = [0x7a; 32];
+        let signature = verifier.sign_transaction(&key, &hash).unwrap();
+        let signer = ecrecover(&B256::from(hash), &signature).unwrap();
+
+        let full = signature.to_bytes();
+        assert_eq!(parse_signature_bytes(&full).unwrap(), signature);
+
+        // v as 0/1 instead of 27/28
+        let mut raw_parity = full.to_vec();
+        raw_parity[64] -= 27;
+        assert_eq!(parse_signature_bytes(&raw_parity).unwrap(), signature);
+
+        // EIP-2098: the y parity is folded into the top bit of s
+        let mut compact = full[..64].to_vec();
+        compact[32] |= (signature.v - 27) << 7;
+        let parsed = parse_signature_bytes(&compact).unwrap();
+        assert_eq!(parsed, signature);
+        assert_eq!(ecrecover(&B256::from(hash), &parsed).unwrap(), signer);
+
+        assert!(parse_signature_bytes(&full[..63]).is_err());
+    }
+
+    #[test]
+    fn test_normalize_signature_rejects_non_canonical() {
+        let low_s = Signature { r: U256::from(1), s: SECP256K1_HALF_ORDER, v: 1 };
+        assert_eq!(normalize_signature(low_s.clone()).unwrap().v, 28);
+
+        let high_s = Signature { s: SECP256K1_HALF_ORDER + U256::from(1), ..low_s.clone() };
+        assert!(normalize_signature(high_s).is_err());
+
+        assert!(normalize_signature(Signature { v: 2, ..low_s.clone() }).is_err());
+        assert!(normalize_signature(Signature { v: 37, ..low_s.clone() }).is_err());
+        assert!(normalize_signature(Signature { r: U256::ZERO, ..low_s }).is_err());
     }
 }
@@ -1,86 +1,226 @@
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use alloy::primitives::{Address, U256};
-    use std::str::FromStr;
-
-    fn create_test_verifier() -> SignatureVerifier {
-        SignatureVerifier::new(
-            "Express402".to_string(),
-            "1".to_string(),
-            1,
-            Address::from_str("0x1234567890123456789012345678901234567890").unwrap(),
-        )
-    }
+use super::*;
+use crate::types::{Priority, RelayerError, Signature, TransactionRequest};
+use crate::utils::CryptoUtils;
+use alloy::primitives::{Address, Bytes, U256};
+use alloy::signers::k256::ecdsa::SigningKey;
+use std::str::FromStr;
 
-    fn create_test_message() -> TransactionMessage {
-        TransactionMessage {
-            user_address: Address::from_str("0x1234567890123456789012345678901234567890").unwrap(),
-            target_contract: Address::from_str("0x0987654321098765432109876543210987654321").unwrap(),
-            calldata: "0x1234".to_string(),
-            value: U256::from(1000000000000000000u64),
-            gas_limit: U256::from(21000u64),
-            max_fee_per_gas: U256::from(20000000000u64),
-            max_priority_fee_per_gas: U256::from(2000000000u64),
-            nonce: U256::from(1u64),
-            timestamp: 1640995200, // 2022-01-01 00:00:00 UTC
-        }
-    }
+fn create_test_verifier() -> SignatureVerifier {
+    SignatureVerifier::new(
+        U256::from(1u64),
+        Address::from_str("0x1234567890123456789012345678901234567890").unwrap(),
+    )
+}
 
-    fn create_test_signature() -> Signature {
-        Signature {
-            r: U256::from_str("0x1234567890123456789012345678901234567890123456789012345678901234").unwrap(),
-            s: U256::from_str("0x0987654321098765432109876543210987654321098765432109876543210987").unwrap(),
-            v: 27,
-        }
+fn create_test_signature(v: u8) -> Signature {
+    Signature {
+        r: U256::from_str("0x1234567890123456789012345678901234567890123456789012345678901234").unwrap(),
+        s: U256::from_str("0x0987654321098765432109876543210987654321098765432109876543210987").unwrap(),
+        v,
     }
+}
 
-    #[test]
-    fn test_signature_verifier_creation() {
-        let verifier = create_test_verifier();
-        assert_eq!(verifier.domain.name, "Express402");
-        assert_eq!(verifier.domain.version, "1");
-        assert_eq!(verifier.domain.chain_id, U256::from(1u64));
-    }
+fn create_test_request(v: u8) -> TransactionRequest {
+    let mut request = TransactionRequest::new(
+        Address::from_str("0x1234567890123456789012345678901234567890").unwrap(),
+        Address::from_str("0x0987654321098765432109876543210987654321").unwrap(),
+        Bytes::from_str("0x1234").unwrap(),
+        U256::from(1000000000000000000u64),
+        U256::from(21000u64),
+        U256::from(20000000000u64),
+        U256::from(2000000000u64),
+        U256::from(1u64),
+        create_test_signature(v),
+        Priority::Normal,
+    );
+    request.valid_before = chrono::DateTime::from_timestamp(chrono::Utc::now().timestamp() + 60, 0);
+    request
+}
 
-    #[test]
-    fn test_verify_nonce() {
-        let verifier = create_test_verifier();
-        let address = Address::from_str("0x1234567890123456789012345678901234567890").unwrap();
-        let nonce = 5;
+fn create_signed_request(verifier: &SignatureVerifier, key: &SigningKey) -> TransactionRequest {
+    let mut request = create_test_request(27);
+    request.user_address = Address::from_private_key(key);
 
-        // First verification should succeed
-        assert!(verifier.verify_nonce(address, nonce));
+    let message_hash = verifier.create_eip712_hash(&TransactionMessage::from_request(&request)).unwrap();
+    request.signature = verifier.sign_transaction(key, &message_hash).unwrap();
+    request
+}
 
-        // Second verification with same nonce should fail
-        assert!(!verifier.verify_nonce(address, nonce));
+#[tokio::test]
+async fn test_verify_transaction_signature_binds_nonce() {
+    let verifier = create_test_verifier();
+    let mut request = create_signed_request(&verifier, &CryptoUtils::generate_private_key());
 
-        // Higher nonce should succeed
-        assert!(verifier.verify_nonce(address, nonce + 1));
-    }
+    assert!(matches!(verifier.verify_transaction_signature(&request).await, Ok(true)));
 
-    #[test]
-    fn test_verify_eip712_signature() {
-        let verifier = create_test_verifier();
-        let message = create_test_message();
-        let signature = create_test_signature();
-        let expected_address = Address::from_str("0x1234567890123456789012345678901234567890").unwrap();
-
-        // This is a placeholder test since we have placeholder implementations
-        // In a real implementation, this would test actual signature verification
-        let result = verifier.verify_eip712_signature(&message, &signature, expected_address);
-        assert!(result.is_ok());
-    }
+    // The nonce is part of the signed message, so it can't be swapped for an unused one
+    request.nonce += U256::from(1u64);
+    assert!(matches!(verifier.verify_transaction_signature(&request).await, Ok(false)));
+}
 
-    #[test]
-    fn test_create_eip712_hash() {
-        let verifier = create_test_verifier();
-        let message = create_test_message();
+#[tokio::test]
+async fn test_verify_transaction_signature_rejects_forgery() {
+    let verifier = create_test_verifier();
+    let key = CryptoUtils::generate_private_key();
 
-        let result = verifier.create_eip712_hash(&message);
-        assert!(result.is_ok());
-        
-        let hash = result.unwrap();
-        assert_eq!(hash.len(), 32); // Should be 32 bytes
-    }
-}
\ No newline at end of file
+    // Signed by someone other than `user_address`
+    let mut forged = create_signed_request(&verifier, &CryptoUtils::generate_private_key());
+    forged.user_address = Address::from_private_key(&key);
+    assert!(matches!(verifier.verify_transaction_signature(&forged).await, Ok(false)));
+
+    // Altered after signing
+    let mut tampered = create_signed_request(&verifier, &key);
+    tampered.value += U256::from(1u64);
+    assert!(matches!(verifier.verify_transaction_signature(&tampered).await, Ok(false)));
+
+    let request = create_signed_request(&verifier, &key);
+    assert!(matches!(verifier.verify_transaction_signature(&request).await, Ok(true)));
+}
+
+#[tokio::test]
+async fn test_verify_transaction_signature_rejects_high_s() {
+    let verifier = create_test_verifier();
+    let mut request = create_signed_request(&verifier, &CryptoUtils::generate_private_key());
+
+    // (r, n - s) with the other parity recovers the same key, but is not canonical
+    let order = SECP256K1_HALF_ORDER * U256::from(2u64) + U256::from(1u64);
+    request.signature.s = order - request.signature.s;
+    request.signature.v = if request.signature.v == 27 { 28 } else { 27 };
+
+    assert!(matches!(
+        verifier.verify_transaction_signature(&request).await,
+        Err(RelayerError::SignatureVerification(_))
+    ));
+}
+
+#[test]
+fn test_recover_address_from_crypto_utils_signature() {
+    let verifier = create_test_verifier();
+    let key = CryptoUtils::generate_private_key();
+    let message = b"express402 relayer";
+
+    let signature: Signature = CryptoUtils::sign_message(&key, message).unwrap().into();
+    let message_hash = CryptoUtils::keccak256(message);
+    assert_eq!(
+        verifier.recover_address_from_signature(&message_hash, &signature).unwrap(),
+        Address::from_private_key(&key)
+    );
+
+    // The relayer's own signatures recover the same way
+    let signature = verifier.sign_transaction(&key, &message_hash).unwrap();
+    assert!(signature.s <= SECP256K1_HALF_ORDER);
+    assert_eq!(
+        verifier.recover_address_from_signature(&message_hash, &signature).unwrap(),
+        Address::from_private_key(&key)
+    );
+
+    let other_hash = CryptoUtils::keccak256(b"something else");
+    assert_ne!(
+        verifier.recover_address_from_signature(&other_hash, &signature).unwrap(),
+        Address::from_private_key(&key)
+    );
+}
+
+#[tokio::test]
+async fn test_verify_transaction_signature_rejects_invalid_v() {
+    let verifier = create_test_verifier();
+    let mut request = create_signed_request(&verifier, &CryptoUtils::generate_private_key());
+    request.signature.v = 30;
+
+    assert!(matches!(
+        verifier.verify_transaction_signature(&request).await,
+        Err(RelayerError::SignatureVerification(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_verify_transaction_signature_rejects_stale_request() {
+    let verifier = create_test_verifier();
+    let mut request = create_signed_request(&verifier, &CryptoUtils::generate_private_key());
+    request.valid_before = Some(chrono::Utc::now() - chrono::Duration::minutes(10));
+
+    assert!(matches!(
+        verifier.verify_transaction_signature(&request).await,
+        Err(RelayerError::Expired(_))
+    ));
+}
+
+#[test]
+fn test_check_validity_window() {
+    let verifier = create_test_verifier()
+        .with_max_validity_window(std::time::Duration::from_secs(300));
+    let now = chrono::Utc::now();
+    let mut request = create_test_request(27);
+
+    request.valid_before = None;
+    assert!(matches!(verifier.check_validity_window(&request, now), Err(RelayerError::Validation(_))));
+
+    request.valid_before = Some(now + chrono::Duration::seconds(300));
+    assert!(verifier.check_validity_window(&request, now).is_ok());
+
+    // The signature timeout caps how long a request may stay valid
+    request.valid_before = Some(now + chrono::Duration::seconds(301));
+    assert!(matches!(verifier.check_validity_window(&request, now), Err(RelayerError::Validation(_))));
+
+    request.valid_before = Some(now + chrono::Duration::seconds(60));
+    request.valid_after = Some(now + chrono::Duration::seconds(30));
+    assert!(matches!(verifier.check_validity_window(&request, now), Err(RelayerError::Validation(_))));
+    assert!(verifier.check_validity_window(&request, now + chrono::Duration::seconds(30)).is_ok());
+    assert!(matches!(
+        verifier.check_validity_window(&request, now + chrono::Duration::seconds(60)),
+        Err(RelayerError::Expired(_))
+    ));
+
+    request.valid_after = request.valid_before;
+    assert!(matches!(verifier.check_validity_window(&request, now), Err(RelayerError::Validation(_))));
+}
+
+#[test]
+fn test_create_eip712_hash() {
+    let verifier = create_test_verifier();
+    let request = create_test_request(27);
+    let message = TransactionMessage {
+        user_address: request.user_address,
+        target_contract: request.target_contract,
+        calldata: request.calldata.clone(),
+        value: request.value,
+        gas_limit: request.gas_limit,
+        max_fee_per_gas: request.max_fee_per_gas,
+        max_priority_fee_per_gas: request.max_priority_fee_per_gas,
+        nonce: request.nonce,
+        valid_after: 1640995200, // 2022-01-01 00:00:00 UTC
+        valid_before: 1640995500,
+    };
+
+    let hash = verifier.create_eip712_hash(&message).unwrap();
+    assert_ne!(hash, [0u8; 32]);
+
+    // Hashing is deterministic and sensitive to the message contents
+    assert_eq!(hash, verifier.create_eip712_hash(&message).unwrap());
+    let mut other = message.clone();
+    other.valid_before += 1;
+    assert_ne!(hash, verifier.create_eip712_hash(&other).unwrap());
+}
+
+#[test]
+fn test_create_eip712_hash_vector() {
+    let verifier = create_test_verifier();
+    let request = create_test_request(27);
+    let message = TransactionMessage {
+        user_address: request.user_address,
+        target_contract: request.target_contract,
+        calldata: request.calldata.clone(),
+        value: request.value,
+        gas_limit: request.gas_limit,
+        max_fee_per_gas: request.max_fee_per_gas,
+        max_priority_fee_per_gas: request.max_priority_fee_per_gas,
+        nonce: request.nonce,
+        valid_after: 1640995200,
+        valid_before: 1640995500,
+    };
+
+    // Calldata is hashed as `bytes`, and the digest matches what `eth_signTypedData_v4` signs
+    let expected = alloy::primitives::B256::from_str("0x8c59b1937c7b50c0e73e9982584cd06e7cf33bd038ef85012137879326eca301").unwrap();
+    assert_eq!(verifier.create_eip712_hash(&message).unwrap(), expected.0);
+    assert_eq!(message.typed_data(verifier.domain()).signing_hash().unwrap(), expected);
+}
@@ -1,37 +1,61 @@
+use alloy::providers::Provider;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::time::Duration;
 
 use crate::{
     config::Config,
     database::DatabaseManager,
     cache::{RedisCache, MemoryCache, CacheManager},
-    wallet::{WalletPool, WalletPoolConfig},
-    queue::{TaskScheduler, ConcurrencyLimits, TaskExecutor, TransactionTracker},
-    security::{SignatureVerifier, ReplayProtection, BalanceChecker},
-    utils::gas::GasPriceOracle,
-    api::{ApiState, create_router},
-    types::{RelayerError, Result},
+    wallet::{
+        read_master_passphrase, read_mnemonic, HdWalletDeriver, HdWalletScaler, KeystoreSigner,
+        RemoteSigner, TreasuryController, WalletKeyStore, WalletMonitor, WalletNonceManager, WalletPool,
+        WalletSigner,
+    },
+    queue::{TaskScheduler, TaskExecutor, TransactionReplacer, TransactionTracker},
+    security::{
+        SignatureVerifier, ContractSignatureVerifier, ReplayStore, MemoryReplayStore,
+        RedisReplayStore, UserNonceManager, PolicyEngine, BudgetManager, AddressScreener,
+        BalanceChecker, ReputationManager,
+    },
+    utils::{gas::GasPriceOracle, simulation::TransactionSimulator},
+    api::{ApiState, auth::{ApiKeyInfo, AuthManager, RateLimit, RateLimiter}},
+    settlement::Facilitator,
+    types::{RelayerError, Result, WalletPoolConfig},
 };
 
 // Type alias for Ethereum provider to simplify usage
 pub type EthereumProvider = alloy::providers::RootProvider<
-    alloy::providers::fillers::JoinFill<alloy::providers::fillers::RecommendedFiller>
+    alloy::transports::http::Http<alloy::transports::http::reqwest::Client>
 >;
 
 pub struct ServiceManager {
     pub config: Config,
     pub database: DatabaseManager,
     pub redis_cache: Option<RedisCache>,
-    pub memory_cache: MemoryCache<String>,
-    pub cache_manager: CacheManager<String>,
+    pub memory_cache: MemoryCache<serde_json::Value>,
+    pub cache_manager: CacheManager<serde_json::Value>,
     pub wallet_pool: WalletPool,
+    pub wallet_keystore: Arc<WalletKeyStore>,
+    pub nonce_manager: Arc<WalletNonceManager>,
     pub task_scheduler: TaskScheduler,
     pub signature_verifier: SignatureVerifier,
-    pub replay_protection: ReplayProtection,
+    pub replay_store: Arc<dyn ReplayStore>,
+    pub user_nonces: Arc<UserNonceManager>,
+    pub policy_engine: Arc<PolicyEngine>,
+    pub budget_manager: Arc<BudgetManager>,
+    pub auth_manager: Arc<AuthManager>,
+    pub address_screener: Arc<AddressScreener>,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub reputation_manager: Arc<ReputationManager>,
     pub ethereum_provider: Arc<EthereumProvider>,
     pub balance_checker: Option<BalanceChecker<EthereumProvider>>,
     pub transaction_tracker: Option<Arc<TransactionTracker>>,
+    pub transaction_replacer: Option<Arc<TransactionReplacer>>,
+    pub treasury_controller: Option<Arc<TreasuryController>>,
+    pub hd_wallet_scaler: Option<Arc<HdWalletScaler>>,
     pub gas_price_oracle: Option<Arc<GasPriceOracle>>,
+    pub transaction_simulator: Option<Arc<TransactionSimulator>>,
+    pub facilitator: Arc<Facilitator>,
 }
 
 impl ServiceManager {
@@ -94,51 +118,107 @@ impl ServiceManager {
         };
         let wallet_pool = WalletPool::new(wallet_config);
 
-        // Load wallets from configuration
+        // Load wallets from configuration, then the encrypted keystores and stored keys
         Self::load_wallets_from_config(&wallet_pool, &config.wallets).await?;
+        let wallet_keystore = Arc::new(WalletKeyStore::new(
+            Arc::new(database.clone()),
+            read_master_passphrase(&config.wallets)?,
+        ));
+        Self::load_encrypted_wallets(&wallet_pool, &wallet_keystore, &config.wallets).await?;
+        Self::load_remote_wallets(&wallet_pool, &config.wallets).await?;
 
         // Initialize task scheduler
-        let concurrency_limits = ConcurrencyLimits {
-            max_concurrent_tasks: config.queue.worker_threads,
-            max_cpu_usage: 80.0,
-            max_memory_usage: 80.0,
-            max_network_usage: 80.0,
-            task_timeout: std::time::Duration::from_secs(config.queue.processing_timeout),
-        };
         let task_scheduler = TaskScheduler::new(
             config.queue.worker_threads,
             config.queue.max_queue_size,
             std::time::Duration::from_secs(config.queue.processing_timeout),
         );
 
-        // Initialize signature verifier
-        let signature_verifier = SignatureVerifier::new(
-            alloy::primitives::U256::from(config.ethereum.chain_id),
-            alloy::primitives::Address::ZERO, // This should be the relayer contract address
-        );
 
-        // Initialize replay protection
-        let replay_protection = ReplayProtection::new(
-            std::time::Duration::from_secs(config.security.nonce_window),
-            std::time::Duration::from_secs(300), // 5 minutes cleanup interval
-        );
+        // Initialize replay protection, shared through Redis so every replica sees each nonce
+        let nonce_window = std::time::Duration::from_secs(config.security.nonce_window);
+        let replay_store: Arc<dyn ReplayStore> = match &redis_cache {
+            Some(_) => match RedisReplayStore::connect(
+                &config.redis.url,
+                config.redis.key_prefix.clone(),
+                nonce_window,
+            ).await {
+                Ok(store) => {
+                    tracing::info!("Replay protection backed by Redis");
+                    Arc::new(store)
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to connect Redis replay store, using process-local store: {}", e);
+                    Arc::new(MemoryReplayStore::new(nonce_window, std::time::Duration::from_secs(300)))
+                }
+            },
+            None => {
+                tracing::warn!("Redis unavailable, replay protection is local to this process");
+                Arc::new(MemoryReplayStore::new(
+                    nonce_window,
+                    std::time::Duration::from_secs(300), // 5 minutes cleanup interval
+                ))
+            }
+        };
+
+        // Initialize durable per-user nonces
+        let user_nonces = Arc::new(UserNonceManager::new(
+            Arc::new(database.clone()),
+            config.security.nonce_mode,
+        ));
+
+        // Initialize contract and function allowlist
+        let policy_engine = Arc::new(PolicyEngine::new(&config.policy, &config.security.trusted_contracts)?);
+        if !policy_engine.is_enforced() {
+            tracing::warn!("No policy rules or trusted contracts configured, relaying calls to any contract");
+        }
+
+        // Initialize rolling spending budgets
+        let budget_manager = Arc::new(BudgetManager::new(Arc::new(database.clone()), &config.budgets)?);
+
+        // Initialize deny-list screening; lists that cannot be read stop startup
+        let address_screener = Arc::new(AddressScreener::new(
+            config.security.deny_lists.iter().map(std::path::PathBuf::from).collect(),
+            Duration::from_secs(config.security.deny_list_reload_interval),
+        ));
+        if address_screener.is_enforced() {
+            let count = address_screener.load().await?;
+            tracing::info!("Loaded {} deny-listed addresses", count);
+        }
+
+        // Initialize request rate limiting and the abuse throttles applied through it
+        let rate_limiter = Arc::new(RateLimiter::new(RateLimit {
+            requests_per_minute: config.server.rate_limit_per_minute,
+            requests_per_hour: config.server.rate_limit_per_minute * 60,
+            requests_per_day: config.server.rate_limit_per_minute * 60 * 24,
+        }));
+        let reputation_manager = Arc::new(ReputationManager::new(
+            config.reputation.clone(),
+            Arc::clone(&rate_limiter),
+        ));
+
+        // Register configured API keys
+        let auth_manager = Arc::new(AuthManager::new());
+        for api_key in &config.api_keys {
+            if let Some(ref limits) = api_key.spending_limits {
+                crate::security::validate_spending_limits(limits)?;
+            }
+            auth_manager.add_api_key(ApiKeyInfo::from(api_key)).await?;
+        }
 
         // Initialize Ethereum provider
         tracing::info!("Initializing Ethereum provider: {}", config.ethereum.rpc_url);
+        let rpc_url = config.ethereum.rpc_url.parse()
+            .map_err(|e| RelayerError::Ethereum(format!("Invalid RPC URL: {}", e)))?;
         let ethereum_provider = Arc::new(
-            alloy::providers::ProviderBuilder::new()
-                .on_http(
-                    alloy::providers::HttpProviderBuilder::new()
-                        .with_url(&config.ethereum.rpc_url)
-                        .map_err(|e| RelayerError::Ethereum(format!("Failed to create HTTP provider: {}", e)))?
-                )
+            alloy::providers::ProviderBuilder::new().on_http(rpc_url)
         );
 
         // Test provider connection
         match ethereum_provider.get_chain_id().await {
             Ok(chain_id) => {
                 tracing::info!("Ethereum provider connected successfully. Chain ID: {}", chain_id);
-                if chain_id != alloy::primitives::U64::from(config.ethereum.chain_id) {
+                if chain_id != config.ethereum.chain_id {
                     tracing::warn!(
                         "Chain ID mismatch: configured {}, provider returned {}",
                         config.ethereum.chain_id,
@@ -151,6 +231,44 @@ impl ServiceManager {
             }
         }
 
+        // Initialize per-wallet nonce allocation, shared by everything that sends from the pool
+        let nonce_manager = Arc::new(WalletNonceManager::new(Arc::clone(&ethereum_provider)));
+
+        // Initialize HD wallets, re-deriving the persisted indexes before anything sends
+        let hd_wallet_scaler = if config.hd_wallets.enabled {
+            let deriver = HdWalletDeriver::new(
+                read_mnemonic(&config.hd_wallets)?,
+                config.hd_wallets.password.clone(),
+                &config.hd_wallets.derivation_path,
+            )?;
+            let scaler = HdWalletScaler::new(
+                &config.hd_wallets,
+                deriver,
+                Arc::new(wallet_pool.clone()),
+                Arc::new(task_scheduler.clone()),
+                Arc::new(database.clone()),
+                Arc::clone(&ethereum_provider),
+                Arc::clone(&nonce_manager),
+            );
+            let restored = scaler.restore().await?;
+            tracing::info!("Loaded {} HD wallets", restored.len());
+            Some(Arc::new(scaler))
+        } else {
+            None
+        };
+
+        // Initialize signature verifier, accepting smart-contract wallet signatures too
+        let signature_verifier = SignatureVerifier::new(
+            alloy::primitives::U256::from(config.ethereum.chain_id),
+            alloy::primitives::Address::ZERO, // This should be the relayer contract address
+        )
+        .with_contract_verifier(ContractSignatureVerifier::new(
+            Arc::clone(&ethereum_provider),
+            config.security.universal_signature_validator,
+            std::time::Duration::from_secs(config.security.signature_cache_ttl),
+        ))
+        .with_max_validity_window(std::time::Duration::from_secs(config.security.signature_timeout));
+
         // Initialize balance checker
         let balance_checker = Some(BalanceChecker::new(
             Arc::clone(&ethereum_provider),
@@ -159,18 +277,16 @@ impl ServiceManager {
         ));
 
         // Initialize transaction tracker
-        use crate::queue::tracker::TransactionTracker;
-        use std::sync::Arc;
         let transaction_tracker = Some(Arc::new(TransactionTracker::new(
             Arc::new(database.clone()),
             Arc::clone(&ethereum_provider),
             std::time::Duration::from_secs(10), // Check every 10 seconds
             config.ethereum.confirmation_blocks,
-        )));
+        )
+        .with_budget_manager(Arc::clone(&budget_manager))
+        .with_reputation_manager(Arc::clone(&reputation_manager))));
 
         // Initialize gas price oracle
-        use crate::utils::gas::GasPriceOracle;
-        use tokio::time::Duration;
         let gas_price_oracle = Some(Arc::new(GasPriceOracle::new(
             Arc::clone(&ethereum_provider),
             config.ethereum.gas_price_multiplier,
@@ -179,6 +295,58 @@ impl ServiceManager {
             Duration::from_secs(30), // Update every 30 seconds
         )));
 
+        // Initialize speed-ups and cancellation of broadcast transactions
+        let transaction_replacer = transaction_tracker.as_ref().map(|tracker| {
+            let replacer = TransactionReplacer::new(
+                Arc::new(wallet_pool.clone()),
+                Arc::clone(&ethereum_provider),
+                Arc::new(database.clone()),
+                Arc::clone(tracker),
+                config.speed_up.clone(),
+                config.speed_up.max_fee_per_gas.unwrap_or(config.ethereum.max_gas_price) as u128,
+            );
+            Arc::new(match gas_price_oracle {
+                Some(ref oracle) => replacer.with_gas_price_oracle(Arc::clone(oracle)),
+                None => replacer,
+            })
+        });
+
+        // Initialize treasury top-ups of low pool wallets
+        let treasury_controller = if config.treasury.enabled {
+            let treasury_key = Self::load_treasury_key(&config.treasury, &wallet_keystore)?;
+            let controller = TreasuryController::new(
+                &config.treasury,
+                treasury_key,
+                alloy::primitives::U256::from(config.wallets.min_balance),
+                Arc::clone(&ethereum_provider),
+                Arc::new(database.clone()),
+                Arc::new(wallet_pool.clone()),
+                Arc::clone(&nonce_manager),
+            )?;
+            let controller = match gas_price_oracle {
+                Some(ref oracle) => controller.with_gas_price_oracle(Arc::clone(oracle)),
+                None => controller,
+            };
+            tracing::info!("Treasury top-ups enabled from {:?}", controller.treasury_address());
+            Some(Arc::new(controller))
+        } else {
+            None
+        };
+
+        // Initialize pre-flight simulation of relayed calls
+        let transaction_simulator = config.security.simulate_transactions
+            .then(|| Arc::new(TransactionSimulator::new(Arc::clone(&ethereum_provider))));
+
+        // Initialize x402 facilitator, settling through the shared task scheduler
+        let facilitator = Arc::new(Facilitator::new(
+            Arc::new(config.clone()),
+            Arc::new(database.clone()),
+            Arc::clone(&ethereum_provider),
+            Arc::new(task_scheduler.clone()),
+            Arc::new(wallet_pool.clone()),
+            gas_price_oracle.clone(),
+        ).with_address_screener(Arc::clone(&address_screener)));
+
         tracing::info!("All services initialized successfully");
 
         Ok(Self {
@@ -188,13 +356,27 @@ impl ServiceManager {
             memory_cache,
             cache_manager,
             wallet_pool,
+            wallet_keystore,
+            nonce_manager,
             task_scheduler,
             signature_verifier,
-            replay_protection,
+            replay_store,
+            user_nonces,
+            policy_engine,
+            budget_manager,
+            auth_manager,
+            address_screener,
+            rate_limiter,
+            reputation_manager,
             ethereum_provider,
             balance_checker,
             transaction_tracker,
+            transaction_replacer,
+            treasury_controller,
+            hd_wallet_scaler,
             gas_price_oracle,
+            transaction_simulator,
+            facilitator,
         })
     }
 
@@ -237,14 +419,91 @@ impl ServiceManager {
         Ok(())
     }
 
+    /// Key of the treasury wallet, from its keystore or plaintext hex
+    fn load_treasury_key(
+        treasury_config: &crate::config::TreasuryConfig,
+        wallet_keystore: &WalletKeyStore,
+    ) -> Result<alloy::signers::k256::ecdsa::SigningKey> {
+        use alloy::signers::k256::ecdsa::SigningKey;
+
+        if let Some(ref path) = treasury_config.keystore_path {
+            return wallet_keystore.load_keystore_files(std::slice::from_ref(path))?
+                .pop()
+                .ok_or_else(|| RelayerError::Config(format!("No treasury keystore found at {}", path)));
+        }
+
+        let private_key = treasury_config.private_key.as_deref()
+            .ok_or_else(|| RelayerError::Config("Treasury needs a private_key or keystore_path".to_string()))?;
+        let key_bytes = hex::decode(private_key.strip_prefix("0x").unwrap_or(private_key))
+            .map_err(|e| RelayerError::Config(format!("Invalid treasury private key format: {}", e)))?;
+        SigningKey::from_slice(&key_bytes)
+            .map_err(|e| RelayerError::Config(format!("Invalid treasury private key: {}", e)))
+    }
+
+    /// Load wallets from keystore files and the `wallets` table, decrypted with the master passphrase
+    async fn load_encrypted_wallets(
+        wallet_pool: &WalletPool,
+        wallet_keystore: &WalletKeyStore,
+        wallet_config: &crate::config::WalletConfig,
+    ) -> Result<()> {
+        let mut signers: Vec<Arc<dyn WalletSigner>> = Vec::new();
+        if !wallet_config.keystore_paths.is_empty() {
+            for signer in wallet_keystore.load_keystore_signers(&wallet_config.keystore_paths)? {
+                signers.push(Arc::new(signer));
+            }
+        }
+        if wallet_config.load_from_database {
+            for signing_key in wallet_keystore.load_from_database().await? {
+                signers.push(Arc::new(KeystoreSigner::new(signing_key, "wallets table")));
+            }
+        }
+
+        Self::add_signers(wallet_pool, signers).await;
+        Ok(())
+    }
+
+    /// Load the accounts of every configured remote signer
+    async fn load_remote_wallets(
+        wallet_pool: &WalletPool,
+        wallet_config: &crate::config::WalletConfig,
+    ) -> Result<()> {
+        let mut signers: Vec<Arc<dyn WalletSigner>> = Vec::new();
+        for remote_config in &wallet_config.remote_signers {
+            let remote_signers = RemoteSigner::connect(remote_config).await?;
+            tracing::info!("Remote signer {} provides {} wallets", remote_config.url, remote_signers.len());
+            for signer in remote_signers {
+                signers.push(Arc::new(signer));
+            }
+        }
+
+        Self::add_signers(wallet_pool, signers).await;
+        Ok(())
+    }
+
+    /// Add signers to the wallet pool, skipping wallets already loaded from another source
+    async fn add_signers(wallet_pool: &WalletPool, signers: Vec<Arc<dyn WalletSigner>>) {
+        for signer in signers {
+            let address = signer.address();
+            if wallet_pool.wallets().read().await.iter().any(|wallet| wallet.address == address) {
+                tracing::warn!("Skipping duplicate wallet {:?}", address);
+                continue;
+            }
+            let kind = signer.kind();
+            match wallet_pool.add_signer(signer).await {
+                Ok(address) => {
+                    tracing::info!("Loaded {} wallet: {:?}", kind, address);
+                }
+                Err(e) => {
+                    tracing::error!("Failed to add wallet: {}", e);
+                }
+            }
+        }
+    }
+
     pub async fn start_background_tasks(&self) -> Result<()> {
         tracing::info!("Starting background tasks...");
 
         // Start task execution loop
-        use crate::queue::executor::TaskExecutor;
-        use std::sync::Arc;
-        use tokio::time::Duration;
-
         let task_executor = Arc::new(TaskExecutor::new(
             Arc::new(self.task_scheduler.clone()),
             Arc::new(self.wallet_pool.clone()),
@@ -254,7 +513,9 @@ impl ServiceManager {
             self.gas_price_oracle.clone(),
             3, // max_retries
             Duration::from_secs(self.config.wallets.retry_delay),
-        ));
+        )
+        .with_budget_manager(Arc::clone(&self.budget_manager))
+        .with_nonce_manager(Arc::clone(&self.nonce_manager)));
 
         // Start execution loop in background
         let executor_clone = Arc::clone(&task_executor);
@@ -265,6 +526,41 @@ impl ServiceManager {
         });
         tracing::info!("Task execution loop started");
 
+        // Start replacing stuck transactions
+        if let Some(ref replacer) = self.transaction_replacer {
+            if self.config.speed_up.enabled {
+                let replacer_clone = Arc::clone(replacer);
+                tokio::spawn(async move {
+                    if let Err(e) = replacer_clone.start_speed_up_loop().await {
+                        tracing::error!("Speed-up loop failed: {}", e);
+                    }
+                });
+                tracing::info!("Speed-up loop started");
+            }
+        }
+
+        // Start treasury top-ups
+        if let Some(ref controller) = self.treasury_controller {
+            let controller_clone = Arc::clone(controller);
+            tokio::spawn(async move {
+                if let Err(e) = controller_clone.start_funding_loop().await {
+                    tracing::error!("Treasury funding loop failed: {}", e);
+                }
+            });
+            tracing::info!("Treasury funding loop started");
+        }
+
+        // Start HD wallet pool scaling
+        if let Some(ref scaler) = self.hd_wallet_scaler {
+            let scaler_clone = Arc::clone(scaler);
+            tokio::spawn(async move {
+                if let Err(e) = scaler_clone.start_scaling_loop().await {
+                    tracing::error!("HD wallet scaling loop failed: {}", e);
+                }
+            });
+            tracing::info!("HD wallet scaling loop started");
+        }
+
         // Start transaction tracking loop
         if let Some(ref tracker) = self.transaction_tracker {
             let tracker_clone = Arc::clone(tracker);
@@ -276,6 +572,12 @@ impl ServiceManager {
             tracing::info!("Transaction tracking loop started");
         }
 
+        // Start deny list hot reload
+        if self.address_screener.is_enforced() {
+            self.address_screener.start_reload_loop().await?;
+            tracing::info!("Deny list reload loop started");
+        }
+
         // Start gas price oracle update loop
         if let Some(ref oracle) = self.gas_price_oracle {
             let oracle_clone = Arc::clone(oracle);
@@ -288,17 +590,11 @@ impl ServiceManager {
         }
 
         // Start wallet balance monitoring if balance_checker is available
-        if let Some(ref balance_checker) = self.balance_checker {
-            use crate::wallet::monitor::WalletMonitor;
-            use tokio::sync::RwLock;
-            
-            // Get wallet addresses from wallet pool
-            // For now, we'll create an empty monitor and add wallets later
-            let wallets = Arc::new(RwLock::new(vec![])); // Empty initially
-            
+        if self.balance_checker.is_some() {
+            // The monitor shares the pool's wallet list so it sees wallets added later
             let monitor = WalletMonitor::new(
                 Arc::clone(&self.ethereum_provider),
-                wallets,
+                self.wallet_pool.wallets(),
                 Duration::from_secs(60), // Check every minute
                 alloy::primitives::U256::from(self.config.wallets.min_balance),
                 10, // Max nonce gap
@@ -328,10 +624,22 @@ impl ServiceManager {
             cache_manager: Arc::new(self.cache_manager.clone()),
             ethereum_provider: Arc::clone(&self.ethereum_provider),
             wallet_pool: Arc::new(self.wallet_pool.clone()),
+            wallet_keystore: Arc::clone(&self.wallet_keystore),
             task_scheduler: Arc::new(self.task_scheduler.clone()),
             signature_verifier: Arc::new(self.signature_verifier.clone()),
-            replay_protection: Arc::new(self.replay_protection.clone()),
+            replay_store: Arc::clone(&self.replay_store),
+            user_nonces: Arc::clone(&self.user_nonces),
+            policy_engine: Arc::clone(&self.policy_engine),
+            budget_manager: Arc::clone(&self.budget_manager),
+            auth_manager: Arc::clone(&self.auth_manager),
+            address_screener: Arc::clone(&self.address_screener),
+            rate_limiter: Arc::clone(&self.rate_limiter),
+            reputation_manager: Arc::clone(&self.reputation_manager),
+            transaction_tracker: self.transaction_tracker.clone(),
+            transaction_replacer: self.transaction_replacer.clone(),
             gas_price_oracle: self.gas_price_oracle.clone(),
+            transaction_simulator: self.transaction_simulator.clone(),
+            facilitator: Arc::clone(&self.facilitator),
             config: Arc::new(self.config.clone()),
         }
     }
@@ -342,10 +650,7 @@ impl ServiceManager {
         // Close database connections
         self.database.get_pool().close().await;
 
-        // Close Redis connections
-        if let Some(ref redis_cache) = self.redis_cache {
-            // Redis connections are automatically closed when dropped
-        }
+        // Redis connections are closed when the client is dropped
 
         tracing::info!("Services shutdown completed");
         Ok(())
@@ -439,13 +744,27 @@ impl Clone for ServiceManager {
             memory_cache: self.memory_cache.clone(),
             cache_manager: self.cache_manager.clone(),
             wallet_pool: self.wallet_pool.clone(),
+            wallet_keystore: Arc::clone(&self.wallet_keystore),
+            nonce_manager: Arc::clone(&self.nonce_manager),
             task_scheduler: self.task_scheduler.clone(),
             signature_verifier: self.signature_verifier.clone(),
-            replay_protection: self.replay_protection.clone(),
+            replay_store: Arc::clone(&self.replay_store),
+            user_nonces: Arc::clone(&self.user_nonces),
+            policy_engine: Arc::clone(&self.policy_engine),
+            budget_manager: Arc::clone(&self.budget_manager),
+            auth_manager: Arc::clone(&self.auth_manager),
+            address_screener: Arc::clone(&self.address_screener),
+            rate_limiter: Arc::clone(&self.rate_limiter),
+            reputation_manager: Arc::clone(&self.reputation_manager),
             ethereum_provider: Arc::clone(&self.ethereum_provider),
             balance_checker: self.balance_checker.clone(),
             transaction_tracker: self.transaction_tracker.clone(),
+            transaction_replacer: self.transaction_replacer.clone(),
+            treasury_controller: self.treasury_controller.clone(),
+            hd_wallet_scaler: self.hd_wallet_scaler.clone(),
             gas_price_oracle: self.gas_price_oracle.clone(),
+            transaction_simulator: self.transaction_simulator.clone(),
+            facilitator: Arc::clone(&self.facilitator),
         }
     }
 }
@@ -0,0 +1,288 @@
+use alloy::{
+    primitives::{Address, PrimitiveSignature, B256, U256},
+    sol,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::{
+    security::{EIP712Domain, TypedData, TypedDataField, TypedDataTypes},
+    services::EthereumProvider,
+    types::{InvalidReason, Result, Signature},
+};
+
+use super::exact::ExactAuthorization;
+use super::rpc::eth_call;
+
+sol! {
+    /// The subset of an EIP-3009 token (e.g. USDC) used for settlement
+    interface IERC3009 {
+        function name() external view returns (string);
+        function version() external view returns (string);
+        function balanceOf(address account) external view returns (uint256);
+        function authorizationState(address authorizer, bytes32 nonce) external view returns (bool);
+        function transferWithAuthorization(
+            address from,
+            address to,
+            uint256 value,
+            uint256 validAfter,
+            uint256 validBefore,
+            bytes32 nonce,
+            uint8 v,
+            bytes32 r,
+            bytes32 s
+        );
+    }
+}
+
+const TRANSFER_WITH_AUTHORIZATION_TYPE: &[u8] =
+    b"TransferWithAuthorization(address from,address to,uint256 value,uint256 validAfter,uint256 validBefore,bytes32 nonce)";
+
+/// Checks EIP-3009 authorizations against the token contract with `eth_call`
+#[derive(Debug, Clone)]
+pub struct Eip3009Verifier {
+    provider: Arc<EthereumProvider>,
+    chain_id: u64,
+    domain_cache: Arc<RwLock<HashMap<Address, EIP712Domain>>>,
+}
+
+impl Eip3009Verifier {
+    pub fn new(provider: Arc<EthereumProvider>, chain_id: u64) -> Self {
+        Self {
+            provider,
+            chain_id,
+            domain_cache: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Verify the signature, that the nonce is unused and that the payer can cover the value
+    pub async fn verify(
+        &self,
+        token: Address,
+        authorization: &ExactAuthorization,
+        extra: Option<&serde_json::Value>,
+    ) -> std::result::Result<(), InvalidReason> {
+        let domain = self.token_domain(token, extra).await.map_err(|e| {
+            tracing::warn!("Failed to resolve EIP-712 domain of {:?}: {}", token, e);
+            InvalidReason::UnexpectedVerifyError
+        })?;
+
+        let digest = authorization_digest(&domain, authorization);
+        if recover_signer(&digest, &authorization.signature) != Some(authorization.from) {
+            return Err(InvalidReason::InvalidExactEvmPayloadSignature);
+        }
+
+        let used = self.authorization_state(token, authorization.from, authorization.nonce).await
+            .map_err(|e| {
+                tracing::warn!("authorizationState call failed: {}", e);
+                InvalidReason::UnexpectedVerifyError
+            })?;
+        if used {
+            return Err(InvalidReason::InvalidTransactionState);
+        }
+
+        let balance = self.balance_of(token, authorization.from).await.map_err(|e| {
+            tracing::warn!("balanceOf call failed: {}", e);
+            InvalidReason::UnexpectedVerifyError
+        })?;
+        if balance < authorization.value {
+            return Err(InvalidReason::InsufficientFunds);
+        }
+
+        Ok(())
+    }
+
+    /// EIP-712 domain of the token, read from the contract and cached per token.
+    /// Tokens without `version()` fall back to `extra.version` from the payment requirements.
+    pub async fn token_domain(&self, token: Address, extra: Option<&serde_json::Value>) -> Result<EIP712Domain> {
+        if let Some(domain) = self.domain_cache.read().await.get(&token) {
+            return Ok(domain.clone());
+        }
+
+        let name = eth_call(&self.provider, token, IERC3009::nameCall {}).await?._0;
+        let version = match eth_call(&self.provider, token, IERC3009::versionCall {}).await {
+            Ok(version) => version._0,
+            Err(e) => extra
+                .and_then(|extra| extra.get("version"))
+                .and_then(|version| version.as_str())
+                .map(str::to_string)
+                .ok_or(e)?,
+        };
+
+        let domain = EIP712Domain {
+            name,
+            version,
+            chain_id: U256::from(self.chain_id),
+            verifying_contract: token,
+        };
+
+        self.domain_cache.write().await.insert(token, domain.clone());
+        Ok(domain)
+    }
+
+    pub async fn authorization_state(&self, token: Address, authorizer: Address, nonce: B256) -> Result<bool> {
+        Ok(eth_call(&self.provider, token, IERC3009::authorizationStateCall { authorizer, nonce }).await?._0)
+    }
+
+    pub async fn balance_of(&self, token: Address, account: Address) -> Result<U256> {
+        Ok(eth_call(&self.provider, token, IERC3009::balanceOfCall { account }).await?._0)
+    }
+}
+
+/// EIP-712 digest of a `TransferWithAuthorization` message under the token's domain
+pub fn authorization_digest(domain: &EIP712Domain, authorization: &ExactAuthorization) -> B256 {
+    use sha3::{Digest, Keccak256};
+
+    let mut struct_hasher = Keccak256::new();
+    struct_hasher.update(Keccak256::digest(TRANSFER_WITH_AUTHORIZATION_TYPE));
+    struct_hasher.update(authorization.from.into_word());
+    struct_hasher.update(authorization.to.into_word());
+    struct_hasher.update(authorization.value.to_be_bytes::<32>());
+    struct_hasher.update(authorization.valid_after.to_be_bytes::<32>());
+    struct_hasher.update(authorization.valid_before.to_be_bytes::<32>());
+    struct_hasher.update(authorization.nonce);
+    let struct_hash = struct_hasher.finalize();
+
+    let mut hasher = Keccak256::new();
+    hasher.update(b"\x19\x01");
+    hasher.update(domain.separator());
+    hasher.update(struct_hash);
+
+    B256::from_slice(&hasher.finalize())
+}
+
+/// The `TransferWithAuthorization` message as `eth_signTypedData_v4` typed data,
+/// the payload a wallet signs for an "exact" payment
+pub fn authorization_typed_data(domain: &EIP712Domain, authorization: &ExactAuthorization) -> TypedData {
+    let fields = [
+        ("from", "address"),
+        ("to", "address"),
+        ("value", "uint256"),
+        ("validAfter", "uint256"),
+        ("validBefore", "uint256"),
+        ("nonce", "bytes32"),
+    ];
+
+    let mut types = TypedDataTypes::new();
+    types.insert(
+        "TransferWithAuthorization".to_string(),
+        fields
+            .iter()
+            .map(|(name, r#type)| TypedDataField {
+                name: name.to_string(),
+                r#type: r#type.to_string(),
+            })
+            .collect(),
+    );
+
+    let message = serde_json::json!({
+        "from": authorization.from.to_string(),
+        "to": authorization.to.to_string(),
+        "value": authorization.value.to_string(),
+        "validAfter": authorization.valid_after.to_string(),
+        "validBefore": authorization.valid_before.to_string(),
+        "nonce": authorization.nonce.to_string(),
+    });
+
+    TypedData::new(types, "TransferWithAuthorization", domain.to_value(), message)
+}
+
+/// Recover the address that signed `digest`, if the signature is well formed
+pub fn recover_signer(digest: &B256, signature: &Signature) -> Option<Address> {
+    let y_parity = match signature.v {
+        27 => false,
+        28 => true,
+        _ => return None,
+    };
+
+    PrimitiveSignature::new(signature.r, signature.s, y_parity)
+        .recover_address_from_prehash(digest)
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::signers::k256::ecdsa::SigningKey;
+    use std::str::FromStr;
+
+    fn usdc_domain() -> EIP712Domain {
+        EIP712Domain {
+            name: "USD Coin".to_string(),
+            version: "2".to_string(),
+            chain_id: U256::from(1u64),
+            verifying_contract: Address::from_str("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48").unwrap(),
+        }
+    }
+
+    fn signed_authorization(key: &SigningKey, domain: &EIP712Domain) -> ExactAuthorization {
+        let mut authorization = ExactAuthorization {
+            from: Address::from_private_key(key),
+            to: Address::repeat_byte(0x22),
+            value: U256::from(10000u64),
+            valid_after: U256::ZERO,
+            valid_before: U256::from(u64::MAX),
+            nonce: B256::repeat_byte(0xab),
+            signature: Signature { r: U256::ZERO, s: U256::ZERO, v: 27 },
+        };
+
+        let digest = authorization_digest(domain, &authorization);
+        let (signature, recovery_id) = key.sign_prehash_recoverable(digest.as_slice()).unwrap();
+        let bytes = signature.to_bytes();
+        authorization.signature = Signature {
+            r: U256::from_be_slice(&bytes[..32]),
+            s: U256::from_be_slice(&bytes[32..]),
+            v: 27 + recovery_id.to_byte(),
+        };
+        authorization
+    }
+
+    #[test]
+    fn test_usdc_domain_separator() {
+        assert_eq!(
+            B256::from(usdc_domain().separator()),
+            B256::from_str("0x06c37168a7db5138defc7866392bb87a741f9b3d104deb5094588ce041cae335").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_authorization_digest_matches_typed_data() {
+        let authorization = ExactAuthorization {
+            from: Address::repeat_byte(0x11),
+            to: Address::repeat_byte(0x22),
+            value: U256::from(10000u64),
+            valid_after: U256::ZERO,
+            valid_before: U256::from(u64::MAX),
+            nonce: B256::repeat_byte(0xab),
+            signature: Signature { r: U256::ZERO, s: U256::ZERO, v: 27 },
+        };
+
+        let digest = authorization_digest(&usdc_domain(), &authorization);
+        assert_eq!(
+            digest,
+            B256::from_str("0xc0732304c141827b9e33d4a43ac550694a42791d1c993465af4506a49557ff9f").unwrap()
+        );
+        assert_eq!(authorization_typed_data(&usdc_domain(), &authorization).signing_hash().unwrap(), digest);
+    }
+
+    #[test]
+    fn test_recover_signer() {
+        let key = SigningKey::random(&mut rand::thread_rng());
+        let domain = usdc_domain();
+        let authorization = signed_authorization(&key, &domain);
+
+        let digest = authorization_digest(&domain, &authorization);
+        assert_eq!(recover_signer(&digest, &authorization.signature), Some(authorization.from));
+
+        // A different domain or message recovers someone else
+        let mut other_domain = domain.clone();
+        other_domain.chain_id = U256::from(8453u64);
+        let other_digest = authorization_digest(&other_domain, &authorization);
+        assert_ne!(recover_signer(&other_digest, &authorization.signature), Some(authorization.from));
+
+        let mut bad_v = authorization.signature.clone();
+        bad_v.v = 29;
+        assert_eq!(recover_signer(&digest, &bad_v), None);
+    }
+}
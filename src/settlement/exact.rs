@@ -0,0 +1,211 @@
+use alloy::{
+    primitives::{Address, Bytes, B256, U256},
+    sol_types::SolCall,
+};
+use std::str::FromStr;
+
+use crate::types::{ExactEvmPayload, InvalidReason, PaymentRequirements, Signature};
+
+use super::eip3009::IERC3009;
+
+/// Name of the x402 scheme settled with EIP-3009 `transferWithAuthorization`
+pub const EXACT_SCHEME: &str = "exact";
+
+/// Seconds an authorization must stay valid for so it can still be mined
+const VALID_BEFORE_BUFFER: u64 = 6;
+
+/// Decoded payload of an "exact" scheme payment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExactAuthorization {
+    pub from: Address,
+    pub to: Address,
+    pub value: U256,
+    pub valid_after: U256,
+    pub valid_before: U256,
+    pub nonce: B256,
+    pub signature: Signature,
+}
+
+impl ExactAuthorization {
+    pub fn from_payload(payload: &serde_json::Value) -> Result<Self, InvalidReason> {
+        let payload: ExactEvmPayload = serde_json::from_value(payload.clone())
+            .map_err(|_| InvalidReason::InvalidPayload)?;
+        let authorization = payload.authorization;
+
+        let from = Address::from_str(&authorization.from).map_err(|_| InvalidReason::InvalidPayload)?;
+        let to = Address::from_str(&authorization.to).map_err(|_| InvalidReason::InvalidPayload)?;
+        let value = U256::from_str(&authorization.value)
+            .map_err(|_| InvalidReason::InvalidExactEvmPayloadAuthorizationValue)?;
+        let valid_after = U256::from_str(&authorization.valid_after)
+            .map_err(|_| InvalidReason::InvalidExactEvmPayloadAuthorizationValidAfter)?;
+        let valid_before = U256::from_str(&authorization.valid_before)
+            .map_err(|_| InvalidReason::InvalidExactEvmPayloadAuthorizationValidBefore)?;
+        let nonce = B256::from_str(&authorization.nonce).map_err(|_| InvalidReason::InvalidPayload)?;
+        let signature = split_signature(&payload.signature)
+            .ok_or(InvalidReason::InvalidExactEvmPayloadSignature)?;
+
+        Ok(Self {
+            from,
+            to,
+            value,
+            valid_after,
+            valid_before,
+            nonce,
+            signature,
+        })
+    }
+
+    /// Check the authorization pays what the requirements ask for and is usable at `now`
+    pub fn check_requirements(&self, requirements: &PaymentRequirements, now: u64) -> Result<(), InvalidReason> {
+        let pay_to = Address::from_str(&requirements.pay_to)
+            .map_err(|_| InvalidReason::InvalidPaymentRequirements)?;
+        Address::from_str(&requirements.asset)
+            .map_err(|_| InvalidReason::InvalidPaymentRequirements)?;
+        let max_amount_required = U256::from_str(&requirements.max_amount_required)
+            .map_err(|_| InvalidReason::InvalidPaymentRequirements)?;
+
+        if self.to != pay_to {
+            return Err(InvalidReason::InvalidExactEvmPayloadRecipientMismatch);
+        }
+
+        if self.value < max_amount_required {
+            return Err(InvalidReason::InvalidExactEvmPayloadAuthorizationValue);
+        }
+
+        if self.valid_after > U256::from(now) {
+            return Err(InvalidReason::InvalidExactEvmPayloadAuthorizationValidAfter);
+        }
+
+        if self.valid_before <= U256::from(now + VALID_BEFORE_BUFFER) {
+            return Err(InvalidReason::InvalidExactEvmPayloadAuthorizationValidBefore);
+        }
+
+        Ok(())
+    }
+
+    /// ABI-encoded `transferWithAuthorization` call for the token contract
+    pub fn calldata(&self) -> Bytes {
+        IERC3009::transferWithAuthorizationCall {
+            from: self.from,
+            to: self.to,
+            value: self.value,
+            validAfter: self.valid_after,
+            validBefore: self.valid_before,
+            nonce: self.nonce,
+            v: self.signature.v,
+            r: B256::from(self.signature.r),
+            s: B256::from(self.signature.s),
+        }
+        .abi_encode()
+        .into()
+    }
+}
+
+/// Split a 65-byte `r || s || v` hex signature, normalizing v to 27/28
+pub fn split_signature(signature: &str) -> Option<Signature> {
+    let bytes = hex::decode(signature.strip_prefix("0x").unwrap_or(signature)).ok()?;
+    if bytes.len() != 65 {
+        return None;
+    }
+
+    let v = if bytes[64] < 27 { bytes[64] + 27 } else { bytes[64] };
+
+    Some(Signature {
+        r: U256::from_be_slice(&bytes[..32]),
+        s: U256::from_be_slice(&bytes[32..64]),
+        v,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn test_payload() -> serde_json::Value {
+        json!({
+            "signature": format!("0x{}{}1b", "11".repeat(32), "22".repeat(32)),
+            "authorization": {
+                "from": "0x1111111111111111111111111111111111111111",
+                "to": "0x2222222222222222222222222222222222222222",
+                "value": "10000",
+                "validAfter": (NOW - 60).to_string(),
+                "validBefore": (NOW + 600).to_string(),
+                "nonce": format!("0x{}", "ab".repeat(32)),
+            }
+        })
+    }
+
+    fn test_requirements() -> PaymentRequirements {
+        serde_json::from_value(json!({
+            "scheme": "exact",
+            "network": "base-sepolia",
+            "maxAmountRequired": "10000",
+            "resource": "https://example.com/weather",
+            "payTo": "0x2222222222222222222222222222222222222222",
+            "maxTimeoutSeconds": 60,
+            "asset": "0x036CbD53842c5426634e7929541eC2318f3dCF7e"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_from_payload() {
+        let authorization = ExactAuthorization::from_payload(&test_payload()).unwrap();
+        assert_eq!(authorization.value, U256::from(10000u64));
+        assert_eq!(authorization.signature.v, 27);
+        assert_eq!(authorization.nonce, B256::repeat_byte(0xab));
+
+        let mut payload = test_payload();
+        payload["signature"] = json!("0x1234");
+        assert_eq!(
+            ExactAuthorization::from_payload(&payload),
+            Err(InvalidReason::InvalidExactEvmPayloadSignature)
+        );
+        assert_eq!(
+            ExactAuthorization::from_payload(&json!({})),
+            Err(InvalidReason::InvalidPayload)
+        );
+    }
+
+    #[test]
+    fn test_check_requirements() {
+        let authorization = ExactAuthorization::from_payload(&test_payload()).unwrap();
+        let requirements = test_requirements();
+        assert!(authorization.check_requirements(&requirements, NOW).is_ok());
+
+        let mut underpaid = requirements.clone();
+        underpaid.max_amount_required = "10001".to_string();
+        assert_eq!(
+            authorization.check_requirements(&underpaid, NOW),
+            Err(InvalidReason::InvalidExactEvmPayloadAuthorizationValue)
+        );
+
+        let mut other_recipient = requirements.clone();
+        other_recipient.pay_to = "0x3333333333333333333333333333333333333333".to_string();
+        assert_eq!(
+            authorization.check_requirements(&other_recipient, NOW),
+            Err(InvalidReason::InvalidExactEvmPayloadRecipientMismatch)
+        );
+
+        assert_eq!(
+            authorization.check_requirements(&requirements, NOW - 120),
+            Err(InvalidReason::InvalidExactEvmPayloadAuthorizationValidAfter)
+        );
+        assert_eq!(
+            authorization.check_requirements(&requirements, NOW + 598),
+            Err(InvalidReason::InvalidExactEvmPayloadAuthorizationValidBefore)
+        );
+    }
+
+    #[test]
+    fn test_calldata() {
+        let authorization = ExactAuthorization::from_payload(&test_payload()).unwrap();
+        let calldata = authorization.calldata();
+
+        assert_eq!(&calldata[..4], &[0xe3, 0xee, 0x16, 0x0e]);
+        assert_eq!(calldata.len(), 4 + 9 * 32);
+    }
+}
@@ -0,0 +1,572 @@
+use alloy::{
+    primitives::{Address, U256},
+    providers::Provider,
+};
+use std::collections::HashMap;
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::{Mutex, OnceCell};
+use tokio::time::Duration;
+
+use crate::{
+    config::Config,
+    database::DatabaseManager,
+    queue::scheduler::TaskScheduler,
+    security::{screening_reason, AddressScreener, SCREENING_BLOCKED_EVENT},
+    services::EthereumProvider,
+    types::{
+        FacilitatorRequest, InvalidReason, PaymentPayload, PaymentRequirements, Priority,
+        RelayerError, Result, SettleResponse, SupportedKind, SupportedResponse, TransactionRequest,
+        VerifyResponse, X402_VERSION,
+    },
+    utils::gas::GasPriceOracle,
+    wallet::pool::WalletPool,
+};
+
+use super::eip3009::Eip3009Verifier;
+use super::exact::{ExactAuthorization, EXACT_SCHEME};
+use super::permit2::{Permit2Transfer, Permit2Verifier, PERMIT2_ADDRESS, PERMIT2_SCHEME};
+use super::upto::{check_upto_requirements, settle_amount, UPTO_SCHEME};
+
+/// x402 facilitator: verifies payment payloads and settles them through the task queue
+#[derive(Debug, Clone)]
+pub struct Facilitator {
+    config: Arc<Config>,
+    database: Arc<DatabaseManager>,
+    ethereum_provider: Arc<EthereumProvider>,
+    task_scheduler: Arc<TaskScheduler>,
+    wallet_pool: Arc<WalletPool>,
+    gas_price_oracle: Option<Arc<GasPriceOracle>>,
+    eip3009: Eip3009Verifier,
+    permit2: Permit2Verifier,
+    address_screener: Option<Arc<AddressScreener>>,
+    /// Settlements being submitted, by the authorization they spend
+    in_flight: Arc<Mutex<HashMap<SettlementKey, Arc<OnceCell<SettleResponse>>>>>,
+}
+
+/// Authorization a settlement spends: the contract tracking its nonce, the payer and the nonce.
+/// These are the target, user and nonce of the settlement transaction, which the database claims.
+type SettlementKey = (Address, Address, U256);
+
+/// A verified payment, ready to be submitted on-chain
+enum VerifiedPayment {
+    Exact {
+        asset: Address,
+        authorization: ExactAuthorization,
+    },
+    Permit2 {
+        pay_to: Address,
+        amount: U256,
+        transfer: Permit2Transfer,
+    },
+    Upto {
+        pay_to: Address,
+        amount: U256,
+        transfer: Permit2Transfer,
+    },
+}
+
+impl VerifiedPayment {
+    fn payer(&self) -> Address {
+        match self {
+            VerifiedPayment::Exact { authorization, .. } => authorization.from,
+            VerifiedPayment::Permit2 { transfer, .. } | VerifiedPayment::Upto { transfer, .. } => transfer.owner,
+        }
+    }
+
+    fn settlement_key(&self) -> SettlementKey {
+        match self {
+            VerifiedPayment::Exact { asset, authorization } => {
+                (*asset, authorization.from, U256::from_be_bytes(authorization.nonce.0))
+            }
+            VerifiedPayment::Permit2 { transfer, .. } | VerifiedPayment::Upto { transfer, .. } => {
+                (PERMIT2_ADDRESS, transfer.owner, transfer.nonce)
+            }
+        }
+    }
+
+    /// Whether nothing is owed, as when a metered payment reports no usage
+    fn is_empty(&self) -> bool {
+        matches!(self, VerifiedPayment::Upto { amount, .. } if amount.is_zero())
+    }
+}
+
+impl Facilitator {
+    pub fn new(
+        config: Arc<Config>,
+        database: Arc<DatabaseManager>,
+        ethereum_provider: Arc<EthereumProvider>,
+        task_scheduler: Arc<TaskScheduler>,
+        wallet_pool: Arc<WalletPool>,
+        gas_price_oracle: Option<Arc<GasPriceOracle>>,
+    ) -> Self {
+        let eip3009 = Eip3009Verifier::new(Arc::clone(&ethereum_provider), config.ethereum.chain_id);
+        let permit2 = Permit2Verifier::new(Arc::clone(&ethereum_provider), config.ethereum.chain_id);
+
+        Self {
+            config,
+            database,
+            ethereum_provider,
+            task_scheduler,
+            wallet_pool,
+            gas_price_oracle,
+            eip3009,
+            permit2,
+            address_screener: None,
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Refuse to settle payments involving deny-listed addresses
+    pub fn with_address_screener(mut self, address_screener: Arc<AddressScreener>) -> Self {
+        self.address_screener = Some(address_screener);
+        self
+    }
+
+    /// Scheme/network pairs this facilitator settles.
+    /// Permit2 payments must name one of the relayer wallets as spender, so those are listed too.
+    pub async fn supported(&self) -> SupportedResponse {
+        let network = match self.config.x402_network() {
+            Some(network) => network,
+            None => return SupportedResponse { kinds: Vec::new() },
+        };
+
+        let mut kinds = Vec::new();
+        for scheme in &self.config.x402.schemes {
+            let extra = match scheme.as_str() {
+                PERMIT2_SCHEME | UPTO_SCHEME => Some(serde_json::json!({
+                    "permit2": PERMIT2_ADDRESS.to_string(),
+                    "spenders": self.wallet_pool.active_addresses().await
+                        .iter()
+                        .map(|address| address.to_string())
+                        .collect::<Vec<_>>(),
+                })),
+                _ => None,
+            };
+
+            kinds.push(SupportedKind {
+                x402_version: X402_VERSION,
+                scheme: scheme.clone(),
+                network: network.clone(),
+                extra,
+            });
+        }
+
+        SupportedResponse { kinds }
+    }
+
+    /// Verify a payment without submitting a transaction; token state is read with `eth_call`
+    pub async fn verify(&self, request: &FacilitatorRequest) -> VerifyResponse {
+        match self.verify_payment(request).await {
+            Ok(payment) => VerifyResponse {
+                is_valid: true,
+                invalid_reason: None,
+                payer: Some(payment.payer().to_string()),
+            },
+            Err((reason, payer)) => VerifyResponse {
+                is_valid: false,
+                invalid_reason: Some(reason),
+                payer: payer.map(|address| address.to_string()),
+            },
+        }
+    }
+
+    /// Verify a payment, submit it and wait for the transaction hash.
+    /// "upto" payments charge the reported `amount`, capped at the signed maximum.
+    pub async fn settle(&self, request: &FacilitatorRequest) -> SettleResponse {
+        let network = request.payment_requirements.network.clone();
+
+        if request.payment_payload.scheme == UPTO_SCHEME && request.amount.is_none() {
+            return SettleResponse {
+                success: false,
+                error_reason: Some(InvalidReason::InvalidUptoSettleAmount),
+                transaction: String::new(),
+                network,
+                payer: None,
+            };
+        }
+
+        let payment = match self.verify_payment(request).await {
+            Ok(payment) => payment,
+            Err((reason, payer)) => {
+                return SettleResponse {
+                    success: false,
+                    error_reason: Some(reason),
+                    transaction: String::new(),
+                    network,
+                    payer: payer.map(|address| address.to_string()),
+                };
+            }
+        };
+        let payer = payment.payer();
+
+        // Nothing was used: submit nothing and let the permit lapse at its deadline
+        if payment.is_empty() {
+            return SettleResponse {
+                success: true,
+                error_reason: None,
+                transaction: String::new(),
+                network,
+                payer: Some(payer.to_string()),
+            };
+        }
+
+        // A second settle of the same authorization would revert on-chain after paying gas.
+        // Concurrent ones share the first response; later ones are refused by the settlement claim.
+        let key = payment.settlement_key();
+        self.settle_once(key, async move {
+            match self.submit(payment).await {
+                Ok(tx_hash) => SettleResponse {
+                    success: true,
+                    error_reason: None,
+                    transaction: tx_hash,
+                    network,
+                    payer: Some(payer.to_string()),
+                },
+                Err(RelayerError::ReplayAttack(reason)) => {
+                    tracing::warn!("Settlement for {:?} refused: {}", payer, reason);
+                    SettleResponse {
+                        success: false,
+                        error_reason: Some(InvalidReason::InvalidTransactionState),
+                        transaction: String::new(),
+                        network,
+                        payer: Some(payer.to_string()),
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Settlement for {:?} failed: {}", payer, e);
+                    SettleResponse {
+                        success: false,
+                        error_reason: Some(InvalidReason::UnexpectedSettleError),
+                        transaction: String::new(),
+                        network,
+                        payer: Some(payer.to_string()),
+                    }
+                }
+            }
+        }).await
+    }
+
+    /// Run `settlement` unless the authorization behind `key` is already being settled, in
+    /// which case wait for that settlement's response instead
+    async fn settle_once(
+        &self,
+        key: SettlementKey,
+        settlement: impl Future<Output = SettleResponse>,
+    ) -> SettleResponse {
+        let cell = Arc::clone(self.in_flight.lock().await.entry(key).or_default());
+        let response = cell.get_or_init(|| settlement).await.clone();
+
+        let mut in_flight = self.in_flight.lock().await;
+        if in_flight.get(&key).is_some_and(|current| Arc::ptr_eq(current, &cell)) {
+            in_flight.remove(&key);
+        }
+        response
+    }
+
+    async fn verify_payment(
+        &self,
+        request: &FacilitatorRequest,
+    ) -> std::result::Result<VerifiedPayment, (InvalidReason, Option<Address>)> {
+        let payload = &request.payment_payload;
+        let requirements = &request.payment_requirements;
+
+        self.check_kind(request.x402_version, payload, requirements)
+            .map_err(|reason| (reason, None))?;
+
+        match payload.scheme.as_str() {
+            EXACT_SCHEME => {
+                let authorization = ExactAuthorization::from_payload(&payload.payload)
+                    .map_err(|reason| (reason, None))?;
+                let payer = Some(authorization.from);
+
+                authorization.check_requirements(requirements, chrono::Utc::now().timestamp() as u64)
+                    .map_err(|reason| (reason, payer))?;
+                let asset = Address::from_str(&requirements.asset)
+                    .map_err(|_| (InvalidReason::InvalidPaymentRequirements, payer))?;
+
+                self.eip3009.verify(asset, &authorization, requirements.extra.as_ref()).await
+                    .map_err(|reason| (reason, payer))?;
+
+                Ok(VerifiedPayment::Exact { asset, authorization })
+            }
+            PERMIT2_SCHEME => {
+                let transfer = Permit2Transfer::from_payload(&payload.payload)
+                    .map_err(|reason| (reason, None))?;
+                let payer = Some(transfer.owner);
+
+                transfer.check_requirements(requirements, chrono::Utc::now().timestamp() as u64)
+                    .map_err(|reason| (reason, payer))?;
+                let pay_to = Address::from_str(&requirements.pay_to)
+                    .map_err(|_| (InvalidReason::InvalidPaymentRequirements, payer))?;
+                let amount = U256::from_str(&requirements.max_amount_required)
+                    .map_err(|_| (InvalidReason::InvalidPaymentRequirements, payer))?;
+
+                // Permit2 only lets the named spender execute the transfer
+                if !self.wallet_pool.active_addresses().await.contains(&transfer.spender) {
+                    return Err((InvalidReason::InvalidPermit2EvmPayloadSpender, payer));
+                }
+
+                self.permit2.verify(&transfer, amount).await
+                    .map_err(|reason| (reason, payer))?;
+
+                Ok(VerifiedPayment::Permit2 { pay_to, amount, transfer })
+            }
+            UPTO_SCHEME => {
+                let transfer = Permit2Transfer::from_payload(&payload.payload)
+                    .map_err(|reason| (reason, None))?;
+                let payer = Some(transfer.owner);
+
+                check_upto_requirements(&transfer, requirements, chrono::Utc::now().timestamp() as u64)
+                    .map_err(|reason| (reason, payer))?;
+                let pay_to = Address::from_str(&requirements.pay_to)
+                    .map_err(|_| (InvalidReason::InvalidPaymentRequirements, payer))?;
+
+                // Before usage is known, the payer must be able to cover the signed maximum
+                let amount = match request.amount {
+                    Some(ref used) => settle_amount(&transfer, used).map_err(|reason| (reason, payer))?,
+                    None => transfer.amount,
+                };
+
+                if !self.wallet_pool.active_addresses().await.contains(&transfer.spender) {
+                    return Err((InvalidReason::InvalidPermit2EvmPayloadSpender, payer));
+                }
+
+                self.permit2.verify(&transfer, amount).await
+                    .map_err(|reason| (reason, payer))?;
+
+                Ok(VerifiedPayment::Upto { pay_to, amount, transfer })
+            }
+            _ => Err((InvalidReason::UnsupportedScheme, None)),
+        }
+    }
+
+    /// Check the payload and requirements agree and name a kind we support
+    fn check_kind(
+        &self,
+        x402_version: u8,
+        payload: &PaymentPayload,
+        requirements: &PaymentRequirements,
+    ) -> std::result::Result<(), InvalidReason> {
+        if x402_version != X402_VERSION || payload.x402_version != X402_VERSION {
+            return Err(InvalidReason::InvalidX402Version);
+        }
+
+        if payload.scheme != requirements.scheme {
+            return Err(InvalidReason::InvalidScheme);
+        }
+
+        if payload.network != requirements.network {
+            return Err(InvalidReason::InvalidNetwork);
+        }
+
+        if self.config.x402_network().as_deref() != Some(payload.network.as_str()) {
+            return Err(InvalidReason::InvalidNetwork);
+        }
+
+        if !self.config.x402.schemes.contains(&payload.scheme) {
+            return Err(InvalidReason::UnsupportedScheme);
+        }
+
+        Ok(())
+    }
+
+    async fn submit(&self, payment: VerifiedPayment) -> Result<String> {
+        let (max_fee_per_gas, max_priority_fee_per_gas) = self.fee_estimate().await?;
+
+        let settlement_amounts = match payment {
+            VerifiedPayment::Upto { amount, ref transfer, .. } => Some((transfer.amount, amount)),
+            _ => None,
+        };
+
+        let request = match payment {
+            VerifiedPayment::Exact { asset, authorization } => TransactionRequest::new(
+                authorization.from,
+                asset,
+                authorization.calldata(),
+                U256::ZERO,
+                U256::from(self.config.x402.settlement_gas_limit),
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+                U256::from_be_bytes(authorization.nonce.0),
+                authorization.signature.clone(),
+                Priority::High,
+            ),
+            VerifiedPayment::Permit2 { pay_to, amount, transfer }
+            | VerifiedPayment::Upto { pay_to, amount, transfer } => {
+                let mut request = TransactionRequest::new(
+                    transfer.owner,
+                    PERMIT2_ADDRESS,
+                    transfer.calldata(pay_to, amount),
+                    U256::ZERO,
+                    U256::from(self.config.x402.settlement_gas_limit),
+                    max_fee_per_gas,
+                    max_priority_fee_per_gas,
+                    transfer.nonce,
+                    transfer.signature.clone(),
+                    Priority::High,
+                );
+                request.sender = Some(transfer.spender);
+                request
+            }
+        };
+
+        if let Some(ref screener) = self.address_screener {
+            let matches = screener.screen(&request).await;
+            if !matches.is_empty() {
+                let reason = screening_reason(&matches);
+                tracing::warn!("Settlement {} refused: {}", request.id, reason);
+                self.database.record_rejected_transaction(
+                    &request,
+                    &reason,
+                    SCREENING_BLOCKED_EVENT,
+                    serde_json::json!({ "matches": matches }),
+                ).await?;
+                return Err(RelayerError::Validation(reason));
+            }
+        }
+
+        // Verification cannot see an authorization spent by a transaction that is not mined yet
+        if !self.database.create_settlement_transaction(&request).await? {
+            return Err(RelayerError::ReplayAttack(format!(
+                "Authorization {} of {:?} is already being settled",
+                request.nonce, request.user_address,
+            )));
+        }
+        if let Some((authorized_amount, settled_amount)) = settlement_amounts {
+            self.database
+                .record_settlement_amounts(request.id, authorized_amount.to_string(), settled_amount.to_string())
+                .await?;
+        }
+        let task_id = self.task_scheduler.schedule_task(request).await?;
+
+        let result = self.task_scheduler
+            .wait_for_result(task_id, Duration::from_secs(self.config.x402.settlement_timeout))
+            .await?;
+
+        match (result.success, result.tx_hash) {
+            (true, Some(tx_hash)) => Ok(tx_hash),
+            _ => Err(RelayerError::Ethereum(
+                result.error_message.unwrap_or_else(|| "Settlement transaction failed".to_string()),
+            )),
+        }
+    }
+
+    async fn fee_estimate(&self) -> Result<(U256, U256)> {
+        if let Some(ref oracle) = self.gas_price_oracle {
+            let gas_info = oracle.get_recommended_gas_price("high").await?;
+            return Ok((gas_info.max_fee_per_gas, gas_info.max_priority_fee_per_gas));
+        }
+
+        let gas_price = self.ethereum_provider.get_gas_price().await
+            .map_err(|e| RelayerError::Ethereum(format!("Failed to get gas price: {}", e)))?;
+        let gas_price = U256::from(gas_price);
+
+        Ok((
+            gas_price * U256::from(110) / U256::from(100),
+            gas_price * U256::from(10) / U256::from(100),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn test_facilitator() -> Facilitator {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgresql://127.0.0.1:1/express402_test")
+            .unwrap();
+        let provider = alloy::providers::ProviderBuilder::new()
+            .on_http("http://127.0.0.1:1".parse().unwrap());
+
+        Facilitator::new(
+            Arc::new(Config::default()),
+            Arc::new(DatabaseManager::from_pool(pool)),
+            Arc::new(provider),
+            Arc::new(TaskScheduler::new(4, 100, Duration::from_secs(30))),
+            Arc::new(WalletPool::new(Default::default())),
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_settlements_of_one_authorization_submit_once() {
+        let facilitator = test_facilitator();
+        let submissions = AtomicUsize::new(0);
+        let settlement = |tx_hash: &'static str| {
+            let submissions = &submissions;
+            async move {
+                submissions.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                SettleResponse {
+                    success: true,
+                    error_reason: None,
+                    transaction: tx_hash.to_string(),
+                    network: "base".to_string(),
+                    payer: None,
+                }
+            }
+        };
+        let key = (PERMIT2_ADDRESS, Address::repeat_byte(0x11), U256::from(7));
+
+        let (first, second) = tokio::join!(
+            facilitator.settle_once(key, settlement("0x01")),
+            facilitator.settle_once(key, settlement("0x02")),
+        );
+        assert_eq!(submissions.load(Ordering::SeqCst), 1);
+        assert_eq!(first.transaction, "0x01");
+        assert_eq!(second.transaction, "0x01");
+
+        // Other authorizations are not held back. Settles of the same one after this response
+        // are no longer shared here; the settlement claim in the database refuses them
+        let other = (PERMIT2_ADDRESS, Address::repeat_byte(0x11), U256::from(8));
+        let (_, _) = tokio::join!(
+            facilitator.settle_once(key, settlement("0x03")),
+            facilitator.settle_once(other, settlement("0x04")),
+        );
+        assert_eq!(submissions.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres at EXPRESS402_TEST_DATABASE_URL"]
+    async fn test_settlement_claim_refuses_authorization_until_its_transaction_fails() {
+        let url = std::env::var("EXPRESS402_TEST_DATABASE_URL").unwrap();
+        let pool = sqlx::postgres::PgPoolOptions::new().connect(&url).await.unwrap();
+        let database = DatabaseManager::from_pool(pool);
+        database.run_migrations().await.unwrap();
+
+        let payer = Address::from_slice(&uuid::Uuid::new_v4().as_bytes().repeat(2)[..20]);
+        let settlement = || TransactionRequest::new(
+            payer,
+            PERMIT2_ADDRESS,
+            alloy::primitives::Bytes::new(),
+            U256::ZERO,
+            U256::from(100_000u64),
+            U256::from(20_000_000_000u64),
+            U256::from(2_000_000_000u64),
+            U256::from(7),
+            crate::types::Signature { r: U256::from(1u64), s: U256::from(1u64), v: 27 },
+            Priority::High,
+        );
+
+        let first = settlement();
+        assert!(database.create_settlement_transaction(&first).await.unwrap());
+
+        // Broadcast but not mined: the authorization still verifies, but is claimed
+        database.update_transaction_status(
+            first.id, crate::types::TransactionStatus::Submitted, Some("0x01".to_string()), None, None, None,
+        ).await.unwrap();
+        let second = settlement();
+        assert!(!database.create_settlement_transaction(&second).await.unwrap());
+        assert!(database.get_transaction(second.id).await.unwrap().is_none());
+
+        // A failed settlement spent nothing, so the authorization can be settled again
+        database.update_transaction_status(
+            first.id, crate::types::TransactionStatus::Failed, None, None, None, Some("reverted".to_string()),
+        ).await.unwrap();
+        assert!(database.create_settlement_transaction(&settlement()).await.unwrap());
+    }
+}
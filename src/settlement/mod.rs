@@ -0,0 +1,12 @@
+pub mod eip3009;
+pub mod exact;
+pub mod facilitator;
+pub mod permit2;
+pub mod rpc;
+pub mod upto;
+
+pub use eip3009::*;
+pub use exact::*;
+pub use facilitator::*;
+pub use permit2::*;
+pub use upto::*;
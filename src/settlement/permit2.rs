@@ -0,0 +1,547 @@
+use alloy::{
+    primitives::{address, Address, Bytes, B256, U256},
+    sol,
+    sol_types::SolCall,
+};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::{
+    security::{TypedData, TypedDataField, TypedDataTypes},
+    services::EthereumProvider,
+    types::{InvalidReason, PaymentRequirements, Permit2EvmPayload, Result, Signature},
+};
+
+use super::eip3009::recover_signer;
+use super::exact::split_signature;
+use super::rpc::eth_call;
+
+/// Name of the x402 scheme settled with Permit2 signature transfers
+pub const PERMIT2_SCHEME: &str = "permit2";
+
+/// Canonical Permit2 deployment, at the same address on every chain
+pub const PERMIT2_ADDRESS: Address = address!("000000000022D473030F116dDEE9F6B43aC78BA3");
+
+/// Seconds a permit must stay valid for so it can still be mined
+const DEADLINE_BUFFER: u64 = 6;
+
+const TOKEN_PERMISSIONS_TYPE: &str = "TokenPermissions(address token,uint256 amount)";
+const PERMIT_TRANSFER_FROM_TYPE: &str =
+    "PermitTransferFrom(TokenPermissions permitted,address spender,uint256 nonce,uint256 deadline)";
+const PERMIT_WITNESS_TRANSFER_FROM_TYPE_STUB: &str =
+    "PermitWitnessTransferFrom(TokenPermissions permitted,address spender,uint256 nonce,uint256 deadline,";
+const WITNESS_TYPE: &str = "X402Payment(address payTo,bytes32 paymentId)";
+
+/// Witness type string passed to `permitWitnessTransferFrom`
+pub const WITNESS_TYPE_STRING: &str =
+    "X402Payment witness)TokenPermissions(address token,uint256 amount)X402Payment(address payTo,bytes32 paymentId)";
+
+sol! {
+    interface IPermit2 {
+        struct TokenPermissions {
+            address token;
+            uint256 amount;
+        }
+
+        struct PermitTransferFrom {
+            TokenPermissions permitted;
+            uint256 nonce;
+            uint256 deadline;
+        }
+
+        struct SignatureTransferDetails {
+            address to;
+            uint256 requestedAmount;
+        }
+
+        function nonceBitmap(address owner, uint256 wordPos) external view returns (uint256);
+
+        function permitTransferFrom(
+            PermitTransferFrom permit,
+            SignatureTransferDetails transferDetails,
+            address owner,
+            bytes signature
+        ) external;
+
+        function permitWitnessTransferFrom(
+            PermitTransferFrom permit,
+            SignatureTransferDetails transferDetails,
+            address owner,
+            bytes32 witness,
+            string witnessTypeString,
+            bytes signature
+        ) external;
+    }
+
+    interface IERC20 {
+        function balanceOf(address account) external view returns (uint256);
+        function allowance(address owner, address spender) external view returns (uint256);
+    }
+}
+
+/// x402 payment a Permit2 transfer is bound to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentWitness {
+    pub pay_to: Address,
+    pub payment_id: B256,
+}
+
+impl PaymentWitness {
+    pub fn hash(&self) -> B256 {
+        use sha3::{Digest, Keccak256};
+
+        let mut hasher = Keccak256::new();
+        hasher.update(Keccak256::digest(WITNESS_TYPE));
+        hasher.update(self.pay_to.into_word());
+        hasher.update(self.payment_id);
+
+        B256::from_slice(&hasher.finalize())
+    }
+}
+
+/// Decoded payload of a "permit2" scheme payment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Permit2Transfer {
+    pub owner: Address,
+    pub token: Address,
+    pub amount: U256,
+    pub spender: Address,
+    pub nonce: U256,
+    pub deadline: U256,
+    pub witness: Option<PaymentWitness>,
+    pub signature: Signature,
+}
+
+impl Permit2Transfer {
+    pub fn from_payload(payload: &serde_json::Value) -> std::result::Result<Self, InvalidReason> {
+        let payload: Permit2EvmPayload = serde_json::from_value(payload.clone())
+            .map_err(|_| InvalidReason::InvalidPayload)?;
+        let permit = payload.permit;
+
+        let owner = Address::from_str(&payload.owner).map_err(|_| InvalidReason::InvalidPayload)?;
+        let token = Address::from_str(&permit.permitted.token).map_err(|_| InvalidReason::InvalidPayload)?;
+        let amount = U256::from_str(&permit.permitted.amount).map_err(|_| InvalidReason::InvalidPayload)?;
+        let spender = Address::from_str(&permit.spender).map_err(|_| InvalidReason::InvalidPayload)?;
+        let nonce = U256::from_str(&permit.nonce).map_err(|_| InvalidReason::InvalidPayload)?;
+        let deadline = U256::from_str(&permit.deadline)
+            .map_err(|_| InvalidReason::InvalidPermit2EvmPayloadDeadline)?;
+        let witness = match payload.witness {
+            Some(witness) => Some(PaymentWitness {
+                pay_to: Address::from_str(&witness.pay_to)
+                    .map_err(|_| InvalidReason::InvalidPermit2EvmPayloadWitness)?,
+                payment_id: B256::from_str(&witness.payment_id)
+                    .map_err(|_| InvalidReason::InvalidPermit2EvmPayloadWitness)?,
+            }),
+            None => None,
+        };
+        let signature = split_signature(&payload.signature)
+            .ok_or(InvalidReason::InvalidExactEvmPayloadSignature)?;
+
+        Ok(Self {
+            owner,
+            token,
+            amount,
+            spender,
+            nonce,
+            deadline,
+            witness,
+            signature,
+        })
+    }
+
+    /// Check the permit covers the requirements and is usable at `now`.
+    /// When the requirements carry `extra.paymentId`, the witness must commit to it.
+    pub fn check_requirements(&self, requirements: &PaymentRequirements, now: u64) -> std::result::Result<(), InvalidReason> {
+        let pay_to = Address::from_str(&requirements.pay_to)
+            .map_err(|_| InvalidReason::InvalidPaymentRequirements)?;
+        let asset = Address::from_str(&requirements.asset)
+            .map_err(|_| InvalidReason::InvalidPaymentRequirements)?;
+        let max_amount_required = U256::from_str(&requirements.max_amount_required)
+            .map_err(|_| InvalidReason::InvalidPaymentRequirements)?;
+
+        if self.token != asset {
+            return Err(InvalidReason::InvalidPayload);
+        }
+
+        if self.amount < max_amount_required {
+            return Err(InvalidReason::InvalidExactEvmPayloadAuthorizationValue);
+        }
+
+        if self.deadline <= U256::from(now + DEADLINE_BUFFER) {
+            return Err(InvalidReason::InvalidPermit2EvmPayloadDeadline);
+        }
+
+        let payment_id = requirements.extra.as_ref()
+            .and_then(|extra| extra.get("paymentId"))
+            .and_then(|id| id.as_str());
+
+        match (&self.witness, payment_id) {
+            (Some(witness), _) if witness.pay_to != pay_to => {
+                Err(InvalidReason::InvalidExactEvmPayloadRecipientMismatch)
+            }
+            (Some(witness), Some(payment_id)) if B256::from_str(payment_id).ok() != Some(witness.payment_id) => {
+                Err(InvalidReason::InvalidPermit2EvmPayloadWitness)
+            }
+            (None, Some(_)) => Err(InvalidReason::InvalidPermit2EvmPayloadWitness),
+            _ => Ok(()),
+        }
+    }
+
+    /// EIP-712 digest Permit2 checks the signature against
+    pub fn digest(&self, chain_id: u64) -> B256 {
+        use sha3::{Digest, Keccak256};
+
+        let mut permissions_hasher = Keccak256::new();
+        permissions_hasher.update(Keccak256::digest(TOKEN_PERMISSIONS_TYPE));
+        permissions_hasher.update(self.token.into_word());
+        permissions_hasher.update(self.amount.to_be_bytes::<32>());
+        let permissions_hash = permissions_hasher.finalize();
+
+        let type_hash = match self.witness {
+            Some(_) => Keccak256::digest(format!("{}{}", PERMIT_WITNESS_TRANSFER_FROM_TYPE_STUB, WITNESS_TYPE_STRING)),
+            None => Keccak256::digest(format!("{}{}", PERMIT_TRANSFER_FROM_TYPE, TOKEN_PERMISSIONS_TYPE)),
+        };
+
+        let mut struct_hasher = Keccak256::new();
+        struct_hasher.update(type_hash);
+        struct_hasher.update(permissions_hash);
+        struct_hasher.update(self.spender.into_word());
+        struct_hasher.update(self.nonce.to_be_bytes::<32>());
+        struct_hasher.update(self.deadline.to_be_bytes::<32>());
+        if let Some(ref witness) = self.witness {
+            struct_hasher.update(witness.hash());
+        }
+        let struct_hash = struct_hasher.finalize();
+
+        let mut hasher = Keccak256::new();
+        hasher.update(b"\x19\x01");
+        hasher.update(domain_separator(chain_id));
+        hasher.update(struct_hash);
+
+        B256::from_slice(&hasher.finalize())
+    }
+
+    /// The permit as `eth_signTypedData_v4` typed data, the payload the owner's wallet signs
+    pub fn typed_data(&self, chain_id: u64) -> TypedData {
+        let field = |name: &str, r#type: &str| TypedDataField {
+            name: name.to_string(),
+            r#type: r#type.to_string(),
+        };
+
+        let mut types = TypedDataTypes::new();
+        types.insert(
+            "TokenPermissions".to_string(),
+            vec![field("token", "address"), field("amount", "uint256")],
+        );
+        let mut permit_fields = vec![
+            field("permitted", "TokenPermissions"),
+            field("spender", "address"),
+            field("nonce", "uint256"),
+            field("deadline", "uint256"),
+        ];
+
+        let mut message = serde_json::json!({
+            "permitted": { "token": self.token.to_string(), "amount": self.amount.to_string() },
+            "spender": self.spender.to_string(),
+            "nonce": self.nonce.to_string(),
+            "deadline": self.deadline.to_string(),
+        });
+
+        let primary_type = match self.witness {
+            Some(ref witness) => {
+                types.insert(
+                    "X402Payment".to_string(),
+                    vec![field("payTo", "address"), field("paymentId", "bytes32")],
+                );
+                permit_fields.push(field("witness", "X402Payment"));
+                message["witness"] = serde_json::json!({
+                    "payTo": witness.pay_to.to_string(),
+                    "paymentId": witness.payment_id.to_string(),
+                });
+                "PermitWitnessTransferFrom"
+            }
+            None => "PermitTransferFrom",
+        };
+        types.insert(primary_type.to_string(), permit_fields);
+
+        let domain = serde_json::json!({
+            "name": "Permit2",
+            "chainId": chain_id,
+            "verifyingContract": PERMIT2_ADDRESS.to_string(),
+        });
+
+        TypedData::new(types, primary_type, domain, message)
+    }
+
+    /// ABI-encoded Permit2 call transferring `requested_amount` to `to`
+    pub fn calldata(&self, to: Address, requested_amount: U256) -> Bytes {
+        let permit = IPermit2::PermitTransferFrom {
+            permitted: IPermit2::TokenPermissions {
+                token: self.token,
+                amount: self.amount,
+            },
+            nonce: self.nonce,
+            deadline: self.deadline,
+        };
+        let transfer_details = IPermit2::SignatureTransferDetails {
+            to,
+            requestedAmount: requested_amount,
+        };
+        let signature = self.signature.to_bytes();
+
+        match self.witness {
+            Some(ref witness) => IPermit2::permitWitnessTransferFromCall {
+                permit,
+                transferDetails: transfer_details,
+                owner: self.owner,
+                witness: witness.hash(),
+                witnessTypeString: WITNESS_TYPE_STRING.to_string(),
+                signature,
+            }
+            .abi_encode()
+            .into(),
+            None => IPermit2::permitTransferFromCall {
+                permit,
+                transferDetails: transfer_details,
+                owner: self.owner,
+                signature,
+            }
+            .abi_encode()
+            .into(),
+        }
+    }
+
+}
+
+/// Permit2's domain has no version: `EIP712Domain(string name,uint256 chainId,address verifyingContract)`
+pub fn domain_separator(chain_id: u64) -> [u8; 32] {
+    use sha3::{Digest, Keccak256};
+
+    let mut hasher = Keccak256::new();
+    hasher.update(Keccak256::digest(b"EIP712Domain(string name,uint256 chainId,address verifyingContract)"));
+    hasher.update(Keccak256::digest(b"Permit2"));
+    hasher.update(U256::from(chain_id).to_be_bytes::<32>());
+    hasher.update(PERMIT2_ADDRESS.into_word());
+
+    hasher.finalize().into()
+}
+
+/// Checks Permit2 transfers against the chain with `eth_call`
+#[derive(Debug, Clone)]
+pub struct Permit2Verifier {
+    provider: Arc<EthereumProvider>,
+    chain_id: u64,
+}
+
+impl Permit2Verifier {
+    pub fn new(provider: Arc<EthereumProvider>, chain_id: u64) -> Self {
+        Self { provider, chain_id }
+    }
+
+    /// Verify the signature, the unordered nonce and that the owner has approved and holds the amount
+    pub async fn verify(&self, transfer: &Permit2Transfer, amount: U256) -> std::result::Result<(), InvalidReason> {
+        if recover_signer(&transfer.digest(self.chain_id), &transfer.signature) != Some(transfer.owner) {
+            return Err(InvalidReason::InvalidExactEvmPayloadSignature);
+        }
+
+        let used = self.nonce_used(transfer.owner, transfer.nonce).await.map_err(|e| {
+            tracing::warn!("nonceBitmap call failed: {}", e);
+            InvalidReason::UnexpectedVerifyError
+        })?;
+        if used {
+            return Err(InvalidReason::InvalidTransactionState);
+        }
+
+        let allowance = eth_call(
+            &self.provider,
+            transfer.token,
+            IERC20::allowanceCall { owner: transfer.owner, spender: PERMIT2_ADDRESS },
+        )
+        .await
+        .map_err(|e| {
+            tracing::warn!("allowance call failed: {}", e);
+            InvalidReason::UnexpectedVerifyError
+        })?
+        ._0;
+        if allowance < amount {
+            return Err(InvalidReason::InsufficientPermit2Allowance);
+        }
+
+        let balance = eth_call(&self.provider, transfer.token, IERC20::balanceOfCall { account: transfer.owner })
+            .await
+            .map_err(|e| {
+                tracing::warn!("balanceOf call failed: {}", e);
+                InvalidReason::UnexpectedVerifyError
+            })?
+            ._0;
+        if balance < amount {
+            return Err(InvalidReason::InsufficientFunds);
+        }
+
+        Ok(())
+    }
+
+    /// Permit2 nonces are unordered: bit `nonce & 0xff` of word `nonce >> 8` marks it used
+    pub async fn nonce_used(&self, owner: Address, nonce: U256) -> Result<bool> {
+        let word_pos = nonce >> 8;
+        let bit = U256::from(1u64) << (nonce & U256::from(0xffu64)).to::<usize>();
+
+        let bitmap = eth_call(&self.provider, PERMIT2_ADDRESS, IPermit2::nonceBitmapCall { owner, wordPos: word_pos })
+            .await?
+            ._0;
+
+        Ok(bitmap & bit != U256::ZERO)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::signers::k256::ecdsa::SigningKey;
+    use serde_json::json;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn test_requirements() -> PaymentRequirements {
+        serde_json::from_value(json!({
+            "scheme": "permit2",
+            "network": "base",
+            "maxAmountRequired": "5000",
+            "payTo": "0x2222222222222222222222222222222222222222",
+            "maxTimeoutSeconds": 60,
+            "asset": "0x4444444444444444444444444444444444444444",
+            "extra": { "paymentId": format!("0x{}", "cd".repeat(32)) }
+        }))
+        .unwrap()
+    }
+
+    fn signed_transfer(key: &SigningKey, witness: Option<PaymentWitness>) -> Permit2Transfer {
+        let mut transfer = Permit2Transfer {
+            owner: Address::from_private_key(key),
+            token: Address::repeat_byte(0x44),
+            amount: U256::from(5000u64),
+            spender: Address::repeat_byte(0x55),
+            nonce: U256::from(0x1_05u64),
+            deadline: U256::from(NOW + 600),
+            witness,
+            signature: Signature { r: U256::ZERO, s: U256::ZERO, v: 27 },
+        };
+
+        let (signature, recovery_id) = key.sign_prehash_recoverable(transfer.digest(8453).as_slice()).unwrap();
+        let bytes = signature.to_bytes();
+        transfer.signature = Signature {
+            r: U256::from_be_slice(&bytes[..32]),
+            s: U256::from_be_slice(&bytes[32..]),
+            v: 27 + recovery_id.to_byte(),
+        };
+        transfer
+    }
+
+    fn test_witness() -> PaymentWitness {
+        PaymentWitness {
+            pay_to: Address::repeat_byte(0x22),
+            payment_id: B256::repeat_byte(0xcd),
+        }
+    }
+
+    #[test]
+    fn test_from_payload() {
+        let payload = json!({
+            "signature": format!("0x{}{}1c", "11".repeat(32), "22".repeat(32)),
+            "owner": "0x1111111111111111111111111111111111111111",
+            "permit": {
+                "permitted": { "token": "0x4444444444444444444444444444444444444444", "amount": "5000" },
+                "spender": "0x5555555555555555555555555555555555555555",
+                "nonce": "261",
+                "deadline": (NOW + 600).to_string()
+            },
+            "witness": {
+                "payTo": "0x2222222222222222222222222222222222222222",
+                "paymentId": format!("0x{}", "cd".repeat(32))
+            }
+        });
+
+        let transfer = Permit2Transfer::from_payload(&payload).unwrap();
+        assert_eq!(transfer.nonce, U256::from(261u64));
+        assert_eq!(transfer.witness, Some(test_witness()));
+        assert_eq!(transfer.signature.v, 28);
+        assert!(transfer.check_requirements(&test_requirements(), NOW).is_ok());
+    }
+
+    #[test]
+    fn test_check_requirements() {
+        let key = SigningKey::random(&mut rand::thread_rng());
+        let requirements = test_requirements();
+
+        let transfer = signed_transfer(&key, Some(test_witness()));
+        assert!(transfer.check_requirements(&requirements, NOW).is_ok());
+        assert_eq!(
+            transfer.check_requirements(&requirements, NOW + 595),
+            Err(InvalidReason::InvalidPermit2EvmPayloadDeadline)
+        );
+
+        let mut other_payment = test_witness();
+        other_payment.payment_id = B256::repeat_byte(0xee);
+        assert_eq!(
+            signed_transfer(&key, Some(other_payment)).check_requirements(&requirements, NOW),
+            Err(InvalidReason::InvalidPermit2EvmPayloadWitness)
+        );
+
+        let mut other_recipient = test_witness();
+        other_recipient.pay_to = Address::repeat_byte(0x33);
+        assert_eq!(
+            signed_transfer(&key, Some(other_recipient)).check_requirements(&requirements, NOW),
+            Err(InvalidReason::InvalidExactEvmPayloadRecipientMismatch)
+        );
+
+        // The requirements ask for a payment id, so an unbound permit is rejected
+        assert_eq!(
+            signed_transfer(&key, None).check_requirements(&requirements, NOW),
+            Err(InvalidReason::InvalidPermit2EvmPayloadWitness)
+        );
+    }
+
+    #[test]
+    fn test_digest_binds_witness() {
+        let key = SigningKey::random(&mut rand::thread_rng());
+        let transfer = signed_transfer(&key, Some(test_witness()));
+        assert_eq!(recover_signer(&transfer.digest(8453), &transfer.signature), Some(transfer.owner));
+
+        let mut tampered = transfer.clone();
+        tampered.witness = Some(PaymentWitness { pay_to: Address::repeat_byte(0x99), ..test_witness() });
+        assert_ne!(recover_signer(&tampered.digest(8453), &tampered.signature), Some(transfer.owner));
+
+        let plain = signed_transfer(&key, None);
+        assert_ne!(plain.digest(8453), transfer.digest(8453));
+        assert_eq!(recover_signer(&plain.digest(8453), &plain.signature), Some(plain.owner));
+    }
+
+    #[test]
+    fn test_digest_matches_typed_data() {
+        let key = SigningKey::random(&mut rand::thread_rng());
+
+        let transfer = signed_transfer(&key, Some(test_witness()));
+        let typed_data = transfer.typed_data(8453);
+        assert_eq!(
+            typed_data.encode_type("PermitWitnessTransferFrom").unwrap(),
+            format!("{}{}", PERMIT_WITNESS_TRANSFER_FROM_TYPE_STUB, WITNESS_TYPE_STRING)
+        );
+        assert_eq!(typed_data.domain_separator().unwrap(), B256::from(domain_separator(8453)));
+        assert_eq!(typed_data.signing_hash().unwrap(), transfer.digest(8453));
+
+        let plain = signed_transfer(&key, None);
+        assert_eq!(plain.typed_data(8453).signing_hash().unwrap(), plain.digest(8453));
+    }
+
+    #[test]
+    fn test_calldata() {
+        let key = SigningKey::random(&mut rand::thread_rng());
+
+        let witness_call = signed_transfer(&key, Some(test_witness()))
+            .calldata(Address::repeat_byte(0x22), U256::from(5000u64));
+        assert_eq!(&witness_call[..4], &[0x13, 0x7c, 0x29, 0xfe]);
+
+        let plain_call = signed_transfer(&key, None).calldata(Address::repeat_byte(0x22), U256::from(5000u64));
+        assert_eq!(&plain_call[..4], &[0x30, 0xf2, 0x8b, 0x7a]);
+    }
+}
@@ -0,0 +1,25 @@
+use alloy::{
+    network::TransactionBuilder,
+    primitives::Address,
+    providers::Provider,
+    rpc::types::TransactionRequest as AlloyTransactionRequest,
+    sol_types::SolCall,
+};
+
+use crate::{
+    services::EthereumProvider,
+    types::{RelayerError, Result},
+};
+
+/// Run a read-only contract call with `eth_call` and decode its return values
+pub async fn eth_call<C: SolCall>(provider: &EthereumProvider, to: Address, call: C) -> Result<C::Return> {
+    let tx = AlloyTransactionRequest::default()
+        .with_to(to)
+        .with_input(call.abi_encode());
+
+    let output = provider.call(&tx).await
+        .map_err(|e| RelayerError::Ethereum(format!("eth_call to {:?} failed: {}", to, e)))?;
+
+    C::abi_decode_returns(&output, true)
+        .map_err(|e| RelayerError::Ethereum(format!("Failed to decode {} result: {}", C::SIGNATURE, e)))
+}
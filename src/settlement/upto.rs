@@ -0,0 +1,96 @@
+use alloy::primitives::U256;
+use std::str::FromStr;
+
+use crate::types::{InvalidReason, PaymentRequirements};
+
+use super::permit2::Permit2Transfer;
+
+/// Name of the x402 scheme for metered payments: the payer signs a Permit2 transfer of up to
+/// `maxAmountRequired`, and the resource server reports the amount actually used at settle time
+pub const UPTO_SCHEME: &str = "upto";
+
+/// Seconds of clock skew tolerated on top of `maxTimeoutSeconds`
+const DEADLINE_SKEW: u64 = 30;
+
+/// Check an "upto" permit covers the maximum price and expires within the payment timeout,
+/// so an authorization that is never settled lapses on its own.
+pub fn check_upto_requirements(
+    transfer: &Permit2Transfer,
+    requirements: &PaymentRequirements,
+    now: u64,
+) -> Result<(), InvalidReason> {
+    transfer.check_requirements(requirements, now)?;
+
+    let latest_deadline = now + requirements.max_timeout_seconds + DEADLINE_SKEW;
+    if transfer.deadline > U256::from(latest_deadline) {
+        return Err(InvalidReason::InvalidPermit2EvmPayloadDeadline);
+    }
+
+    Ok(())
+}
+
+/// Amount to charge: the reported usage, capped at the signed maximum
+pub fn settle_amount(transfer: &Permit2Transfer, used: &str) -> Result<U256, InvalidReason> {
+    let used = U256::from_str(used).map_err(|_| InvalidReason::InvalidUptoSettleAmount)?;
+
+    Ok(used.min(transfer.amount))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Signature;
+    use alloy::primitives::{Address, B256};
+    use serde_json::json;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn test_transfer(deadline: u64) -> Permit2Transfer {
+        Permit2Transfer {
+            owner: Address::repeat_byte(0x11),
+            token: Address::repeat_byte(0x44),
+            amount: U256::from(5000u64),
+            spender: Address::repeat_byte(0x55),
+            nonce: U256::from(7u64),
+            deadline: U256::from(deadline),
+            witness: Some(super::super::permit2::PaymentWitness {
+                pay_to: Address::repeat_byte(0x22),
+                payment_id: B256::repeat_byte(0xcd),
+            }),
+            signature: Signature { r: U256::ZERO, s: U256::ZERO, v: 27 },
+        }
+    }
+
+    fn test_requirements() -> PaymentRequirements {
+        serde_json::from_value(json!({
+            "scheme": "upto",
+            "network": "base",
+            "maxAmountRequired": "5000",
+            "payTo": "0x2222222222222222222222222222222222222222",
+            "maxTimeoutSeconds": 120,
+            "asset": "0x4444444444444444444444444444444444444444"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_check_upto_requirements() {
+        let requirements = test_requirements();
+        assert!(check_upto_requirements(&test_transfer(NOW + 60), &requirements, NOW).is_ok());
+
+        // Long-lived permits would stay spendable after the request is long gone
+        assert_eq!(
+            check_upto_requirements(&test_transfer(NOW + 3600), &requirements, NOW),
+            Err(InvalidReason::InvalidPermit2EvmPayloadDeadline)
+        );
+    }
+
+    #[test]
+    fn test_settle_amount() {
+        let transfer = test_transfer(NOW + 60);
+        assert_eq!(settle_amount(&transfer, "1234"), Ok(U256::from(1234u64)));
+        assert_eq!(settle_amount(&transfer, "0"), Ok(U256::ZERO));
+        assert_eq!(settle_amount(&transfer, "9000"), Ok(U256::from(5000u64)));
+        assert_eq!(settle_amount(&transfer, "lots"), Err(InvalidReason::InvalidUptoSettleAmount));
+    }
+}
@@ -1,6 +1,6 @@
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum RelayerError {
     #[error("Configuration error: {0}")]
     Config(String),
@@ -52,6 +52,12 @@ pub enum RelayerError {
 
     #[error("Validation error: {0}")]
     Validation(String),
+
+    #[error("Budget exceeded: {0}")]
+    BudgetExceeded(String),
+
+    #[error("Request expired: {0}")]
+    Expired(String),
 }
 
 impl From<String> for RelayerError {
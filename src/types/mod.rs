@@ -1,6 +1,7 @@
 pub mod transaction;
 pub mod wallet;
 pub mod error;
+pub mod x402;
 
 #[cfg(test)]
 mod tests;
@@ -8,3 +9,4 @@ mod tests;
 pub use transaction::*;
 pub use wallet::*;
 pub use error::*;
+pub use x402::*;
@@ -1,6 +1,7 @@
 use alloy::{
-    primitives::{Address, Bytes, U256},
+    primitives::{Address, Bytes, PrimitiveSignature, U256},
     rpc::types::TransactionRequest as AlloyTransactionRequest,
+    signers::k256,
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -23,24 +24,54 @@ impl Priority {
             Priority::Critical => 4,
         }
     }
+}
 
-    pub fn to_string(&self) -> String {
-        match self {
-            Priority::Low => "low".to_string(),
-            Priority::Normal => "normal".to_string(),
-            Priority::High => "high".to_string(),
-            Priority::Critical => "critical".to_string(),
-        }
+impl std::fmt::Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Priority::Low => "low",
+            Priority::Normal => "normal",
+            Priority::High => "high",
+            Priority::Critical => "critical",
+        };
+        f.write_str(name)
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Signature {
     pub r: U256,
     pub s: U256,
     pub v: u8,
 }
 
+impl Signature {
+    /// The 65-byte `r ‖ s ‖ v` encoding
+    pub fn to_bytes(&self) -> Bytes {
+        let mut bytes = Vec::with_capacity(65);
+        bytes.extend_from_slice(&self.r.to_be_bytes::<32>());
+        bytes.extend_from_slice(&self.s.to_be_bytes::<32>());
+        bytes.push(self.v);
+        bytes.into()
+    }
+}
+
+impl From<PrimitiveSignature> for Signature {
+    fn from(signature: PrimitiveSignature) -> Self {
+        Self {
+            r: signature.r(),
+            s: signature.s(),
+            v: 27 + signature.v() as u8,
+        }
+    }
+}
+
+impl From<(k256::ecdsa::Signature, k256::ecdsa::RecoveryId)> for Signature {
+    fn from(value: (k256::ecdsa::Signature, k256::ecdsa::RecoveryId)) -> Self {
+        PrimitiveSignature::from(value).into()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransactionRequest {
     pub id: Uuid,
@@ -55,9 +86,25 @@ pub struct TransactionRequest {
     pub signature: Signature,
     pub timestamp: DateTime<Utc>,
     pub priority: Priority,
+    /// Relayer wallet that must send the transaction, when the calldata is bound to one
+    #[serde(default)]
+    pub sender: Option<Address>,
+    /// Raw signature from a smart-contract wallet (EIP-1271 / ERC-6492), used instead of `signature`
+    #[serde(default)]
+    pub signature_data: Option<Bytes>,
+    /// Signed start of the validity window; the request must not be sent before it
+    #[serde(default)]
+    pub valid_after: Option<DateTime<Utc>>,
+    /// Signed end of the validity window; the request must not be sent at or after it
+    #[serde(default)]
+    pub valid_before: Option<DateTime<Utc>>,
+    /// Name of the API key the request was submitted with
+    #[serde(default)]
+    pub api_key_name: Option<String>,
 }
 
 impl TransactionRequest {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         user_address: Address,
         target_contract: Address,
@@ -83,9 +130,31 @@ impl TransactionRequest {
             signature,
             timestamp: Utc::now(),
             priority,
+            sender: None,
+            signature_data: None,
+            valid_after: None,
+            valid_before: None,
+            api_key_name: None,
         }
     }
 
+    /// Whether `now` is past the end of the validity window
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.valid_before.is_some_and(|valid_before| now >= valid_before)
+    }
+
+    /// Whether `now` is before the start of the validity window
+    pub fn is_not_yet_valid(&self, now: DateTime<Utc>) -> bool {
+        self.valid_after.is_some_and(|valid_after| now < valid_after)
+    }
+
+    /// Signature bytes as passed to EIP-1271 `isValidSignature`
+    pub fn signature_bytes(&self) -> Bytes {
+        self.signature_data.clone().unwrap_or_else(|| self.signature.to_bytes())
+    }
+
+    /// The relayed call as sent from relayer wallet `from`. The nonce is left unset: it is
+    /// the sending wallet's, not the user's `nonce`.
     pub fn to_alloy_request(&self, from: Address) -> AlloyTransactionRequest {
         AlloyTransactionRequest {
             from: Some(from),
@@ -96,7 +165,7 @@ impl TransactionRequest {
             max_fee_per_gas: Some(self.max_fee_per_gas.try_into().unwrap_or(u128::MAX)),
             max_priority_fee_per_gas: Some(self.max_priority_fee_per_gas.try_into().unwrap_or(u128::MAX)),
             input: self.calldata.clone().into(),
-            nonce: Some(self.nonce.try_into().unwrap_or(u64::MAX)),
+            nonce: None,
             chain_id: None,
             access_list: None,
             blob_versioned_hashes: None,
@@ -116,18 +185,22 @@ pub enum TransactionStatus {
     Confirmed,
     Failed,
     Cancelled,
+    /// The validity window closed before the transaction was sent
+    Expired,
 }
 
-impl TransactionStatus {
-    pub fn to_string(&self) -> String {
-        match self {
-            TransactionStatus::Pending => "pending".to_string(),
-            TransactionStatus::Processing => "processing".to_string(),
-            TransactionStatus::Submitted => "submitted".to_string(),
-            TransactionStatus::Confirmed => "confirmed".to_string(),
-            TransactionStatus::Failed => "failed".to_string(),
-            TransactionStatus::Cancelled => "cancelled".to_string(),
-        }
+impl std::fmt::Display for TransactionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            TransactionStatus::Pending => "pending",
+            TransactionStatus::Processing => "processing",
+            TransactionStatus::Submitted => "submitted",
+            TransactionStatus::Confirmed => "confirmed",
+            TransactionStatus::Failed => "failed",
+            TransactionStatus::Cancelled => "cancelled",
+            TransactionStatus::Expired => "expired",
+        };
+        f.write_str(name)
     }
 }
 
@@ -142,3 +215,13 @@ pub struct TransactionResult {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
+
+/// Outcome of the pre-flight `eth_call` / `eth_estimateGas` run before a request is queued
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationResult {
+    pub from: Address,
+    pub success: bool,
+    pub gas_estimate: Option<u64>,
+    pub revert_reason: Option<String>,
+    pub simulated_at: DateTime<Utc>,
+}
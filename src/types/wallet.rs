@@ -3,11 +3,13 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
+use crate::wallet::signer::{LocalKeySigner, SignerKind, WalletSigner};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WalletInfo {
     pub address: Address,
     #[serde(skip)]
-    pub private_key: Option<Arc<alloy::signers::k256::ecdsa::SigningKey>>,
+    pub signer: Option<Arc<dyn WalletSigner>>,
     pub balance: alloy::primitives::U256,
     pub nonce: alloy::primitives::U256,
     pub is_active: bool,
@@ -18,10 +20,16 @@ pub struct WalletInfo {
 }
 
 impl WalletInfo {
-    pub fn new(address: Address, private_key: alloy::signers::k256::ecdsa::SigningKey) -> Self {
+    pub fn new(private_key: alloy::signers::k256::ecdsa::SigningKey) -> Self {
+        Self::with_signer(Arc::new(LocalKeySigner::new(private_key)))
+    }
+
+    /// The wallet takes the signer's own address, so it is nonce-managed and tracked under
+    /// the key that actually signs its transactions
+    pub fn with_signer(signer: Arc<dyn WalletSigner>) -> Self {
         Self {
-            address,
-            private_key: Some(Arc::new(private_key)),
+            address: signer.address(),
+            signer: Some(signer),
             balance: alloy::primitives::U256::ZERO,
             nonce: alloy::primitives::U256::ZERO,
             is_active: true,
@@ -32,6 +40,10 @@ impl WalletInfo {
         }
     }
 
+    pub fn signer_kind(&self) -> Option<SignerKind> {
+        self.signer.as_ref().map(|signer| signer.kind())
+    }
+
     pub fn update_success_rate(&mut self) {
         if self.total_transactions > 0 {
             self.success_rate = (self.total_transactions - self.failed_transactions) as f64 
@@ -0,0 +1,185 @@
+use serde::{Deserialize, Serialize};
+
+/// x402 protocol version spoken by the facilitator endpoints
+pub const X402_VERSION: u8 = 1;
+
+/// x402 network names and the chain IDs they map to
+const NETWORKS: &[(&str, u64)] = &[
+    ("ethereum", 1),
+    ("sepolia", 11155111),
+    ("base", 8453),
+    ("base-sepolia", 84532),
+    ("avalanche", 43114),
+    ("avalanche-fuji", 43113),
+    ("polygon", 137),
+    ("polygon-amoy", 80002),
+    ("iotex", 4689),
+    ("sei", 1329),
+    ("sei-testnet", 1328),
+];
+
+pub fn network_for_chain_id(chain_id: u64) -> Option<&'static str> {
+    NETWORKS.iter().find(|(_, id)| *id == chain_id).map(|(name, _)| *name)
+}
+
+pub fn chain_id_for_network(network: &str) -> Option<u64> {
+    NETWORKS.iter().find(|(name, _)| *name == network).map(|(_, id)| *id)
+}
+
+/// What a resource server asks to be paid, as published in its 402 response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentRequirements {
+    pub scheme: String,
+    pub network: String,
+    pub max_amount_required: String,
+    #[serde(default)]
+    pub resource: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub mime_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<serde_json::Value>,
+    pub pay_to: String,
+    pub max_timeout_seconds: u64,
+    pub asset: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra: Option<serde_json::Value>,
+}
+
+/// Signed payment sent by the client; `payload` is scheme specific
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentPayload {
+    pub x402_version: u8,
+    pub scheme: String,
+    pub network: String,
+    pub payload: serde_json::Value,
+}
+
+/// Payload of the "exact" scheme on EVM networks (EIP-3009)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExactEvmPayload {
+    pub signature: String,
+    pub authorization: ExactEvmAuthorization,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExactEvmAuthorization {
+    pub from: String,
+    pub to: String,
+    pub value: String,
+    pub valid_after: String,
+    pub valid_before: String,
+    pub nonce: String,
+}
+
+/// Payload of the "permit2" scheme: a Permit2 signature transfer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Permit2EvmPayload {
+    pub signature: String,
+    pub owner: String,
+    pub permit: Permit2EvmPermit,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub witness: Option<Permit2EvmWitness>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Permit2EvmPermit {
+    pub permitted: Permit2EvmTokenPermissions,
+    pub spender: String,
+    pub nonce: String,
+    pub deadline: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Permit2EvmTokenPermissions {
+    pub token: String,
+    pub amount: String,
+}
+
+/// Witness binding a Permit2 transfer to an x402 payment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Permit2EvmWitness {
+    pub pay_to: String,
+    pub payment_id: String,
+}
+
+/// Body of `POST /verify` and `POST /settle`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FacilitatorRequest {
+    pub x402_version: u8,
+    pub payment_payload: PaymentPayload,
+    pub payment_requirements: PaymentRequirements,
+    /// Amount actually used, reported by the resource server when settling an "upto" payment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<String>,
+}
+
+/// Reason codes defined by the x402 spec for rejected payments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InvalidReason {
+    InsufficientFunds,
+    InvalidExactEvmPayloadAuthorizationValidAfter,
+    InvalidExactEvmPayloadAuthorizationValidBefore,
+    InvalidExactEvmPayloadAuthorizationValue,
+    InvalidExactEvmPayloadSignature,
+    InvalidExactEvmPayloadRecipientMismatch,
+    InvalidPermit2EvmPayloadDeadline,
+    InvalidPermit2EvmPayloadSpender,
+    InvalidPermit2EvmPayloadWitness,
+    InsufficientPermit2Allowance,
+    InvalidUptoSettleAmount,
+    InvalidNetwork,
+    InvalidPayload,
+    InvalidPaymentRequirements,
+    InvalidScheme,
+    UnsupportedScheme,
+    InvalidX402Version,
+    InvalidTransactionState,
+    UnexpectedVerifyError,
+    UnexpectedSettleError,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyResponse {
+    pub is_valid: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invalid_reason: Option<InvalidReason>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payer: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettleResponse {
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_reason: Option<InvalidReason>,
+    pub transaction: String,
+    pub network: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payer: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SupportedKind {
+    pub x402_version: u8,
+    pub scheme: String,
+    pub network: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupportedResponse {
+    pub kinds: Vec<SupportedKind>,
+}
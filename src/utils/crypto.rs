@@ -3,7 +3,6 @@ use alloy::{
     signers::{k256::ecdsa::SigningKey, Signature},
 };
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
 use rand::Rng;
 
 use crate::types::{RelayerError, Result};
@@ -27,13 +26,9 @@ impl CryptoUtils {
 
     /// Convert a hex string to U256
     pub fn hex_to_u256(hex: &str) -> Result<U256> {
-        let hex = if hex.starts_with("0x") {
-            &hex[2..]
-        } else {
-            hex
-        };
+        let hex = hex.strip_prefix("0x").unwrap_or(hex);
 
-        U256::from_str(hex)
+        U256::from_str_radix(hex, 16)
             .map_err(|e| RelayerError::Internal(format!("Invalid hex string: {}", e)))
     }
 
@@ -44,11 +39,7 @@ impl CryptoUtils {
 
     /// Convert a hex string to bytes
     pub fn hex_to_bytes(hex: &str) -> Result<Vec<u8>> {
-        let hex = if hex.starts_with("0x") {
-            &hex[2..]
-        } else {
-            hex
-        };
+        let hex = hex.strip_prefix("0x").unwrap_or(hex);
 
         hex::decode(hex)
             .map_err(|e| RelayerError::Internal(format!("Invalid hex string: {}", e)))
@@ -70,13 +61,9 @@ impl CryptoUtils {
 
     /// Validate hex string format
     pub fn is_valid_hex(hex: &str) -> bool {
-        let hex = if hex.starts_with("0x") {
-            &hex[2..]
-        } else {
-            hex
-        };
+        let hex = hex.strip_prefix("0x").unwrap_or(hex);
 
-        hex.len() % 2 == 0 && hex.chars().all(|c| c.is_ascii_hexdigit())
+        hex.len().is_multiple_of(2) && hex.chars().all(|c| c.is_ascii_hexdigit())
     }
 
     /// Calculate keccak256 hash
@@ -97,10 +84,10 @@ impl CryptoUtils {
 
     /// Sign a message with a private key
     pub fn sign_message(private_key: &SigningKey, message: &[u8]) -> Result<Signature> {
-        use alloy::signers::k256::ecdsa::signature::hazmat::PrehashSigner;
         let message_hash = Self::keccak256(message);
-        private_key.sign_prehash(&message_hash)
-            .map_err(|e| RelayerError::Internal(format!("Signing failed: {}", e)))
+        let (signature, recovery_id) = private_key.sign_prehash_recoverable(&message_hash)
+            .map_err(|e| RelayerError::Internal(format!("Signing failed: {}", e)))?;
+        Ok(Signature::from((signature, recovery_id)))
     }
 
     /// Verify a signature
@@ -127,7 +114,8 @@ impl CryptoUtils {
 
     /// Derive a key from a password using PBKDF2
     pub fn derive_key(password: &str, salt: &[u8], iterations: u32) -> Result<[u8; 32]> {
-        use pbkdf2::{pbkdf2_hmac, Sha256};
+        use pbkdf2::pbkdf2_hmac;
+        use sha2::Sha256;
         
         let mut key = [0u8; 32];
         pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, iterations, &mut key);
@@ -140,8 +128,8 @@ impl CryptoUtils {
         use aes_gcm::{Aes256Gcm, Key, Nonce};
         use aes_gcm::aead::{Aead, KeyInit};
 
-        let cipher = Aes256Gcm::new(Key::from_slice(key));
-        cipher.encrypt(Nonce::from_slice(nonce), data)
+        let cipher = Aes256Gcm::new(&Key::<Aes256Gcm>::from(*key));
+        cipher.encrypt(&Nonce::from(*nonce), data)
             .map_err(|e| RelayerError::Internal(format!("Encryption failed: {}", e)))
     }
 
@@ -150,8 +138,8 @@ impl CryptoUtils {
         use aes_gcm::{Aes256Gcm, Key, Nonce};
         use aes_gcm::aead::{Aead, KeyInit};
 
-        let cipher = Aes256Gcm::new(Key::from_slice(key));
-        cipher.decrypt(Nonce::from_slice(nonce), ciphertext)
+        let cipher = Aes256Gcm::new(&Key::<Aes256Gcm>::from(*key));
+        cipher.decrypt(&Nonce::from(*nonce), ciphertext)
             .map_err(|e| RelayerError::Internal(format!("Decryption failed: {}", e)))
     }
 }
@@ -166,7 +154,7 @@ pub struct EncryptedData {
 impl EncryptedData {
     pub fn encrypt(data: &[u8], password: &str) -> Result<Self> {
         let salt = CryptoUtils::generate_salt();
-        let key = CryptoUtils::derive_key(password, &salt, 100000)?;
+        let key = zeroize::Zeroizing::new(CryptoUtils::derive_key(password, &salt, 100000)?);
         let nonce = CryptoUtils::generate_salt()[..12].try_into().unwrap();
         let ciphertext = CryptoUtils::encrypt_aes_gcm(data, &key, &nonce)?;
 
@@ -178,7 +166,7 @@ impl EncryptedData {
     }
 
     pub fn decrypt(&self, password: &str) -> Result<Vec<u8>> {
-        let key = CryptoUtils::derive_key(password, &self.salt, 100000)?;
+        let key = zeroize::Zeroizing::new(CryptoUtils::derive_key(password, &self.salt, 100000)?);
         CryptoUtils::decrypt_aes_gcm(&self.ciphertext, &key, &self.nonce)
     }
 }
@@ -229,7 +217,7 @@ mod tests {
         let message = b"test message";
         
         let signature = CryptoUtils::sign_message(&private_key, message).unwrap();
-        let address = Address::from(private_key.verifying_key());
+        let address = Address::from_private_key(&private_key);
         
         assert!(CryptoUtils::verify_signature(&signature, message, address).unwrap());
     }
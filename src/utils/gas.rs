@@ -1,18 +1,20 @@
 use alloy::{
     primitives::U256,
-    providers::RootProvider,
+    providers::Provider,
+    rpc::types::{BlockNumberOrTag, BlockTransactionsKind},
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tokio::time::{Duration, Instant};
+use tokio::time::Duration;
 
+use crate::services::EthereumProvider;
 use crate::types::{RelayerError, Result};
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct GasPriceOracle {
-    provider: Arc<RootProvider>,
+    provider: Arc<EthereumProvider>,
     current_gas_price: Arc<RwLock<GasPriceInfo>>,
     gas_price_history: Arc<RwLock<Vec<GasPriceSnapshot>>>,
     update_interval: Duration,
@@ -34,14 +36,11 @@ pub struct GasPriceInfo {
 #[derive(Debug, Clone)]
 struct GasPriceSnapshot {
     max_fee_per_gas: U256,
-    max_priority_fee_per_gas: U256,
-    timestamp: Instant,
-    block_number: u64,
 }
 
 impl GasPriceOracle {
     pub fn new(
-        provider: Arc<RootProvider>,
+        provider: Arc<EthereumProvider>,
         multiplier: f64,
         min_gas_price: U256,
         max_gas_price: U256,
@@ -69,17 +68,16 @@ impl GasPriceOracle {
     pub async fn fetch_gas_price(&self) -> Result<GasPriceInfo> {
         // Get latest block to get base fee
         let latest_block = self.provider
-            .get_block_by_number(alloy::rpc::types::BlockId::latest(), false)
+            .get_block_by_number(BlockNumberOrTag::Latest, BlockTransactionsKind::Hashes)
             .await
             .map_err(|e| RelayerError::Ethereum(format!("Failed to get latest block: {}", e)))?
             .ok_or_else(|| RelayerError::Ethereum("Latest block not found".to_string()))?;
 
-        let block_number = latest_block.header.number
-            .ok_or_else(|| RelayerError::Ethereum("Block number missing".to_string()))?
-            .to::<u64>();
+        let block_number = latest_block.header.number;
 
         // Get base fee per gas (EIP-1559)
-        let base_fee = latest_block.header.base_fee
+        let base_fee = latest_block.header.base_fee_per_gas
+            .map(U256::from)
             .unwrap_or(U256::from(20000000000u64)); // Default 20 gwei if not available
 
         // Get gas price from the provider
@@ -91,7 +89,7 @@ impl GasPriceOracle {
         // Calculate max fee per gas (base fee + priority fee)
         // Priority fee is typically 1-2 gwei
         let priority_fee = U256::from(2000000000u64); // 2 gwei
-        let max_fee_per_gas = base_fee + priority_fee;
+        let max_fee_per_gas = (base_fee + priority_fee).max(U256::from(gas_price));
 
         // Apply multiplier
         let adjusted_max_fee = (max_fee_per_gas.to::<u64>() as f64 * self.multiplier) as u64;
@@ -121,12 +119,7 @@ impl GasPriceOracle {
         // Add to history
         {
             let mut history = self.gas_price_history.write().await;
-            history.push(GasPriceSnapshot {
-                max_fee_per_gas,
-                max_priority_fee_per_gas,
-                timestamp: Instant::now(),
-                block_number,
-            });
+            history.push(GasPriceSnapshot { max_fee_per_gas });
 
             // Keep only recent history
             if history.len() > self.max_history_size {
@@ -231,7 +224,7 @@ impl GasPriceOracle {
         let mut max_max_fee = U256::ZERO;
 
         for snapshot in history.iter() {
-            total_max_fee = total_max_fee + snapshot.max_fee_per_gas;
+            total_max_fee += snapshot.max_fee_per_gas;
             if snapshot.max_fee_per_gas < min_max_fee {
                 min_max_fee = snapshot.max_fee_per_gas;
             }
@@ -2,10 +2,12 @@ pub mod crypto;
 pub mod time;
 pub mod gas;
 pub mod retry;
+pub mod simulation;
 pub mod validation;
 
 pub use crypto::*;
 pub use time::*;
 pub use gas::*;
 pub use retry::*;
+pub use simulation::*;
 pub use validation::*;
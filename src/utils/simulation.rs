@@ -0,0 +1,246 @@
+use alloy::{
+    network::TransactionBuilder,
+    primitives::Address,
+    providers::Provider,
+    rpc::types::TransactionRequest as AlloyTransactionRequest,
+    sol,
+    sol_types::{Panic, Revert, SolError, SolInterface},
+    transports::RpcError,
+};
+use chrono::Utc;
+use std::sync::Arc;
+
+use crate::services::EthereumProvider;
+use crate::types::{RelayerError, Result, SimulationResult, TransactionRequest};
+
+sol! {
+    /// Custom errors common enough to be worth spelling out in rejections
+    interface KnownErrors {
+        error ERC20InsufficientBalance(address sender, uint256 balance, uint256 needed);
+        error ERC20InsufficientAllowance(address spender, uint256 allowance, uint256 needed);
+        error ERC20InvalidSender(address sender);
+        error ERC20InvalidReceiver(address receiver);
+        error OwnableUnauthorizedAccount(address account);
+        error AccessControlUnauthorizedAccount(address account, bytes32 neededRole);
+        error EnforcedPause();
+        error ReentrancyGuardReentrantCall();
+        error SafeERC20FailedOperation(address token);
+        error InvalidNonce();
+        error InvalidSigner();
+        error SignatureExpired(uint256 signatureDeadline);
+        error InvalidAmount(uint256 maxAmount);
+    }
+}
+
+/// Readable reason for revert data: `Error(string)`, `Panic(uint256)`, a known custom error,
+/// or the selector of an unknown one
+pub fn decode_revert_reason(data: &[u8]) -> String {
+    use KnownErrors::KnownErrorsErrors as Known;
+
+    if data.is_empty() {
+        return "execution reverted".to_string();
+    }
+
+    if let Ok(revert) = Revert::abi_decode(data, false) {
+        return revert.to_string();
+    }
+
+    if let Ok(panic) = Panic::abi_decode(data, false) {
+        return panic.to_string();
+    }
+
+    let known = match Known::abi_decode(data, false) {
+        Ok(error) => error,
+        Err(_) => {
+            return match data.get(..4) {
+                Some(selector) => format!("custom error 0x{}", hex::encode(selector)),
+                None => format!("malformed revert data 0x{}", hex::encode(data)),
+            }
+        }
+    };
+
+    match known {
+        Known::ERC20InsufficientBalance(e) => format!(
+            "ERC20InsufficientBalance: {} has {}, needs {}",
+            e.sender, e.balance, e.needed
+        ),
+        Known::ERC20InsufficientAllowance(e) => format!(
+            "ERC20InsufficientAllowance: {} is allowed {}, needs {}",
+            e.spender, e.allowance, e.needed
+        ),
+        Known::ERC20InvalidSender(e) => format!("ERC20InvalidSender: {}", e.sender),
+        Known::ERC20InvalidReceiver(e) => format!("ERC20InvalidReceiver: {}", e.receiver),
+        Known::OwnableUnauthorizedAccount(e) => format!("OwnableUnauthorizedAccount: {}", e.account),
+        Known::AccessControlUnauthorizedAccount(e) => format!(
+            "AccessControlUnauthorizedAccount: {} lacks role {}",
+            e.account, e.neededRole
+        ),
+        Known::EnforcedPause(_) => "EnforcedPause: contract is paused".to_string(),
+        Known::ReentrancyGuardReentrantCall(_) => "ReentrancyGuardReentrantCall".to_string(),
+        Known::SafeERC20FailedOperation(e) => format!("SafeERC20FailedOperation: {}", e.token),
+        Known::InvalidNonce(_) => "InvalidNonce".to_string(),
+        Known::InvalidSigner(_) => "InvalidSigner".to_string(),
+        Known::SignatureExpired(e) => format!("SignatureExpired: deadline {}", e.signatureDeadline),
+        Known::InvalidAmount(e) => format!("InvalidAmount: maximum {}", e.maxAmount),
+    }
+}
+
+/// Dry-runs relayed calls from a relayer wallet so requests that would revert never cost gas
+#[derive(Debug, Clone)]
+pub struct TransactionSimulator {
+    provider: Arc<EthereumProvider>,
+}
+
+impl TransactionSimulator {
+    pub fn new(provider: Arc<EthereumProvider>) -> Self {
+        Self { provider }
+    }
+
+    /// `eth_call` with the request's gas limit, then `eth_estimateGas`. A revert is reported
+    /// in the result; only RPC failures are errors.
+    pub async fn simulate(&self, request: &TransactionRequest, from: Address) -> Result<SimulationResult> {
+        let tx = AlloyTransactionRequest::default()
+            .with_from(from)
+            .with_to(request.target_contract)
+            .with_value(request.value)
+            .with_input(request.calldata.clone());
+
+        let outcome = |gas_estimate: Option<u64>, revert_reason: Option<String>| SimulationResult {
+            from,
+            success: revert_reason.is_none(),
+            gas_estimate,
+            revert_reason,
+            simulated_at: Utc::now(),
+        };
+
+        let call = tx.clone().with_gas_limit(request.gas_limit.saturating_to::<u64>());
+        match self.provider.call(&call).await {
+            Ok(_) => {}
+            Err(RpcError::ErrorResp(e)) => {
+                let reason = e.as_revert_data()
+                    .map(|data| decode_revert_reason(&data))
+                    .unwrap_or_else(|| e.message.to_string());
+                return Ok(outcome(None, Some(reason)));
+            }
+            Err(e) => return Err(RelayerError::Ethereum(format!("Simulation eth_call failed: {}", e))),
+        }
+
+        match self.provider.estimate_gas(&tx).await {
+            Ok(gas) => Ok(outcome(Some(gas), None)),
+            Err(RpcError::ErrorResp(e)) => {
+                let reason = e.as_revert_data()
+                    .map(|data| decode_revert_reason(&data))
+                    .unwrap_or_else(|| e.message.to_string());
+                Ok(outcome(None, Some(reason)))
+            }
+            Err(e) => Err(RelayerError::Ethereum(format!("Simulation eth_estimateGas failed: {}", e))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Priority, Signature};
+    use alloy::primitives::{Bytes, U256};
+    use alloy::sol_types::SolError;
+    use axum::{routing::post, Json, Router};
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_decode_revert_reason() {
+        let revert = Revert::from("ERC20: transfer amount exceeds balance").abi_encode();
+        assert_eq!(decode_revert_reason(&revert), "revert: ERC20: transfer amount exceeds balance");
+
+        let panic = Panic { code: U256::from(0x11) }.abi_encode();
+        assert_eq!(decode_revert_reason(&panic), "panic: arithmetic underflow or overflow (0x11)");
+
+        let insufficient = KnownErrors::ERC20InsufficientBalance {
+            sender: Address::repeat_byte(0x11),
+            balance: U256::from(5u64),
+            needed: U256::from(10u64),
+        }
+        .abi_encode();
+        assert_eq!(
+            decode_revert_reason(&insufficient),
+            "ERC20InsufficientBalance: 0x1111111111111111111111111111111111111111 has 5, needs 10"
+        );
+
+        assert_eq!(decode_revert_reason(&KnownErrors::EnforcedPause {}.abi_encode()), "EnforcedPause: contract is paused");
+        assert_eq!(decode_revert_reason(&[0xde, 0xad, 0xbe, 0xef, 0x01]), "custom error 0xdeadbeef");
+        assert_eq!(decode_revert_reason(&[]), "execution reverted");
+    }
+
+    /// JSON-RPC stub: `eth_call` reverts with `revert_data` when set, `eth_estimateGas` returns 46000
+    async fn spawn_rpc_stub(revert_data: Option<Vec<u8>>) -> Arc<EthereumProvider> {
+        let app = Router::new().route("/", post(move |Json(body): Json<Value>| {
+            let revert_data = revert_data.clone();
+            async move {
+                let id = body["id"].clone();
+                Json(match (body["method"].as_str(), revert_data) {
+                    (Some("eth_call"), Some(data)) => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": {
+                            "code": 3,
+                            "message": "execution reverted",
+                            "data": format!("0x{}", hex::encode(data))
+                        }
+                    }),
+                    (Some("eth_call"), None) => json!({ "jsonrpc": "2.0", "id": id, "result": "0x" }),
+                    _ => json!({ "jsonrpc": "2.0", "id": id, "result": "0xb3b0" }),
+                })
+            }
+        }));
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service()));
+
+        Arc::new(alloy::providers::ProviderBuilder::new().on_http(url.parse().unwrap()))
+    }
+
+    fn test_request() -> TransactionRequest {
+        TransactionRequest::new(
+            Address::repeat_byte(0x11),
+            Address::repeat_byte(0x44),
+            Bytes::from(vec![0xa9, 0x05, 0x9c, 0xbb]),
+            U256::ZERO,
+            U256::from(100000u64),
+            U256::from(20000000000u64),
+            U256::from(2000000000u64),
+            U256::ZERO,
+            Signature { r: U256::ZERO, s: U256::ZERO, v: 27 },
+            Priority::Normal,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_simulate_success_and_revert() {
+        let wallet = Address::repeat_byte(0x99);
+
+        let simulator = TransactionSimulator::new(spawn_rpc_stub(None).await);
+        let result = simulator.simulate(&test_request(), wallet).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.gas_estimate, Some(46000));
+        assert_eq!(result.from, wallet);
+
+        let revert = Revert::from("Pausable: paused").abi_encode();
+        let simulator = TransactionSimulator::new(spawn_rpc_stub(Some(revert)).await);
+        let result = simulator.simulate(&test_request(), wallet).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.revert_reason.as_deref(), Some("revert: Pausable: paused"));
+        assert_eq!(result.gas_estimate, None);
+    }
+
+    #[tokio::test]
+    async fn test_simulate_rpc_unavailable() {
+        let provider = alloy::providers::ProviderBuilder::new().on_http("http://127.0.0.1:1".parse().unwrap());
+        let simulator = TransactionSimulator::new(Arc::new(provider));
+
+        assert!(matches!(
+            simulator.simulate(&test_request(), Address::ZERO).await,
+            Err(RelayerError::Ethereum(_))
+        ));
+    }
+}
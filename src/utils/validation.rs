@@ -1,4 +1,5 @@
 use alloy::primitives::{Address, U256, Bytes};
+use crate::security::SECP256K1_HALF_ORDER;
 use crate::types::{RelayerError, Result, Signature};
 
 /// Transaction validator for validating transaction requests
@@ -53,17 +54,13 @@ impl TransactionValidator {
 
     /// Validate calldata hex string
     pub fn validate_calldata_string(calldata_str: &str) -> Result<Vec<u8>> {
-        let hex_str = if calldata_str.starts_with("0x") {
-            &calldata_str[2..]
-        } else {
-            calldata_str
-        };
+        let hex_str = calldata_str.strip_prefix("0x").unwrap_or(calldata_str);
 
         if hex_str.is_empty() {
             return Err(RelayerError::Api("Calldata cannot be empty".to_string()));
         }
 
-        if hex_str.len() % 2 != 0 {
+        if !hex_str.len().is_multiple_of(2) {
             return Err(RelayerError::Api("Invalid hex string length (must be even)".to_string()));
         }
 
@@ -84,7 +81,7 @@ impl TransactionValidator {
         const MIN_GAS_LIMIT: u64 = 21000; // Minimum for simple transfer
         const MAX_GAS_LIMIT: u64 = 30_000_000; // Block gas limit
 
-        let gas_limit_u64 = gas_limit.to::<u64>();
+        let gas_limit_u64 = gas_limit.saturating_to::<u64>();
         if gas_limit_u64 < MIN_GAS_LIMIT {
             return Err(RelayerError::Api(
                 format!("Gas limit too low: minimum is {}", MIN_GAS_LIMIT)
@@ -125,13 +122,13 @@ impl TransactionValidator {
         const MIN_GAS_PRICE: u64 = 1_000_000_000; // 1 gwei
         const MAX_GAS_PRICE: u64 = 1_000_000_000_000; // 1000 gwei
 
-        if max_fee_per_gas.to::<u64>() < MIN_GAS_PRICE {
+        if max_fee_per_gas.saturating_to::<u64>() < MIN_GAS_PRICE {
             return Err(RelayerError::Api(
                 format!("Gas price too low: minimum is {} gwei", MIN_GAS_PRICE / 1_000_000_000)
             ));
         }
 
-        if max_fee_per_gas.to::<u64>() > MAX_GAS_PRICE {
+        if max_fee_per_gas.saturating_to::<u64>() > MAX_GAS_PRICE {
             return Err(RelayerError::Api(
                 format!("Gas price too high: maximum is {} gwei", MAX_GAS_PRICE / 1_000_000_000)
             ));
@@ -144,8 +141,8 @@ impl TransactionValidator {
     pub fn validate_value(value: &U256) -> Result<()> {
         // Value can be zero for contract calls
         // Just check it's reasonable (not too large)
-        const MAX_VALUE: u64 = 10_000_000_000_000_000_000_000; // 10,000 ETH
-        if value.to::<u128>() > MAX_VALUE as u128 {
+        const MAX_VALUE: u128 = 10_000_000_000_000_000_000_000; // 10,000 ETH
+        if value.saturating_to::<u128>() > MAX_VALUE {
             return Err(RelayerError::Api(
                 format!("Value too large: maximum is {} ETH", MAX_VALUE / 1_000_000_000_000_000_000)
             ));
@@ -157,7 +154,7 @@ impl TransactionValidator {
     /// Validate nonce
     pub fn validate_nonce(nonce: &U256) -> Result<()> {
         // Nonce should be a reasonable value (u64 max)
-        let nonce_u64 = nonce.to::<u64>();
+        let nonce_u64 = nonce.saturating_to::<u64>();
         if nonce_u64 > u64::MAX / 2 {
             return Err(RelayerError::Api("Nonce value too large".to_string()));
         }
@@ -172,17 +169,23 @@ impl TransactionValidator {
             return Err(RelayerError::Api("Signature r and s must be non-zero".to_string()));
         }
 
-        // Signature v must be 27 or 28 (or 35+ for EIP-155)
-        if signature.v != 27 && signature.v != 28 && signature.v < 35 {
+        // Signature v must be 27 or 28, or the raw recovery id 0 or 1
+        if !matches!(signature.v, 0 | 1 | 27 | 28) {
             return Err(RelayerError::Api(
                 format!("Invalid signature v value: {}", signature.v)
             ));
         }
 
+        // Only canonical low-s signatures (EIP-2)
+        if signature.s > SECP256K1_HALF_ORDER {
+            return Err(RelayerError::Api("Signature s value is too high".to_string()));
+        }
+
         Ok(())
     }
 
     /// Comprehensive transaction validation
+    #[allow(clippy::too_many_arguments)]
     pub fn validate_transaction_params(
         user_address: &Address,
         target_contract: &Address,
@@ -192,7 +195,7 @@ impl TransactionValidator {
         max_fee_per_gas: &U256,
         max_priority_fee_per_gas: &U256,
         nonce: &U256,
-        signature: &Signature,
+        signature: Option<&Signature>,
     ) -> Result<()> {
         // Validate addresses
         Self::validate_address(user_address)?;
@@ -211,8 +214,10 @@ impl TransactionValidator {
         // Validate nonce
         Self::validate_nonce(nonce)?;
 
-        // Validate signature
-        Self::validate_signature(signature)?;
+        // Validate signature, unless it is a smart-contract wallet signature checked on-chain
+        if let Some(signature) = signature {
+            Self::validate_signature(signature)?;
+        }
 
         Ok(())
     }
@@ -237,11 +242,7 @@ impl TransactionValidator {
         }
 
         // Remove 0x prefix if present
-        let clean_str = if value_str.starts_with("0x") {
-            &value_str[2..]
-        } else {
-            value_str
-        };
+        let clean_str = value_str.strip_prefix("0x").unwrap_or(value_str);
 
         if clean_str.is_empty() {
             return Err(RelayerError::Api("Value cannot be empty after removing 0x prefix".to_string()));
@@ -253,7 +254,6 @@ impl TransactionValidator {
     }
 }
 
-use std::str::FromStr;
 
 #[cfg(test)]
 mod tests {
@@ -292,5 +292,13 @@ mod tests {
         // Zero values should fail
         assert!(TransactionValidator::validate_gas_prices(&U256::ZERO, &priority_fee).is_err());
     }
-}
 
+    #[test]
+    fn test_validate_signature() {
+        let signature = Signature { r: U256::from(1), s: U256::from(1), v: 27 };
+        assert!(TransactionValidator::validate_signature(&signature).is_ok());
+        assert!(TransactionValidator::validate_signature(&Signature { v: 0, ..signature.clone() }).is_ok());
+        assert!(TransactionValidator::validate_signature(&Signature { v: 37, ..signature.clone() }).is_err());
+        assert!(TransactionValidator::validate_signature(&Signature { s: U256::MAX, ..signature }).is_err());
+    }
+}
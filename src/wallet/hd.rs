@@ -0,0 +1,418 @@
+use alloy::{
+    primitives::Address,
+    providers::Provider,
+    signers::{
+        k256::ecdsa::SigningKey,
+        local::{coins_bip39::English, MnemonicBuilder},
+    },
+};
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::time::Duration;
+use zeroize::Zeroizing;
+
+use crate::{
+    config::HdWalletConfig,
+    database::DatabaseManager,
+    queue::TaskScheduler,
+    services::EthereumProvider,
+    types::{RelayerError, Result},
+    wallet::{nonce::WalletNonceManager, pool::WalletPool},
+};
+
+/// Read the HD wallet mnemonic from `mnemonic_file`, `mnemonic` or `mnemonic_env`, in that order
+pub fn read_mnemonic(config: &HdWalletConfig) -> Result<Zeroizing<String>> {
+    let mnemonic = if let Some(ref path) = config.mnemonic_file {
+        Zeroizing::new(std::fs::read_to_string(path)
+            .map_err(|e| RelayerError::Config(format!("Failed to read mnemonic file {}: {}", path, e)))?)
+    } else if let Some(ref mnemonic) = config.mnemonic {
+        Zeroizing::new(mnemonic.clone())
+    } else {
+        Zeroizing::new(std::env::var(&config.mnemonic_env).unwrap_or_default())
+    };
+
+    let mnemonic = mnemonic.split_whitespace().collect::<Vec<_>>().join(" ");
+    if mnemonic.is_empty() {
+        return Err(RelayerError::Config(format!(
+            "HD wallets need a mnemonic, mnemonic_file or {} to be set",
+            config.mnemonic_env
+        )));
+    }
+    Ok(Zeroizing::new(mnemonic))
+}
+
+/// Derives wallet keys from a BIP-39 mnemonic at `<derivation_path>/<index>`
+#[derive(Clone)]
+pub struct HdWalletDeriver {
+    mnemonic: Zeroizing<String>,
+    password: Option<Zeroizing<String>>,
+    derivation_path: String,
+}
+
+impl std::fmt::Debug for HdWalletDeriver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HdWalletDeriver")
+            .field("derivation_path", &self.derivation_path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl HdWalletDeriver {
+    /// Checks the mnemonic and path by deriving the first wallet
+    pub fn new(mnemonic: Zeroizing<String>, password: Option<String>, derivation_path: &str) -> Result<Self> {
+        let deriver = Self {
+            mnemonic,
+            password: password.map(Zeroizing::new),
+            derivation_path: derivation_path.trim_end_matches('/').to_string(),
+        };
+        deriver.derive(0)?;
+        Ok(deriver)
+    }
+
+    pub fn derivation_path(&self) -> &str {
+        &self.derivation_path
+    }
+
+    /// Key of the wallet at `index` under the derivation path
+    pub fn derive(&self, index: u32) -> Result<SigningKey> {
+        let mut builder = MnemonicBuilder::<English>::default()
+            .phrase(self.mnemonic.as_str())
+            .derivation_path(format!("{}/{}", self.derivation_path, index))
+            .map_err(|e| RelayerError::Config(format!("Invalid derivation path {}: {}", self.derivation_path, e)))?;
+        if let Some(ref password) = self.password {
+            builder = builder.password(password.as_str());
+        }
+
+        let signer = builder.build()
+            .map_err(|e| RelayerError::Config(format!("Failed to derive HD wallet {}: {}", index, e)))?;
+        Ok(signer.into_credential())
+    }
+
+    pub fn derive_address(&self, index: u32) -> Result<Address> {
+        Ok(Address::from_private_key(&self.derive(index)?))
+    }
+}
+
+/// What the pool should do about its size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scaling {
+    /// Derive another wallet
+    Grow,
+    /// Retire an idle derived wallet
+    Shrink,
+    Hold,
+}
+
+/// Pool size bounds and the queue depth that makes the pool grow
+#[derive(Debug, Clone, Copy)]
+pub struct ScalingPolicy {
+    pub min_wallets: usize,
+    pub max_wallets: usize,
+    /// Pending tasks per wallet in rotation above which the pool grows
+    pub queue_depth: usize,
+}
+
+impl ScalingPolicy {
+    /// `active_wallets` are the wallets in rotation, `pool_wallets` also counts suspended ones
+    pub fn decide(&self, pending_tasks: usize, active_wallets: usize, pool_wallets: usize) -> Scaling {
+        let backed_up = pending_tasks > active_wallets.saturating_mul(self.queue_depth);
+        if pool_wallets < self.max_wallets && (active_wallets < self.min_wallets || backed_up) {
+            Scaling::Grow
+        } else if active_wallets > self.min_wallets && pending_tasks == 0 {
+            Scaling::Shrink
+        } else {
+            Scaling::Hold
+        }
+    }
+}
+
+/// Whether a wallet has nothing in flight: every nonce the node has seen is mined and none
+/// is handed out locally but not yet broadcast
+pub fn nonces_settled(latest: u64, pending: u64, next_local: Option<u64>) -> bool {
+    pending == latest && next_local.unwrap_or(latest) <= latest
+}
+
+/// Grows the pool with wallets derived from the HD mnemonic while the queue is backed up and
+/// retires idle ones once their nonces are settled. Derived indexes are persisted, so a
+/// restart re-derives the same addresses.
+#[derive(Debug, Clone)]
+pub struct HdWalletScaler {
+    deriver: Arc<HdWalletDeriver>,
+    wallet_pool: Arc<WalletPool>,
+    task_scheduler: Arc<TaskScheduler>,
+    database: Arc<DatabaseManager>,
+    provider: Arc<EthereumProvider>,
+    nonce_manager: Arc<WalletNonceManager>,
+    policy: ScalingPolicy,
+    idle_timeout: Duration,
+    check_interval: Duration,
+    /// Index of each derived wallet in the pool
+    derived: Arc<Mutex<HashMap<Address, u32>>>,
+}
+
+impl HdWalletScaler {
+    pub fn new(
+        config: &HdWalletConfig,
+        deriver: HdWalletDeriver,
+        wallet_pool: Arc<WalletPool>,
+        task_scheduler: Arc<TaskScheduler>,
+        database: Arc<DatabaseManager>,
+        provider: Arc<EthereumProvider>,
+        nonce_manager: Arc<WalletNonceManager>,
+    ) -> Self {
+        let policy = ScalingPolicy {
+            min_wallets: wallet_pool.config().min_wallets,
+            max_wallets: wallet_pool.config().max_wallets,
+            queue_depth: config.scale_up_queue_depth,
+        };
+
+        Self {
+            deriver: Arc::new(deriver),
+            wallet_pool,
+            task_scheduler,
+            database,
+            provider,
+            nonce_manager,
+            policy,
+            idle_timeout: Duration::from_secs(config.idle_timeout),
+            check_interval: Duration::from_secs(config.check_interval),
+            derived: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Derived wallets in the pool, by derivation index
+    pub async fn derived_wallets(&self) -> Vec<(u32, Address)> {
+        let mut wallets: Vec<_> = self.derived.lock().await
+            .iter()
+            .map(|(&address, &index)| (index, address))
+            .collect();
+        wallets.sort();
+        wallets
+    }
+
+    /// Re-derive the persisted indexes that are not retired, then derive up to `min_wallets`
+    pub async fn restore(&self) -> Result<Vec<Address>> {
+        let path = self.deriver.derivation_path();
+        let indexes = self.database.get_hd_wallet_indexes(path).await?;
+        let mut derived = self.derived.lock().await;
+
+        for index in indexes.iter().filter(|index| !index.retired) {
+            let derivation_index = index.derivation_index as u32;
+            let signing_key = self.deriver.derive(derivation_index)?;
+            let address = Address::from_private_key(&signing_key);
+            if !address.to_string().eq_ignore_ascii_case(&index.address) {
+                return Err(RelayerError::Config(format!(
+                    "HD wallet {}/{} was {} but now derives {}; the mnemonic or password changed",
+                    path, derivation_index, index.address, address
+                )));
+            }
+            if self.wallet_pool.wallets().read().await.iter().any(|wallet| wallet.address == address) {
+                tracing::warn!("Skipping HD wallet {:?}, already loaded from another key source", address);
+                continue;
+            }
+
+            self.wallet_pool.add_wallet(signing_key).await?;
+            derived.insert(address, derivation_index);
+            tracing::info!("Restored HD wallet {}/{}: {:?}", path, derivation_index, address);
+        }
+
+        while self.wallet_pool.active_addresses().await.len() < self.policy.min_wallets
+            && self.wallet_pool.wallets().read().await.len() < self.policy.max_wallets
+        {
+            self.derive_next(&mut derived).await?;
+        }
+
+        Ok(derived.keys().copied().collect())
+    }
+
+    /// Start resizing the pool every `check_interval`
+    pub async fn start_scaling_loop(&self) -> Result<()> {
+        tracing::info!("Starting HD wallet scaling loop...");
+        let mut interval = tokio::time::interval(self.check_interval);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = self.scale().await {
+                tracing::error!("HD wallet scaling error: {}", e);
+            }
+        }
+    }
+
+    /// Grow or shrink the pool by at most one wallet, returning what was done
+    pub async fn scale(&self) -> Result<Scaling> {
+        let queue_stats = self.task_scheduler.get_queue_stats().await?;
+        let active_wallets = self.wallet_pool.active_addresses().await;
+        let pool_wallets = self.wallet_pool.wallets().read().await.len();
+
+        match self.policy.decide(queue_stats.pending_tasks, active_wallets.len(), pool_wallets) {
+            Scaling::Grow => {
+                let mut derived = self.derived.lock().await;
+                let address = self.derive_next(&mut derived).await?;
+                tracing::info!(
+                    "Grew wallet pool with HD wallet {:?} ({} pending tasks, {} wallets in rotation)",
+                    address, queue_stats.pending_tasks, active_wallets.len()
+                );
+                Ok(Scaling::Grow)
+            }
+            Scaling::Shrink => {
+                if self.retire_idle_wallet(&active_wallets).await? {
+                    Ok(Scaling::Shrink)
+                } else {
+                    Ok(Scaling::Hold)
+                }
+            }
+            Scaling::Hold => Ok(Scaling::Hold),
+        }
+    }
+
+    /// Add the wallet at the lowest retired index, or at a new index past every derived one
+    async fn derive_next(&self, derived: &mut HashMap<Address, u32>) -> Result<Address> {
+        let path = self.deriver.derivation_path();
+        let indexes = self.database.get_hd_wallet_indexes(path).await?;
+
+        let index = indexes.iter()
+            .filter(|index| index.retired)
+            .map(|index| index.derivation_index as u32)
+            .find(|index| !derived.values().any(|derived_index| derived_index == index))
+            .unwrap_or_else(|| indexes.iter()
+                .map(|index| index.derivation_index as u32 + 1)
+                .chain(derived.values().map(|&index| index + 1))
+                .max()
+                .unwrap_or(0));
+
+        let signing_key = self.deriver.derive(index)?;
+        let address = Address::from_private_key(&signing_key);
+
+        // Persist first, so a restart never loses track of a wallet the pool used
+        self.database.save_hd_wallet_index(path, index, &address.to_string()).await?;
+        self.wallet_pool.add_wallet(signing_key).await?;
+        derived.insert(address, index);
+
+        tracing::info!("Derived HD wallet {}/{}: {:?}", path, index, address);
+        Ok(address)
+    }
+
+    /// Retire the newest derived wallet that has gone unused for `idle_timeout` and has no
+    /// transaction in flight. Returns whether one was retired.
+    async fn retire_idle_wallet(&self, active_wallets: &[Address]) -> Result<bool> {
+        let mut derived = self.derived.lock().await;
+        let idle_since = Utc::now() - chrono::Duration::from_std(self.idle_timeout)
+            .map_err(|e| RelayerError::Config(format!("Invalid HD wallet idle timeout: {}", e)))?;
+
+        let mut candidates: Vec<(u32, Address)> = derived.iter()
+            .filter(|(address, _)| active_wallets.contains(address))
+            .map(|(&address, &index)| (index, address))
+            .collect();
+        candidates.sort_by(|a, b| b.cmp(a));
+
+        for (index, address) in candidates {
+            let idle = match self.wallet_pool.get_wallet_stats(address).await? {
+                Some(stats) => stats.last_used < idle_since,
+                None => false,
+            };
+            if !idle {
+                continue;
+            }
+
+            // Out of rotation first, so nothing picks the wallet up while its nonces are checked
+            if !self.wallet_pool.suspend_wallet(address).await {
+                continue;
+            }
+            let settled = match self.nonces_settled(address).await {
+                Ok(settled) => settled,
+                Err(e) => {
+                    self.wallet_pool.resume_wallet(address).await;
+                    return Err(e);
+                }
+            };
+            if !settled {
+                self.wallet_pool.resume_wallet(address).await;
+                continue;
+            }
+
+            if let Err(e) = self.database.retire_hd_wallet_index(self.deriver.derivation_path(), index).await {
+                self.wallet_pool.resume_wallet(address).await;
+                return Err(e);
+            }
+            self.wallet_pool.remove_wallet(address).await?;
+            derived.remove(&address);
+
+            tracing::info!("Retired idle HD wallet {}/{}: {:?}", self.deriver.derivation_path(), index, address);
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    async fn nonces_settled(&self, address: Address) -> Result<bool> {
+        let latest = self.provider.get_transaction_count(address).latest().await
+            .map_err(|e| RelayerError::Ethereum(format!("Failed to get nonce of wallet {:?}: {}", address, e)))?;
+        let pending = self.provider.get_transaction_count(address).pending().await
+            .map_err(|e| RelayerError::Ethereum(format!("Failed to get pending nonce of wallet {:?}: {}", address, e)))?;
+
+        Ok(nonces_settled(latest, pending, self.nonce_manager.peek(address).await))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_MNEMONIC: &str = "test test test test test test test test test test test junk";
+
+    #[test]
+    fn test_derives_bip44_addresses() {
+        let deriver = HdWalletDeriver::new(
+            Zeroizing::new(TEST_MNEMONIC.to_string()),
+            None,
+            "m/44'/60'/0'/0/",
+        ).unwrap();
+        assert_eq!(deriver.derivation_path(), "m/44'/60'/0'/0");
+        assert_eq!(
+            deriver.derive_address(0).unwrap(),
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266".parse::<Address>().unwrap()
+        );
+        assert_eq!(
+            deriver.derive_address(1).unwrap(),
+            "0x70997970C51812dc3A010C7d01b50e0d17dc79C8".parse::<Address>().unwrap()
+        );
+
+        // The BIP-39 password changes every address
+        let with_password = HdWalletDeriver::new(
+            Zeroizing::new(TEST_MNEMONIC.to_string()),
+            Some("correct horse".to_string()),
+            "m/44'/60'/0'/0",
+        ).unwrap();
+        assert_ne!(with_password.derive_address(0).unwrap(), deriver.derive_address(0).unwrap());
+
+        let bad_checksum = "test test test test test test test test test test test test";
+        assert!(HdWalletDeriver::new(Zeroizing::new(bad_checksum.to_string()), None, "m/44'/60'/0'/0").is_err());
+    }
+
+    #[test]
+    fn test_scaling_policy() {
+        let policy = ScalingPolicy { min_wallets: 2, max_wallets: 4, queue_depth: 5 };
+
+        assert_eq!(policy.decide(0, 1, 1), Scaling::Grow);
+        assert_eq!(policy.decide(11, 2, 2), Scaling::Grow);
+        assert_eq!(policy.decide(10, 2, 2), Scaling::Hold);
+        // Suspended wallets still count toward the maximum
+        assert_eq!(policy.decide(50, 3, 4), Scaling::Hold);
+        assert_eq!(policy.decide(0, 3, 3), Scaling::Shrink);
+        assert_eq!(policy.decide(1, 3, 3), Scaling::Hold);
+        assert_eq!(policy.decide(0, 2, 2), Scaling::Hold);
+    }
+
+    #[test]
+    fn test_nonces_settled() {
+        assert!(nonces_settled(7, 7, None));
+        assert!(nonces_settled(7, 7, Some(7)));
+        // A transaction in the mempool
+        assert!(!nonces_settled(7, 8, Some(8)));
+        // A nonce handed out but not broadcast yet
+        assert!(!nonces_settled(7, 7, Some(8)));
+    }
+}
@@ -0,0 +1,513 @@
+use alloy::{primitives::Address, signers::k256::ecdsa::SigningKey};
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+use crate::{
+    config::WalletConfig,
+    database::DatabaseManager,
+    types::{RelayerError, Result},
+    utils::crypto::{CryptoUtils, EncryptedData},
+    wallet::signer::KeystoreSigner,
+};
+
+type Aes128Ctr = ctr::Ctr128BE<aes::Aes128>;
+
+const KEYSTORE_VERSION: u32 = 3;
+const KEYSTORE_CIPHER: &str = "aes-128-ctr";
+const KEYSTORE_PRF: &str = "hmac-sha256";
+const DERIVED_KEY_LEN: usize = 32;
+
+// Upper bounds on the work an uploaded keystore can ask for; geth's standard settings
+const MAX_SCRYPT_N: u32 = 1 << 18;
+const MAX_SCRYPT_R: u32 = 8;
+const MAX_SCRYPT_P: u32 = 1;
+const MAX_PBKDF2_ITERATIONS: u32 = 1_000_000;
+
+/// Key derivation used when encrypting a keystore
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeystoreKdf {
+    Scrypt { log_n: u8, r: u32, p: u32 },
+    Pbkdf2 { iterations: u32 },
+}
+
+impl KeystoreKdf {
+    /// scrypt with the parameters geth uses for standard keystores
+    pub fn scrypt() -> Self {
+        Self::Scrypt { log_n: 18, r: 8, p: 1 }
+    }
+
+    pub fn pbkdf2() -> Self {
+        Self::Pbkdf2 { iterations: 262_144 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kdf", content = "kdfparams", rename_all = "lowercase")]
+pub enum KdfParams {
+    Scrypt { dklen: usize, n: u32, r: u32, p: u32, salt: String },
+    Pbkdf2 { dklen: usize, c: u32, prf: String, salt: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CipherParams {
+    pub iv: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeystoreCrypto {
+    pub cipher: String,
+    pub cipherparams: CipherParams,
+    pub ciphertext: String,
+    #[serde(flatten)]
+    pub kdf: KdfParams,
+    pub mac: String,
+}
+
+/// Ethereum Web3 Secret Storage (v3 keystore) document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    pub version: u32,
+    pub id: Uuid,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(alias = "Crypto")]
+    pub crypto: KeystoreCrypto,
+}
+
+impl Keystore {
+    /// Encrypt `private_key` under `password`
+    pub fn encrypt(private_key: &SigningKey, password: &str, kdf: KeystoreKdf) -> Result<Self> {
+        let mut salt = [0u8; 32];
+        let mut iv = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut salt);
+        rand::thread_rng().fill_bytes(&mut iv);
+
+        let kdf = match kdf {
+            KeystoreKdf::Scrypt { log_n, r, p } => KdfParams::Scrypt {
+                dklen: DERIVED_KEY_LEN,
+                n: 1u32.checked_shl(log_n as u32)
+                    .ok_or_else(|| RelayerError::Validation(format!("scrypt log_n {} is too large", log_n)))?,
+                r,
+                p,
+                salt: hex::encode(salt),
+            },
+            KeystoreKdf::Pbkdf2 { iterations } => KdfParams::Pbkdf2 {
+                dklen: DERIVED_KEY_LEN,
+                c: iterations,
+                prf: KEYSTORE_PRF.to_string(),
+                salt: hex::encode(salt),
+            },
+        };
+        let derived_key = derive_key(&kdf, password)?;
+
+        let mut ciphertext = Zeroizing::new(private_key.to_bytes().to_vec());
+        Aes128Ctr::new(derived_key[..16].into(), (&iv).into()).apply_keystream(&mut ciphertext);
+        let mac = keystore_mac(&derived_key, &ciphertext);
+
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            id: Uuid::new_v4(),
+            address: Some(hex::encode(Address::from_private_key(private_key))),
+            crypto: KeystoreCrypto {
+                cipher: KEYSTORE_CIPHER.to_string(),
+                cipherparams: CipherParams { iv: hex::encode(iv) },
+                ciphertext: hex::encode(ciphertext.as_slice()),
+                kdf,
+                mac: hex::encode(mac),
+            },
+        })
+    }
+
+    /// Decrypt the private key, failing on a wrong password or a tampered document
+    pub fn decrypt(&self, password: &str) -> Result<SigningKey> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(RelayerError::Validation(format!("Unsupported keystore version {}", self.version)));
+        }
+        if self.crypto.cipher != KEYSTORE_CIPHER {
+            return Err(RelayerError::Validation(format!("Unsupported keystore cipher {}", self.crypto.cipher)));
+        }
+
+        let ciphertext = decode_hex("ciphertext", &self.crypto.ciphertext)?;
+        let iv: [u8; 16] = decode_hex("iv", &self.crypto.cipherparams.iv)?
+            .try_into()
+            .map_err(|_| RelayerError::Validation("Keystore iv must be 16 bytes".to_string()))?;
+        let mac = decode_hex("mac", &self.crypto.mac)?;
+
+        let derived_key = derive_key(&self.crypto.kdf, password)?;
+        if keystore_mac(&derived_key, &ciphertext).as_slice() != mac.as_slice() {
+            return Err(RelayerError::SignatureVerification(
+                "Keystore MAC mismatch: wrong password or corrupted keystore".to_string(),
+            ));
+        }
+
+        let mut plaintext = Zeroizing::new(ciphertext);
+        Aes128Ctr::new(derived_key[..16].into(), (&iv).into()).apply_keystream(&mut plaintext);
+        let private_key = signing_key_from_bytes(&plaintext)?;
+
+        if let Some(ref address) = self.address {
+            let expected = address.strip_prefix("0x").unwrap_or(address);
+            let actual = hex::encode(Address::from_private_key(&private_key));
+            if !expected.eq_ignore_ascii_case(&actual) {
+                return Err(RelayerError::Validation(format!(
+                    "Keystore address 0x{} does not match its key 0x{}",
+                    expected, actual
+                )));
+            }
+        }
+
+        Ok(private_key)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json)
+            .map_err(|e| RelayerError::Serialization(format!("Invalid keystore: {}", e)))
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self).map_err(|e| RelayerError::Serialization(e.to_string()))
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| RelayerError::Io(format!("Failed to read keystore {}: {}", path.display(), e)))?;
+        Self::from_json(&json)
+    }
+
+    pub fn write_file(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_json()?)
+            .map_err(|e| RelayerError::Io(format!("Failed to write keystore {}: {}", path.display(), e)))
+    }
+}
+
+fn derive_key(kdf: &KdfParams, password: &str) -> Result<Zeroizing<[u8; DERIVED_KEY_LEN]>> {
+    let mut derived_key = Zeroizing::new([0u8; DERIVED_KEY_LEN]);
+    match kdf {
+        KdfParams::Scrypt { dklen, n, r, p, salt } => {
+            check_dklen(*dklen)?;
+            if !n.is_power_of_two() || *n < 2 {
+                return Err(RelayerError::Validation(format!("scrypt n {} is not a power of two", n)));
+            }
+            if *n > MAX_SCRYPT_N || *r > MAX_SCRYPT_R || *p > MAX_SCRYPT_P {
+                return Err(RelayerError::Validation(format!(
+                    "scrypt parameters n={} r={} p={} exceed n={} r={} p={}",
+                    n, r, p, MAX_SCRYPT_N, MAX_SCRYPT_R, MAX_SCRYPT_P
+                )));
+            }
+            let params = scrypt::Params::new(n.trailing_zeros() as u8, *r, *p, DERIVED_KEY_LEN)
+                .map_err(|e| RelayerError::Validation(format!("Invalid scrypt parameters: {}", e)))?;
+            scrypt::scrypt(password.as_bytes(), &decode_hex("salt", salt)?, &params, derived_key.as_mut_slice())
+                .map_err(|e| RelayerError::Internal(format!("scrypt failed: {}", e)))?;
+        }
+        KdfParams::Pbkdf2 { dklen, c, prf, salt } => {
+            check_dklen(*dklen)?;
+            if prf != KEYSTORE_PRF {
+                return Err(RelayerError::Validation(format!("Unsupported keystore prf {}", prf)));
+            }
+            if *c > MAX_PBKDF2_ITERATIONS {
+                return Err(RelayerError::Validation(format!(
+                    "pbkdf2 iterations {} exceed {}", c, MAX_PBKDF2_ITERATIONS
+                )));
+            }
+            pbkdf2::pbkdf2_hmac::<sha2::Sha256>(
+                password.as_bytes(),
+                &decode_hex("salt", salt)?,
+                *c,
+                derived_key.as_mut_slice(),
+            );
+        }
+    }
+    Ok(derived_key)
+}
+
+fn check_dklen(dklen: usize) -> Result<()> {
+    if dklen != DERIVED_KEY_LEN {
+        return Err(RelayerError::Validation(format!("Unsupported keystore dklen {}", dklen)));
+    }
+    Ok(())
+}
+
+fn keystore_mac(derived_key: &[u8; DERIVED_KEY_LEN], ciphertext: &[u8]) -> [u8; 32] {
+    let mut preimage = Zeroizing::new(Vec::with_capacity(16 + ciphertext.len()));
+    preimage.extend_from_slice(&derived_key[16..]);
+    preimage.extend_from_slice(ciphertext);
+    CryptoUtils::keccak256(&preimage)
+}
+
+fn decode_hex(field: &str, value: &str) -> Result<Vec<u8>> {
+    hex::decode(value.strip_prefix("0x").unwrap_or(value))
+        .map_err(|e| RelayerError::Validation(format!("Invalid keystore {}: {}", field, e)))
+}
+
+fn signing_key_from_bytes(bytes: &[u8]) -> Result<SigningKey> {
+    if bytes.len() != 32 {
+        return Err(RelayerError::Validation(format!("Invalid private key length: {}", bytes.len())));
+    }
+    SigningKey::from_bytes(bytes.into())
+        .map_err(|e| RelayerError::Validation(format!("Invalid private key: {}", e)))
+}
+
+/// Read the master passphrase from `passphrase_file`, or else from the `passphrase_env`
+/// variable
+pub fn read_master_passphrase(config: &WalletConfig) -> Result<Option<Zeroizing<String>>> {
+    if let Some(ref path) = config.passphrase_file {
+        let contents = Zeroizing::new(std::fs::read_to_string(path)
+            .map_err(|e| RelayerError::Config(format!("Failed to read wallet passphrase file {}: {}", path, e)))?);
+        let passphrase = contents.trim_end_matches(['\r', '\n']);
+        if passphrase.is_empty() {
+            return Err(RelayerError::Config(format!("Wallet passphrase file {} is empty", path)));
+        }
+        return Ok(Some(Zeroizing::new(passphrase.to_string())));
+    }
+
+    Ok(std::env::var(&config.passphrase_env)
+        .ok()
+        .filter(|passphrase| !passphrase.is_empty())
+        .map(Zeroizing::new))
+}
+
+/// Loads relayer wallet keys that are kept encrypted under the master passphrase, from
+/// keystore files and from the `wallets` table, and imports keystores into that table
+pub struct WalletKeyStore {
+    database: Arc<DatabaseManager>,
+    passphrase: Option<Zeroizing<String>>,
+}
+
+impl std::fmt::Debug for WalletKeyStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WalletKeyStore")
+            .field("has_passphrase", &self.has_passphrase())
+            .finish_non_exhaustive()
+    }
+}
+
+impl WalletKeyStore {
+    pub fn new(database: Arc<DatabaseManager>, passphrase: Option<Zeroizing<String>>) -> Self {
+        Self { database, passphrase }
+    }
+
+    pub fn has_passphrase(&self) -> bool {
+        self.passphrase.is_some()
+    }
+
+    fn passphrase(&self) -> Result<&str> {
+        self.passphrase
+            .as_ref()
+            .map(|passphrase| passphrase.as_str())
+            .ok_or_else(|| RelayerError::Config("No wallet master passphrase configured".to_string()))
+    }
+
+    /// Decrypt every keystore in `paths`; directories contribute their `.json` files
+    pub fn load_keystore_files(&self, paths: &[String]) -> Result<Vec<SigningKey>> {
+        let passphrase = self.passphrase()?;
+        let mut keys = Vec::new();
+        for path in keystore_files(paths)? {
+            let private_key = Keystore::from_file(&path)?
+                .decrypt(passphrase)
+                .map_err(|e| RelayerError::Config(format!("Failed to decrypt keystore {}: {}", path.display(), e)))?;
+            keys.push(private_key);
+        }
+        Ok(keys)
+    }
+
+    /// Keystore-backed signers for every keystore in `paths`
+    pub fn load_keystore_signers(&self, paths: &[String]) -> Result<Vec<KeystoreSigner>> {
+        let passphrase = self.passphrase()?;
+        keystore_files(paths)?
+            .iter()
+            .map(|path| KeystoreSigner::from_file(path, passphrase))
+            .collect()
+    }
+
+    /// Decrypt the `encrypted_private_key` of every active row in the `wallets` table
+    pub async fn load_from_database(&self) -> Result<Vec<SigningKey>> {
+        let passphrase = self.passphrase()?;
+        let mut keys = Vec::new();
+        for (address, encrypted_private_key) in self.database.get_encrypted_wallet_keys().await? {
+            let private_key = decrypt_stored_key(&encrypted_private_key, passphrase)
+                .map_err(|e| RelayerError::Config(format!("Failed to decrypt key of wallet {}: {}", address, e)))?;
+            keys.push(private_key);
+        }
+        Ok(keys)
+    }
+
+    /// Decrypt `keystore` with its own `password` and store the key in the `wallets` table,
+    /// encrypted under the master passphrase
+    pub async fn import(&self, keystore: Keystore, password: Zeroizing<String>) -> Result<SigningKey> {
+        let passphrase = self.passphrase()?;
+        // Key derivation is deliberately slow, keep it off the async workers
+        let private_key = tokio::task::spawn_blocking(move || keystore.decrypt(&password))
+            .await
+            .map_err(|e| RelayerError::Internal(format!("Keystore decryption task failed: {}", e)))??;
+        let key_bytes = Zeroizing::new(private_key.to_bytes().to_vec());
+        let encrypted = EncryptedData::encrypt(&key_bytes, passphrase)?;
+        let encrypted = serde_json::to_string(&encrypted)
+            .map_err(|e| RelayerError::Serialization(e.to_string()))?;
+
+        self.database
+            .store_wallet_key(&Address::from_private_key(&private_key).to_string(), &encrypted)
+            .await?;
+        Ok(private_key)
+    }
+}
+
+/// Stored keys are `EncryptedData` JSON, or a v3 keystore encrypted under the master passphrase
+fn decrypt_stored_key(encrypted_private_key: &str, passphrase: &str) -> Result<SigningKey> {
+    if let Ok(keystore) = Keystore::from_json(encrypted_private_key) {
+        return keystore.decrypt(passphrase);
+    }
+
+    let encrypted: EncryptedData = serde_json::from_str(encrypted_private_key)
+        .map_err(|e| RelayerError::Serialization(format!("Unrecognized encrypted key format: {}", e)))?;
+    let key_bytes = Zeroizing::new(encrypted.decrypt(passphrase)?);
+    signing_key_from_bytes(&key_bytes)
+}
+
+fn keystore_files(paths: &[String]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths.iter().map(PathBuf::from) {
+        if !path.is_dir() {
+            files.push(path);
+            continue;
+        }
+
+        let entries = std::fs::read_dir(&path)
+            .map_err(|e| RelayerError::Io(format!("Failed to read keystore directory {}: {}", path.display(), e)))?;
+        let mut dir_files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|file| file.is_file() && file.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        dir_files.sort();
+        files.extend(dir_files);
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_PRIVATE_KEY: &str = "7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d";
+
+    fn test_key_store() -> WalletKeyStore {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgresql://127.0.0.1:1/express402_test")
+            .unwrap();
+        WalletKeyStore::new(
+            Arc::new(DatabaseManager::from_pool(pool)),
+            Some(Zeroizing::new("master".to_string())),
+        )
+    }
+
+    #[test]
+    fn test_decrypt_web3_secret_storage_vectors() {
+        let pbkdf2 = Keystore::from_json(r#"{
+            "crypto": {
+                "cipher": "aes-128-ctr",
+                "cipherparams": { "iv": "6087dab2f9fdbbfaddc31a909735c1e6" },
+                "ciphertext": "5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46",
+                "kdf": "pbkdf2",
+                "kdfparams": {
+                    "c": 262144,
+                    "dklen": 32,
+                    "prf": "hmac-sha256",
+                    "salt": "ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd"
+                },
+                "mac": "517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2"
+            },
+            "id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+            "version": 3
+        }"#).unwrap();
+        let scrypt = Keystore::from_json(r#"{
+            "Crypto": {
+                "cipher": "aes-128-ctr",
+                "cipherparams": { "iv": "83dbcc02d8ccb40e466191a123791e0e" },
+                "ciphertext": "91c23c03a8b5117a6e3d78dbb5dfc46f037aff9351dff59c2879586f51756502",
+                "kdf": "scrypt",
+                "kdfparams": {
+                    "dklen": 32,
+                    "n": 8192,
+                    "p": 1,
+                    "r": 8,
+                    "salt": "ab0c7876052600dd703518d6fc3fe8984592145b591fc8fb5c6d43190334ba19"
+                },
+                "mac": "8f8639fe08fe1f43d6c13006d66ad2d993c6674ee2ba03f025596797a73614b9"
+            },
+            "id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+            "version": 3
+        }"#).unwrap();
+
+        for keystore in [pbkdf2, scrypt] {
+            let private_key = keystore.decrypt("testpassword").unwrap();
+            assert_eq!(hex::encode(private_key.to_bytes()), TEST_PRIVATE_KEY);
+            assert!(matches!(keystore.decrypt("wrong").unwrap_err(), RelayerError::SignatureVerification(_)));
+        }
+    }
+
+    #[test]
+    fn test_keystore_round_trip() {
+        let private_key = CryptoUtils::generate_private_key();
+
+        for kdf in [KeystoreKdf::Scrypt { log_n: 10, r: 8, p: 1 }, KeystoreKdf::Pbkdf2 { iterations: 1024 }] {
+            let keystore = Keystore::encrypt(&private_key, "secret", kdf).unwrap();
+            assert_eq!(keystore.address, Some(hex::encode(Address::from_private_key(&private_key))));
+
+            let parsed = Keystore::from_json(&keystore.to_json().unwrap()).unwrap();
+            assert_eq!(parsed, keystore);
+            assert_eq!(parsed.decrypt("secret").unwrap().to_bytes(), private_key.to_bytes());
+        }
+
+        // An uploaded keystore asking for oversized key derivation work is refused
+        let salt = hex::encode([0u8; 32]);
+        for kdf in [
+            KdfParams::Scrypt { dklen: 32, n: 1 << 30, r: 8, p: 1, salt: salt.clone() },
+            KdfParams::Scrypt { dklen: 32, n: 1 << 10, r: 1024, p: 1, salt: salt.clone() },
+            KdfParams::Scrypt { dklen: 32, n: 1 << 10, r: 8, p: 64, salt: salt.clone() },
+            KdfParams::Pbkdf2 { dklen: 32, c: u32::MAX, prf: KEYSTORE_PRF.to_string(), salt: salt.clone() },
+        ] {
+            let mut keystore = Keystore::encrypt(&private_key, "secret", KeystoreKdf::Pbkdf2 { iterations: 1024 }).unwrap();
+            keystore.crypto.kdf = kdf;
+            assert!(matches!(keystore.decrypt("secret").unwrap_err(), RelayerError::Validation(_)));
+        }
+
+        // An address that does not belong to the key is rejected
+        let mut keystore = Keystore::encrypt(&private_key, "secret", KeystoreKdf::Pbkdf2 { iterations: 1024 }).unwrap();
+        keystore.address = Some(hex::encode(Address::repeat_byte(0x11)));
+        assert!(matches!(keystore.decrypt("secret").unwrap_err(), RelayerError::Validation(_)));
+    }
+
+    #[tokio::test]
+    async fn test_load_keystore_files_and_stored_keys() {
+        let key_store = test_key_store();
+        let dir = std::env::temp_dir().join(format!("express402-keystores-{}", Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+
+        let first = CryptoUtils::generate_private_key();
+        let second = CryptoUtils::generate_private_key();
+        let kdf = KeystoreKdf::Pbkdf2 { iterations: 1024 };
+        Keystore::encrypt(&first, "master", kdf).unwrap().write_file(&dir.join("a.json")).unwrap();
+        Keystore::encrypt(&second, "master", kdf).unwrap().write_file(&dir.join("b.json")).unwrap();
+        std::fs::write(dir.join("README"), "not a keystore").unwrap();
+
+        let keys = key_store.load_keystore_files(&[dir.display().to_string()]).unwrap();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0].to_bytes(), first.to_bytes());
+        assert_eq!(keys[1].to_bytes(), second.to_bytes());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Stored keys may be EncryptedData or a keystore under the master passphrase
+        let encrypted = serde_json::to_string(&EncryptedData::encrypt(&first.to_bytes(), "master").unwrap()).unwrap();
+        assert_eq!(decrypt_stored_key(&encrypted, "master").unwrap().to_bytes(), first.to_bytes());
+        assert!(decrypt_stored_key(&encrypted, "wrong").is_err());
+        let keystore = Keystore::encrypt(&second, "master", kdf).unwrap().to_json().unwrap();
+        assert_eq!(decrypt_stored_key(&keystore, "master").unwrap().to_bytes(), second.to_bytes());
+
+        let locked = WalletKeyStore::new(Arc::clone(&key_store.database), None);
+        assert!(matches!(locked.load_keystore_files(&[]).unwrap_err(), RelayerError::Config(_)));
+    }
+}
@@ -1,6 +1,11 @@
 pub mod pool;
 pub mod monitor;
 pub mod rotation;
+pub mod keystore;
+pub mod nonce;
+pub mod treasury;
+pub mod hd;
+pub mod signer;
 
 #[cfg(test)]
 mod tests;
@@ -8,3 +13,8 @@ mod tests;
 pub use pool::*;
 pub use monitor::*;
 pub use rotation::*;
+pub use keystore::*;
+pub use nonce::*;
+pub use treasury::*;
+pub use hd::*;
+pub use signer::*;
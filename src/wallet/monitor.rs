@@ -1,13 +1,14 @@
 use alloy::{
     primitives::{Address, U256},
-    providers::{Provider, RootProvider},
+    providers::Provider,
+    transports::http::{reqwest::Client, Http},
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tokio::time::{Duration, Instant};
+use tokio::time::Duration;
 
 use crate::types::{RelayerError, Result, WalletInfo};
 
@@ -32,7 +33,7 @@ pub struct WalletMonitor<P> {
 
 impl<P> WalletMonitor<P>
 where
-    P: Provider + Send + Sync + 'static,
+    P: Provider<Http<Client>> + Send + Sync + 'static,
 {
     pub fn new(
         provider: Arc<P>,
@@ -227,7 +228,7 @@ where
 
 impl<P> Clone for WalletMonitor<P>
 where
-    P: Provider + Send + Sync + 'static,
+    P: Provider<Http<Client>> + Send + Sync + 'static,
 {
     fn clone(&self) -> Self {
         Self {
@@ -300,6 +301,10 @@ impl WalletAlertManager {
         }
     }
 
+    pub fn thresholds(&self) -> &AlertThresholds {
+        &self.alert_thresholds
+    }
+
     pub async fn check_and_create_alerts(&self, health_statuses: Vec<WalletHealthStatus>) -> Result<Vec<WalletAlert>> {
         let mut new_alerts = Vec::new();
 
@@ -372,7 +377,7 @@ impl WalletAlertManager {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use alloy::primitives::Address;
+    
 
     #[tokio::test]
     async fn test_wallet_monitor_creation() {
@@ -0,0 +1,237 @@
+use alloy::{primitives::Address, providers::Provider};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::{
+    services::EthereumProvider,
+    types::{RelayerError, Result},
+};
+
+/// How a node rejected a transaction over its nonce
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonceError {
+    /// The nonce was already used, or a transaction with it is already pending
+    TooLow,
+    /// The nonce leaves a gap the node will not queue behind
+    TooHigh,
+}
+
+impl NonceError {
+    /// Classify a broadcast error message from geth, Erigon, Nethermind or Besu style nodes
+    pub fn classify(message: &str) -> Option<Self> {
+        let message = message.to_ascii_lowercase();
+        if message.contains("nonce too low")
+            || message.contains("nonce is too low")
+            || message.contains("already known")
+            || message.contains("known transaction")
+            || message.contains("replacement transaction underpriced")
+        {
+            Some(Self::TooLow)
+        } else if message.contains("nonce too high") || message.contains("nonce is too high") {
+            Some(Self::TooHigh)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct WalletNonces {
+    /// Next never-allocated nonce; `None` until seeded from the node
+    next: Option<u64>,
+    /// Nonces handed back after failed broadcasts, reused lowest first so no gap is left
+    released: BTreeSet<u64>,
+}
+
+/// Hands out relayer wallet nonces atomically so one wallet can pipeline many transactions.
+/// Each wallet is seeded from `eth_getTransactionCount(pending)` on first use and resynced
+/// when a node reports the local view has drifted.
+#[derive(Debug, Clone)]
+pub struct WalletNonceManager {
+    provider: Arc<EthereumProvider>,
+    wallets: Arc<Mutex<HashMap<Address, Arc<Mutex<WalletNonces>>>>>,
+}
+
+impl WalletNonceManager {
+    pub fn new(provider: Arc<EthereumProvider>) -> Self {
+        Self {
+            provider,
+            wallets: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    async fn wallet(&self, address: Address) -> Arc<Mutex<WalletNonces>> {
+        let mut wallets = self.wallets.lock().await;
+        Arc::clone(wallets.entry(address).or_default())
+    }
+
+    async fn pending_nonce(&self, address: Address) -> Result<u64> {
+        self.provider
+            .get_transaction_count(address)
+            .pending()
+            .await
+            .map_err(|e| RelayerError::Ethereum(format!("Failed to get nonce of wallet {:?}: {}", address, e)))
+    }
+
+    /// Reserve the next nonce of `address`
+    pub async fn allocate(&self, address: Address) -> Result<u64> {
+        let wallet = self.wallet(address).await;
+        let mut nonces = wallet.lock().await;
+
+        if let Some(nonce) = nonces.released.pop_first() {
+            return Ok(nonce);
+        }
+
+        let next = match nonces.next {
+            Some(next) => next,
+            None => self.pending_nonce(address).await?,
+        };
+        nonces.next = Some(next + 1);
+        Ok(next)
+    }
+
+    /// Hand back a nonce whose transaction never reached the node, so it is reused
+    pub async fn release(&self, address: Address, nonce: u64) {
+        let wallet = self.wallet(address).await;
+        let mut nonces = wallet.lock().await;
+
+        match nonces.next {
+            Some(next) if nonce < next => {
+                nonces.released.insert(nonce);
+                // Released nonces at the top of the range simply lower the next nonce
+                while let Some(next) = nonces.next {
+                    if next == 0 || !nonces.released.remove(&(next - 1)) {
+                        break;
+                    }
+                    nonces.next = Some(next - 1);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Reseed `address` from the node, forgetting released nonces. Returns the next nonce.
+    pub async fn resync(&self, address: Address) -> Result<u64> {
+        let wallet = self.wallet(address).await;
+        let mut nonces = wallet.lock().await;
+
+        let next = self.pending_nonce(address).await?;
+        tracing::info!("Resynced nonce of wallet {:?} to {}", address, next);
+        nonces.next = Some(next);
+        nonces.released.clear();
+        Ok(next)
+    }
+
+    /// Set the next nonce of `address` without asking the node
+    pub async fn set_next_nonce(&self, address: Address, next: u64) {
+        let wallet = self.wallet(address).await;
+        let mut nonces = wallet.lock().await;
+        nonces.next = Some(next);
+        nonces.released.clear();
+    }
+
+    /// Put a wallet's nonces right after a failed broadcast: resync from the node when it
+    /// disagrees about the nonce, otherwise hand the nonce back for the next transaction
+    pub async fn recover(&self, address: Address, nonce: u64, error: &str) {
+        match NonceError::classify(error) {
+            Some(kind) => {
+                tracing::warn!("Wallet {:?} nonce {} rejected ({:?}), resyncing", address, nonce, kind);
+                if let Err(e) = self.resync(address).await {
+                    tracing::error!("Failed to resync nonce of wallet {:?}: {}", address, e);
+                    // Without the node's view, retrying the same nonce is the safest guess
+                    self.release(address, nonce).await;
+                }
+            }
+            None => self.release(address, nonce).await,
+        }
+    }
+
+    /// Next nonce `allocate` would hand out, if the wallet has been seeded
+    pub async fn peek(&self, address: Address) -> Option<u64> {
+        let wallet = self.wallet(address).await;
+        let nonces = wallet.lock().await;
+        nonces.released.first().copied().or(nonces.next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_manager() -> WalletNonceManager {
+        let provider = alloy::providers::ProviderBuilder::new()
+            .on_http("http://127.0.0.1:1".parse().unwrap());
+        WalletNonceManager::new(Arc::new(provider))
+    }
+
+    #[test]
+    fn test_classify_nonce_errors() {
+        assert_eq!(NonceError::classify("server returned an error response: error code -32000: nonce too low"), Some(NonceError::TooLow));
+        assert_eq!(NonceError::classify("Nonce too low: next nonce 5, tx nonce 3"), Some(NonceError::TooLow));
+        assert_eq!(NonceError::classify("already known"), Some(NonceError::TooLow));
+        assert_eq!(NonceError::classify("replacement transaction underpriced"), Some(NonceError::TooLow));
+        assert_eq!(NonceError::classify("nonce too high"), Some(NonceError::TooHigh));
+        assert_eq!(NonceError::classify("insufficient funds for gas * price + value"), None);
+    }
+
+    #[tokio::test]
+    async fn test_allocate_is_atomic_per_wallet() {
+        let manager = test_manager();
+        let wallet = Address::repeat_byte(0x01);
+        manager.set_next_nonce(wallet, 7).await;
+
+        let handles: Vec<_> = (0..20)
+            .map(|_| {
+                let manager = manager.clone();
+                tokio::spawn(async move { manager.allocate(wallet).await.unwrap() })
+            })
+            .collect();
+        let mut nonces = Vec::new();
+        for handle in handles {
+            nonces.push(handle.await.unwrap());
+        }
+        nonces.sort();
+        assert_eq!(nonces, (7..27).collect::<Vec<_>>());
+
+        // Wallets do not share nonces
+        let other = Address::repeat_byte(0x02);
+        manager.set_next_nonce(other, 0).await;
+        assert_eq!(manager.allocate(other).await.unwrap(), 0);
+        assert_eq!(manager.peek(wallet).await, Some(27));
+    }
+
+    #[tokio::test]
+    async fn test_released_nonces_are_reused() {
+        let manager = test_manager();
+        let wallet = Address::repeat_byte(0x01);
+        manager.set_next_nonce(wallet, 10).await;
+
+        for expected in 10..14 {
+            assert_eq!(manager.allocate(wallet).await.unwrap(), expected);
+        }
+
+        // A gap in the middle is filled before new nonces are handed out
+        manager.release(wallet, 11).await;
+        assert_eq!(manager.allocate(wallet).await.unwrap(), 11);
+        assert_eq!(manager.allocate(wallet).await.unwrap(), 14);
+
+        // Releasing the top of the range rolls the next nonce back
+        manager.release(wallet, 14).await;
+        manager.release(wallet, 13).await;
+        assert_eq!(manager.peek(wallet).await, Some(13));
+        assert_eq!(manager.allocate(wallet).await.unwrap(), 13);
+
+        // Nonces that were never handed out are ignored
+        manager.release(wallet, 99).await;
+        assert_eq!(manager.allocate(wallet).await.unwrap(), 14);
+    }
+
+    #[tokio::test]
+    async fn test_unseeded_wallet_needs_the_node() {
+        let manager = test_manager();
+        assert!(manager.allocate(Address::repeat_byte(0x01)).await.is_err());
+        assert!(manager.resync(Address::repeat_byte(0x01)).await.is_err());
+        assert_eq!(manager.peek(Address::repeat_byte(0x01)).await, None);
+    }
+}
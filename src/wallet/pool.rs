@@ -7,8 +7,9 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::{RwLock, Semaphore};
-use tokio::time::{Duration, Instant};
 
+use super::rotation::RotationStrategy;
+use super::signer::{LocalKeySigner, WalletSigner};
 use crate::types::{RelayerError, Result, WalletInfo, WalletPoolConfig};
 
 #[derive(Debug)]
@@ -32,14 +33,6 @@ pub struct WalletUsageStats {
     pub total_gas_used: u64,
 }
 
-#[derive(Debug, Clone)]
-pub enum RotationStrategy {
-    RoundRobin,
-    LeastUsed,
-    BestPerformance,
-    Random,
-}
-
 impl WalletPool {
     pub fn new(config: WalletPoolConfig) -> Self {
         let max_concurrent = config.max_concurrent_transactions as usize;
@@ -54,11 +47,20 @@ impl WalletPool {
         }
     }
 
+    pub fn config(&self) -> &WalletPoolConfig {
+        &self.config
+    }
+
     pub async fn add_wallet(&self, private_key: SigningKey) -> Result<Address> {
-        let verifying_key = private_key.verifying_key();
-        let address = Address::from_word(alloy::primitives::keccak256(&verifying_key.to_sec1_bytes()[1..])[12..].try_into().unwrap());
-        
-        let wallet_info = WalletInfo::new(address, private_key);
+        self.add_signer(Arc::new(LocalKeySigner::new(private_key))).await
+    }
+
+    /// Add a wallet whose transactions are signed by `signer`, whatever backend holds its key
+    pub async fn add_signer(&self, signer: Arc<dyn WalletSigner>) -> Result<Address> {
+        let address = signer.address();
+        let kind = signer.kind();
+
+        let wallet_info = WalletInfo::with_signer(signer);
         
         {
             let mut wallets = self.wallets.write().await;
@@ -85,7 +87,7 @@ impl WalletPool {
             });
         }
 
-        tracing::info!("Added wallet: {:?}", address);
+        tracing::info!("Added {} wallet: {:?}", kind, address);
         Ok(address)
     }
 
@@ -117,12 +119,19 @@ impl WalletPool {
         }
 
         let selected_address = match self.rotation_strategy {
-            RotationStrategy::RoundRobin => self.select_round_robin(&active_wallets).await?,
-            RotationStrategy::LeastUsed => self.select_least_used(&active_wallets).await?,
+            RotationStrategy::RoundRobin | RotationStrategy::TimeBased => {
+                self.select_round_robin(&active_wallets).await?
+            }
+            RotationStrategy::LeastUsed | RotationStrategy::LoadBalanced => {
+                self.select_least_used(&active_wallets).await?
+            }
             RotationStrategy::BestPerformance => self.select_best_performance(&active_wallets).await?,
             RotationStrategy::Random => self.select_random(&active_wallets).await?,
         };
 
+        // Mark the selection so rotation moves on to the next wallet
+        self.update_wallet_usage(selected_address, true).await?;
+
         let wallets = self.wallets.read().await;
         let wallet = wallets.iter().find(|w| w.address == selected_address).cloned();
         
@@ -133,13 +142,22 @@ impl WalletPool {
         let _permit = self.semaphore.acquire().await
             .map_err(|e| RelayerError::WalletPool(e.to_string()))?;
 
-        let wallet = self.get_next_wallet().await?;
-        
-        if let Some(ref wallet_info) = wallet {
-            self.update_wallet_usage(wallet_info.address, true).await?;
+        self.get_next_wallet().await
+    }
+
+    /// Acquire a specific wallet, for transactions only that wallet may send
+    pub async fn acquire_specific_wallet(&self, address: Address) -> Result<Option<WalletInfo>> {
+        let _permit = self.semaphore.acquire().await
+            .map_err(|e| RelayerError::WalletPool(e.to_string()))?;
+
+        if !self.active_wallets.read().await.contains(&address) {
+            return Ok(None);
         }
 
-        Ok(wallet)
+        self.update_wallet_usage(address, true).await?;
+
+        let wallets = self.wallets.read().await;
+        Ok(wallets.iter().find(|w| w.address == address).cloned())
     }
 
     pub async fn release_wallet(&self, address: Address, success: bool, gas_used: u64) -> Result<()> {
@@ -168,6 +186,14 @@ impl WalletPool {
         Ok(())
     }
 
+    /// Record the next nonce of a wallet after it sent a transaction
+    pub async fn set_wallet_nonce(&self, address: Address, next_nonce: u64) {
+        let mut wallets = self.wallets.write().await;
+        if let Some(wallet) = wallets.iter_mut().find(|w| w.address == address) {
+            wallet.nonce = alloy::primitives::U256::from(next_nonce);
+        }
+    }
+
     pub async fn get_wallet_stats(&self, address: Address) -> Result<Option<WalletUsageStats>> {
         let usage = self.wallet_usage.read().await;
         Ok(usage.get(&address).cloned())
@@ -198,6 +224,37 @@ impl WalletPool {
         Ok(healthy_wallets)
     }
 
+    pub async fn active_addresses(&self) -> Vec<Address> {
+        self.active_wallets.read().await.clone()
+    }
+
+    /// Take a wallet out of rotation while keeping it in the pool. Returns whether it was in rotation.
+    pub async fn suspend_wallet(&self, address: Address) -> bool {
+        let mut active_wallets = self.active_wallets.write().await;
+        let was_active = active_wallets.contains(&address);
+        active_wallets.retain(|&addr| addr != address);
+        was_active
+    }
+
+    /// Put a suspended pool wallet back into rotation. Returns whether it was out of rotation.
+    pub async fn resume_wallet(&self, address: Address) -> bool {
+        if !self.wallets.read().await.iter().any(|w| w.address == address) {
+            return false;
+        }
+
+        let mut active_wallets = self.active_wallets.write().await;
+        if active_wallets.contains(&address) {
+            return false;
+        }
+        active_wallets.push(address);
+        true
+    }
+
+    /// Shared handle to the pool's wallet list, used by the wallet monitor
+    pub fn wallets(&self) -> Arc<RwLock<Vec<WalletInfo>>> {
+        Arc::clone(&self.wallets)
+    }
+
     pub async fn set_rotation_strategy(&mut self, strategy: RotationStrategy) {
         self.rotation_strategy = strategy;
     }
@@ -314,7 +371,7 @@ impl WalletPool {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct WalletPoolStats {
     pub total_wallets: usize,
     pub active_wallets: usize,
@@ -385,4 +442,49 @@ mod tests {
         // Should be different wallets (in round robin)
         assert_ne!(wallet1.address, wallet2.address);
     }
+
+    #[tokio::test]
+    async fn test_suspended_wallet_leaves_rotation() {
+        let pool = WalletPool::new(WalletPoolConfig::default());
+        let address = pool.add_wallet(SigningKey::random(&mut rand::thread_rng())).await.unwrap();
+
+        assert!(pool.suspend_wallet(address).await);
+        assert!(!pool.suspend_wallet(address).await);
+        assert!(pool.get_next_wallet().await.unwrap().is_none());
+        assert!(pool.acquire_specific_wallet(address).await.unwrap().is_none());
+        assert_eq!(pool.get_pool_stats().await.unwrap().total_wallets, 1);
+
+        assert!(pool.resume_wallet(address).await);
+        assert!(!pool.resume_wallet(address).await);
+        assert_eq!(pool.get_next_wallet().await.unwrap().unwrap().address, address);
+
+        // Only pool wallets can be put into rotation
+        assert!(!pool.resume_wallet(Address::repeat_byte(0x01)).await);
+    }
+
+    #[tokio::test]
+    async fn test_signer_backends_share_rotation() {
+        use crate::wallet::signer::{KeystoreSigner, SignerKind};
+
+        let pool = WalletPool::new(WalletPoolConfig::default());
+        let local = pool.add_wallet(SigningKey::random(&mut rand::thread_rng())).await.unwrap();
+        let keystore = pool.add_signer(Arc::new(KeystoreSigner::new(
+            SigningKey::random(&mut rand::thread_rng()),
+            "/etc/express402/keystores/relayer.json",
+        ))).await.unwrap();
+
+        let first = pool.get_next_wallet().await.unwrap().unwrap();
+        let second = pool.get_next_wallet().await.unwrap().unwrap();
+        let mut selected = vec![first.address, second.address];
+        selected.sort();
+        let mut expected = vec![local, keystore];
+        expected.sort();
+        assert_eq!(selected, expected);
+
+        for wallet in [first, second] {
+            let expected_kind = if wallet.address == local { SignerKind::Local } else { SignerKind::Keystore };
+            assert_eq!(wallet.signer_kind(), Some(expected_kind));
+            assert_eq!(wallet.signer.unwrap().address(), wallet.address);
+        }
+    }
 }
@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tokio::time::{Duration, Instant};
+use tokio::time::Duration;
 
 use crate::types::{RelayerError, Result, WalletInfo};
 
@@ -94,28 +94,14 @@ impl WalletRotator {
 
     pub async fn rotate_wallets(&self) -> Result<Vec<Address>> {
         let wallets = self.wallets.read().await.clone();
-        let mut rotated_addresses = Vec::new();
-
-        match self.policy.strategy {
-            RotationStrategy::RoundRobin => {
-                rotated_addresses = self.rotate_round_robin(&wallets).await?;
-            }
-            RotationStrategy::LeastUsed => {
-                rotated_addresses = self.rotate_least_used(&wallets).await?;
-            }
-            RotationStrategy::BestPerformance => {
-                rotated_addresses = self.rotate_best_performance(&wallets).await?;
-            }
-            RotationStrategy::LoadBalanced => {
-                rotated_addresses = self.rotate_load_balanced(&wallets).await?;
-            }
-            RotationStrategy::TimeBased => {
-                rotated_addresses = self.rotate_time_based(&wallets).await?;
-            }
-            RotationStrategy::Random => {
-                rotated_addresses = self.rotate_random(&wallets).await?;
-            }
-        }
+        let rotated_addresses = match self.policy.strategy {
+            RotationStrategy::RoundRobin => self.rotate_round_robin(&wallets).await?,
+            RotationStrategy::LeastUsed => self.rotate_least_used(&wallets).await?,
+            RotationStrategy::BestPerformance => self.rotate_best_performance(&wallets).await?,
+            RotationStrategy::LoadBalanced => self.rotate_load_balanced(&wallets).await?,
+            RotationStrategy::TimeBased => self.rotate_time_based(&wallets).await?,
+            RotationStrategy::Random => self.rotate_random(&wallets).await?,
+        };
 
         // Update rotation stats
         for address in &rotated_addresses {
@@ -276,7 +262,7 @@ impl WalletRotator {
     }
 
     pub async fn force_rotation(&self, reason: RotationReason) -> Result<Vec<Address>> {
-        let wallets = self.wallets.read().await.clone();
+        let _wallets = self.wallets.read().await.clone();
         let rotated_addresses = self.rotate_wallets().await?;
 
         // Update rotation stats with the forced reason
@@ -339,7 +325,7 @@ impl Clone for WalletRotator {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use alloy::primitives::Address;
+    
     use std::sync::Arc;
     use tokio::sync::RwLock;
 
@@ -389,6 +375,6 @@ mod tests {
         let rotator = WalletRotator::new(wallets, policy);
         
         // Test that rotation strategies can be created
-        assert!(matches!(policy.strategy, RotationStrategy::Random));
+        assert!(matches!(rotator.policy.strategy, RotationStrategy::Random));
     }
 }
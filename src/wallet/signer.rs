@@ -0,0 +1,377 @@
+use alloy::{
+    consensus::TxEnvelope,
+    network::eip2718::Decodable2718,
+    primitives::{Address, Bytes, TxKind, U128, U64},
+    providers::{Provider, RootProvider},
+    rpc::client::RpcClient,
+    signers::k256::ecdsa::SigningKey,
+    transports::http::{reqwest::Client, Http},
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{
+    config::RemoteSignerConfig,
+    queue::replacement::{sign_transaction, SentTransaction},
+    services::EthereumProvider,
+    types::{RelayerError, Result},
+    wallet::keystore::Keystore,
+};
+
+/// Where a pool wallet's key is held
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignerKind {
+    /// A plaintext key in the relayer process
+    Local,
+    /// A key decrypted from a keystore file or the `wallets` table
+    Keystore,
+    /// A key held by a Web3Signer-compatible HTTP signer
+    Remote,
+}
+
+impl std::fmt::Display for SignerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignerKind::Local => write!(f, "local"),
+            SignerKind::Keystore => write!(f, "keystore"),
+            SignerKind::Remote => write!(f, "remote"),
+        }
+    }
+}
+
+/// Signs transactions for one pool wallet, wherever its key is held
+#[async_trait]
+pub trait WalletSigner: std::fmt::Debug + Send + Sync {
+    fn address(&self) -> Address;
+
+    fn kind(&self) -> SignerKind;
+
+    /// Sign `transaction`, returning the raw EIP-2718 encoded transaction
+    async fn sign_transaction(&self, transaction: &SentTransaction) -> Result<Vec<u8>>;
+
+    /// The private key, for backends that hold it in the relayer process
+    fn signing_key(&self) -> Option<&SigningKey> {
+        None
+    }
+}
+
+/// A plaintext key held in process
+#[derive(Debug, Clone)]
+pub struct LocalKeySigner {
+    address: Address,
+    private_key: SigningKey,
+}
+
+impl LocalKeySigner {
+    pub fn new(private_key: SigningKey) -> Self {
+        Self {
+            address: Address::from_private_key(&private_key),
+            private_key,
+        }
+    }
+}
+
+#[async_trait]
+impl WalletSigner for LocalKeySigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn kind(&self) -> SignerKind {
+        SignerKind::Local
+    }
+
+    async fn sign_transaction(&self, transaction: &SentTransaction) -> Result<Vec<u8>> {
+        sign_transaction(transaction, &self.private_key).await
+    }
+
+    fn signing_key(&self) -> Option<&SigningKey> {
+        Some(&self.private_key)
+    }
+}
+
+/// A key decrypted from an encrypted keystore, remembering where it came from
+#[derive(Debug, Clone)]
+pub struct KeystoreSigner {
+    key: LocalKeySigner,
+    source: String,
+}
+
+impl KeystoreSigner {
+    pub fn new(private_key: SigningKey, source: impl Into<String>) -> Self {
+        Self {
+            key: LocalKeySigner::new(private_key),
+            source: source.into(),
+        }
+    }
+
+    /// Decrypt the v3 keystore at `path` with `password`
+    pub fn from_file(path: &Path, password: &str) -> Result<Self> {
+        let private_key = Keystore::from_file(path)?
+            .decrypt(password)
+            .map_err(|e| RelayerError::Config(format!("Failed to decrypt keystore {}: {}", path.display(), e)))?;
+        Ok(Self::new(private_key, path.display().to_string()))
+    }
+
+    /// Keystore file the key was decrypted from, or the table it was stored in
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+#[async_trait]
+impl WalletSigner for KeystoreSigner {
+    fn address(&self) -> Address {
+        self.key.address()
+    }
+
+    fn kind(&self) -> SignerKind {
+        SignerKind::Keystore
+    }
+
+    async fn sign_transaction(&self, transaction: &SentTransaction) -> Result<Vec<u8>> {
+        self.key.sign_transaction(transaction).await
+    }
+
+    fn signing_key(&self) -> Option<&SigningKey> {
+        self.key.signing_key()
+    }
+}
+
+/// An account of a remote signer speaking the Web3Signer eth1 JSON-RPC API. Transactions are
+/// signed with `eth_signTransaction`; the key never enters the relayer process.
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    url: String,
+    address: Address,
+    client: Arc<EthereumProvider>,
+}
+
+impl RemoteSigner {
+    /// Connect to the signer at `config.url` and return one signer per configured account,
+    /// or per account it manages when none are configured
+    pub async fn connect(config: &RemoteSignerConfig) -> Result<Vec<Self>> {
+        let http = Client::builder()
+            .timeout(Duration::from_secs(config.timeout))
+            .build()
+            .map_err(|e| RelayerError::Config(format!("Failed to build remote signer client: {}", e)))?;
+        let url = config.url.parse()
+            .map_err(|e| RelayerError::Config(format!("Invalid remote signer URL {}: {}", config.url, e)))?;
+        let client: Arc<EthereumProvider> = Arc::new(RootProvider::new(RpcClient::new(Http::with_client(http, url), false)));
+
+        let accounts = client.get_accounts().await
+            .map_err(|e| RelayerError::Network(format!("Failed to list accounts of remote signer {}: {}", config.url, e)))?;
+        let addresses = if config.addresses.is_empty() {
+            accounts
+        } else {
+            if let Some(missing) = config.addresses.iter().find(|address| !accounts.contains(address)) {
+                return Err(RelayerError::Config(format!(
+                    "Remote signer {} does not manage account {:?}",
+                    config.url, missing
+                )));
+            }
+            config.addresses.clone()
+        };
+
+        Ok(addresses.into_iter()
+            .map(|address| Self {
+                url: config.url.clone(),
+                address,
+                client: Arc::clone(&client),
+            })
+            .collect())
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+#[async_trait]
+impl WalletSigner for RemoteSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn kind(&self) -> SignerKind {
+        SignerKind::Remote
+    }
+
+    async fn sign_transaction(&self, transaction: &SentTransaction) -> Result<Vec<u8>> {
+        // Web3Signer takes the chain ID from its own configuration, checked on the way back
+        let params = serde_json::json!({
+            "from": transaction.from,
+            "to": transaction.to,
+            "gas": U64::from(transaction.gas_limit),
+            "maxFeePerGas": U128::from(transaction.fees.max_fee_per_gas),
+            "maxPriorityFeePerGas": U128::from(transaction.fees.max_priority_fee_per_gas),
+            "value": transaction.value,
+            "data": transaction.input,
+            "nonce": U64::from(transaction.nonce),
+        });
+
+        let signed: Bytes = self.client
+            .raw_request("eth_signTransaction".into(), [params])
+            .await
+            .map_err(|e| RelayerError::Network(format!("Remote signer {} failed to sign: {}", self.url, e)))?;
+
+        check_signed_transaction(&signed, transaction)?;
+        Ok(signed.to_vec())
+    }
+}
+
+/// Make sure a signed transaction from outside the process is the one that was asked for
+pub fn check_signed_transaction(raw: &[u8], transaction: &SentTransaction) -> Result<()> {
+    let mismatch = |what: &str| RelayerError::Ethereum(format!(
+        "Signed transaction from wallet {:?} has the wrong {}",
+        transaction.from, what
+    ));
+
+    let envelope = TxEnvelope::decode_2718(&mut &raw[..])
+        .map_err(|e| RelayerError::Ethereum(format!("Invalid signed transaction: {}", e)))?;
+    let TxEnvelope::Eip1559(ref signed) = envelope else {
+        return Err(mismatch("transaction type"));
+    };
+    let tx = signed.tx();
+
+    let signer = signed.signature().recover_address_from_prehash(&signed.signature_hash()).ok();
+    if signer != Some(transaction.from) {
+        return Err(mismatch("signer"));
+    }
+    if tx.chain_id != transaction.chain_id {
+        return Err(mismatch("chain ID"));
+    }
+    if tx.nonce != transaction.nonce {
+        return Err(mismatch("nonce"));
+    }
+    if tx.to != TxKind::Call(transaction.to) || tx.value != transaction.value || tx.input != transaction.input {
+        return Err(mismatch("call"));
+    }
+    if tx.gas_limit != transaction.gas_limit
+        || tx.max_fee_per_gas != transaction.fees.max_fee_per_gas
+        || tx.max_priority_fee_per_gas != transaction.fees.max_priority_fee_per_gas
+    {
+        return Err(mismatch("gas"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::replacement::Fees;
+    use crate::types::Priority;
+    use alloy::primitives::U256;
+    use axum::{routing::post, Json, Router};
+
+    fn test_transaction(from: Address) -> SentTransaction {
+        SentTransaction {
+            from,
+            nonce: 7,
+            chain_id: 1,
+            to: Address::repeat_byte(0x44),
+            value: U256::from(1000u64),
+            input: Bytes::from(vec![0xa9, 0x05, 0x9c, 0xbb]),
+            gas_limit: 100_000,
+            fees: Fees { max_fee_per_gas: 30_000_000_000, max_priority_fee_per_gas: 2_000_000_000 },
+            priority: Priority::Normal,
+        }
+    }
+
+    /// Web3Signer stub holding `key`, signing with `chain_id` the way Web3Signer's own
+    /// configured chain ID would
+    async fn stub_signer(key: SigningKey, chain_id: u64) -> String {
+        let signer = Arc::new(LocalKeySigner::new(key));
+        let app = Router::new().route("/", post(move |Json(body): Json<serde_json::Value>| {
+            let signer = Arc::clone(&signer);
+            async move {
+                let id = body["id"].clone();
+                let result = match body["method"].as_str() {
+                    Some("eth_accounts") => serde_json::json!([signer.address()]),
+                    Some("eth_signTransaction") => {
+                        let params = &body["params"][0];
+                        let quantity = |field: &str| u128::from_str_radix(params[field].as_str().unwrap().trim_start_matches("0x"), 16).unwrap();
+                        let transaction = SentTransaction {
+                            from: params["from"].as_str().unwrap().parse().unwrap(),
+                            nonce: quantity("nonce") as u64,
+                            chain_id,
+                            to: params["to"].as_str().unwrap().parse().unwrap(),
+                            value: params["value"].as_str().unwrap().parse().unwrap(),
+                            input: params["data"].as_str().unwrap().parse().unwrap(),
+                            gas_limit: quantity("gas") as u64,
+                            fees: Fees {
+                                max_fee_per_gas: quantity("maxFeePerGas"),
+                                max_priority_fee_per_gas: quantity("maxPriorityFeePerGas"),
+                            },
+                            priority: Priority::Normal,
+                        };
+                        let raw = signer.sign_transaction(&transaction).await.unwrap();
+                        serde_json::json!(format!("0x{}", hex::encode(raw)))
+                    }
+                    _ => serde_json::Value::Null,
+                };
+                Json(serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+            }
+        }));
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service()));
+        url
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_matches_local_signature() {
+        let key = SigningKey::random(&mut rand::thread_rng());
+        let local = LocalKeySigner::new(key.clone());
+        let url = stub_signer(key, 1).await;
+
+        let signers = RemoteSigner::connect(&RemoteSignerConfig { url, ..RemoteSignerConfig::default() })
+            .await
+            .unwrap();
+        assert_eq!(signers.len(), 1);
+        let remote = &signers[0];
+        assert_eq!(remote.address(), local.address());
+        assert_eq!(remote.kind(), SignerKind::Remote);
+        assert!(remote.signing_key().is_none());
+
+        // RFC 6979 signatures are deterministic, so both backends produce the same bytes
+        let transaction = test_transaction(local.address());
+        assert_eq!(
+            remote.sign_transaction(&transaction).await.unwrap(),
+            local.sign_transaction(&transaction).await.unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_rejects_mismatches() {
+        let key = SigningKey::random(&mut rand::thread_rng());
+        let address = Address::from_private_key(&key);
+
+        // Accounts the signer does not manage are refused up front
+        let url = stub_signer(key.clone(), 1).await;
+        let config = RemoteSignerConfig {
+            url,
+            addresses: vec![Address::repeat_byte(0x01)],
+            ..RemoteSignerConfig::default()
+        };
+        assert!(matches!(RemoteSigner::connect(&config).await, Err(RelayerError::Config(_))));
+
+        // A signer configured for another chain returns a transaction that is not sent
+        let url = stub_signer(key, 5).await;
+        let signers = RemoteSigner::connect(&RemoteSignerConfig { url, ..RemoteSignerConfig::default() })
+            .await
+            .unwrap();
+        let error = signers[0].sign_transaction(&test_transaction(address)).await.unwrap_err();
+        assert!(error.to_string().contains("chain ID"));
+
+        // An unreachable signer
+        let config = RemoteSignerConfig { url: "http://127.0.0.1:1".to_string(), ..RemoteSignerConfig::default() };
+        assert!(RemoteSigner::connect(&config).await.is_err());
+    }
+}
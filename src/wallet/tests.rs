@@ -1,66 +1,76 @@
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use alloy::primitives::{Address, U256};
-    use alloy::signers::k256::ecdsa::SigningKey;
-    use std::str::FromStr;
-    use std::sync::Arc;
-    use tokio::sync::RwLock;
+use super::*;
+use crate::types::{WalletInfo, WalletPoolConfig};
+use alloy::primitives::{Address, U256};
+use alloy::signers::k256::ecdsa::SigningKey;
 
-    fn create_test_wallet_info() -> WalletInfo {
-        WalletInfo {
-            address: Address::from_str("0x1234567890123456789012345678901234567890").unwrap(),
-            private_key: SigningKey::random(&mut rand::thread_rng()),
-            balance: U256::from(1000000000000000000u64), // 1 ETH
-            nonce: 5,
-            is_healthy: true,
-            last_used: std::time::SystemTime::now(),
-        }
-    }
+fn create_test_wallet_info() -> WalletInfo {
+    let private_key = SigningKey::random(&mut rand::thread_rng());
+    let mut wallet_info = WalletInfo::new(private_key);
+    wallet_info.balance = U256::from(1000000000000000000u64); // 1 ETH
+    wallet_info.nonce = U256::from(5u64);
+    wallet_info
+}
 
-    fn create_test_pool_config() -> WalletPoolConfig {
-        WalletPoolConfig {
-            min_balance_threshold: U256::from(100000000000000000u64), // 0.1 ETH
-            max_wallets: 10,
-            rotation_strategy: RotationStrategy::RoundRobin,
-            health_check_interval: std::time::Duration::from_secs(60),
-            balance_check_interval: std::time::Duration::from_secs(30),
-        }
+fn create_test_pool_config() -> WalletPoolConfig {
+    WalletPoolConfig {
+        balance_threshold: U256::from(100000000000000000u64), // 0.1 ETH
+        max_wallets: 10,
+        health_check_interval: 60,
+        ..WalletPoolConfig::default()
     }
+}
 
-    #[test]
-    fn test_wallet_info_creation() {
-        let wallet_info = create_test_wallet_info();
-        assert_eq!(wallet_info.balance, U256::from(1000000000000000000u64));
-        assert_eq!(wallet_info.nonce, 5);
-        assert!(wallet_info.is_healthy);
-    }
+#[test]
+fn test_wallet_info_creation() {
+    let wallet_info = create_test_wallet_info();
+    assert_eq!(wallet_info.balance, U256::from(1000000000000000000u64));
+    assert_eq!(wallet_info.nonce, U256::from(5u64));
+    assert_eq!(Some(wallet_info.address), wallet_info.signer.as_ref().map(|signer| signer.address()));
+    assert!(wallet_info.is_healthy());
+}
 
-    #[test]
-    fn test_wallet_pool_config_creation() {
-        let config = create_test_pool_config();
-        assert_eq!(config.max_wallets, 10);
-        assert_eq!(config.rotation_strategy, RotationStrategy::RoundRobin);
-        assert_eq!(config.health_check_interval, std::time::Duration::from_secs(60));
-    }
+#[test]
+fn test_wallet_pool_config_creation() {
+    let config = create_test_pool_config();
+    assert_eq!(config.max_wallets, 10);
+    assert_eq!(config.balance_threshold, U256::from(100000000000000000u64));
+    assert_eq!(config.health_check_interval, 60);
+}
 
-    #[tokio::test]
-    async fn test_wallet_pool_creation() {
-        let config = create_test_pool_config();
-        let pool = WalletPool::new(config);
-        
-        assert_eq!(pool.wallets.len(), 0);
-        assert_eq!(pool.current_index, 0);
-    }
+#[tokio::test]
+async fn test_wallet_pool_creation() {
+    let pool = WalletPool::new(create_test_pool_config());
 
-    #[tokio::test]
-    async fn test_wallet_pool_add_wallet() {
-        let config = create_test_pool_config();
-        let mut pool = WalletPool::new(config);
-        let wallet_info = create_test_wallet_info();
-        
-        pool.add_wallet(wallet_info.clone()).await;
-        assert_eq!(pool.wallets.len(), 1);
-        assert_eq!(pool.wallets[0].address, wallet_info.address);
-    }
-}
\ No newline at end of file
+    let stats = pool.get_pool_stats().await.unwrap();
+    assert_eq!(stats.total_wallets, 0);
+    assert_eq!(stats.active_wallets, 0);
+    assert!(pool.get_next_wallet().await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_wallet_pool_add_wallet() {
+    let pool = WalletPool::new(create_test_pool_config());
+    let private_key = SigningKey::random(&mut rand::thread_rng());
+    let expected = Address::from_private_key(&private_key);
+
+    let address = pool.add_wallet(private_key).await.unwrap();
+    assert_eq!(address, expected);
+
+    let wallets = pool.wallets();
+    let wallets = wallets.read().await;
+    assert_eq!(wallets.len(), 1);
+    assert_eq!(wallets[0].address, expected);
+}
+
+#[tokio::test]
+async fn test_wallet_pool_round_robin() {
+    let pool = WalletPool::new(create_test_pool_config());
+    let first = pool.add_wallet(SigningKey::random(&mut rand::thread_rng())).await.unwrap();
+    let second = pool.add_wallet(SigningKey::random(&mut rand::thread_rng())).await.unwrap();
+
+    let a = pool.get_next_wallet().await.unwrap().unwrap().address;
+    let b = pool.get_next_wallet().await.unwrap().unwrap().address;
+    assert_ne!(a, b);
+    assert!([first, second].contains(&a));
+    assert!([first, second].contains(&b));
+}
@@ -0,0 +1,355 @@
+use alloy::{
+    primitives::{Address, Bytes, U256},
+    providers::Provider,
+    signers::k256::ecdsa::SigningKey,
+};
+use chrono::Utc;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
+
+use crate::{
+    config::TreasuryConfig,
+    database::DatabaseManager,
+    queue::replacement::{sign_transaction, Fees, SentTransaction},
+    services::EthereumProvider,
+    types::{Priority, RelayerError, Result},
+    utils::gas::GasPriceOracle,
+    wallet::{nonce::WalletNonceManager, pool::WalletPool},
+};
+
+/// `wallet_logs` event type of a treasury transfer to a pool wallet
+pub const TREASURY_TOP_UP_EVENT: &str = "treasury_top_up";
+
+/// Gas of a plain value transfer
+const TRANSFER_GAS: u64 = 21_000;
+
+/// Amount that brings a wallet holding `balance` up to `target`, or `None` while it holds
+/// at least `threshold`
+pub fn top_up_amount(balance: U256, threshold: U256, target: U256) -> Option<U256> {
+    (balance < threshold && balance < target).then(|| target - balance)
+}
+
+/// A transfer from the treasury to a pool wallet
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TopUp {
+    pub wallet: Address,
+    pub amount: U256,
+    pub balance_before: U256,
+    pub tx_hash: String,
+}
+
+#[derive(Debug, Default)]
+struct FundingState {
+    /// Wallets this controller took out of rotation for a low balance
+    suspended: HashSet<Address>,
+    /// When the last top-up of each wallet was sent
+    in_flight: HashMap<Address, Instant>,
+}
+
+/// Keeps pool wallets funded from a treasury wallet. A wallet below the pool's balance
+/// threshold is taken out of rotation and sent enough to reach the target balance, within a
+/// rolling 24-hour spend cap; it rejoins the rotation once its balance is back above the
+/// threshold. Every transfer is recorded in `wallet_logs`.
+#[derive(Debug, Clone)]
+pub struct TreasuryController {
+    provider: Arc<EthereumProvider>,
+    database: Arc<DatabaseManager>,
+    wallet_pool: Arc<WalletPool>,
+    nonce_manager: Arc<WalletNonceManager>,
+    gas_price_oracle: Option<Arc<GasPriceOracle>>,
+    treasury_key: Arc<SigningKey>,
+    treasury: Address,
+    threshold: U256,
+    target_balance: U256,
+    daily_cap: U256,
+    check_interval: Duration,
+    retry_after: Duration,
+    state: Arc<Mutex<FundingState>>,
+}
+
+impl TreasuryController {
+    pub fn new(
+        config: &TreasuryConfig,
+        treasury_key: SigningKey,
+        threshold: U256,
+        provider: Arc<EthereumProvider>,
+        database: Arc<DatabaseManager>,
+        wallet_pool: Arc<WalletPool>,
+        nonce_manager: Arc<WalletNonceManager>,
+    ) -> Result<Self> {
+        let parse = |what: &str, amount: &str| U256::from_str(amount)
+            .map_err(|_| RelayerError::Config(format!("Invalid treasury {} '{}'", what, amount)));
+
+        Ok(Self {
+            provider,
+            database,
+            wallet_pool,
+            nonce_manager,
+            gas_price_oracle: None,
+            treasury: Address::from_private_key(&treasury_key),
+            treasury_key: Arc::new(treasury_key),
+            threshold,
+            target_balance: parse("target_balance", &config.target_balance)?,
+            daily_cap: parse("daily_cap", &config.daily_cap)?,
+            check_interval: Duration::from_secs(config.check_interval),
+            retry_after: Duration::from_secs(config.retry_after),
+            state: Arc::new(Mutex::new(FundingState::default())),
+        })
+    }
+
+    /// Price top-ups from the gas oracle instead of the node's fee estimate
+    pub fn with_gas_price_oracle(mut self, gas_price_oracle: Arc<GasPriceOracle>) -> Self {
+        self.gas_price_oracle = Some(gas_price_oracle);
+        self
+    }
+
+    pub fn treasury_address(&self) -> Address {
+        self.treasury
+    }
+
+    /// Start checking pool wallet balances every `check_interval`
+    pub async fn start_funding_loop(&self) -> Result<()> {
+        tracing::info!("Starting treasury funding loop for treasury {:?}...", self.treasury);
+        let mut interval = tokio::time::interval(self.check_interval);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = self.check_wallets().await {
+                tracing::error!("Treasury funding error: {}", e);
+            }
+        }
+    }
+
+    /// Check every pool wallet once, topping up and suspending low ones and resuming funded ones
+    pub async fn check_wallets(&self) -> Result<Vec<TopUp>> {
+        let addresses: Vec<Address> = {
+            let wallets = self.wallet_pool.wallets();
+            let wallets = wallets.read().await;
+            wallets.iter().map(|wallet| wallet.address).collect()
+        };
+
+        let mut top_ups = Vec::new();
+        for address in addresses {
+            // One failed lookup must not hold back the other wallets' top-ups
+            let balance = match self.provider.get_balance(address).await {
+                Ok(balance) => balance,
+                Err(e) => {
+                    tracing::warn!("Failed to get balance of wallet {:?}: {}", address, e);
+                    continue;
+                }
+            };
+
+            let Some(amount) = top_up_amount(balance, self.threshold, self.target_balance) else {
+                self.resume(address).await;
+                continue;
+            };
+
+            if self.wallet_pool.suspend_wallet(address).await {
+                tracing::warn!("Wallet {:?} is low on funds ({} wei), taken out of rotation", address, balance);
+            }
+            let waiting = {
+                let mut state = self.state.lock().await;
+                state.suspended.insert(address);
+                state.in_flight.get(&address).is_some_and(|sent_at| sent_at.elapsed() < self.retry_after)
+            };
+            if waiting {
+                continue;
+            }
+
+            match self.top_up(address, amount, balance).await {
+                Ok(Some(top_up)) => top_ups.push(top_up),
+                Ok(None) => {}
+                Err(e) => tracing::error!("Failed to top up wallet {:?}: {}", address, e),
+            }
+        }
+
+        Ok(top_ups)
+    }
+
+    async fn resume(&self, address: Address) {
+        let mut state = self.state.lock().await;
+        state.in_flight.remove(&address);
+        if state.suspended.remove(&address) && self.wallet_pool.resume_wallet(address).await {
+            tracing::info!("Wallet {:?} is funded again, back in rotation", address);
+        }
+    }
+
+    /// Treasury spending over the last 24 hours
+    pub async fn spent_last_day(&self) -> Result<U256> {
+        let since = Utc::now() - chrono::Duration::days(1);
+        let spent = self.database.sum_wallet_log_amounts(TREASURY_TOP_UP_EVENT, since).await?;
+        U256::from_str(&spent)
+            .map_err(|_| RelayerError::Database(format!("Invalid treasury spending total '{}'", spent)))
+    }
+
+    async fn transfer_fees(&self) -> Result<Fees> {
+        if let Some(ref oracle) = self.gas_price_oracle {
+            let gas = oracle.get_recommended_gas_price("normal").await?;
+            return Ok(Fees {
+                max_fee_per_gas: gas.max_fee_per_gas.to::<u128>(),
+                max_priority_fee_per_gas: gas.max_priority_fee_per_gas.to::<u128>(),
+            });
+        }
+
+        let estimate = self.provider.estimate_eip1559_fees(None).await
+            .map_err(|e| RelayerError::Ethereum(format!("Failed to estimate fees: {}", e)))?;
+        Ok(Fees {
+            max_fee_per_gas: estimate.max_fee_per_gas,
+            max_priority_fee_per_gas: estimate.max_priority_fee_per_gas,
+        })
+    }
+
+    /// Send `amount` to `wallet` unless it would break the daily cap or the treasury is short
+    async fn top_up(&self, wallet: Address, amount: U256, balance_before: U256) -> Result<Option<TopUp>> {
+        let spent = self.spent_last_day().await?;
+        if spent.saturating_add(amount) > self.daily_cap {
+            tracing::warn!(
+                "Not topping up wallet {:?}: {} wei would exceed the treasury daily cap of {} wei ({} wei sent)",
+                wallet, amount, self.daily_cap, spent
+            );
+            return Ok(None);
+        }
+
+        let fees = self.transfer_fees().await?;
+        let treasury_balance = self.provider.get_balance(self.treasury).await
+            .map_err(|e| RelayerError::Ethereum(format!("Failed to get treasury balance: {}", e)))?;
+        let gas_cost = U256::from(TRANSFER_GAS) * U256::from(fees.max_fee_per_gas);
+        if treasury_balance < amount.saturating_add(gas_cost) {
+            tracing::error!(
+                "Treasury {:?} holds {} wei, too little to send {} wei to wallet {:?}",
+                self.treasury, treasury_balance, amount, wallet
+            );
+            return Ok(None);
+        }
+
+        let chain_id = self.provider.get_chain_id().await
+            .map_err(|e| RelayerError::Ethereum(format!("Failed to get chain ID: {}", e)))?;
+        let nonce = self.nonce_manager.allocate(self.treasury).await?;
+        let transfer = SentTransaction {
+            from: self.treasury,
+            nonce,
+            chain_id,
+            to: wallet,
+            value: amount,
+            input: Bytes::new(),
+            gas_limit: TRANSFER_GAS,
+            fees,
+            priority: Priority::Normal,
+        };
+
+        let encoded = match sign_transaction(&transfer, &self.treasury_key).await {
+            Ok(encoded) => encoded,
+            Err(e) => {
+                self.nonce_manager.release(self.treasury, nonce).await;
+                return Err(e);
+            }
+        };
+        let pending_tx = match self.provider.send_raw_transaction(&encoded).await {
+            Ok(pending_tx) => pending_tx,
+            Err(e) => {
+                let error = e.to_string();
+                self.nonce_manager.recover(self.treasury, nonce, &error).await;
+                return Err(RelayerError::Ethereum(format!("Failed to send top-up: {}", error)));
+            }
+        };
+        let tx_hash = format!("{:?}", pending_tx.tx_hash());
+        self.state.lock().await.in_flight.insert(wallet, Instant::now());
+
+        tracing::info!("Topped up wallet {:?} with {} wei from treasury: {}", wallet, amount, tx_hash);
+
+        // The log doubles as the spend ledger, so a failed write is an error worth shouting about
+        let event = serde_json::json!({
+            "treasury": self.treasury.to_string(),
+            "amount": amount.to_string(),
+            "balance_before": balance_before.to_string(),
+            "target_balance": self.target_balance.to_string(),
+            "tx_hash": tx_hash,
+            "nonce": nonce,
+        });
+        let logged = match self.database.register_wallet(&wallet.to_string()).await {
+            Ok(wallet_id) => self.database.record_wallet_log(wallet_id, TREASURY_TOP_UP_EVENT, &event).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = logged {
+            tracing::error!("Failed to record top-up {} of wallet {:?}: {}", tx_hash, wallet, e);
+        }
+
+        Ok(Some(TopUp {
+            wallet,
+            amount,
+            balance_before,
+            tx_hash,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ETH: u64 = 1_000_000_000_000_000_000;
+
+    #[test]
+    fn test_top_up_amount_reaches_target() {
+        let threshold = U256::from(ETH);
+        let target = U256::from(2 * ETH);
+
+        assert_eq!(top_up_amount(U256::from(ETH / 4), threshold, target), Some(U256::from(7 * ETH / 4)));
+        assert_eq!(top_up_amount(U256::ZERO, threshold, target), Some(target));
+        assert_eq!(top_up_amount(threshold, threshold, target), None);
+        assert_eq!(top_up_amount(U256::from(3 * ETH), threshold, target), None);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_amounts_are_rejected() {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgresql://127.0.0.1:1/express402_test")
+            .unwrap();
+        let provider = Arc::new(alloy::providers::ProviderBuilder::new()
+            .on_http("http://127.0.0.1:1".parse().unwrap()));
+        let config = TreasuryConfig { daily_cap: "ten ether".to_string(), ..TreasuryConfig::default() };
+
+        let controller = TreasuryController::new(
+            &config,
+            SigningKey::random(&mut rand::thread_rng()),
+            U256::from(ETH),
+            Arc::clone(&provider),
+            Arc::new(DatabaseManager::from_pool(pool)),
+            Arc::new(WalletPool::new(Default::default())),
+            Arc::new(WalletNonceManager::new(provider)),
+        );
+        assert!(matches!(controller, Err(RelayerError::Config(_))));
+    }
+
+    #[tokio::test]
+    async fn test_failed_balance_lookup_skips_only_that_wallet() {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgresql://127.0.0.1:1/express402_test")
+            .unwrap();
+        let provider = Arc::new(alloy::providers::ProviderBuilder::new()
+            .on_http("http://127.0.0.1:1".parse().unwrap()));
+        let wallet_pool = Arc::new(WalletPool::new(Default::default()));
+        for _ in 0..2 {
+            wallet_pool.add_wallet(SigningKey::random(&mut rand::thread_rng())).await.unwrap();
+        }
+
+        let controller = TreasuryController::new(
+            &TreasuryConfig::default(),
+            SigningKey::random(&mut rand::thread_rng()),
+            U256::from(ETH),
+            Arc::clone(&provider),
+            Arc::new(DatabaseManager::from_pool(pool)),
+            Arc::clone(&wallet_pool),
+            Arc::new(WalletNonceManager::new(provider)),
+        ).unwrap();
+
+        // Every lookup fails against the unreachable node; each wallet is skipped, not the pass
+        let top_ups = controller.check_wallets().await.unwrap();
+        assert!(top_ups.is_empty());
+        assert_eq!(wallet_pool.active_addresses().await.len(), 2);
+    }
+}
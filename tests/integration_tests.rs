@@ -2,6 +2,7 @@ use express402_relayer::api::gateway_simple::*;
 use axum::{
     body::Body,
     http::{Request, StatusCode},
+    routing::{get, post},
     Router,
 };
 use serde_json::json;